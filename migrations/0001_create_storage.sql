@@ -0,0 +1,7 @@
+-- Every key is stored as a row, with links holding their model as JSON.
+CREATE TABLE IF NOT EXISTS storage (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    -- The size of the value in bytes, so storage usage can be measured without reading every value.
+    size INTEGER NOT NULL
+);
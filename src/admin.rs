@@ -0,0 +1,82 @@
+use crate::{
+    messages::ADMIN_ASSET_DOESNT_EXIST_RESPONSE, slugs::short_hash, templates::render_template,
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// Represents a file of the admin interface, which is embedded into the worker when it's built.
+struct AdminAsset {
+    /// The path of the asset relative to `/admin/`.
+    path: &'static str,
+    content_type: &'static str,
+    body: &'static str,
+}
+
+/// Embed a file from `static/admin` as an admin asset served under the same path.
+macro_rules! admin_asset {
+    ($path:literal, $content_type:literal) => {
+        AdminAsset {
+            path: $path,
+            content_type: $content_type,
+            body: include_str!(concat!("../static/admin/", $path)),
+        }
+    };
+}
+
+/// The page every path of the admin interface without an asset is answered with, so the app can route it itself.
+const ADMIN_INDEX: &str = include_str!("../static/admin/index.html");
+
+/// The assets the admin interface loads, which are only ever requested by the version they were built at.
+const ADMIN_ASSETS: &[AdminAsset] = &[
+    admin_asset!("app.js", "text/javascript; charset=utf-8"),
+    admin_asset!("app.css", "text/css; charset=utf-8"),
+];
+
+/// The length of the version assets are requested with.
+const ADMIN_VERSION_LENGTH: usize = 10;
+
+/// The Content-Security-Policy of the admin interface, which only loads its own assets and talks to its own API.
+const ADMIN_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'self'; img-src 'self' data:; object-src 'none'; base-uri 'none'; frame-ancestors 'none'";
+
+/// Get the version of the embedded assets, which changes whenever any of them do.
+fn admin_version() -> String {
+    let contents = ADMIN_ASSETS
+        .iter()
+        .map(|asset| asset.body)
+        .collect::<String>();
+    short_hash(&contents, ADMIN_VERSION_LENGTH)
+}
+
+/// Serve the admin interface, a single-page app for managing links that gets all of its data from the API.
+///
+/// Assets are requested with a version in their query, so they can be cached indefinitely, while the page itself is
+/// always revalidated to pick up new versions. Paths that look like files but aren't assets are not found.
+pub fn admin_handler(_req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let path = ctx.param("path").map(String::as_str).unwrap_or_default();
+
+    if let Some(asset) = ADMIN_ASSETS.iter().find(|asset| asset.path == path) {
+        let mut response = Response::ok(asset.body)?;
+        let headers = response.headers_mut();
+        headers.set("Content-Type", asset.content_type)?;
+        headers.set("Cache-Control", "public, max-age=31536000, immutable")?;
+        headers.set("X-Content-Type-Options", "nosniff")?;
+        return Ok(response);
+    }
+    if path
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.'))
+    {
+        return Response::error(ADMIN_ASSET_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    let mut response = Response::from_html(render_template(
+        ADMIN_INDEX,
+        &[("version", &admin_version())],
+    ))?;
+    let headers = response.headers_mut();
+    headers.set("Cache-Control", "no-cache")?;
+    headers.set("Content-Security-Policy", ADMIN_CONTENT_SECURITY_POLICY)?;
+    headers.set("Referrer-Policy", "no-referrer")?;
+    Ok(response)
+}
@@ -0,0 +1,734 @@
+use crate::{
+    environment::{get_bool_var, get_var},
+    ip_ranges::parse_ip_range_list,
+    jobs::{new_job_id, start_job, store_job_step},
+    models::{
+        job::{JobKind, JobModel, JobStepResult},
+        link::LinkModel,
+        stats::StatsModel,
+        tombstone::TombstoneModel,
+    },
+    networks::{classify_asn, is_excluded_network, NetworkType},
+    routing::Visitor,
+    slugs::stable_hash,
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::list_tenants,
+    user_agents::{parse_user_agent, UserAgent},
+};
+use chrono::{DateTime, Days, Months, NaiveDate, NaiveTime};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    net::IpAddr,
+};
+use url::Url;
+use worker::{js_sys::Math, Date, Env, Request};
+
+/// The prefix shared by the storage keys of all stats.
+pub const STATS_KEY_PREFIX: &str = "stats:";
+
+/// The separator between a link ID and the day in the storage key of daily stats.
+const DAY_KEY_SEPARATOR: &str = ":day:";
+
+/// The separator between a link ID and the month in the storage key of monthly stats.
+const MONTH_KEY_SEPARATOR: &str = ":month:";
+
+/// The separator between a link ID and the visit token in the storage key of conversion markers.
+const CONVERSION_KEY_SEPARATOR: &str = ":conversion:";
+
+/// Get the ID of the link a daily or monthly stats key or conversion marker belongs to.
+pub fn stats_key_link_id(key: &str) -> Option<&str> {
+    let key = key.strip_prefix(STATS_KEY_PREFIX)?;
+    key.rsplit_once(DAY_KEY_SEPARATOR)
+        .or_else(|| key.rsplit_once(MONTH_KEY_SEPARATOR))
+        .or_else(|| key.rsplit_once(CONVERSION_KEY_SEPARATOR))
+        .map(|(id, _)| id)
+}
+
+/// The maximum amount of link months whose daily stats are compacted by a single step of a stats rollup job.
+///
+/// Compacting a month takes a couple of storage requests for each of its days, so a single month is about as much as
+/// fits within the subrequest limit of a worker invocation.
+pub const STATS_ROLLUP_CHUNK_SIZE: usize = 1;
+
+/// The maximum amount of links whose stats are checked by a single step of an orphaned stats sweep job.
+pub const STATS_SWEEP_CHUNK_SIZE: usize = 20;
+
+/// The amount of milliseconds in a day.
+pub const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// The binding name for the variable controlling whether the time a link was last viewed is stored.
+pub const RECORD_LAST_VIEWED_BINDING: &str = "RECORD_LAST_VIEWED";
+
+/// The binding name for the variable controlling whether daily visit stats are stored.
+pub const RECORD_VISIT_STATS_BINDING: &str = "RECORD_VISIT_STATS";
+
+/// The binding name for the variable controlling whether the country of visits is stored.
+pub const RECORD_COUNTRIES_BINDING: &str = "RECORD_COUNTRIES";
+
+/// The binding name for the variable controlling whether the browser, OS and device of visits is stored.
+pub const RECORD_USER_AGENTS_BINDING: &str = "RECORD_USER_AGENTS";
+
+/// The binding name for the variable controlling whether the network (ASN) of visits is stored.
+pub const RECORD_NETWORKS_BINDING: &str = "RECORD_NETWORKS";
+
+/// The binding name for the variable controlling whether distinct visitors are estimated.
+pub const RECORD_UNIQUE_VISITORS_BINDING: &str = "RECORD_UNIQUE_VISITORS";
+
+/// The binding name for the variable controlling the fraction of visits whose details are recorded in visit stats.
+pub const ANALYTICS_SAMPLE_RATE_BINDING: &str = "ANALYTICS_SAMPLE_RATE";
+
+/// Get the fraction of visits (between `0.0` and `1.0`) whose details should be recorded in visit stats.
+///
+/// Defaults to recording every visit. The view count of each link is always exact regardless of the sample rate.
+pub fn get_sample_rate(env: &Env) -> f64 {
+    get_var(env, ANALYTICS_SAMPLE_RATE_BINDING)
+        .and_then(|rate| rate.parse::<f64>().ok())
+        .filter(|rate| rate.is_finite())
+        .map_or(1.0, |rate| rate.clamp(0.0, 1.0))
+}
+
+/// The binding name for the variable holding comma-separated client IP addresses whose visits are never counted.
+pub const EXCLUDE_IPS_BINDING: &str = "EXCLUDE_IPS";
+
+/// The binding name for the variable holding comma-separated client IP ranges whose visits are never counted.
+pub const EXCLUDE_CIDRS_BINDING: &str = "EXCLUDE_CIDRS";
+
+/// Whether or not a visit to the given link should be left out of its views and stats because it
+/// came from an excluded IP address, range or datacenter network, either configured globally or on the link itself.
+pub fn is_excluded_visit(req: &Request, env: &Env, link: &LinkModel) -> bool {
+    req.headers()
+        .get("CF-Connecting-IP")
+        .ok()
+        .flatten()
+        .and_then(|address| address.parse::<IpAddr>().ok())
+        .is_some_and(|address| is_excluded_address(env, link, address))
+        || req
+            .cf()
+            .is_some_and(|cf| is_excluded_network(env, link, cf.asn()))
+}
+
+/// Whether or not visits to the given link from the given client IP address are left out of its views and stats.
+pub fn is_excluded_address(env: &Env, link: &LinkModel, address: IpAddr) -> bool {
+    if link.exclude_ips.contains(&address)
+        || link
+            .exclude_cidrs
+            .iter()
+            .any(|range| range.contains(address))
+    {
+        return true;
+    }
+    [EXCLUDE_IPS_BINDING, EXCLUDE_CIDRS_BINDING]
+        .into_iter()
+        .filter_map(|binding| get_var(env, binding))
+        .any(|list| {
+            parse_ip_range_list(&list)
+                .iter()
+                .any(|range| range.contains(address))
+        })
+}
+
+/// Controls which details about visits are stored, beyond the view count of each link.
+///
+/// Everything is recorded unless disabled, as some operators cannot legally store per-visit data.
+#[derive(Debug, Clone, Copy)]
+pub struct PrivacyConfig {
+    /// Whether or not to store when each link was last viewed.
+    pub last_viewed: bool,
+    /// Whether or not to store daily (and compacted monthly) visit stats.
+    pub visit_stats: bool,
+    /// Whether or not to store the country visits came from.
+    pub countries: bool,
+    /// Whether or not to store the browser, OS and device visits came from.
+    pub user_agents: bool,
+    /// Whether or not to store the network (ASN) visits came from.
+    pub networks: bool,
+    /// Whether or not to estimate how many distinct visitors each link has.
+    pub unique_visitors: bool,
+}
+
+impl PrivacyConfig {
+    /// Get which details about visits should be stored from the environment.
+    pub fn from_env(env: &Env) -> Self {
+        Self {
+            last_viewed: get_bool_var(env, RECORD_LAST_VIEWED_BINDING).unwrap_or(true),
+            visit_stats: get_bool_var(env, RECORD_VISIT_STATS_BINDING).unwrap_or(true),
+            countries: get_bool_var(env, RECORD_COUNTRIES_BINDING).unwrap_or(true),
+            user_agents: get_bool_var(env, RECORD_USER_AGENTS_BINDING).unwrap_or(true),
+            networks: get_bool_var(env, RECORD_NETWORKS_BINDING).unwrap_or(true),
+            unique_visitors: get_bool_var(env, RECORD_UNIQUE_VISITORS_BINDING).unwrap_or(true),
+        }
+    }
+}
+
+/// Format a UNIX timestamp in milliseconds as the UTC day (`YYYY-MM-DD`) it falls on.
+pub fn day_from_timestamp(timestamp: u64) -> String {
+    DateTime::from_timestamp_millis(timestamp as i64)
+        .unwrap_or_default()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Represents a single visit to a link and the details recorded about it.
+pub struct Visit {
+    /// The UNIX timestamp of the visit.
+    pub timestamp: u64,
+    /// The ISO 3166-1 Alpha 2 country code the visit came from, if known.
+    pub country: Option<String>,
+    /// The parsed User-Agent of the visiting client, if it sent one.
+    pub user_agent: Option<UserAgent>,
+    /// The Autonomous System Number of the network the visit came from and whether it's a datacenter, if known.
+    pub network: Option<(u32, NetworkType)>,
+    /// Whether or not this visit was sampled and should have its details recorded in visit stats.
+    pub sampled: bool,
+    /// The index of the variant the visitor was sent to, if they were assigned one.
+    pub variant: Option<usize>,
+    /// The HTTP status code the visitor was redirected with, if they were redirected over HTTP.
+    pub status_code: Option<u16>,
+    /// A hash of the visitor's fingerprint used to estimate distinct visitors, which is never stored itself.
+    pub visitor: Option<u64>,
+}
+
+impl Visit {
+    /// Get the details of a visit from the request that made it, leaving out any details that shouldn't be stored.
+    ///
+    /// The visit is randomly sampled according to the given sample rate.
+    pub fn from_request(
+        req: &Request,
+        env: &Env,
+        privacy: &PrivacyConfig,
+        sample_rate: f64,
+    ) -> Self {
+        Self {
+            timestamp: Date::now().as_millis(),
+            country: req
+                .cf()
+                .filter(|_| privacy.countries)
+                .and_then(|cf| cf.country()),
+            user_agent: req
+                .headers()
+                .get("User-Agent")
+                .ok()
+                .flatten()
+                .filter(|_| privacy.user_agents)
+                .map(|user_agent| parse_user_agent(&user_agent)),
+            network: req
+                .cf()
+                .filter(|_| privacy.networks)
+                .map(|cf| (cf.asn(), classify_asn(env, cf.asn()))),
+            sampled: privacy.visit_stats && Math::random() < sample_rate,
+            variant: None,
+            status_code: None,
+            visitor: privacy
+                .unique_visitors
+                .then(|| stable_hash(&Visitor::from_request(req).fingerprint)),
+        }
+    }
+}
+
+/// Record a visit to the given link in its stats for the day it happened.
+pub async fn record_visit(storage: &impl StorageDriver, id: &str, visit: Visit) -> bool {
+    let key = StatsModel::day_key(id, &day_from_timestamp(visit.timestamp));
+    let mut stats = storage
+        .get_deserialized_json::<StatsModel>(&key)
+        .await
+        .unwrap_or_default();
+
+    stats.add_visit(visit);
+
+    storage.set_serialized_json(&key, &stats, None).await
+}
+
+/// The query parameter for the start of the range of time stats are restricted to, as an RFC 3339 timestamp.
+pub const STATS_FROM_QUERY_PARAM: &str = "from";
+
+/// The query parameter for the end of the range of time stats are restricted to, as an RFC 3339 timestamp.
+pub const STATS_TO_QUERY_PARAM: &str = "to";
+
+/// A range of time stats are restricted to, with either end left open when it isn't given.
+///
+/// Stats are only stored per day (or per month once compacted), so a period is included whole whenever
+/// any part of it falls within the range.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StatsRange {
+    /// The UNIX timestamp in milliseconds the range starts at.
+    pub from: Option<u64>,
+    /// The UNIX timestamp in milliseconds the range ends at, inclusive.
+    pub to: Option<u64>,
+}
+
+impl StatsRange {
+    /// Read the range from the `from` and `to` query parameters of a request URL.
+    ///
+    /// Returns [`None`] if either isn't a valid RFC 3339 timestamp or the range ends before it starts.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        let mut range = Self::default();
+        for (key, value) in url.query_pairs() {
+            let end = match key.as_ref() {
+                STATS_FROM_QUERY_PARAM => &mut range.from,
+                STATS_TO_QUERY_PARAM => &mut range.to,
+                _ => continue,
+            };
+            let timestamp = DateTime::parse_from_rfc3339(&value).ok()?;
+            *end = Some(timestamp.timestamp_millis().max(0) as u64);
+        }
+        match range {
+            Self {
+                from: Some(from),
+                to: Some(to),
+            } if to < from => None,
+            range => Some(range),
+        }
+    }
+
+    /// Whether or not the range restricts stats at all.
+    pub fn is_bounded(&self) -> bool {
+        self.from.is_some() || self.to.is_some()
+    }
+
+    /// Whether or not any part of a period, given as the day (`YYYY-MM-DD`) or month (`YYYY-MM`) its stats
+    /// are keyed by, falls within the range.
+    pub fn contains_period(&self, period: &str) -> bool {
+        let Some((start, end)) = period_bounds(period) else {
+            return false;
+        };
+        self.from.map_or(true, |from| end > from as i64)
+            && self.to.map_or(true, |to| start <= to as i64)
+    }
+
+    /// Keep only the stats of the periods that fall within the range.
+    pub fn filter(&self, periods: BTreeMap<String, StatsModel>) -> BTreeMap<String, StatsModel> {
+        if !self.is_bounded() {
+            return periods;
+        }
+        periods
+            .into_iter()
+            .filter(|(period, _)| self.contains_period(period))
+            .collect()
+    }
+}
+
+/// Get the UNIX timestamps in milliseconds a period, given as the day (`YYYY-MM-DD`) or month (`YYYY-MM`) its stats
+/// are keyed by, starts at (inclusive) and ends at (exclusive).
+fn period_bounds(period: &str) -> Option<(i64, i64)> {
+    let (start, end) = match NaiveDate::parse_from_str(period, "%Y-%m-%d") {
+        Ok(day) => (day, day.checked_add_days(Days::new(1))?),
+        Err(_) => {
+            let month = NaiveDate::parse_from_str(&format!("{period}-01"), "%Y-%m-%d").ok()?;
+            (month, month.checked_add_months(Months::new(1))?)
+        }
+    };
+    let millis = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc().timestamp_millis();
+    Some((millis(start), millis(end)))
+}
+
+/// Get the UNIX timestamp in milliseconds a period, given as the day (`YYYY-MM-DD`) or month (`YYYY-MM`) its stats
+/// are keyed by, starts at.
+pub fn period_start_timestamp(period: &str) -> Option<u64> {
+    period_bounds(period).map(|(start, _)| start.max(0) as u64)
+}
+
+/// Estimate the views during some periods from the views recorded in their stats, which only include sampled visits.
+pub fn estimate_views<'a>(
+    periods: impl IntoIterator<Item = &'a StatsModel>,
+    sample_rate: f64,
+) -> u64 {
+    if sample_rate <= 0.0 {
+        return 0;
+    }
+    let sampled_views = periods.into_iter().map(|stats| stats.views).sum::<u64>();
+    (sampled_views as f64 / sample_rate).round() as u64
+}
+
+/// Get all the stored stats under the given key prefix, keyed by the period they cover.
+pub async fn get_stats(storage: &impl StorageDriver, prefix: &str) -> BTreeMap<String, StatsModel> {
+    let mut periods = BTreeMap::new();
+    for key in storage.list(prefix).await {
+        if let Some(stats) = storage.get_deserialized_json(&key).await {
+            periods.insert(key.trim_start_matches(prefix).to_string(), stats);
+        }
+    }
+    periods
+}
+
+/// Delete all of the daily and monthly stats and conversion markers of a link, returning the amount of keys deleted.
+pub async fn delete_stats(storage: &impl StorageDriver, id: &str) -> usize {
+    let mut deleted = 0;
+    for prefix in [
+        StatsModel::day_key_prefix(id),
+        StatsModel::month_key_prefix(id),
+        StatsModel::conversion_key_prefix(id),
+    ] {
+        for key in storage.list(&prefix).await {
+            if storage.delete(&key).await {
+                deleted += 1;
+            }
+        }
+    }
+    deleted
+}
+
+/// Start a job deleting the stats left behind by every tenant's links that no longer exist and aren't retained as a
+/// tombstone, returning the amount of jobs started.
+pub async fn schedule_orphaned_stats_sweeps(storage: &impl StorageDriver, env: &Env) -> usize {
+    let tenants = std::iter::once(None).chain(list_tenants(storage).await.into_iter().map(Some));
+    let mut started = 0;
+    for tenant in tenants {
+        let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+            continue;
+        };
+        let ids = link_storage
+            .list(STATS_KEY_PREFIX)
+            .await
+            .iter()
+            .filter_map(|key| stats_key_link_id(key).map(str::to_string))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            continue;
+        }
+
+        let job_id = new_job_id(env);
+        let mut total_steps = 0;
+        for chunk in ids.chunks(STATS_SWEEP_CHUNK_SIZE) {
+            let Ok(data) = serde_json::to_string(chunk) else {
+                continue;
+            };
+            if store_job_step(storage, &job_id, total_steps, &data).await {
+                total_steps += 1;
+            }
+        }
+
+        let job = JobModel::new(JobKind::StatsSweep { tenant }, total_steps);
+        if start_job(storage, env, &job_id, &job).await {
+            started += 1;
+        }
+    }
+    started
+}
+
+/// Delete the stats of the links in a chunk stored by an orphaned stats sweep job if they no longer exist and aren't
+/// retained as a tombstone.
+pub async fn run_orphaned_stats_sweep_step(
+    link_storage: &impl StorageDriver,
+    data: &str,
+) -> JobStepResult {
+    let Ok(ids) = serde_json::from_str::<Vec<String>>(data) else {
+        return JobStepResult::default();
+    };
+
+    let mut deleted = 0;
+    for id in ids {
+        let orphaned = link_storage.get(&id).await.is_none()
+            && !link_storage
+                .get_deserialized_json::<TombstoneModel>(&TombstoneModel::key(&id))
+                .await
+                .is_some_and(|tombstone| tombstone.is_retained());
+        if orphaned {
+            deleted += delete_stats(link_storage, &id).await;
+        }
+    }
+
+    JobStepResult {
+        counts: BTreeMap::from([("deleted".to_string(), deleted)]),
+        failures: Vec::new(),
+    }
+}
+
+/// Move all of the daily and monthly stats of a link into the stats of another link, adding them
+/// to any stats the other link already has for the same periods.
+///
+/// Returns the amount of periods that were moved.
+pub async fn merge_stats(storage: &impl StorageDriver, source_id: &str, target_id: &str) -> usize {
+    let mut merged = 0;
+    for (source_prefix, target_prefix) in [
+        (
+            StatsModel::day_key_prefix(source_id),
+            StatsModel::day_key_prefix(target_id),
+        ),
+        (
+            StatsModel::month_key_prefix(source_id),
+            StatsModel::month_key_prefix(target_id),
+        ),
+    ] {
+        for source_key in storage.list(&source_prefix).await {
+            let Some(source_stats) = storage
+                .get_deserialized_json::<StatsModel>(&source_key)
+                .await
+            else {
+                continue;
+            };
+            let target_key = source_key.replacen(&source_prefix, &target_prefix, 1);
+            let mut target_stats = storage
+                .get_deserialized_json::<StatsModel>(&target_key)
+                .await
+                .unwrap_or_default();
+            target_stats.merge(source_stats);
+
+            // Only remove the source stats once they're safely part of the target's stats.
+            if storage
+                .set_serialized_json(&target_key, &target_stats, None)
+                .await
+                && storage.delete(&source_key).await
+            {
+                merged += 1;
+            }
+        }
+    }
+    merged
+}
+
+/// Get the link ID and day of a daily stats key.
+fn daily_stats_key_day(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix(STATS_KEY_PREFIX)?
+        .rsplit_once(DAY_KEY_SEPARATOR)
+}
+
+/// Group daily stats keys by the link and month they belong to, skipping any other keys.
+fn group_daily_stats_by_month(
+    keys: impl IntoIterator<Item = String>,
+) -> BTreeMap<(String, String), Vec<String>> {
+    let mut months = BTreeMap::<(String, String), Vec<String>>::new();
+    for key in keys {
+        let Some((id, month)) =
+            daily_stats_key_day(&key).and_then(|(id, day)| Some((id, day.get(..7)?)))
+        else {
+            continue;
+        };
+        months
+            .entry((id.to_string(), month.to_string()))
+            .or_default()
+            .push(key.clone());
+    }
+    months
+}
+
+/// Start a job compacting the daily stats of every tenant's links that are older than the given amount of days into
+/// monthly stats, returning the amount of jobs started.
+///
+/// The daily stats of a link's month are always compacted by the same step, so steps never update the same monthly
+/// stats.
+pub async fn schedule_stats_rollups(
+    storage: &impl StorageDriver,
+    env: &Env,
+    older_than_days: u64,
+) -> usize {
+    let cutoff_day = day_from_timestamp(
+        Date::now()
+            .as_millis()
+            .saturating_sub(older_than_days * DAY_MILLIS),
+    );
+
+    let tenants = std::iter::once(None).chain(list_tenants(storage).await.into_iter().map(Some));
+    let mut started = 0;
+    for tenant in tenants {
+        let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+            continue;
+        };
+        let day_keys = link_storage
+            .list(STATS_KEY_PREFIX)
+            .await
+            .into_iter()
+            .filter(|key| {
+                daily_stats_key_day(key).is_some_and(|(_, day)| day < cutoff_day.as_str())
+            });
+        let months = group_daily_stats_by_month(day_keys)
+            .into_values()
+            .collect::<Vec<_>>();
+        if months.is_empty() {
+            continue;
+        }
+
+        let job_id = new_job_id(env);
+        let mut total_steps = 0;
+        for chunk in months.chunks(STATS_ROLLUP_CHUNK_SIZE) {
+            let Ok(data) = serde_json::to_string(&chunk.concat()) else {
+                continue;
+            };
+            if store_job_step(storage, &job_id, total_steps, &data).await {
+                total_steps += 1;
+            }
+        }
+
+        let job = JobModel::new(JobKind::StatsRollup { tenant }, total_steps);
+        if start_job(storage, env, &job_id, &job).await {
+            started += 1;
+        }
+    }
+    started
+}
+
+/// Compact a chunk of daily stats stored by a stats rollup job into the monthly stats of their links, deleting the
+/// compacted daily stats afterwards.
+pub async fn run_stats_rollup_step(link_storage: &impl StorageDriver, data: &str) -> JobStepResult {
+    let Ok(day_keys) = serde_json::from_str::<Vec<String>>(data) else {
+        return JobStepResult::default();
+    };
+
+    let mut compacted = 0;
+    for ((id, month), day_keys) in group_daily_stats_by_month(day_keys) {
+        let month_key = StatsModel::month_key(&id, &month);
+        let mut month_stats = link_storage
+            .get_deserialized_json::<StatsModel>(&month_key)
+            .await
+            .unwrap_or_default();
+        for day_key in &day_keys {
+            if let Some(day_stats) = link_storage.get_deserialized_json(day_key).await {
+                month_stats.merge(day_stats);
+            }
+        }
+
+        // Only remove the daily stats once they're safely part of the monthly stats.
+        if !link_storage
+            .set_serialized_json(&month_key, &month_stats, None)
+            .await
+        {
+            continue;
+        }
+        for day_key in &day_keys {
+            if link_storage.delete(day_key).await {
+                compacted += 1;
+            }
+        }
+    }
+
+    JobStepResult {
+        counts: BTreeMap::from([("compacted".to_string(), compacted)]),
+        failures: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Midnight UTC at the start of 2024-05-01.
+    const MAY_FIRST: u64 = 1_714_521_600_000;
+
+    fn range(query: &str) -> Option<StatsRange> {
+        StatsRange::from_url(
+            &Url::parse(&format!("https://example.com/launch/stats?{query}")).unwrap(),
+        )
+    }
+
+    #[test]
+    fn ranges_are_read_from_the_query() {
+        assert_eq!(range(""), Some(StatsRange::default()));
+        assert_eq!(
+            range("from=2024-05-01T00:00:00Z"),
+            Some(StatsRange {
+                from: Some(MAY_FIRST),
+                to: None,
+            })
+        );
+        assert_eq!(
+            range("to=2024-05-01T02:00:00%2B02:00&other=1"),
+            Some(StatsRange {
+                from: None,
+                to: Some(MAY_FIRST),
+            })
+        );
+        assert_eq!(
+            range("from=2024-05-01T00:00:00Z&to=2024-05-01T00:00:00Z"),
+            Some(StatsRange {
+                from: Some(MAY_FIRST),
+                to: Some(MAY_FIRST),
+            })
+        );
+        assert_eq!(
+            range("from=1960-01-01T00:00:00Z"),
+            Some(StatsRange {
+                from: Some(0),
+                to: None,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_ranges_are_rejected() {
+        assert_eq!(range("from=2024-05-01"), None);
+        assert_eq!(range("from=yesterday"), None);
+        assert_eq!(range("to="), None);
+        assert_eq!(
+            range("from=2024-05-02T00:00:00Z&to=2024-05-01T00:00:00Z"),
+            None
+        );
+    }
+
+    #[test]
+    fn periods_overlapping_the_range_are_included_whole() {
+        let range = StatsRange {
+            from: Some(MAY_FIRST + 12 * 60 * 60 * 1000),
+            to: Some(MAY_FIRST + DAY_MILLIS),
+        };
+        assert!(range.contains_period("2024-05-01"));
+        assert!(range.contains_period("2024-05-02"));
+        assert!(range.contains_period("2024-05"));
+        assert!(!range.contains_period("2024-04-30"));
+        assert!(!range.contains_period("2024-05-03"));
+        assert!(!range.contains_period("2024-04"));
+        assert!(!range.contains_period("2024-06"));
+    }
+
+    #[test]
+    fn range_boundaries() {
+        // A period ending exactly where the range starts is outside of it.
+        let from = StatsRange {
+            from: Some(MAY_FIRST),
+            to: None,
+        };
+        assert!(!from.contains_period("2024-04-30"));
+        assert!(!from.contains_period("2024-04"));
+        assert!(from.contains_period("2024-05-01"));
+
+        // A period starting exactly where the range ends is inside of it.
+        let to = StatsRange {
+            from: None,
+            to: Some(MAY_FIRST),
+        };
+        assert!(to.contains_period("2024-05-01"));
+        assert!(to.contains_period("2024-05"));
+        assert!(!to.contains_period("2024-05-02"));
+    }
+
+    #[test]
+    fn invalid_periods_are_never_included() {
+        let unbounded = StatsRange::default();
+        assert!(!unbounded.contains_period("2024-13"));
+        assert!(!unbounded.contains_period("2023-02-29"));
+        assert!(!unbounded.contains_period("launch"));
+        assert!(unbounded.contains_period("2024-02-29"));
+    }
+
+    #[test]
+    fn periods_are_bucketed_by_utc_day_and_month() {
+        assert_eq!(day_from_timestamp(MAY_FIRST), "2024-05-01");
+        assert_eq!(day_from_timestamp(MAY_FIRST - 1), "2024-04-30");
+        assert_eq!(period_start_timestamp("2024-05-01"), Some(MAY_FIRST));
+        assert_eq!(period_start_timestamp("2024-05"), Some(MAY_FIRST));
+        assert_eq!(
+            period_bounds("2024-02"),
+            Some((1_706_745_600_000, 1_709_251_200_000))
+        );
+        assert_eq!(period_start_timestamp("2024-05-32"), None);
+    }
+
+    #[test]
+    fn filtering_keeps_periods_within_the_range() {
+        let periods = || {
+            ["2024-04", "2024-04-30", "2024-05-01", "2024-05-02"]
+                .map(|period| (period.to_string(), StatsModel::default()))
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+        };
+        let range = StatsRange {
+            from: Some(MAY_FIRST),
+            to: Some(MAY_FIRST),
+        };
+        assert_eq!(
+            range.filter(periods()).into_keys().collect::<Vec<_>>(),
+            ["2024-05-01"]
+        );
+        assert_eq!(StatsRange::default().filter(periods()).len(), 4);
+    }
+}
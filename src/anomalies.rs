@@ -0,0 +1,211 @@
+use crate::{
+    analytics::{day_from_timestamp, DAY_MILLIS, STATS_KEY_PREFIX},
+    environment::{get_bool_var, get_var},
+    models::{
+        anomalies::{AnomaliesModel, AnomalyKind, LinkAnomaly},
+        stats::StatsModel,
+    },
+    networks::NetworkType,
+    notifications::{notify, Notification},
+    storage::StorageDriver,
+};
+use std::collections::BTreeMap;
+use worker::{Date, Env};
+
+/// The binding name for the variable controlling whether link stats are analyzed for anomalous traffic.
+pub const ANOMALY_DETECTION_BINDING: &str = "ANOMALY_DETECTION";
+
+/// The binding name for the variable holding the least visits a day needs before it can be flagged.
+pub const ANOMALY_MIN_VIEWS_BINDING: &str = "ANOMALY_MIN_VIEWS";
+
+/// The binding name for the variable holding how many times the usual daily visits make a spike.
+pub const ANOMALY_SPIKE_FACTOR_BINDING: &str = "ANOMALY_SPIKE_FACTOR";
+
+/// The binding name for the variable holding the share of visits from bots or datacenter networks that gets a day flagged.
+pub const ANOMALY_BOT_SHARE_BINDING: &str = "ANOMALY_BOT_SHARE";
+
+/// The default least visits a day needs before it can be flagged.
+const DEFAULT_ANOMALY_MIN_VIEWS: u64 = 100;
+
+/// The default amount of times the usual daily visits that make a spike.
+const DEFAULT_ANOMALY_SPIKE_FACTOR: u64 = 5;
+
+/// The default share of visits from bots that gets a day flagged.
+const DEFAULT_ANOMALY_BOT_SHARE: f64 = 0.5;
+
+/// The amount of days before the analyzed day that make up a link's usual daily visits.
+const BASELINE_DAYS: u64 = 7;
+
+/// The device class visits from bots are recorded under.
+const BOT_DEVICE: &str = "bot";
+
+/// Configuration for analyzing link stats for anomalous traffic.
+pub struct AnomalyDetectionConfig {
+    /// The least visits a day needs before it can be flagged, so quiet links aren't flagged over a handful of visits.
+    min_views: u64,
+    /// How many times the usual daily visits make a spike.
+    spike_factor: u64,
+    /// The share of visits from bots or datacenter networks that gets a day flagged.
+    bot_share: f64,
+}
+
+impl AnomalyDetectionConfig {
+    /// Get the anomaly detection configuration from the environment, or [`None`] if anomaly detection is disabled.
+    pub fn from_env(env: &Env) -> Option<Self> {
+        if !get_bool_var(env, ANOMALY_DETECTION_BINDING).unwrap_or(false) {
+            return None;
+        }
+        Some(Self {
+            min_views: get_var(env, ANOMALY_MIN_VIEWS_BINDING)
+                .and_then(|views| views.parse().ok())
+                .unwrap_or(DEFAULT_ANOMALY_MIN_VIEWS),
+            spike_factor: get_var(env, ANOMALY_SPIKE_FACTOR_BINDING)
+                .and_then(|factor| factor.parse().ok())
+                .filter(|factor| *factor > 1)
+                .unwrap_or(DEFAULT_ANOMALY_SPIKE_FACTOR),
+            bot_share: get_var(env, ANOMALY_BOT_SHARE_BINDING)
+                .and_then(|share| share.parse::<f64>().ok())
+                .filter(|share| (0.0..=1.0).contains(share))
+                .unwrap_or(DEFAULT_ANOMALY_BOT_SHARE),
+        })
+    }
+}
+
+/// Find anomalous traffic in a link's stats for a day, compared against its stats for the days before.
+fn find_anomalies(
+    config: &AnomalyDetectionConfig,
+    day: &str,
+    stats: &StatsModel,
+    baseline: &[&StatsModel],
+) -> Vec<LinkAnomaly> {
+    let mut anomalies = Vec::new();
+    if stats.views < config.min_views {
+        return anomalies;
+    }
+
+    // Days without any stats had no visits, so they still count towards the usual daily visits.
+    let baseline_views =
+        baseline.iter().map(|stats| stats.views).sum::<u64>() as f64 / BASELINE_DAYS as f64;
+    if stats.views as f64 >= baseline_views.max(1.0) * config.spike_factor as f64 {
+        anomalies.push(LinkAnomaly {
+            kind: AnomalyKind::ViewSpike,
+            day: day.to_string(),
+            views: stats.views,
+            baseline_views: Some(baseline_views),
+            bot_views: None,
+            datacenter_views: None,
+        });
+    }
+
+    let bot_views = stats.devices.get(BOT_DEVICE).copied().unwrap_or_default();
+    if bot_views as f64 >= stats.views as f64 * config.bot_share {
+        anomalies.push(LinkAnomaly {
+            kind: AnomalyKind::BotTraffic,
+            day: day.to_string(),
+            views: stats.views,
+            baseline_views: None,
+            bot_views: Some(bot_views),
+            datacenter_views: None,
+        });
+    }
+
+    let datacenter_views = stats
+        .networks
+        .get(&NetworkType::Datacenter.to_string())
+        .copied()
+        .unwrap_or_default();
+    if datacenter_views as f64 >= stats.views as f64 * config.bot_share {
+        anomalies.push(LinkAnomaly {
+            kind: AnomalyKind::DatacenterTraffic,
+            day: day.to_string(),
+            views: stats.views,
+            baseline_views: None,
+            bot_views: None,
+            datacenter_views: Some(datacenter_views),
+        });
+    }
+
+    anomalies
+}
+
+/// Analyze the daily stats of every link for anomalous traffic on the last full day, storing what was
+/// found and sending a notification for every link that has been newly flagged.
+///
+/// Only stored stats are analyzed, so nothing is found when visit stats aren't recorded.
+pub async fn detect_anomalies(
+    storage: &impl StorageDriver,
+    env: &Env,
+    tenant: Option<&str>,
+) -> Option<AnomaliesModel> {
+    let config = AnomalyDetectionConfig::from_env(env)?;
+    let previous = storage
+        .get_deserialized_json::<AnomaliesModel>(AnomaliesModel::key())
+        .await
+        .unwrap_or_default();
+
+    let now = Date::now().as_millis();
+    let day = day_from_timestamp(now.saturating_sub(DAY_MILLIS));
+    let baseline_start = day_from_timestamp(now.saturating_sub((BASELINE_DAYS + 1) * DAY_MILLIS));
+
+    // Group the daily stats of the analyzed day and the days before it by the link they belong to.
+    let mut links = BTreeMap::<String, BTreeMap<String, StatsModel>>::new();
+    for key in storage.list(STATS_KEY_PREFIX).await {
+        let Some((id, key_day)) = key
+            .strip_prefix(STATS_KEY_PREFIX)
+            .and_then(|key| key.rsplit_once(":day:"))
+        else {
+            continue;
+        };
+        if key_day < baseline_start.as_str() || key_day > day.as_str() {
+            continue;
+        }
+        if let Some(stats) = storage.get_deserialized_json::<StatsModel>(&key).await {
+            links
+                .entry(id.to_string())
+                .or_default()
+                .insert(key_day.to_string(), stats);
+        }
+    }
+
+    let mut analysis = AnomaliesModel {
+        links: BTreeMap::new(),
+        analyzed_at_timestamp: now,
+    };
+    for (id, days) in links {
+        let Some(stats) = days.get(&day) else {
+            continue;
+        };
+        let baseline = days
+            .iter()
+            .filter(|(baseline_day, _)| **baseline_day != day)
+            .map(|(_, stats)| stats)
+            .collect::<Vec<_>>();
+        let anomalies = find_anomalies(&config, &day, stats, &baseline);
+        if anomalies.is_empty() {
+            continue;
+        }
+
+        // Only notify about anomalies that weren't already flagged by the last analysis.
+        for anomaly in &anomalies {
+            if !previous.has_anomaly(&id, anomaly.kind) {
+                notify(
+                    env,
+                    &Notification::LinkAnomalyDetected {
+                        id: id.clone(),
+                        tenant: tenant.map(str::to_string),
+                        kind: anomaly.kind,
+                        day: anomaly.day.clone(),
+                        views: anomaly.views,
+                    },
+                )
+                .await;
+            }
+        }
+        analysis.links.insert(id, anomalies);
+    }
+
+    storage
+        .set_serialized_json(AnomaliesModel::key(), &analysis, None)
+        .await;
+    Some(analysis)
+}
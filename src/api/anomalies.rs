@@ -0,0 +1,24 @@
+use crate::{
+    authentication::authorized_guard, models::anomalies::AnomaliesModel, storage::StorageDriver,
+    tenants::get_request_storage,
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// Get the links flagged with anomalous traffic by the last scheduled analysis of their stats.
+pub async fn get_anomalies_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    Response::from_json(
+        &storage
+            .get_deserialized_json::<AnomaliesModel>(AnomaliesModel::key())
+            .await
+            .unwrap_or_default(),
+    )
+}
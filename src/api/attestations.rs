@@ -0,0 +1,82 @@
+use super::{requests::VerifyAttestationRequestBody, responses::VerifyAttestationResponse};
+use crate::{
+    attestations::{attestations_enabled, public_key_jwk, verify_payload, AttestationStatement},
+    links::get_link_following_alias,
+    messages::*,
+    tenants::get_request_storage,
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// Publish the public key attestations are signed with as a JWK, so they can be verified offline.
+pub async fn attestation_key_handler(
+    _req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    match public_key_jwk(&ctx.env) {
+        Some(public_key) => Response::from_json(&public_key),
+        None => Response::error(ATTESTATIONS_DISABLED_RESPONSE, 501),
+    }
+}
+
+/// Serve a page that verifies an attestation pasted into it using [`verify_attestation_handler`].
+pub async fn verify_attestation_page_handler(
+    _req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    if !attestations_enabled(&ctx.env) {
+        return Response::error(ATTESTATIONS_DISABLED_RESPONSE, 501);
+    }
+    Response::from_html(include_str!("../../static/verify.html"))
+}
+
+/// Verify an attestation was signed by this deployment, and whether the link it describes still
+/// sends visitors to the same destinations.
+///
+/// This is public as it only reveals whether a link still matches an attestation of it.
+pub async fn verify_attestation_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    if !attestations_enabled(&ctx.env) {
+        return Response::error(ATTESTATIONS_DISABLED_RESPONSE, 501);
+    }
+    let Ok(body) = req.json::<VerifyAttestationRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+
+    let Some(valid) = verify_payload(&ctx.env, &body.payload, &body.signature).await else {
+        return Response::error(GENERIC_ATTESTATION_VERIFY_ERROR_RESPONSE, 500);
+    };
+    let Some(statement) = valid
+        .then(|| serde_json::from_str::<AttestationStatement>(&body.payload).ok())
+        .flatten()
+    else {
+        return Response::from_json(&VerifyAttestationResponse {
+            valid: false,
+            statement: None,
+            unchanged: None,
+        });
+    };
+
+    // Links can only be compared on the host they were attested on, as other hosts may belong to another tenant.
+    let unchanged = if req.url()?.host_str() == Some(statement.host.as_str()) {
+        let storage = get_request_storage(&req, &ctx.env).await?;
+        let (_, link) = get_link_following_alias(&storage, statement.id.clone()).await;
+        let current = link
+            .filter(|link| !link.disabled && link.is_valid())
+            .map(|link| {
+                AttestationStatement::new(statement.host.clone(), statement.id.clone(), &link, 0)
+            });
+        Some(current.is_some_and(|current| {
+            current.url == statement.url && current.alternate_urls == statement.alternate_urls
+        }))
+    } else {
+        None
+    };
+
+    Response::from_json(&VerifyAttestationResponse {
+        valid: true,
+        statement: Some(statement),
+        unchanged,
+    })
+}
@@ -0,0 +1,188 @@
+use super::{
+    requests::{BatchDeleteLinksRequestBody, BatchLinkRecord, MAX_BATCH_LINKS},
+    responses::{
+        BatchCreateLinksResponse, BatchDeleteLinkResult, BatchDeleteLinksResponse, BatchLinkResult,
+        BatchLinkStatus,
+    },
+};
+use crate::{
+    authentication::{authorized_guard, authorized_key_guard},
+    delete_link,
+    durable_objects::link_lock::with_link_lock,
+    link_cache::invalidate_cached_link,
+    links::{find_deduplicated_id, prepare_link_request, save_link, SaveLinkError},
+    messages::*,
+    notifications::{notify_in_background, Notification},
+    slugs::{
+        canonicalize_decoded_id, generate_unique_id, get_random_id_alphabet, get_random_id_length,
+    },
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use serde_json::Value;
+use url::Url;
+use validator::Validate;
+use worker::{Context, Env, Request, Response, RouteContext};
+
+/// Create many links in a single request from a JSON array of link records, returning the outcome of each record.
+///
+/// Records use the same fields as creating a single link, with an optional `id`. Each record is validated and
+/// saved on its own, so an invalid record doesn't stop the rest of the batch.
+pub async fn batch_create_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    // Records are parsed one at a time so a malformed record only fails itself.
+    let Ok(records) = req.json::<Vec<Value>>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if records.is_empty() || records.len() as u64 > MAX_BATCH_LINKS {
+        return Response::error(INVALID_BATCH_SIZE_RESPONSE, 400);
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let host_url = req.url()?;
+    let mut response = BatchCreateLinksResponse::default();
+    for (index, record) in records.into_iter().enumerate() {
+        let (id, outcome) = create_batch_record(&storage, &ctx.env, &host_url, record).await;
+        match (&id, &outcome) {
+            (Some(id), Ok(BatchLinkStatus::Created)) => notify_in_background(
+                &ctx.data,
+                &ctx.env,
+                Notification::LinkCreated {
+                    id: id.clone(),
+                    tenant: tenant.clone(),
+                },
+            ),
+            (Some(id), Ok(BatchLinkStatus::Updated)) => {
+                invalidate_cached_link(&ctx.env, tenant.as_deref(), id).await;
+            }
+            _ => {}
+        }
+        let (status, error) = match outcome {
+            Ok(status) => (status, None),
+            Err(err) => (BatchLinkStatus::Failed, Some(err.message())),
+        };
+        match status {
+            BatchLinkStatus::Created => response.created += 1,
+            BatchLinkStatus::Updated => response.updated += 1,
+            BatchLinkStatus::Skipped => response.skipped += 1,
+            BatchLinkStatus::Failed => response.failed += 1,
+        }
+        response.results.push(BatchLinkResult {
+            index,
+            id,
+            status,
+            error,
+        });
+    }
+
+    Response::from_json(&response)
+}
+
+/// Validate and save a single record of a batch, returning the ID it was saved under (if it got one) and its outcome.
+///
+/// Records that would replace an existing link without `overwrite`, or deduplicate to an existing link,
+/// are skipped instead of failing.
+async fn create_batch_record(
+    storage: &impl StorageDriver,
+    env: &Env,
+    host_url: &Url,
+    record: Value,
+) -> (Option<String>, Result<BatchLinkStatus, SaveLinkError>) {
+    let Ok(mut record) = serde_json::from_value::<BatchLinkRecord>(record) else {
+        return (None, Err(SaveLinkError::InvalidPayload));
+    };
+    if let Err(err) = prepare_link_request(env, host_url, &mut record.link) {
+        return (record.id, Err(err));
+    }
+
+    let id = match record.id {
+        Some(id) if record.link.dedupe => return (Some(id), Err(SaveLinkError::DedupeWithId)),
+        Some(id) => canonicalize_decoded_id(&id),
+        None if record.link.dedupe => match find_deduplicated_id(storage, &record.link.url).await {
+            Some((id, Some(_))) => return (Some(id), Ok(BatchLinkStatus::Skipped)),
+            Some((id, None)) => id,
+            None => return (None, Err(SaveLinkError::StorageFailure)),
+        },
+        None => match generate_unique_id(
+            storage,
+            &get_random_id_alphabet(env),
+            get_random_id_length(env),
+        )
+        .await
+        {
+            Some(id) => id,
+            None => return (None, Err(SaveLinkError::StorageFailure)),
+        },
+    };
+
+    match save_link(storage, env, id.clone(), record.link, false).await {
+        Ok(saved) if saved.created => (Some(saved.id), Ok(BatchLinkStatus::Created)),
+        Ok(saved) => (Some(saved.id), Ok(BatchLinkStatus::Updated)),
+        Err(SaveLinkError::AlreadyExists) => (Some(id), Ok(BatchLinkStatus::Skipped)),
+        Err(err) => (Some(id), Err(err)),
+    }
+}
+
+/// Delete many links in a single request, returning whether each one was deleted.
+///
+/// Each link is deleted exactly as if it was deleted on its own, so a missing or failed link doesn't stop the rest.
+pub async fn batch_delete_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let key = match authorized_key_guard(&req, &ctx).await {
+        Ok(key) => key,
+        Err(err) => return err,
+    };
+
+    let Ok(body) = req.json::<BatchDeleteLinksRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.validate().is_err() {
+        return Response::error(INVALID_BATCH_SIZE_RESPONSE, 400);
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let mut response = BatchDeleteLinksResponse::default();
+    for id in body.ids {
+        let id = canonicalize_decoded_id(&id);
+        let mut outcome = Ok(());
+        let lock_response = with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+            outcome = delete_link(&storage, &ctx, tenant.as_deref(), &id, &key)
+                .await
+                .map_err(|err| err.message());
+            Response::empty()
+        })
+        .await?;
+        if lock_response.status_code() == 409 {
+            outcome = Err(LINK_LOCKED_RESPONSE);
+        }
+
+        let error = match outcome {
+            Ok(()) => {
+                response.deleted += 1;
+                None
+            }
+            Err(error) => {
+                response.failed += 1;
+                Some(error)
+            }
+        };
+        response.results.push(BatchDeleteLinkResult {
+            deleted: error.is_none(),
+            id,
+            error,
+        });
+    }
+
+    Response::from_json(&response)
+}
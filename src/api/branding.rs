@@ -0,0 +1,86 @@
+use crate::{
+    authentication::authorized_guard,
+    messages::*,
+    models::branding::BrandingAsset,
+    storage::{get_storage, StorageDriver},
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// Get the storage key of the branding asset from the route parameters of a request.
+fn get_branding_key_from_route(ctx: &RouteContext<Context>) -> worker::Result<Option<String>> {
+    let (Some(host), Some(asset)) = (ctx.param("host"), ctx.param("asset")) else {
+        Err("Unable to find hostname or branding asset from request URL.")?
+    };
+    Ok(BrandingAsset::from_name(asset).map(|asset| asset.key(host)))
+}
+
+/// Get the current override of a branding asset for a hostname.
+pub async fn get_branding_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(key) = get_branding_key_from_route(&ctx)? else {
+        return Response::error(UNKNOWN_BRANDING_ASSET_RESPONSE, 404);
+    };
+
+    match storage.get(&key).await {
+        Some(content) => Response::ok(content),
+        None => Response::error(BRANDING_DOESNT_EXIST_RESPONSE, 404),
+    }
+}
+
+/// Override a branding asset for a hostname with the raw request body.
+pub async fn set_branding_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(key) = get_branding_key_from_route(&ctx)? else {
+        return Response::error(UNKNOWN_BRANDING_ASSET_RESPONSE, 404);
+    };
+    let Ok(content) = req.text().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+
+    if !storage.set(&key, &content, None).await {
+        return Response::error(GENERIC_BRANDING_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(BRANDING_UPDATE_SUCCESS_RESPONSE)
+}
+
+/// Remove the override of a branding asset for a hostname, returning it to the default.
+pub async fn delete_branding_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(key) = get_branding_key_from_route(&ctx)? else {
+        return Response::error(UNKNOWN_BRANDING_ASSET_RESPONSE, 404);
+    };
+    if storage.get(&key).await.is_none() {
+        return Response::error(BRANDING_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    if !storage.delete(&key).await {
+        return Response::error(GENERIC_BRANDING_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(BRANDING_DELETE_SUCCESS_RESPONSE)
+}
@@ -0,0 +1,132 @@
+use super::{
+    requests::{BulkLinkUpdate, BulkUpdateLinksRequestBody},
+    responses::{BulkUpdateLinkResult, BulkUpdateLinkStatus, BulkUpdateLinksResponse},
+};
+use crate::{
+    authentication::authorized_guard,
+    durable_objects::link_lock::with_link_lock,
+    get_tombstone_retention,
+    link_cache::invalidate_cached_link,
+    messages::*,
+    models::link::LinkModel,
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use validator::Validate;
+use worker::{Context, Date, Request, Response, RouteContext};
+
+/// Apply a bulk update to a link in-place, returning whether anything changed.
+fn apply_update(link: &mut LinkModel, update: &BulkLinkUpdate) -> bool {
+    let expiry_timestamp = update
+        .expire_in
+        .map(|time| Date::now().as_millis() + time.as_millis() as u64)
+        .or(link.expiry_timestamp);
+    let max_views = update.max_views.or(link.max_views);
+    let disabled = update.disabled.unwrap_or(link.disabled);
+    if expiry_timestamp == link.expiry_timestamp
+        && max_views == link.max_views
+        && disabled == link.disabled
+    {
+        return false;
+    }
+
+    link.expiry_timestamp = expiry_timestamp;
+    link.max_views = max_views;
+    link.disabled = disabled;
+    link.modified_at_timestamp = Date::now().as_millis();
+    true
+}
+
+/// Apply the same partial update (e.g. a new expiry or disabling) to every link matching a filter,
+/// returning a report of what happened to each link.
+///
+/// With `"dry_run": true` the report shows what would change without writing anything.
+pub async fn bulk_update_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let Ok(body) = req.json::<BulkUpdateLinksRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    let update = &body.update;
+    if body.validate().is_err()
+        || (update.expire_in.is_none() && update.max_views.is_none() && update.disabled.is_none())
+    {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+
+    // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+    let ids = storage
+        .list(&body.filter.prefix)
+        .await
+        .into_iter()
+        .filter(|key| !key.contains(':'))
+        .collect::<Vec<_>>();
+
+    let mut response = BulkUpdateLinksResponse {
+        dry_run: body.dry_run,
+        matched: ids.len(),
+        updated: 0,
+        failed: 0,
+        results: Vec::with_capacity(ids.len()),
+    };
+    for id in ids {
+        let mut outcome = Err(LINK_DOESNT_EXIST_RESPONSE);
+        if body.dry_run {
+            if let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await {
+                outcome = Ok(apply_update(&mut link, update));
+            }
+        } else {
+            let lock_response = with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+                let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+                    return Response::empty();
+                };
+                outcome = if !apply_update(&mut link, update) {
+                    Ok(false)
+                } else if storage
+                    .set_serialized_json(
+                        &id,
+                        &link,
+                        link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+                    )
+                    .await
+                {
+                    invalidate_cached_link(&ctx.env, tenant.as_deref(), &id).await;
+                    Ok(true)
+                } else {
+                    Err(GENERIC_LINK_UPDATE_ERROR_RESPONSE)
+                };
+                Response::empty()
+            })
+            .await?;
+            if lock_response.status_code() == 409 {
+                outcome = Err(LINK_LOCKED_RESPONSE);
+            }
+        }
+
+        let (status, error) = match outcome {
+            Ok(true) => {
+                response.updated += 1;
+                (BulkUpdateLinkStatus::Updated, None)
+            }
+            Ok(false) => (BulkUpdateLinkStatus::Unchanged, None),
+            Err(error) => {
+                response.failed += 1;
+                (BulkUpdateLinkStatus::Failed, Some(error))
+            }
+        };
+        response
+            .results
+            .push(BulkUpdateLinkResult { id, status, error });
+    }
+
+    Response::from_json(&response)
+}
@@ -0,0 +1,78 @@
+use super::{
+    compare::MAX_COMPARE_LINKS,
+    grafana::MAX_GRAFANA_TARGETS,
+    links::MAX_LIST_LINKS_LIMIT,
+    requests::{MAX_BATCH_LINKS, MAX_SYNC_LINKS, MAX_WARM_LINK_IDS},
+    responses::{CapabilitiesFeatures, CapabilitiesLimits, CapabilitiesResponse},
+};
+use crate::{
+    analytics::{get_sample_rate, PrivacyConfig},
+    attestations::attestations_enabled,
+    durable_objects::{
+        link_lock::LINK_LOCK_BINDING, rate_limiter::RATE_LIMITER_BINDING,
+        view_counter::VIEW_COUNTER_BINDING,
+    },
+    environment::get_environment,
+    health::HealthCheckConfig,
+    jobs::jobs_queue_bound,
+    link_cache::get_link_cache_ttl,
+    metrics::metrics_export_enabled,
+    notifications::notifications_enabled,
+    screenshots::screenshots_enabled,
+    storage::{get_storage_backend, StorageBackend},
+    tenants::multi_tenant_enabled,
+    visit_buffer::VisitBufferConfig,
+};
+use worker::{Context, Env, Request, Response, RouteContext};
+
+/// Get which optional features are enabled with the current environment.
+pub async fn enabled_features(env: &Env) -> CapabilitiesFeatures {
+    CapabilitiesFeatures {
+        multi_tenant: multi_tenant_enabled(env),
+        screenshots: screenshots_enabled(env),
+        health_checks: HealthCheckConfig::from_env(env).is_some(),
+        visit_stats: PrivacyConfig::from_env(env).visit_stats,
+        visit_buffering: VisitBufferConfig::from_env(env).is_some(),
+        background_jobs: jobs_queue_bound(env),
+        notifications: notifications_enabled(env).await,
+        link_locking: env.durable_object(LINK_LOCK_BINDING).is_ok(),
+        rate_limiting: env.durable_object(RATE_LIMITER_BINDING).is_ok(),
+        view_counting: env.durable_object(VIEW_COUNTER_BINDING).is_ok(),
+        attestations: attestations_enabled(env),
+        d1_storage: get_storage_backend(env) == StorageBackend::D1,
+        link_cache: get_link_cache_ttl(env).is_some(),
+        metrics_export: metrics_export_enabled(env),
+    }
+}
+
+/// Get the limits applied to requests with the current environment.
+pub fn current_limits(env: &Env) -> CapabilitiesLimits {
+    CapabilitiesLimits {
+        max_warm_link_ids: MAX_WARM_LINK_IDS,
+        max_list_links_limit: MAX_LIST_LINKS_LIMIT,
+        max_batch_links: MAX_BATCH_LINKS,
+        max_compare_links: MAX_COMPARE_LINKS,
+        max_sync_links: MAX_SYNC_LINKS,
+        max_grafana_targets: MAX_GRAFANA_TARGETS,
+        analytics_sample_rate: get_sample_rate(env),
+    }
+}
+
+/// Describe what this deployment supports, so client tools can discover its features and limits
+/// before calling the API.
+///
+/// This is public as it doesn't reveal any link data or configuration values.
+pub async fn capabilities_handler(
+    _req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    Response::from_json(&CapabilitiesResponse {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        environment: get_environment(&ctx.env),
+        features: enabled_features(&ctx.env).await,
+        limits: current_limits(&ctx.env),
+        // Creating links always requires an API token.
+        public_create: false,
+    })
+}
@@ -0,0 +1,93 @@
+use super::responses::{CompareLinksResponse, LinkComparison};
+use crate::{
+    analytics::{estimate_views, get_sample_rate, get_stats, PrivacyConfig, StatsRange},
+    authentication::authorized_guard,
+    durable_objects::view_counter::pending_counted_views,
+    messages::{INVALID_COMPARE_IDS_RESPONSE, INVALID_STATS_RANGE_RESPONSE},
+    models::{link::LinkModel, stats::StatsModel},
+    slugs::canonicalize_decoded_id,
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use std::collections::BTreeMap;
+use worker::{Context, Request, Response, RouteContext};
+
+/// The query parameter for the comma-separated IDs of the links to compare.
+const IDS_QUERY_PARAM: &str = "ids";
+
+/// The most links that can be compared in a single request.
+pub const MAX_COMPARE_LINKS: u64 = 20;
+
+/// Compare the views, unique visitors, conversions and countries of several links side by side, optionally
+/// restricted to the range of time given by the `from` and `to` query parameters.
+///
+/// Links that don't exist are listed as missing instead of failing the whole comparison.
+pub async fn compare_links_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let url = req.url()?;
+    let mut ids = Vec::<String>::new();
+    for (_, value) in url.query_pairs().filter(|(key, _)| key == IDS_QUERY_PARAM) {
+        for id in value.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let id = canonicalize_decoded_id(id);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    if ids.is_empty() || ids.len() as u64 > MAX_COMPARE_LINKS {
+        return Response::error(INVALID_COMPARE_IDS_RESPONSE, 400);
+    }
+    let Some(range) = StatsRange::from_url(&url) else {
+        return Response::error(INVALID_STATS_RANGE_RESPONSE, 400);
+    };
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let visit_stats = PrivacyConfig::from_env(&ctx.env).visit_stats;
+    let sample_rate = get_sample_rate(&ctx.env);
+
+    let mut response = CompareLinksResponse {
+        from_timestamp: range.from,
+        to_timestamp: range.to,
+        sample_rate,
+        links: Vec::with_capacity(ids.len()),
+        missing: Vec::new(),
+    };
+    for id in ids {
+        let Some(link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+            response.missing.push(id);
+            continue;
+        };
+
+        // Days and months are keyed differently, so both can be kept in the same map.
+        let mut periods = BTreeMap::new();
+        if visit_stats {
+            for prefix in [
+                StatsModel::day_key_prefix(&id),
+                StatsModel::month_key_prefix(&id),
+            ] {
+                periods.extend(range.filter(get_stats(&storage, &prefix).await));
+            }
+        }
+
+        // The link's own view count is exact but can't be split up by time, so the views within a range are
+        // estimated from the sampled visits in its stats instead.
+        let views = if range.is_bounded() {
+            estimate_views(periods.values(), sample_rate)
+        } else {
+            link.views + pending_counted_views(&ctx.env, tenant.as_deref(), &id).await
+        };
+        response
+            .links
+            .push(LinkComparison::from_stats(id, views, periods.values()));
+    }
+
+    Response::from_json(&response)
+}
@@ -0,0 +1,311 @@
+use super::{
+    capabilities::{current_limits, enabled_features},
+    responses::{ConfigCheckResponse, ConfigIssue, ConfigIssueSeverity},
+};
+use crate::{
+    analytics::{
+        ANALYTICS_SAMPLE_RATE_BINDING, EXCLUDE_CIDRS_BINDING, EXCLUDE_IPS_BINDING,
+        RECORD_COUNTRIES_BINDING, RECORD_LAST_VIEWED_BINDING, RECORD_NETWORKS_BINDING,
+        RECORD_UNIQUE_VISITORS_BINDING, RECORD_USER_AGENTS_BINDING, RECORD_VISIT_STATS_BINDING,
+    },
+    anomalies::{
+        AnomalyDetectionConfig, ANOMALY_BOT_SHARE_BINDING, ANOMALY_DETECTION_BINDING,
+        ANOMALY_MIN_VIEWS_BINDING, ANOMALY_SPIKE_FACTOR_BINDING,
+    },
+    attestations::ATTESTATION_SIGNING_KEY_BINDING,
+    authentication::{authorized_guard, ADDITIONAL_AUTH_TOKENS_BINDING, AUTH_TOKEN_BINDING},
+    durable_objects::{
+        link_lock::LINK_LOCK_BINDING, rate_limiter::RATE_LIMITER_BINDING,
+        view_counter::VIEW_COUNTER_BINDING,
+    },
+    environment::{get_bool_var, get_var, ENVIRONMENT_BINDING},
+    health::{
+        HealthCheckConfig, AUTO_PAUSE_UNHEALTHY_LINKS_BINDING, HEALTH_CHECKS_BINDING,
+        HEALTH_CHECK_FAILURE_THRESHOLD_BINDING,
+    },
+    ip_ranges::IpRange,
+    jobs::JOBS_QUEUE_BINDING,
+    link_cache::LINK_CACHE_TTL_BINDING,
+    links::{NORMALIZE_IDS_BINDING, NORMALIZE_URLS_BINDING, STRIP_TRACKING_PARAMS_BINDING},
+    metrics::{PROMETHEUS_REMOTE_WRITE_TOKEN_BINDING, PROMETHEUS_REMOTE_WRITE_URL_BINDING},
+    networks::{DATACENTER_ASNS_BINDING, EXCLUDE_DATACENTER_VISITS_BINDING},
+    notifications::{NOTIFY_MAX_ATTEMPTS_BINDING, NOTIFY_WEBHOOK_URL_BINDING},
+    responses::PRECONNECT_HINTS_BINDING,
+    screenshots::{
+        BROWSER_RENDERING_ACCOUNT_ID_BINDING, BROWSER_RENDERING_API_TOKEN_BINDING,
+        SCREENSHOTS_BUCKET_BINDING,
+    },
+    slugs::{RANDOM_ID_ALPHABET_BINDING, RANDOM_ID_LENGTH_BINDING},
+    storage::{
+        cloudflare_d1_driver::CLOUDFLARE_D1_BINDING,
+        cloudflare_kv_driver::{
+            CLOUDFLARE_KV_ANALYTICS_BINDING, CLOUDFLARE_KV_BINDING, CLOUDFLARE_KV_SHADOW_BINDING,
+        },
+        get_storage_backend,
+        shadow_driver::SHADOW_READS_BINDING,
+        StorageBackend, STORAGE_DRIVER_BINDING,
+    },
+    tenants::MULTI_TENANT_BINDING,
+    usage::{STORAGE_ALERT_BYTES_BINDING, STORAGE_ALERT_KEYS_BINDING},
+    visit_buffer::{
+        VisitBufferConfig, VISIT_FLUSH_INTERVAL_BINDING, VISIT_FLUSH_THRESHOLD_BINDING,
+    },
+    warnings::WARN_DOMAINS_BINDING,
+    DELETION_CONFIRMATION_WINDOW_BINDING, STATS_ROLLUP_AFTER_DAYS_BINDING,
+    TOMBSTONE_RETENTION_BINDING,
+};
+use std::{collections::BTreeMap, net::IpAddr};
+use url::Url;
+use worker::{Context, Env, Request, Response, RouteContext};
+
+/// Auth tokens shorter than this are reported as being easy to guess.
+const MIN_RECOMMENDED_AUTH_TOKEN_LENGTH: usize = 32;
+
+/// The kinds of value a variable can hold.
+#[derive(Debug, Clone, Copy)]
+enum VariableKind {
+    Text,
+    Bool,
+    Number,
+    Fraction,
+    Duration,
+    Url,
+    IpList,
+    StorageBackend,
+}
+
+impl VariableKind {
+    /// Whether or not the given value is valid for this kind of variable.
+    fn is_valid(self, value: &str) -> bool {
+        match self {
+            Self::Text => true,
+            Self::Bool => value.parse::<bool>().is_ok(),
+            Self::Number => value.parse::<u64>().is_ok(),
+            Self::Fraction => value
+                .parse::<f64>()
+                .is_ok_and(|fraction| (0.0..=1.0).contains(&fraction)),
+            Self::Duration => humantime::parse_duration(value).is_ok(),
+            Self::Url => Url::parse(value).is_ok(),
+            Self::IpList => value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .all(|entry| entry.parse::<IpAddr>().is_ok() || entry.parse::<IpRange>().is_ok()),
+            Self::StorageBackend => value.parse::<StorageBackend>().is_ok(),
+        }
+    }
+}
+
+/// Every non-secret variable the worker reads and the kind of value it holds.
+const VARIABLES: &[(&str, VariableKind)] = &[
+    (ENVIRONMENT_BINDING, VariableKind::Text),
+    (STORAGE_DRIVER_BINDING, VariableKind::StorageBackend),
+    (NORMALIZE_IDS_BINDING, VariableKind::Bool),
+    (NORMALIZE_URLS_BINDING, VariableKind::Bool),
+    (STRIP_TRACKING_PARAMS_BINDING, VariableKind::Bool),
+    (RANDOM_ID_ALPHABET_BINDING, VariableKind::Text),
+    (RANDOM_ID_LENGTH_BINDING, VariableKind::Number),
+    (VISIT_FLUSH_INTERVAL_BINDING, VariableKind::Duration),
+    (VISIT_FLUSH_THRESHOLD_BINDING, VariableKind::Number),
+    (LINK_CACHE_TTL_BINDING, VariableKind::Duration),
+    (STATS_ROLLUP_AFTER_DAYS_BINDING, VariableKind::Number),
+    (TOMBSTONE_RETENTION_BINDING, VariableKind::Duration),
+    (DELETION_CONFIRMATION_WINDOW_BINDING, VariableKind::Duration),
+    (RECORD_LAST_VIEWED_BINDING, VariableKind::Bool),
+    (RECORD_VISIT_STATS_BINDING, VariableKind::Bool),
+    (RECORD_COUNTRIES_BINDING, VariableKind::Bool),
+    (RECORD_USER_AGENTS_BINDING, VariableKind::Bool),
+    (RECORD_NETWORKS_BINDING, VariableKind::Bool),
+    (RECORD_UNIQUE_VISITORS_BINDING, VariableKind::Bool),
+    (ANALYTICS_SAMPLE_RATE_BINDING, VariableKind::Fraction),
+    (EXCLUDE_IPS_BINDING, VariableKind::IpList),
+    (EXCLUDE_CIDRS_BINDING, VariableKind::IpList),
+    (EXCLUDE_DATACENTER_VISITS_BINDING, VariableKind::Bool),
+    (DATACENTER_ASNS_BINDING, VariableKind::Text),
+    (WARN_DOMAINS_BINDING, VariableKind::Text),
+    (MULTI_TENANT_BINDING, VariableKind::Bool),
+    (HEALTH_CHECKS_BINDING, VariableKind::Bool),
+    (HEALTH_CHECK_FAILURE_THRESHOLD_BINDING, VariableKind::Number),
+    (AUTO_PAUSE_UNHEALTHY_LINKS_BINDING, VariableKind::Bool),
+    (ANOMALY_DETECTION_BINDING, VariableKind::Bool),
+    (ANOMALY_MIN_VIEWS_BINDING, VariableKind::Number),
+    (ANOMALY_SPIKE_FACTOR_BINDING, VariableKind::Number),
+    (ANOMALY_BOT_SHARE_BINDING, VariableKind::Fraction),
+    (NOTIFY_WEBHOOK_URL_BINDING, VariableKind::Url),
+    (NOTIFY_MAX_ATTEMPTS_BINDING, VariableKind::Number),
+    (PROMETHEUS_REMOTE_WRITE_URL_BINDING, VariableKind::Url),
+    (STORAGE_ALERT_KEYS_BINDING, VariableKind::Number),
+    (STORAGE_ALERT_BYTES_BINDING, VariableKind::Number),
+    (SHADOW_READS_BINDING, VariableKind::Bool),
+    (PRECONNECT_HINTS_BINDING, VariableKind::Bool),
+    (BROWSER_RENDERING_ACCOUNT_ID_BINDING, VariableKind::Text),
+];
+
+/// Every secret the worker reads, whose values are never reported.
+const SECRETS: &[&str] = &[
+    AUTH_TOKEN_BINDING,
+    ADDITIONAL_AUTH_TOKENS_BINDING,
+    BROWSER_RENDERING_API_TOKEN_BINDING,
+    ATTESTATION_SIGNING_KEY_BINDING,
+    PROMETHEUS_REMOTE_WRITE_TOKEN_BINDING,
+];
+
+/// Check the environment for misconfigurations, such as invalid variable values or features that
+/// are only partially set up.
+fn find_config_issues(env: &Env) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |severity, message: String| issues.push(ConfigIssue { severity, message });
+
+    match get_var(env, AUTH_TOKEN_BINDING) {
+        None => issue(
+            ConfigIssueSeverity::Error,
+            format!("{AUTH_TOKEN_BINDING} is not set, so links can't be created or managed."),
+        ),
+        Some(token) if token.len() < MIN_RECOMMENDED_AUTH_TOKEN_LENGTH => issue(
+            ConfigIssueSeverity::Warning,
+            format!(
+                "{AUTH_TOKEN_BINDING} is shorter than {MIN_RECOMMENDED_AUTH_TOKEN_LENGTH} characters and may be easy to guess."
+            ),
+        ),
+        Some(_) => {}
+    }
+    match get_storage_backend(env) {
+        StorageBackend::KV if env.kv(CLOUDFLARE_KV_BINDING).is_err() => issue(
+            ConfigIssueSeverity::Error,
+            format!("The '{CLOUDFLARE_KV_BINDING}' KV namespace is not bound, so links can't be stored."),
+        ),
+        StorageBackend::D1 if env.d1(CLOUDFLARE_D1_BINDING).is_err() => issue(
+            ConfigIssueSeverity::Error,
+            format!(
+                "{STORAGE_DRIVER_BINDING} is 'd1' but the '{CLOUDFLARE_D1_BINDING}' D1 database is not bound, so links can't be stored."
+            ),
+        ),
+        _ => {}
+    }
+
+    for (name, kind) in VARIABLES {
+        if let Some(value) = get_var(env, name) {
+            if !kind.is_valid(&value) {
+                issue(
+                    ConfigIssueSeverity::Warning,
+                    format!("{name} has an invalid value and is being ignored."),
+                );
+            }
+        }
+    }
+
+    let screenshot_parts = [
+        env.bucket(SCREENSHOTS_BUCKET_BINDING).is_ok(),
+        get_var(env, BROWSER_RENDERING_ACCOUNT_ID_BINDING).is_some(),
+        get_var(env, BROWSER_RENDERING_API_TOKEN_BINDING).is_some(),
+    ];
+    if screenshot_parts.contains(&true) && screenshot_parts.contains(&false) {
+        issue(
+            ConfigIssueSeverity::Warning,
+            format!("Screenshots are only partially configured, they need the {SCREENSHOTS_BUCKET_BINDING} bucket, {BROWSER_RENDERING_ACCOUNT_ID_BINDING} and {BROWSER_RENDERING_API_TOKEN_BINDING}."),
+        );
+    }
+    if get_var(env, PROMETHEUS_REMOTE_WRITE_TOKEN_BINDING).is_some()
+        && get_var(env, PROMETHEUS_REMOTE_WRITE_URL_BINDING).is_none()
+    {
+        issue(
+            ConfigIssueSeverity::Warning,
+            format!("{PROMETHEUS_REMOTE_WRITE_TOKEN_BINDING} has no effect unless {PROMETHEUS_REMOTE_WRITE_URL_BINDING} is set."),
+        );
+    }
+    if get_bool_var(env, AUTO_PAUSE_UNHEALTHY_LINKS_BINDING).unwrap_or(false)
+        && HealthCheckConfig::from_env(env).is_none()
+    {
+        issue(
+            ConfigIssueSeverity::Warning,
+            format!("{AUTO_PAUSE_UNHEALTHY_LINKS_BINDING} has no effect unless {HEALTH_CHECKS_BINDING} is enabled."),
+        );
+    }
+    if AnomalyDetectionConfig::from_env(env).is_some()
+        && !get_bool_var(env, RECORD_VISIT_STATS_BINDING).unwrap_or(true)
+    {
+        issue(
+            ConfigIssueSeverity::Warning,
+            format!("{ANOMALY_DETECTION_BINDING} has no effect unless {RECORD_VISIT_STATS_BINDING} is enabled, as it analyzes visit stats."),
+        );
+    }
+    if get_var(env, VISIT_FLUSH_THRESHOLD_BINDING).is_some()
+        && VisitBufferConfig::from_env(env).is_none()
+    {
+        issue(
+            ConfigIssueSeverity::Warning,
+            format!("{VISIT_FLUSH_THRESHOLD_BINDING} has no effect unless {VISIT_FLUSH_INTERVAL_BINDING} is set."),
+        );
+    }
+
+    issues
+}
+
+/// Report the resolved configuration of this deployment: which bindings are present, which optional
+/// features are active, the limits in use and any misconfigurations found.
+///
+/// Nothing can be changed through the API until an auth token is set, so this check is public until
+/// then to help with first-time setup.
+pub async fn config_check_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    if get_var(&ctx.env, AUTH_TOKEN_BINDING).is_some() {
+        let auth_guard = authorized_guard(&req, &ctx).await;
+        if let Err(err) = auth_guard {
+            return err;
+        }
+    }
+
+    let env = &ctx.env;
+    let bindings = BTreeMap::from([
+        (CLOUDFLARE_KV_BINDING, env.kv(CLOUDFLARE_KV_BINDING).is_ok()),
+        (CLOUDFLARE_D1_BINDING, env.d1(CLOUDFLARE_D1_BINDING).is_ok()),
+        (
+            CLOUDFLARE_KV_ANALYTICS_BINDING,
+            env.kv(CLOUDFLARE_KV_ANALYTICS_BINDING).is_ok(),
+        ),
+        (
+            CLOUDFLARE_KV_SHADOW_BINDING,
+            env.kv(CLOUDFLARE_KV_SHADOW_BINDING).is_ok(),
+        ),
+        (
+            SCREENSHOTS_BUCKET_BINDING,
+            env.bucket(SCREENSHOTS_BUCKET_BINDING).is_ok(),
+        ),
+        (JOBS_QUEUE_BINDING, env.queue(JOBS_QUEUE_BINDING).is_ok()),
+        (
+            LINK_LOCK_BINDING,
+            env.durable_object(LINK_LOCK_BINDING).is_ok(),
+        ),
+        (
+            RATE_LIMITER_BINDING,
+            env.durable_object(RATE_LIMITER_BINDING).is_ok(),
+        ),
+        (
+            VIEW_COUNTER_BINDING,
+            env.durable_object(VIEW_COUNTER_BINDING).is_ok(),
+        ),
+    ]);
+    let variables = VARIABLES
+        .iter()
+        .map(|(name, _)| (*name, get_var(env, name)))
+        .collect();
+    let secrets = SECRETS
+        .iter()
+        .map(|name| (*name, get_var(env, name).is_some()))
+        .collect();
+    let issues = find_config_issues(env);
+
+    Response::from_json(&ConfigCheckResponse {
+        ok: !issues
+            .iter()
+            .any(|issue| issue.severity == ConfigIssueSeverity::Error),
+        bindings,
+        variables,
+        secrets,
+        features: enabled_features(env).await,
+        limits: current_limits(env),
+        issues,
+    })
+}
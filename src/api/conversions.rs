@@ -0,0 +1,66 @@
+use super::{requests::RecordConversionRequestBody, responses::RecordConversionResponse};
+use crate::{
+    analytics::PrivacyConfig,
+    authentication::authorized_guard,
+    conversions::{record_conversion, ConversionOutcome, VisitId},
+    messages::*,
+    notifications::{notify_in_background, Notification},
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use validator::Validate;
+use worker::{Context, Date, Request, Response, RouteContext};
+
+/// Record a conversion for a visit, using the visit ID that was appended to the destination when the visitor was redirected.
+///
+/// The conversion is attributed to the link and variant the visitor came through.
+pub async fn record_conversion_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    if !PrivacyConfig::from_env(&ctx.env).visit_stats {
+        return Response::error(CONVERSIONS_DISABLED_RESPONSE, 501);
+    }
+    let Ok(body) = req.json::<RecordConversionRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.validate().is_err() {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    }
+    let Some(visit_id) = VisitId::parse(&body.visit_id) else {
+        return Response::error(INVALID_VISIT_ID_RESPONSE, 400);
+    };
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    if storage.get(&visit_id.id).await.is_none() {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    match record_conversion(&storage, &visit_id, body.value, Date::now().as_millis()).await {
+        ConversionOutcome::Recorded => {
+            notify_in_background(
+                &ctx.data,
+                &ctx.env,
+                Notification::ConversionReported {
+                    id: visit_id.id.clone(),
+                    tenant,
+                    variant: visit_id.variant,
+                    value: body.value,
+                },
+            );
+            Response::from_json(&RecordConversionResponse {
+                id: visit_id.id,
+                variant: visit_id.variant,
+                value: body.value,
+            })
+        }
+        ConversionOutcome::Duplicate => Response::error(CONVERSION_ALREADY_RECORDED_RESPONSE, 409),
+        ConversionOutcome::Failed => Response::error(GENERIC_CONVERSION_RECORD_ERROR_RESPONSE, 500),
+    }
+}
@@ -0,0 +1,98 @@
+use super::{requests::SetFeatureFlagRequestBody, responses::FeatureFlagResponse};
+use crate::{
+    authentication::authorized_guard,
+    flags::{get_feature_flags, FeatureFlag},
+    messages::*,
+    models::flags::{FeatureFlagRollout, FeatureFlagsModel},
+    storage::{get_storage, StorageDriver},
+};
+use validator::Validate;
+use worker::{Context, Request, Response, RouteContext};
+
+/// Get the feature flag from the route parameters of a request.
+fn get_flag_from_route(ctx: &RouteContext<Context>) -> worker::Result<Option<FeatureFlag>> {
+    let Some(flag) = ctx.param("flag") else {
+        Err("Unable to find feature flag from request URL.")?
+    };
+    Ok(FeatureFlag::from_name(flag))
+}
+
+/// List every feature flag alongside its current rollout.
+pub async fn list_feature_flags_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let flags = get_feature_flags(&ctx.env).await;
+    Response::from_json(
+        &FeatureFlag::ALL
+            .iter()
+            .map(|flag| FeatureFlagResponse::from_flags(&flags, *flag))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Change the percentage of requests a feature flag is enabled for.
+pub async fn set_feature_flag_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(flag) = get_flag_from_route(&ctx)? else {
+        return Response::error(FEATURE_FLAG_DOESNT_EXIST_RESPONSE, 404);
+    };
+    let Ok(body) = req.json::<SetFeatureFlagRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.validate().is_err() {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    }
+
+    let mut flags = get_feature_flags(&ctx.env).await;
+    flags
+        .flags
+        .insert(flag, FeatureFlagRollout::new(body.percentage));
+    if !storage
+        .set_serialized_json(FeatureFlagsModel::key(), &flags, None)
+        .await
+    {
+        return Response::error(GENERIC_FEATURE_FLAG_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::from_json(&FeatureFlagResponse::from_flags(&flags, flag))
+}
+
+/// Reset a feature flag back to its default rollout.
+pub async fn reset_feature_flag_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(flag) = get_flag_from_route(&ctx)? else {
+        return Response::error(FEATURE_FLAG_DOESNT_EXIST_RESPONSE, 404);
+    };
+    let mut flags = get_feature_flags(&ctx.env).await;
+    if flags.flags.remove(&flag).is_some()
+        && !storage
+            .set_serialized_json(FeatureFlagsModel::key(), &flags, None)
+            .await
+    {
+        return Response::error(GENERIC_FEATURE_FLAG_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(FEATURE_FLAG_RESET_SUCCESS_RESPONSE)
+}
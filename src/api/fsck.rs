@@ -0,0 +1,146 @@
+use super::{
+    requests::FsckRequestBody,
+    responses::{FsckFinding, FsckProblem, FsckResponse},
+};
+use crate::{
+    analytics::{stats_key_link_id, STATS_KEY_PREFIX},
+    authentication::authorized_guard,
+    get_tombstone_retention,
+    messages::*,
+    models::{link::LinkModel, tombstone::TombstoneModel},
+    slugs::is_valid_id,
+    storage::StorageDriver,
+    tenants::get_request_storage,
+};
+use std::collections::HashSet;
+use worker::{Context, Request, Response, RouteContext};
+
+/// The prefix of the keys records are moved under when they're quarantined.
+const QUARANTINE_KEY_PREFIX: &str = "quarantine:";
+
+/// Move a record under the quarantine prefix so it's kept for inspection without being served.
+async fn quarantine(storage: &impl StorageDriver, key: &str, record: &str) -> bool {
+    storage
+        .set(&format!("{QUARANTINE_KEY_PREFIX}{key}"), record, None)
+        .await
+        && storage.delete(key).await
+}
+
+/// Get whether a problem was repaired and the error to report if it wasn't.
+fn repair_outcome(success: bool) -> (bool, Option<&'static str>) {
+    if success {
+        (true, None)
+    } else {
+        (false, Some(GENERIC_FSCK_REPAIR_ERROR_RESPONSE))
+    }
+}
+
+/// Scan stored records for problems that would otherwise go unnoticed, such as corrupt links that
+/// just look like they're missing, and report them.
+///
+/// With `"repair": true` problems are also repaired where possible: unreadable or invalid links are moved
+/// under the `quarantine:` prefix, merged links with a missing target are turned back into regular links
+/// and orphaned stats are deleted.
+pub async fn fsck_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let body = match req.text().await {
+        Ok(text) if text.trim().is_empty() => FsckRequestBody::default(),
+        Ok(text) => match serde_json::from_str::<FsckRequestBody>(&text) {
+            Ok(body) => body,
+            Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+        },
+        Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+    };
+
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+    let ids = storage
+        .list("")
+        .await
+        .into_iter()
+        .filter(|key| !key.contains(':'))
+        .collect::<HashSet<_>>();
+
+    let mut findings = Vec::new();
+    let mut scanned = 0;
+    for id in &ids {
+        scanned += 1;
+        let Some(record) = storage.get(id).await else {
+            continue;
+        };
+        let link = serde_json::from_str::<LinkModel>(&record);
+
+        let problem = match &link {
+            Err(_) => FsckProblem::CorruptRecord,
+            Ok(_) if !is_valid_id(id) => FsckProblem::InvalidId,
+            Ok(link)
+                if link
+                    .alias_of
+                    .as_ref()
+                    .is_some_and(|target| !ids.contains(target)) =>
+            {
+                FsckProblem::DanglingAlias
+            }
+            Ok(_) => continue,
+        };
+
+        let (repaired, error) = match (body.repair, problem, link) {
+            (false, ..) => (false, None),
+            (true, FsckProblem::DanglingAlias, Ok(mut link)) => {
+                link.alias_of = None;
+                repair_outcome(
+                    storage
+                        .set_serialized_json(
+                            id,
+                            &link,
+                            link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+                        )
+                        .await,
+                )
+            }
+            (true, ..) => repair_outcome(quarantine(&storage, id, &record).await),
+        };
+        findings.push(FsckFinding {
+            key: id.clone(),
+            problem,
+            repaired,
+            error,
+        });
+    }
+
+    for key in storage.list(STATS_KEY_PREFIX).await {
+        scanned += 1;
+        let Some(id) = stats_key_link_id(&key) else {
+            continue;
+        };
+        // Stats of removed links are still shown alongside their tombstone until it's gone.
+        let retained = storage
+            .get_deserialized_json::<TombstoneModel>(&TombstoneModel::key(id))
+            .await
+            .is_some_and(|tombstone| tombstone.is_retained());
+        if ids.contains(id) || retained {
+            continue;
+        }
+
+        let (repaired, error) = if body.repair {
+            repair_outcome(storage.delete(&key).await)
+        } else {
+            (false, None)
+        };
+        findings.push(FsckFinding {
+            key,
+            problem: FsckProblem::OrphanedStats,
+            repaired,
+            error,
+        });
+    }
+
+    Response::from_json(&FsckResponse { scanned, findings })
+}
@@ -0,0 +1,141 @@
+use super::{
+    requests::{GrafanaQueryRequestBody, GrafanaSearchRequestBody},
+    responses::GrafanaTimeSeries,
+};
+use crate::{
+    analytics::{
+        estimate_views, get_sample_rate, get_stats, period_start_timestamp, PrivacyConfig,
+        StatsRange,
+    },
+    authentication::authorized_guard,
+    messages::INVALID_GRAFANA_QUERY_RESPONSE,
+    models::{link::LinkModel, stats::StatsModel},
+    slugs::canonicalize_decoded_id,
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use chrono::DateTime;
+use worker::{Context, Request, Response, RouteContext};
+
+/// The most targets that can be queried in a single Grafana query.
+pub const MAX_GRAFANA_TARGETS: u64 = 20;
+
+/// Answer the connection test Grafana makes when a simple JSON datasource is saved, which only checks the API key.
+pub async fn grafana_test_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+    Response::empty()
+}
+
+/// List the IDs of the links containing the searched text, which are the metrics Grafana's simple JSON datasource
+/// can query.
+pub async fn grafana_search_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    // Grafana sends an empty body when listing every metric.
+    let body = req
+        .json::<GrafanaSearchRequestBody>()
+        .await
+        .unwrap_or_default();
+    let search = body.target.trim().to_lowercase();
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+    let ids = storage
+        .list("")
+        .await
+        .into_iter()
+        .filter(|id| !id.contains(':') && id.to_lowercase().contains(&search))
+        .collect::<Vec<_>>();
+
+    Response::from_json(&ids)
+}
+
+/// Get the daily views of the queried links as time series for Grafana's simple JSON datasource, so dashboards can be
+/// built directly against the worker.
+///
+/// Each point is the estimated views of a day, or of a month once its days have been rolled up, at the time the
+/// period starts. Links that don't exist are left out.
+pub async fn grafana_query_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let Ok(body) = req.json::<GrafanaQueryRequestBody>().await else {
+        return Response::error(INVALID_GRAFANA_QUERY_RESPONSE, 400);
+    };
+    let parse_timestamp = |timestamp: &str| {
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|timestamp| timestamp.timestamp_millis().max(0) as u64)
+    };
+    let range = match (
+        parse_timestamp(&body.range.from),
+        parse_timestamp(&body.range.to),
+    ) {
+        (Some(from), Some(to)) if from <= to => StatsRange {
+            from: Some(from),
+            to: Some(to),
+        },
+        _ => return Response::error(INVALID_GRAFANA_QUERY_RESPONSE, 400),
+    };
+    if body.targets.len() as u64 > MAX_GRAFANA_TARGETS {
+        return Response::error(INVALID_GRAFANA_QUERY_RESPONSE, 400);
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let visit_stats = PrivacyConfig::from_env(&ctx.env).visit_stats;
+    let sample_rate = get_sample_rate(&ctx.env);
+
+    let mut series = Vec::with_capacity(body.targets.len());
+    for target in body.targets.into_iter().filter(|target| !target.hide) {
+        let id = canonicalize_decoded_id(target.target.trim());
+        if storage
+            .get_deserialized_json::<LinkModel>(&id)
+            .await
+            .is_none()
+        {
+            continue;
+        }
+
+        // Only the view count is available when visit stats aren't stored, which can't be split up by time.
+        let mut datapoints = Vec::new();
+        if visit_stats {
+            for prefix in [
+                StatsModel::day_key_prefix(&id),
+                StatsModel::month_key_prefix(&id),
+            ] {
+                for (period, stats) in range.filter(get_stats(&storage, &prefix).await) {
+                    if let Some(timestamp) = period_start_timestamp(&period) {
+                        let views = estimate_views([&stats], sample_rate);
+                        datapoints.push((views as f64, timestamp));
+                    }
+                }
+            }
+            datapoints.sort_by_key(|(_, timestamp)| *timestamp);
+        }
+        series.push(GrafanaTimeSeries {
+            target: target.target,
+            datapoints,
+        });
+    }
+
+    Response::from_json(&series)
+}
@@ -0,0 +1,181 @@
+use super::{requests::CreateLinkRequestBody, responses::InboundHookResponse};
+use crate::{
+    authentication::authorized_query_guard,
+    durable_objects::link_lock::with_link_lock,
+    links::{prepare_link_request, save_link},
+    messages::{GENERIC_LINK_CREATE_ERROR_RESPONSE, INVALID_PAYLOAD_RESPONSE},
+    notifications::{notify_in_background, Notification},
+    slugs::{
+        canonicalize_decoded_id, generate_unique_id, get_random_id_alphabet, get_random_id_length,
+    },
+    storage::get_tenant_storage,
+    tenants::resolve_tenant,
+};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use worker::{Context, Request, Response, RouteContext};
+
+/// The names the destination URL is accepted under, as no-code tools and forms all name it differently.
+const URL_FIELDS: &[&str] = &[
+    "url",
+    "long_url",
+    "longurl",
+    "destination",
+    "destination_url",
+];
+
+/// The names the ID of the link is accepted under.
+const ID_FIELDS: &[&str] = &["id", "slug", "alias"];
+
+/// The names the duration until the link expires is accepted under.
+const EXPIRE_IN_FIELDS: &[&str] = &["expire_in", "expires_in"];
+
+/// The names the maximum amount of views is accepted under.
+const MAX_VIEWS_FIELDS: &[&str] = &["max_views"];
+
+/// Normalize the name of a field so `Long URL`, `long-url` and `long_url` are all the same field.
+fn normalize_field_name(name: &str) -> String {
+    name.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+/// Get the first non-empty value of any of the given field names, as forms send fields left blank as empty strings.
+fn get_field<'a>(fields: &'a BTreeMap<String, String>, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .filter_map(|name| fields.get(*name))
+        .map(|value| value.trim())
+        .find(|value| !value.is_empty())
+}
+
+/// Read the fields of a request body, which can be flat JSON or a form.
+///
+/// Nested JSON values are ignored, and multipart forms can only be read for the fields that are accepted.
+async fn read_fields(req: &mut Request) -> Option<BTreeMap<String, String>> {
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if content_type.starts_with("multipart/form-data") {
+        let form = req.form_data().await.ok()?;
+        let fields = [URL_FIELDS, ID_FIELDS, EXPIRE_IN_FIELDS, MAX_VIEWS_FIELDS]
+            .concat()
+            .into_iter()
+            .filter_map(|name| Some((name.to_string(), form.get_field(name)?)))
+            .collect();
+        return Some(fields);
+    }
+
+    let text = req.text().await.ok()?;
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        return Some(
+            url::form_urlencoded::parse(text.as_bytes())
+                .map(|(name, value)| (normalize_field_name(&name), value.into_owned()))
+                .collect(),
+        );
+    }
+
+    let Value::Object(object) = serde_json::from_str::<Value>(&text).ok()? else {
+        return None;
+    };
+    let fields = object
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                _ => return None,
+            };
+            Some((normalize_field_name(&name), value))
+        })
+        .collect();
+    Some(fields)
+}
+
+/// Build the request for creating a link from the fields of an inbound hook.
+fn hook_link_request(fields: &BTreeMap<String, String>) -> Option<CreateLinkRequestBody> {
+    let mut body = Map::new();
+    body.insert("url".to_string(), json!(get_field(fields, URL_FIELDS)?));
+    if let Some(expire_in) = get_field(fields, EXPIRE_IN_FIELDS) {
+        body.insert("expire_in".to_string(), json!(expire_in));
+    }
+    if let Some(max_views) = get_field(fields, MAX_VIEWS_FIELDS) {
+        body.insert(
+            "max_views".to_string(),
+            json!(max_views.parse::<u64>().ok()?),
+        );
+    }
+    serde_json::from_value(Value::Object(body)).ok()
+}
+
+/// Create a link from a loosely formatted request, for no-code tools (e.g. Zapier or Make) and plain HTML forms
+/// that can't easily send the payload links are normally created with.
+///
+/// The body can be flat JSON or a form, the destination can be named `url`, `long_url` or `destination`, and the
+/// API key can be given in the `api_key` query parameter instead of the Authorization header. Existing links are
+/// never overwritten.
+pub async fn inbound_create_link_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_query_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let Some(fields) = read_fields(&mut req).await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    let Some(mut body) = hook_link_request(&fields) else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    let host_url = req.url()?;
+    if let Err(err) = prepare_link_request(&ctx.env, &host_url, &mut body) {
+        return err.to_response();
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = match get_field(&fields, ID_FIELDS) {
+        Some(id) => canonicalize_decoded_id(id),
+        None => match generate_unique_id(
+            &storage,
+            &get_random_id_alphabet(&ctx.env),
+            get_random_id_length(&ctx.env),
+        )
+        .await
+        {
+            Some(id) => id,
+            None => return Response::error(GENERIC_LINK_CREATE_ERROR_RESPONSE, 500),
+        },
+    };
+
+    with_link_lock(&ctx.env, tenant.as_deref(), &id.clone(), async {
+        match save_link(&storage, &ctx.env, id, body, false).await {
+            Ok(saved) => {
+                notify_in_background(
+                    &ctx.data,
+                    &ctx.env,
+                    Notification::LinkCreated {
+                        id: saved.id.clone(),
+                        tenant: tenant.clone(),
+                    },
+                );
+                let mut short_url = host_url.clone();
+                short_url.set_path(&saved.id);
+                short_url.set_query(None);
+                Response::from_json(&InboundHookResponse {
+                    id: saved.id,
+                    short_url: short_url.to_string(),
+                    url: saved.model.url.to_string(),
+                })
+            }
+            Err(err) => err.to_response(),
+        }
+    })
+    .await
+}
@@ -0,0 +1,98 @@
+use super::{requests::SetHostMappingRequestBody, responses::ListHostMappingsResponse};
+use crate::{
+    authentication::authorized_guard,
+    messages::*,
+    models::host::HostMappingModel,
+    storage::{get_storage, StorageDriver},
+    tenants::is_valid_tenant_name,
+};
+use std::collections::BTreeMap;
+use worker::{Context, Request, Response, RouteContext};
+
+/// Get the hostname from the route parameters of a request.
+fn get_host_from_route(ctx: &RouteContext<Context>) -> worker::Result<String> {
+    let Some(host) = ctx.param("host") else {
+        Err("Unable to find hostname from request URL.")?
+    };
+    Ok(host.to_lowercase())
+}
+
+/// List all hostname to tenant mappings.
+pub async fn list_host_mappings_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let mut hosts = BTreeMap::new();
+    for key in storage.list(HostMappingModel::key_prefix()).await {
+        let Some(mapping) = storage
+            .get_deserialized_json::<HostMappingModel>(&key)
+            .await
+        else {
+            continue;
+        };
+        if let Some(host) = key.strip_prefix(HostMappingModel::key_prefix()) {
+            hosts.insert(host.to_string(), mapping.tenant);
+        }
+    }
+
+    Response::from_json(&ListHostMappingsResponse { hosts })
+}
+
+/// Map a hostname to a tenant, replacing any existing mapping.
+pub async fn set_host_mapping_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let host = get_host_from_route(&ctx)?;
+    let Ok(body) = req.json::<SetHostMappingRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if !is_valid_tenant_name(&body.tenant) {
+        return Response::error(INVALID_TENANT_NAME_RESPONSE, 400);
+    }
+
+    let mapping = HostMappingModel::new(body.tenant);
+    if !storage
+        .set_serialized_json(&HostMappingModel::key(&host), &mapping, None)
+        .await
+    {
+        return Response::error(GENERIC_HOST_MAPPING_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::from_json(&mapping)
+}
+
+/// Remove the tenant mapping of a hostname, returning it to the default tenant.
+pub async fn delete_host_mapping_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let key = HostMappingModel::key(&get_host_from_route(&ctx)?);
+    if storage.get(&key).await.is_none() {
+        return Response::error(HOST_MAPPING_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    if !storage.delete(&key).await {
+        return Response::error(GENERIC_HOST_MAPPING_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(HOST_MAPPING_DELETE_SUCCESS_RESPONSE)
+}
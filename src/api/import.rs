@@ -0,0 +1,354 @@
+use super::{
+    requests::ImportLinkRecord,
+    responses::{
+        ImportRecordResponse, ImportRecordStatus, ImportSummary, ImportSummaryResponse,
+        QueuedJobResponse,
+    },
+};
+use crate::{
+    authentication::authorized_guard,
+    jobs::{new_job_id, start_job, store_job_step},
+    links::{prepare_link_request, save_link, SaveLinkError},
+    messages::*,
+    models::job::{JobFailure, JobKind, JobModel, JobStepResult},
+    slugs::canonicalize_decoded_id,
+    storage::{get_storage, Storage, StorageDriver},
+    tenants::{get_request_storage, resolve_tenant},
+};
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use url::Url;
+use worker::{ByteStream, Context, Env, Request, Response, RouteContext};
+
+/// The content type used for both import bodies and their progress reports.
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// The query parameter used to request that an import runs in the background.
+const BACKGROUND_QUERY_PARAM: &str = "background";
+
+/// The amount of records stored in each chunk of a background import.
+///
+/// Importing a record takes several storage requests, so chunks are kept small enough to be imported within the
+/// subrequest limit of a single worker invocation.
+pub const IMPORT_CHUNK_SIZE: usize = 10;
+
+/// Represents a chunk of records stored as a step of a background import job.
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportChunk {
+    /// The line number of the first record in the chunk.
+    first_line: usize,
+    records: String,
+}
+
+/// Import links from a newline-delimited JSON body, one link record per line.
+///
+/// Records are validated and written as they are read instead of buffering the whole body, and a progress
+/// report is streamed back with a line per record followed by a final summary line.
+///
+/// With `?background=true` the body is instead split into chunks that are imported by a background job,
+/// and the ID of the job is returned so it can be polled for progress.
+pub async fn import_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let is_ndjson = req
+        .headers()
+        .get("Content-Type")?
+        .is_some_and(|content_type| content_type.starts_with(NDJSON_CONTENT_TYPE));
+    if !is_ndjson {
+        return Response::error(UNSUPPORTED_IMPORT_CONTENT_TYPE_RESPONSE, 415);
+    }
+
+    let background = req
+        .url()?
+        .query_pairs()
+        .any(|(key, value)| key == BACKGROUND_QUERY_PARAM && value == "true");
+    if background {
+        return start_import_job(req, ctx).await;
+    }
+
+    let import = LinkImport {
+        storage: get_request_storage(&req, &ctx.env).await?,
+        env: ctx.env.clone(),
+        host_url: req.url()?,
+        reader: NdjsonReader::new(req.stream()?),
+        summary: ImportSummary::default(),
+        finished: false,
+    };
+    let report = stream::unfold(import, |mut import| async move {
+        let line = import.next_report_line().await?;
+        Some((line, import))
+    });
+
+    let mut response = Response::from_stream(report)?;
+    response
+        .headers_mut()
+        .set("Content-Type", NDJSON_CONTENT_TYPE)?;
+    Ok(response)
+}
+
+/// Split an import body into chunks in storage and start a job to import them in the background.
+async fn start_import_job(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let storage = get_storage(&ctx.env)?;
+    let job_id = new_job_id(&ctx.env);
+    let mut reader = NdjsonReader::new(req.stream()?);
+
+    // Store the body in chunks as it's read so that it never has to be held in memory all at once.
+    let mut total_chunks = 0;
+    let mut chunk = ImportChunk {
+        first_line: 0,
+        records: String::new(),
+    };
+    let mut chunk_lines = 0;
+    loop {
+        let line = reader.next_line().await?;
+        if let Some(line) = &line {
+            if chunk_lines == 0 {
+                chunk.first_line = reader.line;
+            }
+            chunk.records.push_str(&String::from_utf8_lossy(line));
+            chunk.records.push('\n');
+            chunk_lines += 1;
+        }
+
+        if chunk_lines == IMPORT_CHUNK_SIZE || (line.is_none() && chunk_lines > 0) {
+            let Ok(data) = serde_json::to_string(&chunk) else {
+                return Response::error(GENERIC_JOB_CREATE_ERROR_RESPONSE, 500);
+            };
+            if !store_job_step(&storage, &job_id, total_chunks, &data).await {
+                return Response::error(GENERIC_JOB_CREATE_ERROR_RESPONSE, 500);
+            }
+            total_chunks += 1;
+            chunk.records.clear();
+            chunk_lines = 0;
+        }
+
+        if line.is_none() {
+            break;
+        }
+    }
+
+    let job = JobModel::new(
+        JobKind::Import {
+            host_url: req.url()?,
+            tenant: resolve_tenant(&req, &ctx.env).await,
+        },
+        total_chunks,
+    );
+    if !start_job(&storage, &ctx.env, &job_id, &job).await {
+        return Response::error(GENERIC_JOB_CREATE_ERROR_RESPONSE, 500);
+    }
+
+    Ok(Response::from_json(&QueuedJobResponse {
+        job_id,
+        total_steps: job.total_steps,
+    })?
+    .with_status(202))
+}
+
+/// Import a chunk of records stored by a background import job.
+pub async fn run_import_step(
+    storage: &impl StorageDriver,
+    env: &Env,
+    host_url: &Url,
+    data: &str,
+) -> JobStepResult {
+    let mut summary = ImportSummary::default();
+    let mut failures = Vec::new();
+    let Ok(chunk) = serde_json::from_str::<ImportChunk>(data) else {
+        return JobStepResult::default();
+    };
+
+    for (offset, line) in chunk.records.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let report = import_record(
+            storage,
+            env,
+            host_url,
+            chunk.first_line + offset,
+            line.as_bytes(),
+            &mut summary,
+        )
+        .await;
+        if let Some(error) = report.error {
+            failures.push(JobFailure {
+                id: report.id,
+                line: Some(report.line),
+                error: error.to_string(),
+            });
+        }
+    }
+
+    JobStepResult {
+        counts: BTreeMap::from([
+            ("total".to_string(), summary.total),
+            ("created".to_string(), summary.created),
+            ("updated".to_string(), summary.updated),
+            ("failed".to_string(), summary.failed),
+        ]),
+        failures,
+    }
+}
+
+/// Reads newline-delimited records from a body without buffering more than a single line at a time.
+struct NdjsonReader {
+    body: ByteStream,
+    buffer: Vec<u8>,
+    /// The line number of the last line that was read.
+    line: usize,
+}
+
+impl NdjsonReader {
+    fn new(body: ByteStream) -> Self {
+        Self {
+            body,
+            buffer: Vec::new(),
+            line: 0,
+        }
+    }
+
+    /// Read the next line from the body, only pulling as many chunks from the body as required.
+    async fn next_line(&mut self) -> worker::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(position) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let mut line = self.buffer.drain(..=position).collect::<Vec<_>>();
+                line.pop();
+                self.line += 1;
+                return Ok(Some(line));
+            }
+
+            match self.body.next().await {
+                Some(chunk) => self.buffer.extend(chunk?),
+                None if self.buffer.is_empty() => return Ok(None),
+                None => {
+                    self.line += 1;
+                    return Ok(Some(std::mem::take(&mut self.buffer)));
+                }
+            }
+        }
+    }
+}
+
+/// The state of an in-progress streaming import.
+struct LinkImport {
+    storage: Storage,
+    env: Env,
+    host_url: Url,
+    reader: NdjsonReader,
+    summary: ImportSummary,
+    finished: bool,
+}
+
+impl LinkImport {
+    /// Import the next record from the body and get its report line, or the summary line once the body is exhausted.
+    ///
+    /// Returns [`None`] after the summary has been produced.
+    async fn next_report_line(&mut self) -> Option<worker::Result<Vec<u8>>> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match self.reader.next_line().await {
+                Ok(Some(line)) if line.iter().all(u8::is_ascii_whitespace) => continue,
+                Ok(Some(line)) => {
+                    let report = import_record(
+                        &self.storage,
+                        &self.env,
+                        &self.host_url,
+                        self.reader.line,
+                        &line,
+                        &mut self.summary,
+                    )
+                    .await;
+                    return Some(Ok(to_ndjson_line(&report)));
+                }
+                Ok(None) => {
+                    self.finished = true;
+                    return Some(Ok(to_ndjson_line(&ImportSummaryResponse {
+                        summary: std::mem::take(&mut self.summary),
+                    })));
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+/// Validate and store a single link record, adding its outcome to the given summary.
+async fn import_record(
+    storage: &impl StorageDriver,
+    env: &Env,
+    host_url: &Url,
+    line: usize,
+    raw_record: &[u8],
+    summary: &mut ImportSummary,
+) -> ImportRecordResponse {
+    summary.total += 1;
+
+    let (id, result) = match serde_json::from_slice::<ImportLinkRecord>(raw_record) {
+        Ok(mut record) => {
+            let id = canonicalize_decoded_id(&record.id);
+            // Imported records always carry their own ID, so it can't be derived from the URL.
+            let result = if record.link.dedupe {
+                Err(SaveLinkError::DedupeWithId)
+            } else {
+                match prepare_link_request(env, host_url, &mut record.link) {
+                    Ok(()) => save_link(storage, env, id.clone(), record.link, false).await,
+                    Err(err) => Err(err),
+                }
+            };
+            (Some(id), result)
+        }
+        Err(_) => (None, Err(SaveLinkError::InvalidPayload)),
+    };
+
+    match result {
+        Ok(saved) => {
+            let status = if saved.created {
+                summary.created += 1;
+                ImportRecordStatus::Created
+            } else {
+                summary.updated += 1;
+                ImportRecordStatus::Updated
+            };
+            ImportRecordResponse {
+                line,
+                id: Some(saved.id),
+                status,
+                error: None,
+            }
+        }
+        Err(err) => {
+            summary.failed += 1;
+            ImportRecordResponse {
+                line,
+                id,
+                status: ImportRecordStatus::Failed,
+                error: Some(err.message()),
+            }
+        }
+    }
+}
+
+/// Serialize a value as a single newline-terminated JSON line.
+fn to_ndjson_line(value: &impl Serialize) -> Vec<u8> {
+    let mut line = serde_json::to_vec(value).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
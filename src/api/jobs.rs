@@ -0,0 +1,31 @@
+use crate::{
+    authentication::authorized_guard,
+    messages::JOB_DOESNT_EXIST_RESPONSE,
+    models::job::JobModel,
+    storage::{get_storage, StorageDriver},
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// Get the status and progress of a background job.
+pub async fn job_status_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(job_id) = ctx.param("job_id") else {
+        Err("Unable to find job ID from request URL.")?
+    };
+
+    match storage
+        .get_deserialized_json::<JobModel>(&JobModel::key(job_id))
+        .await
+    {
+        Some(job) => Response::from_json(&job),
+        None => Response::error(JOB_DOESNT_EXIST_RESPONSE, 404),
+    }
+}
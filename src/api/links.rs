@@ -0,0 +1,76 @@
+use super::responses::ListLinksResponse;
+use crate::{
+    authentication::authorized_guard, messages::INVALID_LIST_LIMIT_RESPONSE,
+    models::link::LinkModel, storage::StorageDriver, tenants::get_request_storage,
+};
+use futures_util::future::join_all;
+use std::collections::BTreeMap;
+use worker::{Context, Request, Response, RouteContext};
+
+/// The query parameter used to filter listed links by an ID prefix.
+const PREFIX_QUERY_PARAM: &str = "prefix";
+
+/// The query parameter used to continue a listing from a previous page.
+const CURSOR_QUERY_PARAM: &str = "cursor";
+
+/// The query parameter used to set how many keys are read for a page.
+const LIMIT_QUERY_PARAM: &str = "limit";
+
+/// How many keys are read for a page when the request doesn't say.
+const DEFAULT_LIST_LINKS_LIMIT: u64 = 50;
+
+/// The most keys that can be read for a single page, as every link on the page is also read.
+pub const MAX_LIST_LINKS_LIMIT: u64 = 100;
+
+/// List stored links and their details a page at a time, optionally filtered by a prefix (e.g. `?prefix=team/`).
+///
+/// Pages can contain fewer links than the limit as internal keys are skipped, so the listing is only
+/// complete once no cursor is returned.
+pub async fn list_links_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let url = req.url()?;
+    let query_param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+    };
+    let prefix = query_param(PREFIX_QUERY_PARAM).unwrap_or_default();
+    let cursor = query_param(CURSOR_QUERY_PARAM).filter(|cursor| !cursor.is_empty());
+    let limit = match query_param(LIMIT_QUERY_PARAM).map(|limit| limit.parse::<u64>()) {
+        None => DEFAULT_LIST_LINKS_LIMIT,
+        Some(Ok(limit)) if (1..=MAX_LIST_LINKS_LIMIT).contains(&limit) => limit,
+        Some(_) => return Response::error(INVALID_LIST_LIMIT_RESPONSE, 400),
+    };
+
+    let page = storage.list_page(&prefix, cursor.as_deref(), limit).await;
+    // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+    let ids = page
+        .keys
+        .into_iter()
+        .filter(|key| !key.contains(':'))
+        .collect::<Vec<_>>();
+    let links = join_all(
+        ids.iter()
+            .map(|id| storage.get_deserialized_json::<LinkModel>(id)),
+    )
+    .await;
+
+    Response::from_json(&ListLinksResponse {
+        links: ids
+            .iter()
+            .cloned()
+            .zip(links)
+            .filter_map(|(id, link)| Some((id, link?)))
+            .collect::<BTreeMap<_, _>>(),
+        ids,
+        cursor: page.cursor,
+    })
+}
@@ -0,0 +1,123 @@
+use super::{requests::MergeLinksRequestBody, responses::MergeLinksResponse};
+use crate::{
+    analytics::merge_stats,
+    authentication::authorized_guard,
+    durable_objects::link_lock::with_link_lock,
+    get_tombstone_retention,
+    link_cache::invalidate_cached_link,
+    messages::*,
+    models::link::LinkModel,
+    notifications::{notify, Notification},
+    slugs::canonicalize_decoded_id,
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use worker::{Context, Date, Request, Response, RouteContext};
+
+/// Fold one link into another, such as after near-duplicate links split a campaign's metrics.
+///
+/// The source link becomes an alias of the target, so visiting it redirects to the target's destination
+/// and counts towards the target. Its views are added to the target's views and its stats are merged
+/// into the target's stats.
+pub async fn merge_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let Ok(body) = req.json::<MergeLinksRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    let source = canonicalize_decoded_id(&body.source);
+    let target = canonicalize_decoded_id(&body.target);
+    if source == target {
+        return Response::error(CANNOT_MERGE_LINK_INTO_ITSELF_RESPONSE, 400);
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+
+    // Locks are always taken in the same order so that two merges of the same links can't deadlock.
+    let (first, second) = if source < target {
+        (&source, &target)
+    } else {
+        (&target, &source)
+    };
+    with_link_lock(
+        &ctx.env,
+        tenant.as_deref(),
+        first,
+        with_link_lock(&ctx.env, tenant.as_deref(), second, async {
+            let (Some(mut source_link), Some(mut target_link)) = (
+                storage.get_deserialized_json::<LinkModel>(&source).await,
+                storage.get_deserialized_json::<LinkModel>(&target).await,
+            ) else {
+                return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+            };
+            if source_link.alias_of.is_some() || target_link.alias_of.is_some() {
+                return Response::error(LINK_ALREADY_MERGED_RESPONSE, 400);
+            }
+
+            let now = Date::now().as_millis();
+            // Merging a link sends its visitors to the target instead, which changes its destination.
+            if source_link.is_frozen_at(now) {
+                return Response::error(LINK_FROZEN_RESPONSE, 423);
+            }
+            target_link.views += source_link.views;
+            target_link.last_viewed_timestamp = target_link
+                .last_viewed_timestamp
+                .max(source_link.last_viewed_timestamp);
+            target_link.modified_at_timestamp = now;
+            if !storage
+                .set_serialized_json(
+                    &target,
+                    &target_link,
+                    target_link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+                )
+                .await
+            {
+                return Response::error(GENERIC_LINK_MERGE_ERROR_RESPONSE, 500);
+            }
+
+            source_link.views = 0;
+            source_link.alias_of = Some(target.clone());
+            source_link.modified_at_timestamp = now;
+            if !storage
+                .set_serialized_json(
+                    &source,
+                    &source_link,
+                    source_link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+                )
+                .await
+            {
+                return Response::error(GENERIC_LINK_MERGE_ERROR_RESPONSE, 500);
+            }
+
+            invalidate_cached_link(&ctx.env, tenant.as_deref(), &source).await;
+            invalidate_cached_link(&ctx.env, tenant.as_deref(), &target).await;
+
+            let merged_stats_periods = merge_stats(&storage, &source, &target).await;
+            notify(
+                &ctx.env,
+                &Notification::LinksMerged {
+                    source: source.clone(),
+                    target: target.clone(),
+                    tenant: tenant.clone(),
+                    views: target_link.views,
+                },
+            )
+            .await;
+
+            Response::from_json(&MergeLinksResponse {
+                source: source.clone(),
+                target: target.clone(),
+                views: target_link.views,
+                merged_stats_periods,
+            })
+        }),
+    )
+    .await
+}
@@ -0,0 +1,132 @@
+use super::responses::QueuedJobResponse;
+use crate::{
+    authentication::authorized_guard,
+    get_tombstone_retention,
+    jobs::{new_job_id, start_job, store_job_step},
+    messages::*,
+    models::{
+        job::{JobFailure, JobKind, JobModel, JobStepResult},
+        link::{LinkModel, LINK_SCHEMA_VERSION},
+    },
+    storage::{get_storage, get_tenant_storage, StorageDriver},
+    tenants::list_tenants,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use worker::{Context, Env, Request, Response, RouteContext};
+
+/// The amount of links checked by each step of a migration.
+pub const MIGRATION_CHUNK_SIZE: usize = 20;
+
+/// Represents a chunk of link IDs stored as a step of a migration job.
+#[derive(Debug, Serialize, Deserialize)]
+struct MigrationChunk {
+    /// The tenant the links belong to, if any.
+    tenant: Option<String>,
+    ids: Vec<String>,
+}
+
+/// Start a background job that upgrades every stored link of every tenant to the current schema version,
+/// returning the ID of the job so it can be polled for how many links were migrated or skipped.
+pub async fn migrate_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let job_id = new_job_id(&ctx.env);
+    let tenants = std::iter::once(None).chain(list_tenants(&storage).await.into_iter().map(Some));
+    let mut total_steps = 0;
+    for tenant in tenants {
+        let link_storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+        // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+        let ids = link_storage
+            .list("")
+            .await
+            .into_iter()
+            .filter(|key| !key.contains(':'))
+            .collect::<Vec<_>>();
+
+        for ids in ids.chunks(MIGRATION_CHUNK_SIZE) {
+            let chunk = MigrationChunk {
+                tenant: tenant.clone(),
+                ids: ids.to_vec(),
+            };
+            let Ok(data) = serde_json::to_string(&chunk) else {
+                return Response::error(GENERIC_JOB_CREATE_ERROR_RESPONSE, 500);
+            };
+            if !store_job_step(&storage, &job_id, total_steps, &data).await {
+                return Response::error(GENERIC_JOB_CREATE_ERROR_RESPONSE, 500);
+            }
+            total_steps += 1;
+        }
+    }
+
+    let job = JobModel::new(JobKind::Migrate, total_steps);
+    if !start_job(&storage, &ctx.env, &job_id, &job).await {
+        return Response::error(GENERIC_JOB_CREATE_ERROR_RESPONSE, 500);
+    }
+
+    Ok(Response::from_json(&QueuedJobResponse {
+        job_id,
+        total_steps: job.total_steps,
+    })?
+    .with_status(202))
+}
+
+/// Upgrade a chunk of links stored by a migration job to the current schema version.
+///
+/// Links are rewritten with any fields added since they were stored filled in with their defaults,
+/// while links already on the current version are skipped.
+pub async fn run_migration_step(env: &Env, data: &str) -> JobStepResult {
+    let Ok(chunk) = serde_json::from_str::<MigrationChunk>(data) else {
+        return JobStepResult::default();
+    };
+    let Ok(link_storage) = get_tenant_storage(env, chunk.tenant.as_deref()) else {
+        return JobStepResult::default();
+    };
+
+    let mut counts = BTreeMap::<String, usize>::new();
+    let mut failures = Vec::new();
+    for id in chunk.ids {
+        // Links can be deleted between the job starting and this step running.
+        let Some(record) = link_storage.get(&id).await else {
+            continue;
+        };
+        let Ok(mut link) = serde_json::from_str::<LinkModel>(&record) else {
+            *counts.entry("failed".to_string()).or_default() += 1;
+            failures.push(JobFailure {
+                id: Some(id),
+                line: None,
+                error: INVALID_LINK_RECORD_RESPONSE.to_string(),
+            });
+            continue;
+        };
+        if link.schema_version >= LINK_SCHEMA_VERSION {
+            *counts.entry("skipped".to_string()).or_default() += 1;
+            continue;
+        }
+
+        link.schema_version = LINK_SCHEMA_VERSION;
+        if link_storage
+            .set_serialized_json(
+                &id,
+                &link,
+                link.storage_expiration(get_tombstone_retention(env).is_some()),
+            )
+            .await
+        {
+            *counts.entry("migrated".to_string()).or_default() += 1;
+        } else {
+            *counts.entry("failed".to_string()).or_default() += 1;
+            failures.push(JobFailure {
+                id: Some(id),
+                line: None,
+                error: GENERIC_LINK_UPDATE_ERROR_RESPONSE.to_string(),
+            });
+        }
+    }
+
+    JobStepResult { counts, failures }
+}
@@ -1,2 +1,30 @@
+pub mod anomalies;
+pub mod attestations;
+pub mod batch;
+pub mod branding;
+pub mod bulk_update;
+pub mod capabilities;
+pub mod compare;
+pub mod config;
+pub mod conversions;
+pub mod flags;
+pub mod fsck;
+pub mod grafana;
+pub mod hooks;
+pub mod hosts;
+pub mod import;
+pub mod jobs;
+pub mod links;
+pub mod merge;
+pub mod migrate;
+pub mod oembed;
+pub mod qr;
 pub mod requests;
 pub mod responses;
+pub mod simulate;
+pub mod stats;
+pub mod suggest;
+pub mod sync;
+pub mod warm;
+pub mod warnlist;
+pub mod webhooks;
@@ -0,0 +1,40 @@
+use crate::{
+    embeds::link_oembed, links::get_public_link, messages::*, slugs::canonicalize_id,
+    tenants::get_request_storage,
+};
+use url::Url;
+use worker::{Context, Request, Response, RouteContext};
+
+/// Describe a short URL on this deployment as oEmbed JSON, following <https://oembed.com>.
+///
+/// Supports the `url`, `maxwidth`, `maxheight` and `format` query parameters, although only the JSON format is available.
+pub async fn oembed_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let host_url = req.url()?;
+    let query = |name: &str| {
+        host_url
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+    };
+
+    if query("format").is_some_and(|format| format != "json") {
+        return Response::error(UNSUPPORTED_OEMBED_FORMAT_RESPONSE, 501);
+    }
+    let Some(url) = query("url").and_then(|url| Url::parse(&url).ok()) else {
+        return Response::error(INVALID_OEMBED_URL_RESPONSE, 400);
+    };
+
+    // Only short URLs on the host being asked can be described, as other hosts may belong to another tenant.
+    let id = match url.path().strip_prefix('/').and_then(canonicalize_id) {
+        Some(id) if url.host_str() == host_url.host_str() && !id.is_empty() => id,
+        _ => return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+    };
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let Some(link) = get_public_link(&storage, &id).await else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    let max_width = query("maxwidth").and_then(|width| width.parse().ok());
+    let max_height = query("maxheight").and_then(|height| height.parse().ok());
+    Response::from_json(&link_oembed(&host_url, &id, &link, max_width, max_height))
+}
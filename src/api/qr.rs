@@ -0,0 +1,106 @@
+use super::requests::BatchQrCodesRequestBody;
+use crate::{
+    archives::ZipWriter,
+    authentication::authorized_guard,
+    embeds::short_url,
+    links::get_public_link,
+    messages::*,
+    qr::{QrCode, QrImageFormat},
+    slugs::canonicalize_decoded_id,
+    storage::Storage,
+    tenants::get_request_storage,
+};
+use futures_util::stream;
+use url::Url;
+use validator::Validate;
+use worker::{Context, Request, Response, RouteContext};
+
+/// The name of the file listing the IDs that had no QR code generated, which is only added if there are any.
+const MISSING_LINKS_FILE_NAME: &str = "missing.txt";
+
+/// The state of a QR code archive that is being streamed to the client.
+struct QrArchive {
+    storage: Storage,
+    host_url: Url,
+    ids: std::vec::IntoIter<String>,
+    format: QrImageFormat,
+    zip: Option<ZipWriter>,
+    /// The IDs that don't belong to a link that is currently being served.
+    missing: Vec<String>,
+}
+
+impl QrArchive {
+    /// Generate the next part of the archive, or [`None`] once it has been finished.
+    ///
+    /// Each part is a single QR code, except for the last which finishes the archive.
+    async fn next_part(&mut self) -> Option<Vec<u8>> {
+        let zip = self.zip.as_mut()?;
+        for id in self.ids.by_ref() {
+            let id = canonicalize_decoded_id(&id);
+            if get_public_link(&self.storage, &id).await.is_none() {
+                self.missing.push(id);
+                continue;
+            }
+            let Some(code) = QrCode::encode(short_url(&self.host_url, &id).as_str().as_bytes())
+            else {
+                self.missing.push(id);
+                continue;
+            };
+            let name = format!("{id}.{}", self.format.extension());
+            return Some(zip.add_file(&name, &code.render(self.format)));
+        }
+
+        let mut part = Vec::new();
+        if !self.missing.is_empty() {
+            part = zip.add_file(MISSING_LINKS_FILE_NAME, self.missing.join("\n").as_bytes());
+        }
+        part.extend(self.zip.take()?.finish());
+        Some(part)
+    }
+}
+
+/// Generate the QR codes of many links at once, streaming them back as a ZIP archive of SVG or PNG images.
+///
+/// IDs that don't belong to a link that is currently being served are listed in a `missing.txt` file instead.
+pub async fn batch_qr_codes_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let Ok(body) = req.json::<BatchQrCodesRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.validate().is_err() {
+        return Response::error(INVALID_BATCH_SIZE_RESPONSE, 400);
+    }
+
+    let archive = QrArchive {
+        storage: get_request_storage(&req, &ctx.env).await?,
+        host_url: req.url()?,
+        ids: body.ids.into_iter(),
+        format: body.format,
+        zip: Some(ZipWriter::default()),
+        missing: Vec::new(),
+    };
+    // The archive is generated as it's sent, so large batches don't have to be held in memory.
+    let parts = stream::unfold(archive, |mut archive| async move {
+        let part = archive.next_part().await?;
+        Some((Ok::<_, worker::Error>(part), archive))
+    });
+
+    let mut response = Response::from_stream(parts)?;
+    let headers = response.headers_mut();
+    headers.set("Content-Type", "application/zip")?;
+    headers.set(
+        "Content-Disposition",
+        &format!(
+            r#"attachment; filename="qr-codes-{}.zip""#,
+            body.format.extension()
+        ),
+    )?;
+    Ok(response)
+}
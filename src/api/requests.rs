@@ -1,5 +1,15 @@
-use serde::Deserialize;
-use std::time::Duration;
+use crate::{
+    ip_ranges::IpRange,
+    models::{
+        link::{ExpiryBehavior, LinkUtm, LinkVariant, RedirectMechanism},
+        webhook::WebhookEvent,
+    },
+    qr::QrImageFormat,
+    schedule::{ActiveHours, Recurrence},
+};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, net::IpAddr, time::Duration};
 use url::Url;
 use validator::Validate;
 
@@ -13,8 +23,425 @@ pub struct CreateLinkRequestBody {
     #[serde(with = "humantime_serde")]
     pub expire_in: Option<Duration>,
     #[serde(default)]
+    pub expiry_timestamp: Option<u64>,
+    #[serde(default)]
     #[validate(range(min = 1))]
     pub max_views: Option<u64>,
     #[serde(default)]
+    pub on_expire: ExpiryBehavior,
+    #[serde(default)]
+    pub fallback_url: Option<Url>,
+    #[serde(default)]
+    #[validate(range(max = 100))]
+    pub rollout_percent: Option<u8>,
+    #[serde(default)]
     pub disabled: bool,
+    #[serde(default)]
+    pub activate_at_timestamp: Option<u64>,
+    #[serde(default)]
+    pub activate_at: Option<String>,
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub start_in: Option<Duration>,
+    #[serde(default)]
+    pub countdown: bool,
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub normalize_id: Option<bool>,
+    #[serde(default)]
+    pub normalize_url: Option<bool>,
+    #[serde(default)]
+    pub dedupe: bool,
+    #[serde(default)]
+    pub exclude_ips: Vec<IpAddr>,
+    #[serde(default)]
+    pub exclude_cidrs: Vec<IpRange>,
+    #[serde(default)]
+    pub exclude_datacenter: bool,
+    #[serde(default)]
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub mechanism: RedirectMechanism,
+    #[serde(default)]
+    pub status_codes: Vec<u16>,
+    #[serde(default)]
+    pub language_rules: BTreeMap<String, Url>,
+    #[serde(default)]
+    pub geo_targets: BTreeMap<String, Url>,
+    #[serde(default)]
+    pub variants: Vec<LinkVariant>,
+    #[serde(default)]
+    pub sticky_variants: bool,
+    #[serde(default)]
+    pub correlation_param: Option<String>,
+    #[serde(default)]
+    pub forward_query: bool,
+    #[serde(default)]
+    pub forward_path: bool,
+    #[serde(default)]
+    pub utm: Option<LinkUtm>,
+    #[serde(default)]
+    pub requires: Option<String>,
+    #[serde(default)]
+    #[validate(range(min = 1))]
+    pub requires_views: Option<u64>,
+    #[serde(default)]
+    pub freeze_until_timestamp: Option<u64>,
+    #[serde(default)]
+    pub protected: bool,
+}
+
+/// Represents the request body for partially updating a Link, leaving any unset fields as they are.
+///
+/// Fields that can be unset on a link are cleared by setting them to `null`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PatchLinkRequestBody {
+    #[serde(default)]
+    pub url: Option<Url>,
+    #[serde(default, deserialize_with = "deserialize_nullable_duration")]
+    pub expire_in: Option<Option<Duration>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub expiry_timestamp: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub max_views: Option<Option<u64>>,
+    #[serde(default)]
+    pub on_expire: Option<ExpiryBehavior>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub fallback_url: Option<Option<Url>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub rollout_percent: Option<Option<u8>>,
+    #[serde(default)]
+    pub disabled: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub activate_at_timestamp: Option<Option<u64>>,
+    #[serde(default)]
+    pub activate_at: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_nullable_duration")]
+    pub start_in: Option<Option<Duration>>,
+    #[serde(default)]
+    pub countdown: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub active_hours: Option<Option<ActiveHours>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub recurrence: Option<Option<Recurrence>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub timezone: Option<Option<String>>,
+    #[serde(default)]
+    pub normalize_url: Option<bool>,
+    #[serde(default)]
+    pub exclude_ips: Option<Vec<IpAddr>>,
+    #[serde(default)]
+    pub exclude_cidrs: Option<Vec<IpRange>>,
+    #[serde(default)]
+    pub exclude_datacenter: Option<bool>,
+    #[serde(default)]
+    pub response_headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub mechanism: Option<RedirectMechanism>,
+    #[serde(default)]
+    pub status_codes: Option<Vec<u16>>,
+    #[serde(default)]
+    pub language_rules: Option<BTreeMap<String, Url>>,
+    #[serde(default)]
+    pub geo_targets: Option<BTreeMap<String, Url>>,
+    #[serde(default)]
+    pub variants: Option<Vec<LinkVariant>>,
+    #[serde(default)]
+    pub sticky_variants: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub correlation_param: Option<Option<String>>,
+    #[serde(default)]
+    pub forward_query: Option<bool>,
+    #[serde(default)]
+    pub forward_path: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub utm: Option<Option<LinkUtm>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub requires: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub requires_views: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub freeze_until_timestamp: Option<Option<u64>>,
+    #[serde(default)]
+    pub protected: Option<bool>,
+}
+
+/// Deserialize a field that distinguishes being set to `null` (`Some(None)`) from being missing (`None`).
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Deserialize a human-readable duration (e.g. `1h`) that distinguishes being set to `null` from being missing.
+fn deserialize_nullable_duration<'de, D>(
+    deserializer: D,
+) -> Result<Option<Option<Duration>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<humantime_serde::Serde<Duration>>::deserialize(deserializer)
+        .map(|duration| Some(duration.map(humantime_serde::Serde::into_inner)))
+}
+
+/// The most links that can be created in a single batch.
+pub const MAX_BATCH_LINKS: u64 = 100;
+
+/// Represents a single record of a batch of links to create, using the same fields as creating a link
+/// alongside an optional ID.
+#[derive(Debug, Deserialize)]
+pub struct BatchLinkRecord {
+    /// The ID to create the link under, or [`None`] to generate one.
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub link: CreateLinkRequestBody,
+}
+
+/// The most links a sync can declare.
+pub const MAX_SYNC_LINKS: u64 = 250;
+
+/// Represents the request body for syncing links to a declared state.
+#[derive(Debug, Deserialize)]
+pub struct SyncLinksRequestBody {
+    /// The prefix of the IDs the sync manages, which must be given explicitly (even if empty) to manage every link.
+    pub prefix: String,
+    /// Every link that should exist under the prefix.
+    pub links: Vec<SyncLinkRecord>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Represents a single link declared by a sync, using the same fields as creating a link alongside its ID.
+///
+/// The fields are only parsed once the sync is planned, so an invalid link only fails itself.
+#[derive(Debug, Deserialize)]
+pub struct SyncLinkRecord {
+    pub id: String,
+    #[serde(flatten)]
+    pub link: Map<String, Value>,
+}
+
+/// Represents the request body for deleting a batch of links.
+#[derive(Debug, Validate, Deserialize)]
+pub struct BatchDeleteLinksRequestBody {
+    #[validate(length(min = 1, max = MAX_BATCH_LINKS))]
+    pub ids: Vec<String>,
+}
+
+/// Represents the request body for generating the QR codes of a batch of links.
+#[derive(Debug, Validate, Deserialize)]
+pub struct BatchQrCodesRequestBody {
+    #[validate(length(min = 1, max = MAX_BATCH_LINKS))]
+    pub ids: Vec<String>,
+    #[serde(default)]
+    pub format: QrImageFormat,
+}
+
+/// Represents the request body for suggesting link IDs.
+#[derive(Debug, Deserialize)]
+pub struct SuggestSlugsRequestBody {
+    pub url: Url,
+}
+
+/// Represents a single record of a link import, using the same fields as creating a link alongside its ID.
+#[derive(Debug, Deserialize)]
+pub struct ImportLinkRecord {
+    pub id: String,
+    #[serde(flatten)]
+    pub link: CreateLinkRequestBody,
+}
+
+/// Represents the request body for mapping a hostname to a tenant.
+#[derive(Debug, Deserialize)]
+pub struct SetHostMappingRequestBody {
+    pub tenant: String,
+}
+
+/// Represents the request body for pausing a Link.
+#[derive(Debug, Default, Deserialize)]
+pub struct PauseLinkRequestBody {
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub retry_after: Option<Duration>,
+}
+
+/// Represents the request body for adding a domain to the warn list.
+#[derive(Debug, Default, Deserialize)]
+pub struct SetWarnedDomainRequestBody {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Represents the request body for changing the rollout of a feature flag.
+#[derive(Debug, Validate, Deserialize)]
+pub struct SetFeatureFlagRequestBody {
+    #[validate(range(max = 100))]
+    pub percentage: u8,
+}
+
+/// Represents the request body for verifying an attestation, which can be the attestation exactly as it was returned.
+#[derive(Debug, Deserialize)]
+pub struct VerifyAttestationRequestBody {
+    /// The exact serialized statement that was signed.
+    pub payload: String,
+    /// The signature of the payload, encoded as hex.
+    pub signature: String,
+}
+
+/// Represents the request body for adding a webhook.
+#[derive(Debug, Validate, Deserialize)]
+pub struct CreateWebhookRequestBody {
+    pub url: Url,
+    /// The events the webhook is sent, or every event if empty.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// The secret to sign notifications with, which is generated if not given.
+    #[validate(length(min = 16, max = 256))]
+    pub secret: Option<String>,
+}
+
+/// Represents the request body for reporting a conversion.
+#[derive(Debug, Validate, Deserialize)]
+pub struct RecordConversionRequestBody {
+    /// The visit ID that was appended to the destination when the visitor was redirected.
+    pub visit_id: String,
+    /// The value of the conversion, such as the amount of an order.
+    #[serde(default)]
+    #[validate(range(min = 0.0))]
+    pub value: f64,
+}
+
+/// Represents the request body for simulating a visit to a link.
+///
+/// Every detail of the visitor is optional, with missing details treated as unknown.
+#[derive(Debug, Default, Deserialize)]
+pub struct SimulateVisitRequestBody {
+    /// The client IP address of the visitor.
+    #[serde(default)]
+    pub ip: Option<IpAddr>,
+    /// The ISO 3166-1 Alpha 2 country code the visitor comes from.
+    #[serde(default)]
+    pub country: Option<String>,
+    /// The Autonomous System Number of the network the visitor comes from.
+    #[serde(default)]
+    pub asn: Option<u32>,
+    /// The User-Agent header sent by the visitor.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// The Accept-Language header sent by the visitor.
+    #[serde(default)]
+    pub accept_language: Option<String>,
+    /// The UNIX timestamp of the visit, defaulting to now.
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    /// Whether or not the visitor has confirmed they want to continue to a flagged destination.
+    #[serde(default)]
+    pub confirmed: bool,
+    /// The query string the link is visited with, e.g. `utm_source=newsletter`.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// The rest of the path the link is visited with after its ID, e.g. `getting-started`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// The most links that can be warmed in a single request.
+pub const MAX_WARM_LINK_IDS: u64 = 100;
+
+/// Represents the request body for warming the cache of links.
+#[derive(Debug, Validate, Deserialize)]
+pub struct WarmLinksRequestBody {
+    #[validate(length(min = 1, max = MAX_WARM_LINK_IDS))]
+    pub ids: Vec<String>,
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub cache_for: Option<Duration>,
+}
+
+/// Represents the request body for updating every link matching a filter.
+#[derive(Debug, Validate, Deserialize)]
+pub struct BulkUpdateLinksRequestBody {
+    pub filter: BulkLinkFilter,
+    #[validate(nested)]
+    pub update: BulkLinkUpdate,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Represents the links a bulk update applies to.
+#[derive(Debug, Deserialize)]
+pub struct BulkLinkFilter {
+    /// The prefix of the IDs to update, which must be given explicitly (even if empty) to update every link.
+    pub prefix: String,
+}
+
+/// Represents the fields a bulk update changes, leaving any unset fields as they are.
+#[derive(Debug, Validate, Deserialize)]
+pub struct BulkLinkUpdate {
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub expire_in: Option<Duration>,
+    #[serde(default)]
+    #[validate(range(min = 1))]
+    pub max_views: Option<u64>,
+    #[serde(default)]
+    pub disabled: Option<bool>,
+}
+
+/// Represents the request body for merging one link into another.
+#[derive(Debug, Deserialize)]
+pub struct MergeLinksRequestBody {
+    /// The ID of the link to merge, which becomes an alias of the target.
+    pub source: String,
+    /// The ID of the link to merge into.
+    pub target: String,
+}
+
+/// Represents the request body for checking the consistency of stored records.
+#[derive(Debug, Default, Deserialize)]
+pub struct FsckRequestBody {
+    /// Whether or not problems should be repaired instead of only reported.
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// Represents the request body Grafana's simple JSON datasource searches for the metrics it can query with.
+#[derive(Debug, Default, Deserialize)]
+pub struct GrafanaSearchRequestBody {
+    /// The text to search for, which is empty when listing every metric.
+    #[serde(default)]
+    pub target: String,
+}
+
+/// Represents the request body Grafana's simple JSON datasource queries time series with.
+#[derive(Debug, Deserialize)]
+pub struct GrafanaQueryRequestBody {
+    pub range: GrafanaQueryRange,
+    pub targets: Vec<GrafanaQueryTarget>,
+}
+
+/// Represents the range of time a Grafana query covers, as RFC 3339 timestamps.
+#[derive(Debug, Deserialize)]
+pub struct GrafanaQueryRange {
+    pub from: String,
+    pub to: String,
+}
+
+/// Represents a single metric queried by Grafana.
+#[derive(Debug, Deserialize)]
+pub struct GrafanaQueryTarget {
+    /// The metric to query, which is the ID of a link.
+    #[serde(default)]
+    pub target: String,
+    /// Whether or not the query is hidden in the panel, in which case it doesn't need to be answered.
+    #[serde(default)]
+    pub hide: bool,
 }
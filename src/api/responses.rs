@@ -1,23 +1,931 @@
-use crate::models::link::LinkModel;
+use crate::{
+    analytics::StatsRange,
+    attestations::AttestationStatement,
+    flags::FeatureFlag,
+    models::{
+        flags::FeatureFlagsModel,
+        link::LinkModel,
+        stats::{StatsModel, StatusCodeStats, UniqueVisitors, VariantStats},
+        webhook::{WebhookEndpoint, WebhookEvent},
+        webhook_failure::WebhookFailureModel,
+    },
+    networks::NetworkType,
+    schedule::local_date_time,
+    storage::StorageUsage,
+};
 use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use url::Url;
 
 /// Represents the response body for successfully creating a Link.
 #[derive(Debug, Serialize)]
 pub struct CreateLinkResponse {
+    pub id: String,
     pub url: String,
+    pub created: bool,
     pub expiry_timestamp: Option<u64>,
     pub max_views: Option<u64>,
     pub disabled: bool,
 }
 
 impl CreateLinkResponse {
-    pub fn from_model(link_model: &LinkModel, web_url: Url) -> Self {
+    pub fn from_model(link_model: &LinkModel, id: &str, mut web_url: Url, created: bool) -> Self {
+        web_url.set_path(id);
+        web_url.set_query(None);
         CreateLinkResponse {
+            id: id.to_string(),
             url: web_url.to_string(),
+            created,
             expiry_timestamp: link_model.expiry_timestamp,
             max_views: link_model.max_views,
             disabled: link_model.disabled,
         }
     }
 }
+
+/// Represents the flat response body for a link created by an inbound hook, which no-code tools can map
+/// fields from directly.
+#[derive(Debug, Serialize)]
+pub struct InboundHookResponse {
+    pub id: String,
+    pub short_url: String,
+    pub url: String,
+}
+
+/// Represents the response body for link ID suggestions.
+#[derive(Debug, Serialize)]
+pub struct SuggestSlugsResponse {
+    pub suggestions: Vec<String>,
+}
+
+/// Represents the response body for listing links.
+#[derive(Debug, Serialize)]
+pub struct ListLinksResponse {
+    pub ids: Vec<String>,
+    /// The details of each listed link, keyed by ID.
+    pub links: BTreeMap<String, LinkModel>,
+    /// The cursor to request the next page with, or [`None`] if this is the last page.
+    pub cursor: Option<String>,
+}
+
+/// Represents the response body for a link's stats.
+#[derive(Debug, Serialize)]
+pub struct LinkStatsResponse {
+    /// The link's views, which are estimated from the sampled visits when the stats are restricted to a range.
+    pub views: u64,
+    /// The UNIX timestamp in milliseconds the stats start at, or [`None`] if they start from the beginning.
+    pub from_timestamp: Option<u64>,
+    /// The UNIX timestamp in milliseconds the stats end at, or [`None`] if they go up until now.
+    pub to_timestamp: Option<u64>,
+    /// An estimate of the distinct visitors across every period, which only includes sampled visits.
+    pub unique_visitors: u64,
+    /// Whether or not visits are buffered before being stored, making these stats approximate.
+    pub approximate: bool,
+    /// The fraction of visits whose details are currently recorded in the stats, with `1.0` meaning every visit.
+    pub sample_rate: f64,
+    /// The amount of visits during each hour of the day (UTC) across every period, starting from midnight.
+    pub hours: [u64; 24],
+    /// The amount of visits on each day of the week (UTC) across every period, starting from Monday.
+    pub weekdays: [u64; 7],
+    /// The amount of visits from each browser family across every period.
+    pub browsers: BTreeMap<String, u64>,
+    /// The amount of visits from each operating system family across every period.
+    pub operating_systems: BTreeMap<String, u64>,
+    /// The amount of visits from each class of device across every period.
+    pub devices: BTreeMap<String, u64>,
+    /// The amount of visits from each network, keyed by Autonomous System Number, across every period.
+    pub asns: BTreeMap<String, u64>,
+    /// The amount of visits from each kind of network (`datacenter` or `residential`) across every period.
+    pub networks: BTreeMap<String, u64>,
+    /// The amount of conversions reported across every period.
+    pub conversions: u64,
+    /// The total value of the conversions reported across every period.
+    pub conversion_value: f64,
+    /// The fraction of views that converted.
+    pub conversion_rate: f64,
+    /// The visits and conversions of each variant across every period, keyed by the variant's index.
+    pub variants: BTreeMap<String, VariantStatsResponse>,
+    /// The visits redirected with each HTTP status code across every period, keyed by status code.
+    pub status_codes: BTreeMap<String, StatusCodeStats>,
+    pub days: BTreeMap<String, StatsModel>,
+    pub months: BTreeMap<String, StatsModel>,
+}
+
+impl LinkStatsResponse {
+    /// Build the response from a link's daily and monthly stats, totalling their histograms and client breakdowns.
+    pub fn from_stats(
+        views: u64,
+        range: StatsRange,
+        approximate: bool,
+        sample_rate: f64,
+        days: BTreeMap<String, StatsModel>,
+        months: BTreeMap<String, StatsModel>,
+    ) -> Self {
+        let mut hours = [0; 24];
+        let mut weekdays = [0; 7];
+        let mut browsers = BTreeMap::<String, u64>::new();
+        let mut operating_systems = BTreeMap::<String, u64>::new();
+        let mut devices = BTreeMap::<String, u64>::new();
+        let mut asns = BTreeMap::<String, u64>::new();
+        let mut networks = BTreeMap::<String, u64>::new();
+        let mut conversions = 0;
+        let mut conversion_value = 0.0;
+        let mut variants = BTreeMap::<String, VariantStats>::new();
+        let mut status_codes = BTreeMap::<String, StatusCodeStats>::new();
+        let mut visitors = UniqueVisitors::default();
+        for stats in days.values().chain(months.values()) {
+            visitors.merge(&stats.visitors);
+            conversions += stats.conversions;
+            conversion_value += stats.conversion_value;
+            for (variant, variant_stats) in &stats.variants {
+                variants
+                    .entry(variant.clone())
+                    .or_default()
+                    .merge(variant_stats);
+            }
+            for (status_code, status_code_stats) in &stats.status_codes {
+                status_codes
+                    .entry(status_code.clone())
+                    .or_default()
+                    .merge(status_code_stats);
+            }
+            for (total, views) in hours.iter_mut().zip(stats.hours) {
+                *total += views;
+            }
+            for (total, views) in weekdays.iter_mut().zip(stats.weekdays) {
+                *total += views;
+            }
+            for (browser, views) in &stats.browsers {
+                *browsers.entry(browser.clone()).or_default() += views;
+            }
+            for (os, views) in &stats.operating_systems {
+                *operating_systems.entry(os.clone()).or_default() += views;
+            }
+            for (device, views) in &stats.devices {
+                *devices.entry(device.clone()).or_default() += views;
+            }
+            for (asn, views) in &stats.asns {
+                *asns.entry(asn.clone()).or_default() += views;
+            }
+            for (network, views) in &stats.networks {
+                *networks.entry(network.clone()).or_default() += views;
+            }
+        }
+        LinkStatsResponse {
+            views,
+            from_timestamp: range.from,
+            to_timestamp: range.to,
+            unique_visitors: visitors.estimate(),
+            approximate,
+            sample_rate,
+            hours,
+            weekdays,
+            browsers,
+            operating_systems,
+            devices,
+            asns,
+            networks,
+            conversions,
+            conversion_value,
+            conversion_rate: conversion_rate(conversions, views as f64),
+            variants: variants
+                .into_iter()
+                .map(|(variant, stats)| {
+                    (
+                        variant,
+                        VariantStatsResponse::from_stats(stats, sample_rate),
+                    )
+                })
+                .collect(),
+            status_codes,
+            days,
+            months,
+        }
+    }
+}
+
+/// Represents the response body for comparing the stats of several links side by side.
+#[derive(Debug, Serialize)]
+pub struct CompareLinksResponse {
+    /// The UNIX timestamp in milliseconds the compared stats start at, or [`None`] if they start from the beginning.
+    pub from_timestamp: Option<u64>,
+    /// The UNIX timestamp in milliseconds the compared stats end at, or [`None`] if they go up until now.
+    pub to_timestamp: Option<u64>,
+    /// The fraction of visits whose details are currently recorded in the stats, with `1.0` meaning every visit.
+    pub sample_rate: f64,
+    /// The compared links, in the order they were requested.
+    pub links: Vec<LinkComparison>,
+    /// The requested IDs that don't belong to a link.
+    pub missing: Vec<String>,
+}
+
+/// Represents the stats of a single link in a comparison.
+#[derive(Debug, Serialize)]
+pub struct LinkComparison {
+    pub id: String,
+    /// The link's views, which are estimated from the sampled visits when the comparison is restricted to a range.
+    pub views: u64,
+    /// An estimate of the link's distinct visitors, which only includes sampled visits.
+    pub unique_visitors: u64,
+    pub conversions: u64,
+    pub conversion_value: f64,
+    /// The fraction of views that converted.
+    pub conversion_rate: f64,
+    /// The sampled visits from each country and their share of all visits with a known country.
+    pub countries: BTreeMap<String, CountryShare>,
+}
+
+impl LinkComparison {
+    /// Build the comparison of a link from its views and the stats of every period being compared.
+    pub fn from_stats<'a>(
+        id: String,
+        views: u64,
+        periods: impl Iterator<Item = &'a StatsModel>,
+    ) -> Self {
+        let mut visitors = UniqueVisitors::default();
+        let mut conversions = 0;
+        let mut conversion_value = 0.0;
+        let mut countries = BTreeMap::<String, u64>::new();
+        for stats in periods {
+            visitors.merge(&stats.visitors);
+            conversions += stats.conversions;
+            conversion_value += stats.conversion_value;
+            for (country, views) in &stats.countries {
+                *countries.entry(country.clone()).or_default() += views;
+            }
+        }
+        let country_views = countries.values().sum::<u64>();
+        LinkComparison {
+            id,
+            views,
+            unique_visitors: visitors.estimate(),
+            conversions,
+            conversion_value,
+            conversion_rate: conversion_rate(conversions, views as f64),
+            countries: countries
+                .into_iter()
+                .map(|(country, views)| {
+                    let share = views as f64 / country_views as f64;
+                    (country, CountryShare { views, share })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Represents the visits from a single country and their share of all visits with a known country.
+#[derive(Debug, Serialize)]
+pub struct CountryShare {
+    pub views: u64,
+    pub share: f64,
+}
+
+/// Get the fraction of the given views that converted, or `0.0` if there were none.
+fn conversion_rate(conversions: u64, views: f64) -> f64 {
+    if views > 0.0 {
+        conversions as f64 / views
+    } else {
+        0.0
+    }
+}
+
+/// Represents a single time series answering a Grafana query, in the format of Grafana's simple JSON datasource.
+#[derive(Debug, Serialize)]
+pub struct GrafanaTimeSeries {
+    pub target: String,
+    /// The value of each point alongside the UNIX timestamp in milliseconds it's at, oldest first.
+    pub datapoints: Vec<(f64, u64)>,
+}
+
+/// Represents the visits and conversions of a single variant of a link.
+#[derive(Debug, Serialize)]
+pub struct VariantStatsResponse {
+    /// The amount of recorded visits sent to the variant, which only includes sampled visits.
+    pub views: u64,
+    pub conversions: u64,
+    pub conversion_value: f64,
+    /// The fraction of visits sent to the variant that converted, estimated from the sampled visits.
+    pub conversion_rate: f64,
+}
+
+impl VariantStatsResponse {
+    /// Build the response from a variant's stats, scaling its sampled views back up by the sample rate.
+    pub fn from_stats(stats: VariantStats, sample_rate: f64) -> Self {
+        let estimated_views = if sample_rate > 0.0 {
+            stats.views as f64 / sample_rate
+        } else {
+            0.0
+        };
+        Self {
+            views: stats.views,
+            conversions: stats.conversions,
+            conversion_value: stats.conversion_value,
+            conversion_rate: conversion_rate(stats.conversions, estimated_views),
+        }
+    }
+}
+
+/// Represents the response body for a signed attestation of a link's destinations.
+#[derive(Debug, Serialize)]
+pub struct AttestationResponse {
+    pub algorithm: &'static str,
+    pub statement: AttestationStatement,
+    /// The exact serialized statement that was signed, which is what the signature must be verified against.
+    pub payload: String,
+    /// The signature of the payload, encoded as lowercase hex.
+    pub signature: String,
+}
+
+/// Represents the response body for verifying an attestation.
+#[derive(Debug, Serialize)]
+pub struct VerifyAttestationResponse {
+    /// Whether or not the attestation was signed by this deployment.
+    pub valid: bool,
+    /// The attested statement, if the attestation is valid.
+    pub statement: Option<AttestationStatement>,
+    /// Whether or not the link still sends visitors to the attested destinations, or [`None`] if it couldn't be
+    /// checked because the attestation is invalid or was made on another host.
+    pub unchanged: Option<bool>,
+}
+
+/// Represents the response body for a recorded conversion.
+#[derive(Debug, Serialize)]
+pub struct RecordConversionResponse {
+    pub id: String,
+    pub variant: Option<usize>,
+    pub value: f64,
+}
+
+/// The outcome of importing a single link record.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportRecordStatus {
+    Created,
+    Updated,
+    Failed,
+}
+
+/// Represents a line of the import progress report for a single record.
+#[derive(Debug, Serialize)]
+pub struct ImportRecordResponse {
+    pub line: usize,
+    pub id: Option<String>,
+    pub status: ImportRecordStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Represents the totals of a finished import.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub total: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub failed: usize,
+}
+
+/// Represents the final line of the import progress report.
+#[derive(Debug, Serialize)]
+pub struct ImportSummaryResponse {
+    pub summary: ImportSummary,
+}
+
+/// Represents the response body for a job that was queued to run in the background.
+#[derive(Debug, Serialize)]
+pub struct QueuedJobResponse {
+    pub job_id: String,
+    pub total_steps: usize,
+}
+
+/// Represents the response body for listing hostname to tenant mappings.
+#[derive(Debug, Serialize)]
+pub struct ListHostMappingsResponse {
+    pub hosts: BTreeMap<String, String>,
+}
+
+/// Represents a webhook added with the API.
+#[derive(Debug, Serialize)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: Url,
+    /// The events the webhook is sent, or every event if empty.
+    pub events: Vec<WebhookEvent>,
+    /// The secret notifications are signed with, which is only returned when the webhook is added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at_timestamp: u64,
+}
+
+impl WebhookResponse {
+    /// Build the response for a webhook, only including its secret if asked to.
+    pub fn from_endpoint(id: String, webhook: WebhookEndpoint, include_secret: bool) -> Self {
+        Self {
+            id,
+            url: webhook.url,
+            events: webhook.events,
+            secret: include_secret.then_some(webhook.secret),
+            created_at_timestamp: webhook.created_at_timestamp,
+        }
+    }
+}
+
+/// Represents the response body for listing the webhooks added with the API.
+#[derive(Debug, Serialize)]
+pub struct ListWebhooksResponse {
+    pub webhooks: Vec<WebhookResponse>,
+}
+
+/// Represents the response body for listing notifications that couldn't be delivered to a webhook.
+#[derive(Debug, Serialize)]
+pub struct ListWebhookFailuresResponse {
+    /// The failed deliveries, least recently failed first.
+    pub failures: Vec<WebhookFailureModel>,
+}
+
+/// Represents the response body for warming the cache of links.
+#[derive(Debug, Default, Serialize)]
+pub struct WarmLinksResponse {
+    /// The IDs of the links that were loaded into the cache.
+    pub warmed: Vec<String>,
+    /// The IDs that don't have a link.
+    pub missing: Vec<String>,
+}
+
+/// Represents an oEmbed response for a link, as described by <https://oembed.com>.
+#[derive(Debug, Serialize)]
+pub struct OEmbedResponse {
+    pub version: &'static str,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub title: String,
+    pub provider_name: &'static str,
+    pub provider_url: String,
+    pub html: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Represents the capabilities of this deployment, so clients can discover what it supports.
+#[derive(Debug, Serialize)]
+pub struct CapabilitiesResponse {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// The environment this deployment belongs to, or [`None`] for production.
+    pub environment: Option<String>,
+    pub features: CapabilitiesFeatures,
+    pub limits: CapabilitiesLimits,
+    /// Whether or not links can be created without an API token.
+    pub public_create: bool,
+}
+
+/// Represents the optional features of a deployment and whether they're enabled.
+#[derive(Debug, Serialize)]
+pub struct CapabilitiesFeatures {
+    pub multi_tenant: bool,
+    pub screenshots: bool,
+    pub health_checks: bool,
+    pub visit_stats: bool,
+    pub visit_buffering: bool,
+    pub background_jobs: bool,
+    pub notifications: bool,
+    pub link_locking: bool,
+    pub rate_limiting: bool,
+    pub view_counting: bool,
+    pub attestations: bool,
+    pub d1_storage: bool,
+    pub link_cache: bool,
+    pub metrics_export: bool,
+}
+
+/// Represents the limits applied to requests made to a deployment.
+#[derive(Debug, Serialize)]
+pub struct CapabilitiesLimits {
+    pub max_warm_link_ids: u64,
+    pub max_list_links_limit: u64,
+    pub max_batch_links: u64,
+    pub max_compare_links: u64,
+    pub max_sync_links: u64,
+    pub max_grafana_targets: u64,
+    pub analytics_sample_rate: f64,
+}
+
+/// Represents the outcome of a bulk update for a single link.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkUpdateLinkStatus {
+    Updated,
+    Unchanged,
+    Failed,
+}
+
+/// Represents the result of a bulk update for a single link.
+#[derive(Debug, Serialize)]
+pub struct BulkUpdateLinkResult {
+    pub id: String,
+    pub status: BulkUpdateLinkStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Represents the response body for a bulk update of links.
+#[derive(Debug, Serialize)]
+pub struct BulkUpdateLinksResponse {
+    /// Whether or not the update was only previewed, in which case nothing was written.
+    pub dry_run: bool,
+    pub matched: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub results: Vec<BulkUpdateLinkResult>,
+}
+
+/// Represents the outcome of creating a single link of a batch.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchLinkStatus {
+    Created,
+    /// An existing link was replaced as the record set `overwrite`.
+    Updated,
+    /// A link already exists under the ID, or for the destination when deduplicating, so nothing was written.
+    Skipped,
+    Failed,
+}
+
+/// Represents the result of creating a single link of a batch.
+#[derive(Debug, Serialize)]
+pub struct BatchLinkResult {
+    /// The position of the record in the batch.
+    pub index: usize,
+    pub id: Option<String>,
+    pub status: BatchLinkStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Represents the response body for creating a batch of links.
+#[derive(Debug, Default, Serialize)]
+pub struct BatchCreateLinksResponse {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub results: Vec<BatchLinkResult>,
+}
+
+/// Represents the result of deleting a single link of a batch.
+#[derive(Debug, Serialize)]
+pub struct BatchDeleteLinkResult {
+    pub id: String,
+    pub deleted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Represents the response body for deleting a batch of links.
+#[derive(Debug, Default, Serialize)]
+pub struct BatchDeleteLinksResponse {
+    pub deleted: usize,
+    pub failed: usize,
+    pub results: Vec<BatchDeleteLinkResult>,
+}
+
+/// Represents what a sync does to a single link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncLinkAction {
+    Create,
+    Update,
+    Delete,
+    Unchanged,
+}
+
+/// Represents the result of a sync for a single link.
+#[derive(Debug, Serialize)]
+pub struct SyncLinkResult {
+    pub id: String,
+    pub action: SyncLinkAction,
+    /// Why the action couldn't be taken, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Represents the response body for syncing links to a declared state.
+#[derive(Debug, Default, Serialize)]
+pub struct SyncLinksResponse {
+    /// Whether or not the sync was only planned, in which case nothing was written.
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub results: Vec<SyncLinkResult>,
+}
+
+/// Represents the response body for merging one link into another.
+#[derive(Debug, Serialize)]
+pub struct MergeLinksResponse {
+    pub source: String,
+    pub target: String,
+    /// The total views of the target link after merging.
+    pub views: u64,
+    /// The amount of days and months of stats moved from the source link to the target.
+    pub merged_stats_periods: usize,
+}
+
+/// Represents a kind of problem found while checking the consistency of stored records.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsckProblem {
+    /// A link record that can't be read as a link.
+    CorruptRecord,
+    /// A link stored under an ID that isn't allowed by the current validation rules.
+    InvalidId,
+    /// A merged link whose target no longer exists.
+    DanglingAlias,
+    /// Stats belonging to a link that no longer exists and isn't retained as a tombstone.
+    OrphanedStats,
+}
+
+/// Represents a problem found with a single stored record.
+#[derive(Debug, Serialize)]
+pub struct FsckFinding {
+    pub key: String,
+    pub problem: FsckProblem,
+    /// Whether or not the problem was repaired.
+    pub repaired: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+}
+
+/// Represents the response body for checking the consistency of stored records.
+#[derive(Debug, Serialize)]
+pub struct FsckResponse {
+    /// The amount of keys that were checked.
+    pub scanned: usize,
+    pub findings: Vec<FsckFinding>,
+}
+
+/// Represents the response body for the amount of data held in storage.
+#[derive(Debug, Serialize)]
+pub struct StorageStatsResponse {
+    #[serde(flatten)]
+    pub usage: StorageUsage,
+    /// The UNIX timestamp of when the usage was measured.
+    pub measured_at_timestamp: u64,
+    /// The key count that triggers a notification, if any.
+    pub alert_keys: Option<u64>,
+    /// The stored bytes that trigger a notification, if any.
+    pub alert_bytes: Option<u64>,
+}
+
+/// Represents how serious a configuration issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigIssueSeverity {
+    /// The worker can't work correctly until this is fixed.
+    Error,
+    /// The worker works, but probably not as intended.
+    Warning,
+}
+
+/// Represents a problem found with the configuration of a deployment.
+#[derive(Debug, Serialize)]
+pub struct ConfigIssue {
+    pub severity: ConfigIssueSeverity,
+    pub message: String,
+}
+
+/// Represents the response body for checking the configuration of a deployment.
+#[derive(Debug, Serialize)]
+pub struct ConfigCheckResponse {
+    /// Whether or not no errors were found.
+    pub ok: bool,
+    /// Whether or not each binding is present.
+    pub bindings: BTreeMap<&'static str, bool>,
+    /// The value of each non-secret variable, or [`None`] if it's unset.
+    pub variables: BTreeMap<&'static str, Option<String>>,
+    /// Whether or not each secret is set, without revealing its value.
+    pub secrets: BTreeMap<&'static str, bool>,
+    pub features: CapabilitiesFeatures,
+    pub limits: CapabilitiesLimits,
+    pub issues: Vec<ConfigIssue>,
+}
+
+/// Represents the current rollout of a feature flag.
+#[derive(Debug, Serialize)]
+pub struct FeatureFlagResponse {
+    pub flag: FeatureFlag,
+    /// The percentage of requests the flag is enabled for.
+    pub percentage: u8,
+    /// The percentage of requests the flag is enabled for when its rollout hasn't been changed.
+    pub default_percentage: u8,
+    /// The UNIX timestamp of when the rollout was last changed, or [`None`] if it's using the default.
+    pub modified_at_timestamp: Option<u64>,
+}
+
+impl FeatureFlagResponse {
+    /// Describe the rollout of a flag from the stored feature flags.
+    pub fn from_flags(flags: &FeatureFlagsModel, flag: FeatureFlag) -> Self {
+        Self {
+            flag,
+            percentage: flags.percentage(flag),
+            default_percentage: flag.default_percentage(),
+            modified_at_timestamp: flags
+                .flags
+                .get(&flag)
+                .map(|rollout| rollout.modified_at_timestamp),
+        }
+    }
+}
+
+/// Represents what the redirect handler does with a visit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimulatedOutcome {
+    /// The visitor is redirected to the destination.
+    Redirect,
+    /// The visitor is shown the not found page.
+    NotFound,
+    /// The visitor is shown the temporarily unavailable page.
+    Paused,
+    /// The visitor is shown a countdown to the link's activation time.
+    Countdown,
+    /// The visitor is told the link has expired and is permanently gone.
+    Gone,
+    /// The visitor is asked to confirm they want to continue to a flagged destination.
+    Warning,
+}
+
+/// Represents the details that would be recorded in a link's visit stats.
+#[derive(Debug, Serialize)]
+pub struct SimulatedVisitDetails {
+    pub country: Option<String>,
+    pub browser: Option<&'static str>,
+    pub os: Option<&'static str>,
+    pub device: Option<&'static str>,
+    pub asn: Option<u32>,
+    pub network: Option<NetworkType>,
+}
+
+/// Represents the response body for simulating a visit to a link.
+#[derive(Debug, Serialize)]
+pub struct SimulateVisitResponse {
+    /// The ID of the link that serves the visit, which differs from the requested ID if it was merged into another link.
+    pub id: String,
+    pub outcome: SimulatedOutcome,
+    /// Where the visitor would be redirected, if they would be.
+    pub destination: Option<Url>,
+    /// Whether or not the visit would count towards the link's views.
+    pub counted: bool,
+    /// The HTTP status code the visitor would be redirected with, if they would be redirected over HTTP.
+    pub status_code: Option<u16>,
+    /// The details that would be recorded in visit stats, or [`None`] if they wouldn't be recorded.
+    pub recorded: Option<SimulatedVisitDetails>,
+    /// The sample rate deciding whether the recorded details are kept.
+    pub sample_rate: f64,
+    /// Why each decision was made, in the order they were made.
+    pub reasons: Vec<String>,
+}
+
+/// Represents a single check deciding whether a link is served to visitors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkCheck {
+    /// Whether the link was merged into another link, which serves its visits instead.
+    Alias,
+    /// Whether the link is disabled.
+    Disabled,
+    /// Whether the link is paused.
+    Paused,
+    /// Whether the link has passed its expiry time.
+    Expiry,
+    /// Whether the link has reached its maximum views.
+    MaxViews,
+    /// Whether the link has reached its activation time.
+    Activation,
+    /// Whether the link is within its active hours.
+    ActiveHours,
+    /// Whether the link's recurrence includes the current day.
+    Recurrence,
+    /// Whether the link this link depends on has expired or reached the required views.
+    Requires,
+}
+
+/// Represents the result of a single check deciding whether a link is served to visitors.
+#[derive(Debug, Serialize)]
+pub struct LinkCheckResult {
+    pub check: LinkCheck,
+    pub passed: bool,
+    /// The values the check compared.
+    pub values: Value,
+}
+
+/// Represents the response body for explaining whether a link is served to visitors.
+#[derive(Debug, Serialize)]
+pub struct ExplainLinkResponse {
+    /// The ID of the link that serves visits, which differs from the requested ID if it was merged into another link.
+    pub id: String,
+    /// Whether or not visitors are redirected, which is only the case if every check passed.
+    pub served: bool,
+    pub checks: Vec<LinkCheckResult>,
+}
+
+impl ExplainLinkResponse {
+    /// Run every check against the link serving visits to the requested ID, as of the given UNIX timestamp.
+    ///
+    /// The dependency should be the link the link depends on, if it has one and it still exists.
+    /// The view count should include any visits that are buffered and haven't been written to the link yet.
+    pub fn from_link(
+        requested_id: &str,
+        id: String,
+        link: &LinkModel,
+        dependency: Option<&LinkModel>,
+        views: u64,
+        timestamp: u64,
+    ) -> Self {
+        let checks = vec![
+            LinkCheckResult {
+                check: LinkCheck::Alias,
+                passed: true,
+                values: json!({
+                    "requested_id": requested_id,
+                    "alias_of": (requested_id != id).then_some(&id),
+                }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::Disabled,
+                passed: !link.disabled,
+                values: json!({ "disabled": link.disabled }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::Paused,
+                passed: !link.paused,
+                values: json!({
+                    "paused": link.paused,
+                    "auto_paused": link.health.as_ref().is_some_and(|health| health.auto_paused),
+                    "retry_after_seconds": link.pause_retry_after_seconds,
+                }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::Expiry,
+                passed: link
+                    .expiry_timestamp
+                    .map_or(true, |expiry_timestamp| timestamp <= expiry_timestamp),
+                values: json!({
+                    "expiry_timestamp": link.expiry_timestamp,
+                    "now_timestamp": timestamp,
+                    "on_expire": link.on_expire,
+                }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::MaxViews,
+                passed: link.max_views.map_or(true, |max_views| views < max_views),
+                values: json!({
+                    "views": views,
+                    "max_views": link.max_views,
+                    "on_expire": link.on_expire,
+                }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::Activation,
+                passed: link.is_activated_at(timestamp),
+                values: json!({
+                    "activate_at_timestamp": link.activate_at_timestamp,
+                    "now_timestamp": timestamp,
+                    "countdown": link.countdown,
+                }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::ActiveHours,
+                passed: link.is_within_active_hours_at(timestamp),
+                values: json!({
+                    "active_hours": link.active_hours,
+                    "timezone": link.timezone,
+                    "local_time": local_date_time(timestamp, link.timezone.as_deref())
+                        .map(|local| local.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::Recurrence,
+                passed: link.is_recurring_at(timestamp),
+                values: json!({ "recurrence": link.recurrence, "timezone": link.timezone }),
+            },
+            LinkCheckResult {
+                check: LinkCheck::Requires,
+                passed: link.requires.is_none() || link.is_dependency_met_by(dependency),
+                values: json!({
+                    "requires": link.requires,
+                    "requires_views": link.requires_views,
+                    "dependency_views": dependency.map(|dependency| dependency.views),
+                    "dependency_valid": dependency.map(LinkModel::is_valid),
+                }),
+            },
+        ];
+        Self {
+            id,
+            served: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
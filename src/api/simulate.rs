@@ -0,0 +1,295 @@
+use super::{
+    requests::SimulateVisitRequestBody,
+    responses::{SimulateVisitResponse, SimulatedOutcome, SimulatedVisitDetails},
+};
+use crate::{
+    analytics::{get_sample_rate, is_excluded_address, PrivacyConfig},
+    authentication::authorized_guard,
+    get_link_id_from_route,
+    links::{get_link_following_alias, is_dependency_met},
+    messages::*,
+    models::link::{ExpiryBehavior, LinkModel, RedirectMechanism, GEO_TARGET_DEFAULT},
+    networks::{classify_asn, is_excluded_network},
+    routing::{
+        is_valid_country_code, pick_status_code, resolve_destination, visitor_fingerprint,
+        DestinationRule, Visitor,
+    },
+    storage::get_tenant_storage,
+    tenants::resolve_tenant,
+    urls::{append_path, merge_query_pairs},
+    user_agents::parse_user_agent,
+    visit_buffer::pending_views,
+    warnings::{find_domain_warning, CONFIRM_QUERY_PARAM},
+};
+use url::{form_urlencoded, Url};
+use worker::{Context, Date, Request, Response, RouteContext};
+
+/// Simulate a visit to a link from the given visitor, returning what the redirect handler would do and why.
+///
+/// Nothing is written, so the visit isn't counted and expired links aren't removed.
+pub async fn simulate_visit_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let body = match req.text().await {
+        Ok(text) if text.trim().is_empty() => SimulateVisitRequestBody::default(),
+        Ok(text) => match serde_json::from_str::<SimulateVisitRequestBody>(&text) {
+            Ok(body) => body,
+            Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+        },
+        Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+    };
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let requested_id = get_link_id_from_route(&ctx)?;
+    let (id, link) = get_link_following_alias(&storage, requested_id.clone()).await;
+
+    let mut response = SimulateVisitResponse {
+        id: id.clone(),
+        outcome: SimulatedOutcome::NotFound,
+        destination: None,
+        counted: false,
+        recorded: None,
+        status_code: None,
+        sample_rate: get_sample_rate(&ctx.env),
+        reasons: Vec::new(),
+    };
+    if id != requested_id {
+        response
+            .reasons
+            .push(format!("'{requested_id}' was merged into '{id}'"));
+    }
+
+    let Some(mut link) = link else {
+        response
+            .reasons
+            .push(format!("no link exists with ID '{id}'"));
+        return Response::from_json(&response);
+    };
+    if link.disabled {
+        response.reasons.push("the link is disabled".to_string());
+        return Response::from_json(&response);
+    }
+    if link.paused {
+        response.outcome = SimulatedOutcome::Paused;
+        response.reasons.push("the link is paused".to_string());
+        return Response::from_json(&response);
+    }
+
+    let visitor = Visitor {
+        accept_language: body.accept_language,
+        country: body
+            .country
+            .as_deref()
+            .map(str::to_ascii_uppercase)
+            .filter(|country| is_valid_country_code(country)),
+        fingerprint: visitor_fingerprint(
+            body.ip.map(|address| address.to_string()).as_deref(),
+            body.user_agent.as_deref(),
+        ),
+    };
+    let status_code =
+        (link.mechanism == RedirectMechanism::Http).then(|| pick_status_code(&link, &visitor));
+
+    link.views += pending_views(tenant.as_deref(), &id);
+    let timestamp = body.timestamp.unwrap_or_else(|| Date::now().as_millis());
+    if !link.is_valid_at(timestamp) {
+        response.reasons.push(match link.max_views {
+            Some(max_views) if link.views >= max_views => {
+                format!("the link has reached its maximum of {max_views} views")
+            }
+            _ => "the link has expired".to_string(),
+        });
+        match (link.on_expire, &link.fallback_url) {
+            (ExpiryBehavior::RedirectFallback, Some(fallback_url)) => {
+                let mut fallback_url = fallback_url.clone();
+                forward_simulated_visit(
+                    body.path.as_deref(),
+                    body.query.as_deref(),
+                    &link,
+                    &mut fallback_url,
+                    &mut response.reasons,
+                );
+                response.outcome = SimulatedOutcome::Redirect;
+                response.destination = Some(fallback_url);
+                response.status_code = status_code;
+                response.reasons.push(
+                    "expired links redirect to their fallback URL without counting the visit"
+                        .to_string(),
+                );
+            }
+            (ExpiryBehavior::Gone, _) => response.outcome = SimulatedOutcome::Gone,
+            _ => {}
+        }
+        return Response::from_json(&response);
+    }
+
+    if !link.is_activated_at(timestamp) {
+        if link.countdown {
+            response.outcome = SimulatedOutcome::Countdown;
+        }
+        response.reasons.push(format!(
+            "the link isn't active until {}",
+            link.activate_at_timestamp.unwrap_or_default()
+        ));
+        return Response::from_json(&response);
+    }
+    if !link.is_within_active_hours_at(timestamp) {
+        response
+            .reasons
+            .push("the visit is outside the link's active hours".to_string());
+        return Response::from_json(&response);
+    }
+    if !link.is_recurring_at(timestamp) {
+        response
+            .reasons
+            .push("the visit is on a day the link's recurrence doesn't include".to_string());
+        return Response::from_json(&response);
+    }
+
+    if !is_dependency_met(&storage, &link).await {
+        response.reasons.push(format!(
+            "the link it depends on, '{}', hasn't expired or reached the required views",
+            link.requires.as_deref().unwrap_or_default()
+        ));
+        return Response::from_json(&response);
+    }
+
+    let mut destination = resolve_destination(&link, &visitor);
+    forward_simulated_visit(
+        body.path.as_deref(),
+        body.query.as_deref(),
+        &link,
+        &mut destination.url,
+        &mut response.reasons,
+    );
+    match &destination.rule {
+        Some(DestinationRule::Geo(key)) if key == GEO_TARGET_DEFAULT => response.reasons.push(
+            "the visitor's country has no geo target or is unknown, so the default geo target is used"
+                .to_string(),
+        ),
+        Some(DestinationRule::Geo(country)) => response.reasons.push(format!(
+            "the visitor's country matched the geo target for '{country}'"
+        )),
+        Some(DestinationRule::Language(tag)) => response.reasons.push(format!(
+            "the visitor's languages matched the language rule for '{tag}'"
+        )),
+        Some(DestinationRule::Variant(index)) => response.reasons.push(format!(
+            "the visitor was assigned variant {index}{}",
+            if link.sticky_variants {
+                ", which they'll keep on later visits"
+            } else {
+                ", which is picked again on every visit"
+            }
+        )),
+        Some(DestinationRule::Rollout(rollout_percent)) => response.reasons.push(format!(
+            "the visitor isn't among the {rollout_percent}% the destination is rolled out to, so the fallback URL is used"
+        )),
+        None => response
+            .reasons
+            .push("no destination rule matched, so the default destination is used".to_string()),
+    }
+
+    if body.confirmed {
+        response
+            .reasons
+            .push("the visitor confirmed they want to continue".to_string());
+    } else if let Some((domain, _)) = find_domain_warning(&ctx.env, &destination.url).await {
+        response.outcome = SimulatedOutcome::Warning;
+        response.reasons.push(format!(
+            "the destination falls under the flagged domain '{domain}'"
+        ));
+        return Response::from_json(&response);
+    }
+
+    response.outcome = SimulatedOutcome::Redirect;
+    response.status_code = status_code;
+    if let (Some(status_code), [_, _, ..]) = (status_code, link.status_codes.as_slice()) {
+        response.reasons.push(format!(
+            "the visitor was assigned the {status_code} status code, which they'll keep on later visits"
+        ));
+    }
+    if body
+        .ip
+        .is_some_and(|address| is_excluded_address(&ctx.env, &link, address))
+    {
+        response
+            .reasons
+            .push("visits from the visitor's IP address are excluded".to_string());
+    } else if body
+        .asn
+        .is_some_and(|asn| is_excluded_network(&ctx.env, &link, asn))
+    {
+        response
+            .reasons
+            .push("visits from datacenter networks are excluded".to_string());
+    } else {
+        response.counted = true;
+        let privacy = PrivacyConfig::from_env(&ctx.env);
+        if privacy.visit_stats {
+            let user_agent = body
+                .user_agent
+                .as_deref()
+                .filter(|_| privacy.user_agents)
+                .map(parse_user_agent);
+            response.recorded = Some(SimulatedVisitDetails {
+                country: body.country.filter(|_| privacy.countries),
+                browser: user_agent.map(|user_agent| user_agent.browser),
+                os: user_agent.map(|user_agent| user_agent.os),
+                device: user_agent.map(|user_agent| user_agent.device),
+                asn: body.asn.filter(|_| privacy.networks),
+                network: body
+                    .asn
+                    .filter(|_| privacy.networks)
+                    .map(|asn| classify_asn(&ctx.env, asn)),
+            });
+        } else {
+            response
+                .reasons
+                .push("visit stats aren't recorded".to_string());
+        }
+    }
+    response.destination = Some(destination.url);
+
+    Response::from_json(&response)
+}
+
+/// Add the link's UTM parameters to the given destination and forward the simulated path and query to it if the link
+/// forwards them, explaining why.
+fn forward_simulated_visit(
+    path: Option<&str>,
+    query: Option<&str>,
+    link: &LinkModel,
+    destination: &mut Url,
+    reasons: &mut Vec<String>,
+) {
+    if let Some(utm) = &link.utm {
+        merge_query_pairs(destination, &utm.query_pairs());
+        reasons.push("the link adds its UTM parameters to the destination".to_string());
+    }
+    if let Some(path) = path.filter(|_| link.forward_path) {
+        append_path(destination, path.trim_start_matches('/'));
+        reasons.push(
+            "the link forwards the rest of the path it was visited with to the destination"
+                .to_string(),
+        );
+    }
+    let Some(query) = query.filter(|_| link.forward_query) else {
+        return;
+    };
+    let pairs = form_urlencoded::parse(query.trim_start_matches('?').as_bytes())
+        .filter(|(key, _)| key != CONFIRM_QUERY_PARAM)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    if !pairs.is_empty() {
+        merge_query_pairs(destination, &pairs);
+        reasons
+            .push("the link forwards the query it was visited with to the destination".to_string());
+    }
+}
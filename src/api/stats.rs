@@ -0,0 +1,45 @@
+use super::responses::StorageStatsResponse;
+use crate::{
+    authentication::authorized_guard,
+    models::usage::StorageUsageModel,
+    storage::{get_storage, StorageDriver},
+    usage::{measure_storage_usage, StorageAlertThresholds},
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// The query parameter used to measure storage usage now instead of returning the last measurement.
+const REFRESH_QUERY_PARAM: &str = "refresh";
+
+/// Get how much data is held in storage across every tenant, as of the last scheduled measurement.
+///
+/// Usage is measured now if it hasn't been measured yet or `?refresh=true` is given.
+pub async fn storage_stats_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let refresh = req
+        .url()?
+        .query_pairs()
+        .any(|(key, value)| key == REFRESH_QUERY_PARAM && value == "true");
+    let measurement = match storage
+        .get_deserialized_json::<StorageUsageModel>(StorageUsageModel::key())
+        .await
+    {
+        Some(measurement) if !refresh => measurement,
+        _ => measure_storage_usage(&storage, &ctx.env).await,
+    };
+
+    let thresholds = StorageAlertThresholds::from_env(&ctx.env);
+    Response::from_json(&StorageStatsResponse {
+        usage: measurement.usage,
+        measured_at_timestamp: measurement.measured_at_timestamp,
+        alert_keys: thresholds.keys,
+        alert_bytes: thresholds.bytes,
+    })
+}
@@ -0,0 +1,157 @@
+use super::{requests::SuggestSlugsRequestBody, responses::SuggestSlugsResponse};
+use crate::{
+    authentication::authorized_guard,
+    messages::INVALID_PAYLOAD_RESPONSE,
+    slugs::{
+        generate_unique_id, get_random_id_alphabet, get_random_id_length, is_valid_id,
+        random_words, short_hash, slugify,
+    },
+    storage::StorageDriver,
+    tenants::get_request_storage,
+};
+use futures_util::StreamExt;
+use url::Url;
+use worker::{Context, Fetch, Request, Response, RouteContext};
+
+/// The amount of random word suggestions to include.
+const RANDOM_WORD_SUGGESTIONS: usize = 2;
+
+/// The amount of attempts to make at finding an available variant of a suggestion.
+const MAX_SUGGESTION_ATTEMPTS: usize = 4;
+
+/// The length of the hash-based suggestion.
+const HASH_SUGGESTION_LENGTH: usize = 6;
+
+/// The most bytes of a page that are read when looking for its title.
+const MAX_TITLE_SCAN_BYTES: usize = 64 * 1024;
+
+/// The tag that ends a page's title, after which the rest of the page doesn't need to be read.
+const TITLE_END_TAG: &[u8] = b"</title>";
+
+/// Suggest several available, human-friendly link IDs for the given destination URL.
+pub async fn suggest_slugs_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let Ok(body) = req.json::<SuggestSlugsRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+
+    let mut suggestions = Vec::new();
+
+    // Prefer the page title, falling back to the URL itself if it can't be fetched.
+    let title_slug = match fetch_page_title(&body.url).await {
+        Some(title) if !slugify(&title).is_empty() => slugify(&title),
+        _ => slugify_url(&body.url),
+    };
+    if !title_slug.is_empty() {
+        if let Some(slug) = find_available(&storage, |attempt| match attempt {
+            0 => title_slug.clone(),
+            n => format!("{title_slug}-{}", n + 1),
+        })
+        .await
+        {
+            suggestions.push(slug);
+        }
+    }
+
+    for _ in 0..RANDOM_WORD_SUGGESTIONS {
+        if let Some(slug) = find_available(&storage, |_| random_words(2)).await {
+            if !suggestions.contains(&slug) {
+                suggestions.push(slug);
+            }
+        }
+    }
+
+    if let Some(slug) = find_available(&storage, |attempt| {
+        short_hash(body.url.as_str(), HASH_SUGGESTION_LENGTH + attempt)
+    })
+    .await
+    {
+        suggestions.push(slug);
+    }
+
+    if let Some(id) = generate_unique_id(
+        &storage,
+        &get_random_id_alphabet(&ctx.env),
+        get_random_id_length(&ctx.env),
+    )
+    .await
+    {
+        suggestions.push(id);
+    }
+
+    Response::from_json(&SuggestSlugsResponse { suggestions })
+}
+
+/// Find the first candidate that is not already in use as a link ID.
+async fn find_available(
+    storage: &impl StorageDriver,
+    candidate: impl Fn(usize) -> String,
+) -> Option<String> {
+    for attempt in 0..MAX_SUGGESTION_ATTEMPTS {
+        let slug = candidate(attempt);
+        if is_valid_id(&slug) && storage.get(&slug).await.is_none() {
+            return Some(slug);
+        }
+    }
+    None
+}
+
+/// Attempt to fetch the given URL and extract the contents of its `<title>` element.
+async fn fetch_page_title(url: &Url) -> Option<String> {
+    let mut response = Fetch::Url(url.clone()).send().await.ok()?;
+
+    // Only the start of the page is read, as that's where the title is and pages can be arbitrarily large.
+    let mut body = response.stream().ok()?;
+    let mut bytes = Vec::new();
+    while bytes.len() < MAX_TITLE_SCAN_BYTES
+        && !bytes
+            .to_ascii_lowercase()
+            .windows(TITLE_END_TAG.len())
+            .any(|window| window == TITLE_END_TAG)
+    {
+        match body.next().await {
+            Some(chunk) => bytes.extend(chunk.ok()?),
+            None => break,
+        }
+    }
+    bytes.truncate(MAX_TITLE_SCAN_BYTES);
+    let html = String::from_utf8_lossy(&bytes);
+
+    // Lowercasing ASCII keeps byte offsets identical to the original document.
+    let lowercase_html = html.to_ascii_lowercase();
+    let tag_start = lowercase_html.find("<title")?;
+    let start = tag_start + lowercase_html[tag_start..].find('>')? + 1;
+    let end = start + lowercase_html[start..].find("</title>")?;
+
+    Some(
+        html[start..end]
+            .trim()
+            .replace("&amp;", "&")
+            .replace("&#39;", "'")
+            .replace("&quot;", "\""),
+    )
+}
+
+/// Derive a slug from the host and last path segment of a URL.
+fn slugify_url(url: &Url) -> String {
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches("www.");
+    let host = host.split('.').next().unwrap_or_default();
+    match url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+    {
+        Some(segment) if !segment.is_empty() => slugify(&format!("{host} {segment}")),
+        _ => slugify(host),
+    }
+}
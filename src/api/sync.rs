@@ -0,0 +1,204 @@
+use super::{
+    requests::{CreateLinkRequestBody, SyncLinkRecord, SyncLinksRequestBody, MAX_SYNC_LINKS},
+    responses::{SyncLinkAction, SyncLinkResult, SyncLinksResponse},
+};
+use crate::{
+    authentication::authorized_key_guard,
+    delete_link,
+    durable_objects::link_lock::with_link_lock,
+    link_cache::invalidate_cached_link,
+    links::{check_link_update, is_link_unchanged, prepare_link_request, save_link, SaveLinkError},
+    messages::*,
+    models::link::LinkModel,
+    notifications::{notify_in_background, Notification},
+    slugs::{canonicalize_decoded_id, is_valid_id},
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::resolve_tenant,
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use url::Url;
+use worker::{Context, Env, Request, Response, RouteContext};
+
+/// Parse and prepare the link a sync declares, forcing it to be saved exactly under its declared ID.
+fn prepare_sync_record(
+    env: &Env,
+    host_url: &Url,
+    mut link: Map<String, Value>,
+) -> Result<CreateLinkRequestBody, SaveLinkError> {
+    link.insert("overwrite".to_string(), Value::Bool(true));
+    link.insert("normalize_id".to_string(), Value::Bool(false));
+    let Ok(mut body) = serde_json::from_value::<CreateLinkRequestBody>(Value::Object(link)) else {
+        return Err(SaveLinkError::InvalidPayload);
+    };
+    if body.dedupe {
+        return Err(SaveLinkError::DedupeWithId);
+    }
+    prepare_link_request(env, host_url, &mut body)?;
+    Ok(body)
+}
+
+/// Plan what a sync does to a declared link, checking it could be saved without writing anything.
+async fn plan_sync_record(
+    storage: &impl StorageDriver,
+    env: &Env,
+    host_url: &Url,
+    id: &str,
+    link: &Map<String, Value>,
+) -> (SyncLinkAction, Result<(), SaveLinkError>) {
+    let existing = storage.get_deserialized_json::<LinkModel>(id).await;
+    let action = match existing {
+        Some(_) => SyncLinkAction::Update,
+        None => SyncLinkAction::Create,
+    };
+    if !is_valid_id(id) {
+        return (action, Err(SaveLinkError::InvalidId));
+    }
+    let body = match prepare_sync_record(env, host_url, link.clone()) {
+        Ok(body) => body,
+        Err(err) => return (action, Err(err)),
+    };
+    let Some(existing) = existing else {
+        return (action, Ok(()));
+    };
+    if let Err(err) = check_link_update(&existing, &body) {
+        return (action, Err(err));
+    }
+    if is_link_unchanged(&existing, body) {
+        (SyncLinkAction::Unchanged, Ok(()))
+    } else {
+        (action, Ok(()))
+    }
+}
+
+/// Save the prepared link declared by a sync, or delete the link if [`None`], returning why it failed if it did.
+///
+/// Callers should hold the link's lock, as deleting and saving links expect.
+async fn apply_sync_change(
+    storage: &impl StorageDriver,
+    ctx: &RouteContext<Context>,
+    tenant: Option<&str>,
+    key: &str,
+    id: &str,
+    link: Option<CreateLinkRequestBody>,
+) -> Result<(), &'static str> {
+    let Some(link) = link else {
+        return delete_link(storage, ctx, tenant, id, key)
+            .await
+            .map_err(|err| err.message());
+    };
+
+    let saved = save_link(storage, &ctx.env, id.to_string(), link, false)
+        .await
+        .map_err(|err| err.message())?;
+    if saved.created {
+        notify_in_background(
+            &ctx.data,
+            &ctx.env,
+            Notification::LinkCreated {
+                id: saved.id,
+                tenant: tenant.map(str::to_string),
+            },
+        );
+    } else {
+        invalidate_cached_link(&ctx.env, tenant, id).await;
+    }
+    Ok(())
+}
+
+/// Reconcile the links under a prefix with a declared list of links, for managing links from infrastructure-as-code
+/// pipelines.
+///
+/// Declared links that don't exist are created, those that differ are updated and links under the prefix that
+/// aren't declared are deleted. With `"dry_run": true` the response is the plan of what would change without
+/// writing anything.
+pub async fn sync_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let key = match authorized_key_guard(&req, &ctx).await {
+        Ok(key) => key,
+        Err(err) => return err,
+    };
+
+    let Ok(body) = req.json::<SyncLinksRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.links.len() as u64 > MAX_SYNC_LINKS {
+        return Response::error(INVALID_SYNC_LINKS_RESPONSE, 400);
+    }
+
+    // Declared IDs decide which links are deleted, so they have to be unambiguous before anything is written.
+    let mut declared = BTreeSet::new();
+    let mut records = Vec::with_capacity(body.links.len());
+    for SyncLinkRecord { id, link } in body.links {
+        let id = canonicalize_decoded_id(&id);
+        if !id.starts_with(&body.prefix) || !declared.insert(id.clone()) {
+            return Response::error(INVALID_SYNC_LINKS_RESPONSE, 400);
+        }
+        records.push((id, link));
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let host_url = req.url()?;
+    let mut plan = Vec::with_capacity(records.len());
+    for (id, link) in records {
+        let (action, outcome) = plan_sync_record(&storage, &ctx.env, &host_url, &id, &link).await;
+        plan.push((id, action, outcome.map(|_| link)));
+    }
+    // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+    for id in storage.list(&body.prefix).await {
+        if !id.contains(':') && !declared.contains(&id) {
+            plan.push((id, SyncLinkAction::Delete, Ok(Map::new())));
+        }
+    }
+
+    let mut response = SyncLinksResponse {
+        dry_run: body.dry_run,
+        ..Default::default()
+    };
+    for (id, action, planned) in plan {
+        let mut outcome = planned.as_ref().map(|_| ()).map_err(|err| err.message());
+        if let (Ok(link), false) = (planned, body.dry_run || action == SyncLinkAction::Unchanged) {
+            let link = match action {
+                SyncLinkAction::Delete => Ok(None),
+                _ => prepare_sync_record(&ctx.env, &host_url, link).map(Some),
+            };
+            match link {
+                Ok(link) => {
+                    let lock_response = with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+                        outcome =
+                            apply_sync_change(&storage, &ctx, tenant.as_deref(), &key, &id, link)
+                                .await;
+                        Response::empty()
+                    })
+                    .await?;
+                    if lock_response.status_code() == 409 {
+                        outcome = Err(LINK_LOCKED_RESPONSE);
+                    }
+                }
+                Err(err) => outcome = Err(err.message()),
+            }
+        }
+
+        let error = match outcome {
+            Ok(()) => {
+                match action {
+                    SyncLinkAction::Create => response.created += 1,
+                    SyncLinkAction::Update => response.updated += 1,
+                    SyncLinkAction::Delete => response.deleted += 1,
+                    SyncLinkAction::Unchanged => response.unchanged += 1,
+                }
+                None
+            }
+            Err(error) => {
+                response.failed += 1;
+                Some(error)
+            }
+        };
+        response.results.push(SyncLinkResult { id, action, error });
+    }
+
+    Response::from_json(&response)
+}
@@ -0,0 +1,46 @@
+use super::{requests::WarmLinksRequestBody, responses::WarmLinksResponse};
+use crate::{
+    authentication::authorized_guard, messages::INVALID_PAYLOAD_RESPONSE,
+    slugs::canonicalize_decoded_id, storage::StorageDriver, tenants::get_request_storage,
+};
+use std::time::Duration;
+use validator::Validate;
+use worker::{Context, Request, Response, RouteContext};
+
+/// How long warmed links are cached for when the request doesn't say.
+const DEFAULT_WARM_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Load the given links into the edge cache ahead of expected traffic, such as right before a launch
+/// or mass email send, so that the first wave of visits doesn't all miss the cache.
+///
+/// Only the cache of the location handling this request is warmed.
+pub async fn warm_links_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let Ok(body) = req.json::<WarmLinksRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.validate().is_err() {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    }
+
+    let cache_ttl = body.cache_for.unwrap_or(DEFAULT_WARM_CACHE_TTL);
+    let mut response = WarmLinksResponse::default();
+    for id in body.ids {
+        let id = canonicalize_decoded_id(&id);
+        if storage.warm(&id, cache_ttl).await {
+            response.warmed.push(id);
+        } else {
+            response.missing.push(id);
+        }
+    }
+
+    Response::from_json(&response)
+}
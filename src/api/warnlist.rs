@@ -0,0 +1,111 @@
+use super::requests::SetWarnedDomainRequestBody;
+use crate::{
+    authentication::authorized_guard,
+    messages::*,
+    models::warnlist::{WarnListModel, WarnedDomain},
+    storage::{get_storage, StorageDriver},
+    warnings::normalize_domain,
+};
+use worker::{Context, Request, Response, RouteContext};
+
+/// Get the normalized domain from the route parameters of a request.
+fn get_domain_from_route(ctx: &RouteContext<Context>) -> worker::Result<String> {
+    let Some(domain) = ctx.param("domain") else {
+        Err("Unable to find domain from request URL.")?
+    };
+    Ok(normalize_domain(domain))
+}
+
+/// List the flagged destination domains stored in the warn list.
+///
+/// Domains flagged through the environment aren't included.
+pub async fn get_warnlist_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let warn_list = storage
+        .get_deserialized_json::<WarnListModel>(WarnListModel::key())
+        .await
+        .unwrap_or_default();
+
+    Response::from_json(&warn_list)
+}
+
+/// Add a destination domain to the warn list, replacing its warning if it's already flagged.
+///
+/// The request body is optional and can set the warning shown to visitors.
+pub async fn set_warned_domain_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let domain = get_domain_from_route(&ctx)?;
+    if domain.is_empty() {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    }
+    let body = match req.text().await {
+        Ok(text) if text.trim().is_empty() => SetWarnedDomainRequestBody::default(),
+        Ok(text) => match serde_json::from_str::<SetWarnedDomainRequestBody>(&text) {
+            Ok(body) => body,
+            Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+        },
+        Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+    };
+
+    let mut warn_list = storage
+        .get_deserialized_json::<WarnListModel>(WarnListModel::key())
+        .await
+        .unwrap_or_default();
+    warn_list
+        .domains
+        .insert(domain, WarnedDomain::new(body.reason));
+    if !storage
+        .set_serialized_json(WarnListModel::key(), &warn_list, None)
+        .await
+    {
+        return Response::error(GENERIC_WARNLIST_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::from_json(&warn_list)
+}
+
+/// Remove a destination domain from the warn list.
+pub async fn delete_warned_domain_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let domain = get_domain_from_route(&ctx)?;
+    let mut warn_list = storage
+        .get_deserialized_json::<WarnListModel>(WarnListModel::key())
+        .await
+        .unwrap_or_default();
+    if warn_list.domains.remove(&domain).is_none() {
+        return Response::error(WARNED_DOMAIN_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    if !storage
+        .set_serialized_json(WarnListModel::key(), &warn_list, None)
+        .await
+    {
+        return Response::error(GENERIC_WARNLIST_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(WARNED_DOMAIN_DELETE_SUCCESS_RESPONSE)
+}
@@ -0,0 +1,218 @@
+use super::{
+    requests::CreateWebhookRequestBody,
+    responses::{ListWebhookFailuresResponse, ListWebhooksResponse, WebhookResponse},
+};
+use crate::{
+    authentication::authorized_guard,
+    messages::*,
+    models::{
+        webhook::{WebhookEndpoint, WebhooksModel},
+        webhook_failure::WebhookFailureModel,
+    },
+    notifications::{generate_webhook_secret, send_delivery, NotificationDelivery},
+    slugs::{get_random_id_alphabet, random_id},
+    storage::{get_storage, StorageDriver},
+};
+use validator::Validate;
+use worker::{Context, Date, Request, Response, RouteContext};
+
+/// The length of generated webhook IDs.
+const WEBHOOK_ID_LENGTH: usize = 12;
+
+/// List the webhooks added with the API, without their signing secrets.
+pub async fn list_webhooks_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let webhooks = storage
+        .get_deserialized_json::<WebhooksModel>(WebhooksModel::key())
+        .await
+        .unwrap_or_default();
+
+    Response::from_json(&ListWebhooksResponse {
+        webhooks: webhooks
+            .endpoints
+            .into_iter()
+            .map(|(id, webhook)| WebhookResponse::from_endpoint(id, webhook, false))
+            .collect(),
+    })
+}
+
+/// Add a webhook that's sent the given events, returning its signing secret.
+///
+/// The secret is only ever returned here, so it has to be kept by the caller.
+pub async fn create_webhook_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Ok(body) = req.json::<CreateWebhookRequestBody>().await else {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+    if body.validate().is_err() {
+        return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    }
+    if !matches!(body.url.scheme(), "http" | "https") {
+        return Response::error(INVALID_WEBHOOK_URL_RESPONSE, 400);
+    }
+    let Some(secret) = body.secret.or_else(generate_webhook_secret) else {
+        return Response::error(GENERIC_WEBHOOK_UPDATE_ERROR_RESPONSE, 500);
+    };
+
+    let mut webhooks = storage
+        .get_deserialized_json::<WebhooksModel>(WebhooksModel::key())
+        .await
+        .unwrap_or_default();
+    let id = random_id(&get_random_id_alphabet(&ctx.env), WEBHOOK_ID_LENGTH);
+    let webhook = WebhookEndpoint::new(body.url, body.events, secret);
+    webhooks.endpoints.insert(id.clone(), webhook.clone());
+    if !storage
+        .set_serialized_json(WebhooksModel::key(), &webhooks, None)
+        .await
+    {
+        return Response::error(GENERIC_WEBHOOK_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::from_json(&WebhookResponse::from_endpoint(id, webhook, true))
+}
+
+/// Remove a webhook added with the API, so it's no longer sent any notifications.
+pub async fn delete_webhook_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let Some(webhook_id) = ctx.param("webhook_id") else {
+        Err("Unable to find webhook ID from request URL.")?
+    };
+    let mut webhooks = storage
+        .get_deserialized_json::<WebhooksModel>(WebhooksModel::key())
+        .await
+        .unwrap_or_default();
+    if webhooks.endpoints.remove(webhook_id).is_none() {
+        return Response::error(WEBHOOK_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    if !storage
+        .set_serialized_json(WebhooksModel::key(), &webhooks, None)
+        .await
+    {
+        return Response::error(GENERIC_WEBHOOK_UPDATE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(WEBHOOK_DELETE_SUCCESS_RESPONSE)
+}
+
+/// Get the key of the failed delivery from the route parameters of a request.
+fn get_failure_key_from_route(ctx: &RouteContext<Context>) -> worker::Result<String> {
+    let Some(delivery_id) = ctx.param("delivery_id") else {
+        Err("Unable to find delivery ID from request URL.")?
+    };
+    Ok(WebhookFailureModel::key(delivery_id))
+}
+
+/// List the notifications that couldn't be delivered to a webhook after every attempt.
+pub async fn list_webhook_failures_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let mut failures = Vec::new();
+    for key in storage.list(WebhookFailureModel::key_prefix()).await {
+        if let Some(failure) = storage
+            .get_deserialized_json::<WebhookFailureModel>(&key)
+            .await
+        {
+            failures.push(failure);
+        }
+    }
+    failures.sort_by_key(|failure| failure.failed_at_timestamp);
+
+    Response::from_json(&ListWebhookFailuresResponse { failures })
+}
+
+/// Attempt to deliver a failed notification to its webhook again, removing it once it's delivered.
+pub async fn replay_webhook_failure_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let key = get_failure_key_from_route(&ctx)?;
+    let Some(mut failure) = storage
+        .get_deserialized_json::<WebhookFailureModel>(&key)
+        .await
+    else {
+        return Response::error(WEBHOOK_FAILURE_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    let delivery = NotificationDelivery {
+        delivery_id: failure.id.clone(),
+        webhook_id: failure.webhook_id.clone(),
+        body: failure.notification.to_string(),
+        attempts: failure.attempts,
+        created_at_timestamp: failure.created_at_timestamp,
+    };
+    match send_delivery(&ctx.env, &delivery).await {
+        Ok(()) => {
+            storage.delete(&key).await;
+            Response::ok(WEBHOOK_REPLAY_SUCCESS_RESPONSE)
+        }
+        Err(None) => Response::error(WEBHOOK_DOESNT_EXIST_RESPONSE, 404),
+        Err(Some(error)) => {
+            failure.attempts += 1;
+            failure.last_error = error;
+            failure.failed_at_timestamp = Date::now().as_millis();
+            storage.set_serialized_json(&key, &failure, None).await;
+            Response::error(WEBHOOK_REPLAY_FAILED_RESPONSE, 502)
+        }
+    }
+}
+
+/// Discard a failed notification without delivering it.
+pub async fn delete_webhook_failure_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let storage = get_storage(&ctx.env)?;
+    let key = get_failure_key_from_route(&ctx)?;
+    if storage.get(&key).await.is_none() {
+        return Response::error(WEBHOOK_FAILURE_DOESNT_EXIST_RESPONSE, 404);
+    }
+
+    if !storage.delete(&key).await {
+        return Response::error(GENERIC_WEBHOOK_FAILURE_DELETE_ERROR_RESPONSE, 500);
+    }
+
+    Response::ok(WEBHOOK_FAILURE_DELETE_SUCCESS_RESPONSE)
+}
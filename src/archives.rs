@@ -0,0 +1,183 @@
+/// The DOS date of every file in an archive, 1980-01-01, as entries are generated rather than modified.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// The general purpose flag marking a file name as UTF-8, as link IDs may contain emoji.
+const UTF8_NAME_FLAG: u16 = 1 << 11;
+
+/// Compute the CRC-32 (IEEE) checksum of some data, as used by ZIP archives and PNG images.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+/// A file that has been written to an archive, which is listed again in its central directory.
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes a ZIP archive one file at a time, so it can be streamed to the client as it's built.
+///
+/// Files are stored without compression, as the images it's used for are either already compressed or small.
+#[derive(Default)]
+pub struct ZipWriter {
+    entries: Vec<ZipEntry>,
+    /// The amount of bytes written so far.
+    offset: u32,
+}
+
+impl ZipWriter {
+    /// Add a file to the archive, returning the bytes to write for it.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Vec<u8> {
+        let entry = ZipEntry {
+            name: name.to_string(),
+            crc: crc32(data),
+            size: data.len() as u32,
+            offset: self.offset,
+        };
+
+        let mut bytes = Vec::with_capacity(30 + name.len() + data.len());
+        bytes.extend(0x0403_4B50u32.to_le_bytes());
+        bytes.extend(20u16.to_le_bytes());
+        bytes.extend(entry.header_fields());
+        bytes.extend(0u16.to_le_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.extend(data);
+
+        self.offset += bytes.len() as u32;
+        self.entries.push(entry);
+        bytes
+    }
+
+    /// Finish the archive, returning the bytes of its central directory.
+    pub fn finish(self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for entry in &self.entries {
+            bytes.extend(0x0201_4B50u32.to_le_bytes());
+            bytes.extend(20u16.to_le_bytes());
+            bytes.extend(20u16.to_le_bytes());
+            bytes.extend(entry.header_fields());
+            // No extra field, comment, disk number or attributes.
+            bytes.extend([0; 12]);
+            bytes.extend(entry.offset.to_le_bytes());
+            bytes.extend(entry.name.as_bytes());
+        }
+
+        let count = self.entries.len() as u16;
+        let directory_size = bytes.len() as u32;
+        bytes.extend(0x0605_4B50u32.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(directory_size.to_le_bytes());
+        bytes.extend(self.offset.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        bytes
+    }
+}
+
+impl ZipEntry {
+    /// The fields shared by the local file header and central directory, from the flags up to the name length.
+    fn header_fields(&self) -> Vec<u8> {
+        let mut fields = Vec::with_capacity(22);
+        fields.extend(UTF8_NAME_FLAG.to_le_bytes());
+        // Stored without compression, at midnight.
+        fields.extend([0; 4]);
+        fields.extend(DOS_DATE.to_le_bytes());
+        fields.extend(self.crc.to_le_bytes());
+        fields.extend(self.size.to_le_bytes());
+        fields.extend(self.size.to_le_bytes());
+        fields.extend((self.name.len() as u16).to_le_bytes());
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+    use zip::{CompressionMethod, ZipArchive};
+
+    const FILES: [(&str, &[u8]); 3] = [
+        ("launch.png", b"\x89PNG not really an image"),
+        (
+            "team/🔥.svg",
+            b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>",
+        ),
+        ("empty.txt", b""),
+    ];
+
+    fn archive() -> (Vec<u8>, Vec<usize>) {
+        let mut writer = ZipWriter::default();
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::new();
+        for (name, data) in FILES {
+            offsets.push(bytes.len());
+            bytes.extend(writer.add_file(name, data));
+        }
+        bytes.extend(writer.finish());
+        (bytes, offsets)
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn archives_can_be_read_back() {
+        let (bytes, offsets) = archive();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), FILES.len());
+
+        for (index, (name, data)) in FILES.into_iter().enumerate() {
+            let mut file = archive.by_index(index).unwrap();
+            assert_eq!(file.name(), name);
+            assert_eq!(file.compression(), CompressionMethod::Stored);
+            assert_eq!(file.size(), data.len() as u64);
+            assert_eq!(file.compressed_size(), data.len() as u64);
+            assert_eq!(file.crc32(), crc32(data));
+            assert_eq!(file.header_start(), offsets[index] as u64);
+
+            // Reading the whole file also has the reader verify its CRC.
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents).unwrap();
+            assert_eq!(contents, data);
+        }
+        assert!(archive.by_name("team/🔥.svg").is_ok());
+    }
+
+    #[test]
+    fn end_of_central_directory_points_at_the_central_directory() {
+        let (bytes, offsets) = archive();
+        let end = &bytes[bytes.len() - 22..];
+        assert_eq!(end[..4], 0x0605_4B50u32.to_le_bytes());
+        assert_eq!(end[8..10], (FILES.len() as u16).to_le_bytes());
+        assert_eq!(end[10..12], (FILES.len() as u16).to_le_bytes());
+
+        let directory_size = u32::from_le_bytes(end[12..16].try_into().unwrap()) as usize;
+        let directory_offset = u32::from_le_bytes(end[16..20].try_into().unwrap()) as usize;
+        assert_eq!(directory_offset + directory_size, bytes.len() - 22);
+
+        // Every central directory record points back at its file's local header.
+        let mut record = &bytes[directory_offset..directory_offset + directory_size];
+        for offset in offsets {
+            assert_eq!(record[..4], 0x0201_4B50u32.to_le_bytes());
+            assert_eq!(record[42..46], (offset as u32).to_le_bytes());
+            assert_eq!(bytes[offset..offset + 4], 0x0403_4B50u32.to_le_bytes());
+            let name_length = u16::from_le_bytes([record[28], record[29]]) as usize;
+            record = &record[46 + name_length..];
+        }
+        assert!(record.is_empty());
+    }
+}
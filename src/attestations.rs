@@ -0,0 +1,171 @@
+use crate::{environment::get_var, models::link::LinkModel};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use url::Url;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use worker::{
+    js_sys::{global, Array, Function, Promise, Reflect, Uint8Array, JSON},
+    Env,
+};
+
+/// The binding name for the secret holding the Ed25519 private key attestations are signed with, as a JWK.
+///
+/// Attestations are disabled unless this is set.
+pub const ATTESTATION_SIGNING_KEY_BINDING: &str = "ATTESTATION_SIGNING_KEY";
+
+/// The signature algorithm attestations are signed with, as named by WebCrypto.
+pub const ATTESTATION_ALGORITHM: &str = "Ed25519";
+
+/// A statement of where a link sends visitors at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationStatement {
+    /// The host the link is served from, so a statement can't be replayed for another deployment.
+    pub host: String,
+    pub id: String,
+    /// The default destination of the link.
+    pub url: Url,
+    /// Every other destination visitors of the link may be sent to, such as language rules and variants, sorted.
+    pub alternate_urls: Vec<Url>,
+    /// The UNIX timestamp until which the link's destinations can't be changed, if they're frozen.
+    pub freeze_until_timestamp: Option<u64>,
+    /// The UNIX timestamp of when the link was last modified.
+    pub modified_at_timestamp: u64,
+    /// The UNIX timestamp of when the statement was made.
+    pub attested_at_timestamp: u64,
+}
+
+impl AttestationStatement {
+    /// Create the statement for a link as it currently is.
+    pub fn new(host: String, id: String, link: &LinkModel, timestamp: u64) -> Self {
+        let mut alternate_urls = link
+            .language_rules
+            .values()
+            .chain(link.geo_targets.values())
+            .chain(link.variants.iter().map(|variant| &variant.url))
+            .chain(link.fallback_url.iter())
+            .filter(|url| **url != link.url)
+            .cloned()
+            .collect::<Vec<_>>();
+        alternate_urls.sort();
+        alternate_urls.dedup();
+        Self {
+            host,
+            id,
+            url: link.url.clone(),
+            alternate_urls,
+            freeze_until_timestamp: link.freeze_until_timestamp,
+            modified_at_timestamp: link.modified_at_timestamp,
+            attested_at_timestamp: timestamp,
+        }
+    }
+}
+
+/// Whether or not attestations can be signed with the current environment.
+pub fn attestations_enabled(env: &Env) -> bool {
+    get_var(env, ATTESTATION_SIGNING_KEY_BINDING).is_some()
+}
+
+/// Get the `SubtleCrypto` instance of the runtime.
+fn subtle_crypto() -> Option<JsValue> {
+    let crypto = Reflect::get(&global(), &"crypto".into()).ok()?;
+    Reflect::get(&crypto, &"subtle".into()).ok()
+}
+
+/// Call a method of `SubtleCrypto` with the given arguments, awaiting the promise it returns.
+pub async fn call_subtle_crypto(method: &str, args: &Array) -> Option<JsValue> {
+    let subtle = subtle_crypto()?;
+    let function = Reflect::get(&subtle, &method.into())
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+    let promise = function
+        .apply(&subtle, args)
+        .ok()?
+        .dyn_into::<Promise>()
+        .ok()?;
+    JsFuture::from(promise).await.ok()
+}
+
+/// Import a key in JWK format for the given usage (`sign` or `verify`) with WebCrypto.
+async fn import_key(jwk: &str, usage: &str) -> Option<JsValue> {
+    let jwk = JSON::parse(jwk).ok()?;
+    call_subtle_crypto(
+        "importKey",
+        &Array::of5(
+            &"jwk".into(),
+            &jwk,
+            &ATTESTATION_ALGORITHM.into(),
+            &false.into(),
+            &Array::of1(&usage.into()),
+        ),
+    )
+    .await
+}
+
+/// Get the public half of the signing key from the environment as a JWK, which can be published so
+/// attestations can be verified without contacting the worker.
+pub fn public_key_jwk(env: &Env) -> Option<Map<String, Value>> {
+    let private_key =
+        serde_json::from_str::<Map<String, Value>>(&get_var(env, ATTESTATION_SIGNING_KEY_BINDING)?)
+            .ok()?;
+    // Only the members describing the public key are kept, so the private key (`d`) is never exposed.
+    let mut public_key = ["kty", "crv", "x", "kid"]
+        .into_iter()
+        .filter_map(|member| Some((member.to_string(), private_key.get(member)?.clone())))
+        .collect::<Map<_, _>>();
+    public_key.insert("use".to_string(), "sig".into());
+    public_key.contains_key("x").then_some(public_key)
+}
+
+/// Sign a payload with the signing key from the environment, returning the signature as lowercase hex.
+///
+/// Returns [`None`] if no signing key is set or it isn't a valid Ed25519 private key.
+pub async fn sign_payload(env: &Env, payload: &str) -> Option<String> {
+    let key = import_key(&get_var(env, ATTESTATION_SIGNING_KEY_BINDING)?, "sign").await?;
+    let data = Uint8Array::from(payload.as_bytes());
+    let signature = call_subtle_crypto(
+        "sign",
+        &Array::of3(&ATTESTATION_ALGORITHM.into(), &key, &data),
+    )
+    .await?;
+    Some(to_hex(&Uint8Array::new(&signature).to_vec()))
+}
+
+/// Verify a hex-encoded signature of a payload against the public half of the signing key from the environment.
+///
+/// Returns [`None`] if no signing key is set or it isn't a valid Ed25519 key.
+pub async fn verify_payload(env: &Env, payload: &str, signature: &str) -> Option<bool> {
+    let public_key = serde_json::to_string(&public_key_jwk(env)?).ok()?;
+    let key = import_key(&public_key, "verify").await?;
+    let Some(signature) = from_hex(signature) else {
+        return Some(false);
+    };
+    let valid = call_subtle_crypto(
+        "verify",
+        &Array::of4(
+            &ATTESTATION_ALGORITHM.into(),
+            &key,
+            &Uint8Array::from(signature.as_slice()),
+            &Uint8Array::from(payload.as_bytes()),
+        ),
+    )
+    .await?;
+    valid.as_bool()
+}
+
+/// Encode bytes as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decode hex into bytes, or [`None`] if it isn't valid hex.
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
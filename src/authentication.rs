@@ -1,28 +1,55 @@
-use crate::messages::{
-    FORBIDDEN_REQUEST_RESPONSE, NOT_INITIALISED_WITH_AUTHTOKEN_RESPONSE,
-    UNAUTHORIZED_REQUEST_RESPONSE,
+use crate::{
+    durable_objects::rate_limiter::{take_tokens, RateLimit},
+    environment::get_var,
+    messages::{
+        FORBIDDEN_REQUEST_RESPONSE, NOT_INITIALISED_WITH_AUTHTOKEN_RESPONSE,
+        TOO_MANY_AUTH_FAILURES_RESPONSE, UNAUTHORIZED_REQUEST_RESPONSE,
+    },
 };
 use worker::{Request, Response, RouteContext};
 
 /// The binding name for the Authorization token variable set in the Cloudflare Worker env vars.
-const AUTH_TOKEN_BINDING: &str = "AUTH_TOKEN";
+pub const AUTH_TOKEN_BINDING: &str = "AUTH_TOKEN";
+
+/// The binding name for the secret holding a comma-separated list of additional tokens accepted alongside [`AUTH_TOKEN_BINDING`].
+pub const ADDITIONAL_AUTH_TOKENS_BINDING: &str = "ADDITIONAL_AUTH_TOKENS";
+
+/// The name of the API key a request authorized with [`AUTH_TOKEN_BINDING`] is attributed to.
+const PRIMARY_KEY_NAME: &str = "primary";
 
 /// The header to check to find the Authorization token.
 const AUTHORIZATION_HEADER: &str = "Authorization";
 
+/// The query parameter the Authorization token can be given in to endpoints meant for tools that can't set headers.
+pub const AUTH_TOKEN_QUERY_PARAM: &str = "api_key";
+
+/// The header containing the IP address of the client as seen by Cloudflare.
+const CONNECTING_IP_HEADER: &str = "CF-Connecting-IP";
+
+/// How many failed authentication attempts a single IP address can make before being locked out,
+/// regaining one attempt every minute.
+const AUTH_FAILURE_LIMIT: RateLimit = RateLimit {
+    capacity: 10,
+    refill_per_second: 1.0 / 60.0,
+};
+
 /// Represents a requests authorization state.
 #[derive(Debug)]
 enum AuthorizationState {
-    Authorized,
+    /// The request is authorized with the API key of the given name.
+    Authorized(String),
     Unauthorized,
     NoAuthorizationSent,
     InternalNoTokenSet,
 }
 
-/// Check if the request is authorized by comparing the Authorization header to the [`AUTH_TOKEN_BINDING`] value.
-fn is_request_authorized(
-    req: &Request,
-    ctx: &RouteContext<()>,
+/// Check if the request is authorized by comparing the token it sent to the [`AUTH_TOKEN_BINDING`] value
+/// and any [`ADDITIONAL_AUTH_TOKENS_BINDING`] values.
+///
+/// Keys are named by their position rather than anything derived from the token, e.g. `primary` or `additional-2`.
+fn is_request_authorized<D>(
+    auth_header: Option<String>,
+    ctx: &RouteContext<D>,
 ) -> worker::Result<AuthorizationState> {
     let auth_token = ctx.var(AUTH_TOKEN_BINDING)?.to_string();
 
@@ -32,26 +59,102 @@ fn is_request_authorized(
         return Ok(AuthorizationState::InternalNoTokenSet);
     }
 
-    let auth_header = match req.headers().get(AUTHORIZATION_HEADER)? {
+    let auth_header = match auth_header {
         Some(header) => header,
         None => return Ok(AuthorizationState::NoAuthorizationSent),
     };
 
     if auth_header == auth_token {
-        Ok(AuthorizationState::Authorized)
-    } else {
-        Ok(AuthorizationState::Unauthorized)
+        return Ok(AuthorizationState::Authorized(PRIMARY_KEY_NAME.to_string()));
+    }
+
+    let additional_tokens = get_var(&ctx.env, ADDITIONAL_AUTH_TOKENS_BINDING).unwrap_or_default();
+    let additional_key = additional_tokens
+        .split(',')
+        .map(str::trim)
+        .position(|token| !token.is_empty() && token == auth_header);
+    match additional_key {
+        Some(index) => Ok(AuthorizationState::Authorized(format!(
+            "additional-{}",
+            index + 1
+        ))),
+        None => Ok(AuthorizationState::Unauthorized),
     }
 }
 
 /// Guard a request by checking if it's authorized and returning a response value with an error if it isn't.
-pub fn authorized_guard(
+///
+/// If the rate limiter is bound, clients that repeatedly fail to authenticate are locked out for a
+/// while, even if they then provide the correct token.
+pub async fn authorized_guard<D>(
+    req: &Request,
+    ctx: &RouteContext<D>,
+) -> Result<(), worker::Result<worker::Response>> {
+    authorized_key_guard(req, ctx).await.map(|_| ())
+}
+
+/// Guard a request like [`authorized_guard`], returning the name of the API key it was authorized with.
+pub async fn authorized_key_guard<D>(
     req: &Request,
-    ctx: &RouteContext<()>,
+    ctx: &RouteContext<D>,
+) -> Result<String, worker::Result<worker::Response>> {
+    let auth_header = req.headers().get(AUTHORIZATION_HEADER).ok().flatten();
+    token_guard(req, ctx, auth_header).await
+}
+
+/// Guard a request like [`authorized_guard`], also accepting the token in the [`AUTH_TOKEN_QUERY_PARAM`] query
+/// parameter for clients that can't set headers.
+///
+/// Query parameters are more likely to end up in logs than headers, so this is only used by endpoints meant for
+/// no-code tools.
+pub async fn authorized_query_guard<D>(
+    req: &Request,
+    ctx: &RouteContext<D>,
 ) -> Result<(), worker::Result<worker::Response>> {
-    match is_request_authorized(req, ctx).unwrap() {
-        AuthorizationState::Authorized => Ok(()),
-        AuthorizationState::Unauthorized => Err(Response::error(FORBIDDEN_REQUEST_RESPONSE, 403)),
+    let auth_header = req
+        .headers()
+        .get(AUTHORIZATION_HEADER)
+        .ok()
+        .flatten()
+        .or_else(|| {
+            req.url().ok().and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == AUTH_TOKEN_QUERY_PARAM)
+                    .map(|(_, value)| value.into_owned())
+            })
+        });
+    token_guard(req, ctx, auth_header).await.map(|_| ())
+}
+
+/// Guard a request by checking the token it sent, returning the name of the API key it was authorized with.
+async fn token_guard<D>(
+    req: &Request,
+    ctx: &RouteContext<D>,
+    auth_header: Option<String>,
+) -> Result<String, worker::Result<worker::Response>> {
+    let lockout_key = req
+        .headers()
+        .get(CONNECTING_IP_HEADER)
+        .ok()
+        .flatten()
+        .map(|ip| format!("auth-failures:{ip}"));
+
+    if let Some(key) = &lockout_key {
+        if let Some(decision) = take_tokens(&ctx.env, key, AUTH_FAILURE_LIMIT, 0).await {
+            if !decision.allowed {
+                return Err(too_many_failures_response(decision.retry_after_seconds));
+            }
+        }
+    }
+
+    match is_request_authorized(auth_header, ctx).unwrap() {
+        AuthorizationState::Authorized(key) => Ok(key),
+        AuthorizationState::Unauthorized => {
+            if let Some(key) = &lockout_key {
+                take_tokens(&ctx.env, key, AUTH_FAILURE_LIMIT, 1).await;
+            }
+            Err(Response::error(FORBIDDEN_REQUEST_RESPONSE, 403))
+        }
         AuthorizationState::NoAuthorizationSent => {
             Err(Response::error(UNAUTHORIZED_REQUEST_RESPONSE, 401))
         }
@@ -61,3 +164,12 @@ pub fn authorized_guard(
         )),
     }
 }
+
+/// Create a response for a client that has been locked out after too many failed authentication attempts.
+fn too_many_failures_response(retry_after_seconds: u64) -> worker::Result<Response> {
+    let mut response = Response::error(TOO_MANY_AUTH_FAILURES_RESPONSE, 429)?;
+    response
+        .headers_mut()
+        .set("Retry-After", &retry_after_seconds.to_string())?;
+    Ok(response)
+}
@@ -0,0 +1,202 @@
+use crate::{
+    messages::{
+        LINK_DOESNT_EXIST_RESPONSE, LINK_GONE_RESPONSE, LINK_NOT_ACTIVE_RESPONSE,
+        LINK_PAUSED_RESPONSE, LINK_WARNING_RESPONSE,
+    },
+    models::branding::BrandingAsset,
+    responses::{apply_preconnect_hint, text_response, ResponseFormat},
+    storage::{get_storage, StorageDriver},
+    templates::render_template,
+    warnings::confirm_url,
+};
+use chrono::{DateTime, SecondsFormat};
+use url::Url;
+use worker::{Date, Env, Request, Response};
+
+/// Get the override of a branding asset for the hostname the request was made to, if one has been set.
+pub async fn get_branding(req: &Request, env: &Env, asset: BrandingAsset) -> Option<String> {
+    let url = req.url().ok()?;
+    get_storage(env)
+        .ok()?
+        .get(&asset.key(url.host_str()?))
+        .await
+}
+
+/// Build the response for a link that can't be found, using the hostname's not found template if it has one.
+///
+/// Command-line clients are given a plain text message instead.
+pub async fn link_not_found_response(
+    req: &Request,
+    env: &Env,
+    id: &str,
+) -> worker::Result<Response> {
+    link_unavailable_response(req, env, id, LINK_DOESNT_EXIST_RESPONSE, 404).await
+}
+
+/// Build the response for a link that has permanently expired, using the hostname's not found template if it has one.
+///
+/// Command-line clients are given a plain text message instead.
+pub async fn link_gone_response(req: &Request, env: &Env, id: &str) -> worker::Result<Response> {
+    link_unavailable_response(req, env, id, LINK_GONE_RESPONSE, 410).await
+}
+
+/// Build the response for a link that isn't being served, using the given message and status code.
+async fn link_unavailable_response(
+    req: &Request,
+    env: &Env,
+    id: &str,
+    message: &str,
+    status: u16,
+) -> worker::Result<Response> {
+    if ResponseFormat::negotiate(req) == ResponseFormat::Text {
+        return text_response(message, status);
+    }
+
+    let template = get_branding(req, env, BrandingAsset::NotFound)
+        .await
+        .unwrap_or_else(|| include_str!("../static/not_found.html").to_string());
+
+    let url = req.url()?;
+    let html = render_template(
+        &template,
+        &[
+            ("host", url.host_str().unwrap_or_default()),
+            ("id", id),
+            ("message", message),
+        ],
+    );
+    Ok(Response::from_html(html)?.with_status(status))
+}
+
+/// The default amount of seconds clients are asked to wait before retrying a paused link.
+const DEFAULT_PAUSE_RETRY_AFTER_SECONDS: u64 = 3600;
+
+/// Build the response for a paused link, using the hostname's paused template if it has one.
+///
+/// Command-line clients are given a plain text message instead.
+pub async fn link_paused_response(
+    req: &Request,
+    env: &Env,
+    id: &str,
+    retry_after_seconds: Option<u64>,
+) -> worker::Result<Response> {
+    let mut response = match ResponseFormat::negotiate(req) {
+        ResponseFormat::Text => text_response(LINK_PAUSED_RESPONSE, 503)?,
+        ResponseFormat::Html => {
+            let template = get_branding(req, env, BrandingAsset::Paused)
+                .await
+                .unwrap_or_else(|| include_str!("../static/paused.html").to_string());
+
+            let url = req.url()?;
+            let html = render_template(
+                &template,
+                &[
+                    ("host", url.host_str().unwrap_or_default()),
+                    ("id", id),
+                    ("message", LINK_PAUSED_RESPONSE),
+                ],
+            );
+            Response::from_html(html)?.with_status(503)
+        }
+    };
+    response.headers_mut().set(
+        "Retry-After",
+        &retry_after_seconds
+            .unwrap_or(DEFAULT_PAUSE_RETRY_AFTER_SECONDS)
+            .to_string(),
+    )?;
+    Ok(response)
+}
+
+/// Build the countdown page for a link that isn't active yet, using the hostname's countdown template if it has one.
+///
+/// Command-line clients are given a plain text message with the activation time instead.
+pub async fn link_countdown_response(
+    req: &Request,
+    env: &Env,
+    id: &str,
+    destination: &Url,
+    activate_at_timestamp: u64,
+) -> worker::Result<Response> {
+    let activate_at = DateTime::from_timestamp_millis(activate_at_timestamp as i64)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut response = match ResponseFormat::negotiate(req) {
+        ResponseFormat::Text => text_response(
+            &format!("{LINK_NOT_ACTIVE_RESPONSE}\nActivates at: {activate_at}"),
+            503,
+        )?,
+        ResponseFormat::Html => {
+            let template = get_branding(req, env, BrandingAsset::Countdown)
+                .await
+                .unwrap_or_else(|| include_str!("../static/countdown.html").to_string());
+
+            let url = req.url()?;
+            let html = render_template(
+                &template,
+                &[
+                    ("host", url.host_str().unwrap_or_default()),
+                    ("id", id),
+                    ("message", LINK_NOT_ACTIVE_RESPONSE),
+                    ("activate_at", &activate_at),
+                    ("activate_at_timestamp", &activate_at_timestamp.to_string()),
+                ],
+            );
+            let mut response = Response::from_html(html)?.with_status(503);
+            apply_preconnect_hint(env, &mut response, destination)?;
+            response
+        }
+    };
+    let retry_after_seconds = activate_at_timestamp
+        .saturating_sub(Date::now().as_millis())
+        .div_ceil(1000);
+    let headers = response.headers_mut();
+    headers.set("Retry-After", &retry_after_seconds.to_string())?;
+    headers.set("Cache-Control", "no-store")?;
+    Ok(response)
+}
+
+/// Build the interstitial asking visitors to confirm they want to continue to a flagged destination,
+/// using the hostname's warning template if it has one.
+///
+/// Command-line clients are given a plain text message with the destination and confirmation URL instead.
+pub async fn link_warning_response(
+    req: &Request,
+    env: &Env,
+    id: &str,
+    destination: &Url,
+    domain: &str,
+    reason: Option<&str>,
+) -> worker::Result<Response> {
+    let message = reason.unwrap_or(LINK_WARNING_RESPONSE);
+    let continue_url = confirm_url(req)?;
+    let mut response = match ResponseFormat::negotiate(req) {
+        ResponseFormat::Text => text_response(
+            &format!("{message}\nDestination: {destination}\nContinue: {continue_url}"),
+            200,
+        )?,
+        ResponseFormat::Html => {
+            let template = get_branding(req, env, BrandingAsset::Warning)
+                .await
+                .unwrap_or_else(|| include_str!("../static/warning.html").to_string());
+
+            let url = req.url()?;
+            let html = render_template(
+                &template,
+                &[
+                    ("host", url.host_str().unwrap_or_default()),
+                    ("id", id),
+                    ("message", message),
+                    ("url", destination.as_str()),
+                    ("domain", domain),
+                    ("continue_url", continue_url.as_str()),
+                ],
+            );
+            let mut response = Response::from_html(html)?;
+            apply_preconnect_hint(env, &mut response, destination)?;
+            response
+        }
+    };
+    response.headers_mut().set("Cache-Control", "no-store")?;
+    Ok(response)
+}
@@ -0,0 +1,137 @@
+use crate::{
+    analytics::day_from_timestamp,
+    models::stats::StatsModel,
+    slugs::{random_id, DEFAULT_RANDOM_ID_ALPHABET},
+    storage::StorageDriver,
+};
+use std::fmt;
+use url::Url;
+
+/// The length of the random token that makes each visit ID unique.
+const VISIT_TOKEN_LENGTH: usize = 12;
+
+/// The separator between the parts of a visit ID.
+const VISIT_ID_SEPARATOR: char = '.';
+
+/// The variant written in a visit ID for visitors that weren't assigned one.
+const NO_VARIANT: &str = "-";
+
+/// The longest a link's correlation query parameter name can be.
+const MAX_CORRELATION_PARAM_LENGTH: usize = 64;
+
+/// Identifies a single counted visit, so that a conversion reported later can be attributed to the
+/// link and variant the visitor came through.
+///
+/// Visit IDs are written as `<token>.<variant>.<link ID>`, with `-` as the variant when the visitor
+/// wasn't assigned one, so nothing has to be stored when the visit happens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitId {
+    /// The random token that makes the visit ID unique.
+    pub token: String,
+    /// The index of the variant the visitor was sent to, if they were assigned one.
+    pub variant: Option<usize>,
+    /// The ID of the link that was visited.
+    pub id: String,
+}
+
+impl VisitId {
+    /// Generate a new visit ID for a visit to the given link.
+    pub fn new(id: &str, variant: Option<usize>) -> Self {
+        Self {
+            token: random_id(
+                &DEFAULT_RANDOM_ID_ALPHABET.chars().collect::<Vec<_>>(),
+                VISIT_TOKEN_LENGTH,
+            ),
+            variant,
+            id: id.to_string(),
+        }
+    }
+
+    /// Parse a visit ID that was previously handed out with a visit.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, VISIT_ID_SEPARATOR);
+        let token = parts.next()?;
+        let variant = match parts.next()? {
+            NO_VARIANT => None,
+            variant => Some(variant.parse::<usize>().ok()?),
+        };
+        let id = parts.next()?;
+        if token.len() != VISIT_TOKEN_LENGTH
+            || !token
+                .chars()
+                .all(|char| DEFAULT_RANDOM_ID_ALPHABET.contains(char))
+            || id.is_empty()
+        {
+            return None;
+        }
+        Some(Self {
+            token: token.to_string(),
+            variant,
+            id: id.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for VisitId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.variant {
+            Some(variant) => write!(f, "{}.{variant}.{}", self.token, self.id),
+            None => write!(f, "{}.{NO_VARIANT}.{}", self.token, self.id),
+        }
+    }
+}
+
+/// Whether or not the given name can be used as the query parameter visit IDs are appended to destinations under.
+pub fn is_valid_correlation_param(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_CORRELATION_PARAM_LENGTH
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+}
+
+/// Append a visit ID to a destination under the given query parameter, so the destination can report conversions.
+pub fn append_visit_id(destination: &mut Url, param: &str, visit_id: &VisitId) {
+    destination
+        .query_pairs_mut()
+        .append_pair(param, &visit_id.to_string());
+}
+
+/// The outcome of recording a conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionOutcome {
+    /// The conversion was added to the link's stats.
+    Recorded,
+    /// A conversion has already been recorded for the visit.
+    Duplicate,
+    /// The conversion could not be stored.
+    Failed,
+}
+
+/// Record a conversion for a visit in its link's stats for the day the conversion happened.
+///
+/// Each visit can only convert once, so retried postbacks aren't counted twice.
+pub async fn record_conversion(
+    storage: &impl StorageDriver,
+    visit_id: &VisitId,
+    value: f64,
+    timestamp: u64,
+) -> ConversionOutcome {
+    let marker_key = StatsModel::conversion_key(&visit_id.id, &visit_id.token);
+    if storage.get(&marker_key).await.is_some() {
+        return ConversionOutcome::Duplicate;
+    }
+
+    let key = StatsModel::day_key(&visit_id.id, &day_from_timestamp(timestamp));
+    let mut stats = storage
+        .get_deserialized_json::<StatsModel>(&key)
+        .await
+        .unwrap_or_default();
+    stats.add_conversion(visit_id.variant, value);
+
+    if !storage.set_serialized_json(&key, &stats, None).await {
+        return ConversionOutcome::Failed;
+    }
+    storage.set(&marker_key, &timestamp.to_string(), None).await;
+    ConversionOutcome::Recorded
+}
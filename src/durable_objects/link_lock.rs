@@ -0,0 +1,166 @@
+use crate::{
+    messages::LINK_LOCKED_RESPONSE,
+    slugs::{get_random_id_alphabet, random_id},
+};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, time::Duration};
+use wasm_bindgen::JsValue;
+use worker::{
+    async_trait, durable_object, Date, Delay, Env, Method, Request, RequestInit, Response, Result,
+    State, Stub,
+};
+
+/// The binding name for the [`LinkLock`] Durable Object namespace.
+pub const LINK_LOCK_BINDING: &str = "LINK_LOCK";
+
+/// The storage key the current lease is persisted under.
+const LEASE_STORAGE_KEY: &str = "lease";
+
+/// How long a lock is held for before it's considered abandoned, in case its holder never releases it.
+const LEASE_DURATION: Duration = Duration::from_secs(60);
+
+/// How long to keep trying to acquire a lock held by another request before giving up.
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait between attempts at acquiring a lock held by another request.
+const ACQUIRE_RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The length of the random tokens identifying the holder of a lock.
+const LOCK_TOKEN_LENGTH: usize = 16;
+
+/// A request to acquire or release a lock.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum LockRequest {
+    Acquire { token: String, lease_millis: u64 },
+    Release { token: String },
+}
+
+/// The outcome of a request to a lock.
+#[derive(Debug, Serialize, Deserialize)]
+struct LockResponse {
+    /// Whether or not the lock is now held (when acquiring) or was released (when releasing) by the given token.
+    success: bool,
+}
+
+/// The persisted state of a held lock.
+#[derive(Debug, Serialize, Deserialize)]
+struct Lease {
+    token: String,
+    expires_at_timestamp: u64,
+}
+
+/// A Durable Object implementing a lease-based lock.
+///
+/// One instance is used per link so that updates to the same link are serialized, while updates
+/// to different links never wait on each other.
+#[durable_object]
+pub struct LinkLock {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for LinkLock {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let now = Date::now().as_millis();
+        let lease = self
+            .state
+            .storage()
+            .get::<Lease>(LEASE_STORAGE_KEY)
+            .await
+            .ok()
+            .filter(|lease| lease.expires_at_timestamp > now);
+
+        let success = match req.json::<LockRequest>().await? {
+            LockRequest::Acquire {
+                token,
+                lease_millis,
+            } => {
+                let available = lease.map_or(true, |lease| lease.token == token);
+                if available {
+                    let lease = Lease {
+                        token,
+                        expires_at_timestamp: now + lease_millis,
+                    };
+                    self.state.storage().put(LEASE_STORAGE_KEY, &lease).await?;
+                }
+                available
+            }
+            LockRequest::Release { token } => match lease {
+                Some(lease) if lease.token == token => {
+                    self.state.storage().delete(LEASE_STORAGE_KEY).await?;
+                    true
+                }
+                _ => false,
+            },
+        };
+
+        Response::from_json(&LockResponse { success })
+    }
+}
+
+/// Send a request to the lock behind the given stub, returning whether it succeeded.
+async fn send_lock_request(stub: &Stub, lock_request: &LockRequest) -> Option<bool> {
+    let body = serde_json::to_string(lock_request).ok()?;
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(JsValue::from_str(&body)));
+    let request = Request::new_with_init("https://link-lock/", &init).ok()?;
+
+    let response = stub
+        .fetch_with_request(request)
+        .await
+        .ok()?
+        .json::<LockResponse>()
+        .await
+        .ok()?;
+    Some(response.success)
+}
+
+/// Run an update to a link while holding its lock, so concurrent updates to the same link are
+/// serialized instead of interleaving their reads and writes.
+///
+/// The update runs without a lock if the lock isn't bound or couldn't be reached, and a conflict
+/// response is returned if the lock couldn't be acquired in time.
+pub async fn with_link_lock(
+    env: &Env,
+    tenant: Option<&str>,
+    id: &str,
+    update: impl Future<Output = worker::Result<Response>>,
+) -> worker::Result<Response> {
+    let Ok(namespace) = env.durable_object(LINK_LOCK_BINDING) else {
+        return update.await;
+    };
+    let Some(stub) = namespace
+        .id_from_name(&format!("{}:{id}", tenant.unwrap_or_default()))
+        .ok()
+        .and_then(|object_id| object_id.get_stub().ok())
+    else {
+        return update.await;
+    };
+
+    let token = random_id(&get_random_id_alphabet(env), LOCK_TOKEN_LENGTH);
+    let acquire = LockRequest::Acquire {
+        token: token.clone(),
+        lease_millis: LEASE_DURATION.as_millis() as u64,
+    };
+    let deadline = Date::now().as_millis() + ACQUIRE_TIMEOUT.as_millis() as u64;
+    loop {
+        match send_lock_request(&stub, &acquire).await {
+            Some(true) => break,
+            Some(false) if Date::now().as_millis() < deadline => {
+                Delay::from(ACQUIRE_RETRY_INTERVAL).await;
+            }
+            Some(false) => return Response::error(LINK_LOCKED_RESPONSE, 409),
+            None => return update.await,
+        }
+    }
+
+    let response = update.await;
+    send_lock_request(&stub, &LockRequest::Release { token }).await;
+    response
+}
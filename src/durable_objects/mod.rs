@@ -0,0 +1,3 @@
+pub mod link_lock;
+pub mod rate_limiter;
+pub mod view_counter;
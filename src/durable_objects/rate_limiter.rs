@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use worker::{
+    async_trait, durable_object, Date, Env, Method, Request, RequestInit, Response, Result, State,
+};
+
+/// The binding name for the [`RateLimiter`] Durable Object namespace.
+pub const RATE_LIMITER_BINDING: &str = "RATE_LIMITER";
+
+/// The storage key the token bucket is persisted under.
+const BUCKET_STORAGE_KEY: &str = "bucket";
+
+/// Describes the limit a rate limiter should enforce.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// The maximum amount of tokens the bucket can hold.
+    pub capacity: u32,
+    /// The amount of tokens added back to the bucket every second.
+    pub refill_per_second: f64,
+}
+
+/// A request to take tokens from a rate limiter.
+#[derive(Debug, Serialize, Deserialize)]
+struct TakeRequest {
+    limit: RateLimit,
+    /// The amount of tokens to take. A cost of 0 checks the limit without consuming or writing anything.
+    cost: u32,
+}
+
+/// The outcome of taking tokens from a rate limiter.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateLimitDecision {
+    /// Whether or not the action is allowed under the rate limit.
+    pub allowed: bool,
+    /// The amount of whole tokens remaining in the bucket.
+    pub remaining: u32,
+    /// How many seconds until the action would be allowed, or 0 if it is allowed.
+    pub retry_after_seconds: u64,
+}
+
+/// The persisted state of a token bucket.
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill_timestamp: u64,
+}
+
+/// A Durable Object implementing a token bucket rate limiter.
+///
+/// Every instance holds a single bucket, so one instance should be used per key being rate limited
+/// (e.g. per client IP). The limit is provided with every request so one class can serve many limits.
+#[durable_object]
+pub struct RateLimiter {
+    state: State,
+    bucket: Option<TokenBucket>,
+}
+
+#[durable_object]
+impl DurableObject for RateLimiter {
+    fn new(state: State, _env: Env) -> Self {
+        Self {
+            state,
+            bucket: None,
+        }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let TakeRequest { limit, cost } = req.json().await?;
+        let now = Date::now().as_millis();
+
+        let mut bucket = match self.bucket.take() {
+            Some(bucket) => bucket,
+            None => self
+                .state
+                .storage()
+                .get(BUCKET_STORAGE_KEY)
+                .await
+                .unwrap_or(TokenBucket {
+                    tokens: limit.capacity as f64,
+                    last_refill_timestamp: now,
+                }),
+        };
+
+        let elapsed_seconds = now.saturating_sub(bucket.last_refill_timestamp) as f64 / 1000.0;
+        let mut tokens =
+            (bucket.tokens + elapsed_seconds * limit.refill_per_second).min(limit.capacity as f64);
+
+        let required = cost.max(1) as f64;
+        let allowed = tokens >= required;
+        if allowed {
+            tokens -= cost as f64;
+        }
+
+        let retry_after_seconds = if allowed || limit.refill_per_second <= 0.0 {
+            0
+        } else {
+            ((required - tokens) / limit.refill_per_second).ceil() as u64
+        };
+        let decision = RateLimitDecision {
+            allowed,
+            remaining: tokens.floor() as u32,
+            retry_after_seconds,
+        };
+
+        // Checking the limit with a cost of 0 is a read-only peek, so the bucket is only written back when tokens are
+        // being taken.
+        if cost > 0 {
+            bucket = TokenBucket {
+                tokens,
+                last_refill_timestamp: now,
+            };
+            self.state
+                .storage()
+                .put(BUCKET_STORAGE_KEY, &bucket)
+                .await?;
+        }
+        self.bucket = Some(bucket);
+
+        Response::from_json(&decision)
+    }
+}
+
+/// Take tokens from the rate limiter for the given key, or check the limit without consuming anything if `cost` is 0.
+///
+/// Returns [`None`] if the rate limiter isn't bound or couldn't be reached, in which case callers should fail open.
+pub async fn take_tokens(
+    env: &Env,
+    key: &str,
+    limit: RateLimit,
+    cost: u32,
+) -> Option<RateLimitDecision> {
+    let stub = env
+        .durable_object(RATE_LIMITER_BINDING)
+        .ok()?
+        .id_from_name(key)
+        .ok()?
+        .get_stub()
+        .ok()?;
+
+    let body = serde_json::to_string(&TakeRequest { limit, cost }).ok()?;
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(JsValue::from_str(&body)));
+    let request = Request::new_with_init("https://rate-limiter/take", &init).ok()?;
+
+    stub.fetch_with_request(request)
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()
+}
@@ -0,0 +1,225 @@
+use super::link_lock::with_link_lock;
+use crate::{
+    analytics::PrivacyConfig,
+    get_tombstone_retention,
+    models::link::LinkModel,
+    storage::{get_tenant_storage, StorageDriver},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use wasm_bindgen::JsValue;
+use worker::{
+    async_trait, durable_object, Date, Env, Method, Request, RequestInit, Response, Result, State,
+};
+
+/// The binding name for the [`ViewCounter`] Durable Object namespace.
+pub const VIEW_COUNTER_BINDING: &str = "VIEW_COUNTER";
+
+/// The storage key the unflushed views are persisted under.
+const COUNT_STORAGE_KEY: &str = "count";
+
+/// How long after the first unflushed view the views are written to the link.
+const FLUSH_DELAY: Duration = Duration::from_secs(10);
+
+/// A request to a view counter.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum CounterRequest {
+    /// Count a view of the link, which is identified so the counter knows where to flush it to.
+    Increment {
+        tenant: Option<String>,
+        id: String,
+        timestamp: u64,
+    },
+    /// Get the amount of views that haven't been flushed yet without counting one.
+    Peek,
+}
+
+/// The outcome of a request to a view counter.
+#[derive(Debug, Serialize, Deserialize)]
+struct CounterResponse {
+    /// The amount of views counted that haven't been written to the link yet.
+    pending_views: u64,
+}
+
+/// The persisted views of a link that haven't been written to it yet.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PendingCount {
+    tenant: Option<String>,
+    id: String,
+    views: u64,
+    /// The UNIX timestamp of the first unflushed view, so views of a deleted link aren't flushed into a new one.
+    first_viewed_timestamp: u64,
+    last_viewed_timestamp: u64,
+}
+
+/// A Durable Object atomically counting the views of a single link.
+///
+/// One instance is used per link, so concurrent visits are counted one after another instead of
+/// each rewriting the link and losing each other's views. Counted views are written to the link
+/// in one update shortly after, while holding the link's lock.
+#[durable_object]
+pub struct ViewCounter {
+    state: State,
+    env: Env,
+}
+
+#[durable_object]
+impl DurableObject for ViewCounter {
+    fn new(state: State, env: Env) -> Self {
+        Self { state, env }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let mut count = self
+            .state
+            .storage()
+            .get::<PendingCount>(COUNT_STORAGE_KEY)
+            .await
+            .unwrap_or_default();
+
+        if let CounterRequest::Increment {
+            tenant,
+            id,
+            timestamp,
+        } = req.json::<CounterRequest>().await?
+        {
+            if count.views == 0 {
+                count.first_viewed_timestamp = timestamp;
+            }
+            count.tenant = tenant;
+            count.id = id;
+            count.views += 1;
+            count.last_viewed_timestamp = count.last_viewed_timestamp.max(timestamp);
+            self.state.storage().put(COUNT_STORAGE_KEY, &count).await?;
+            if self.state.storage().get_alarm().await?.is_none() {
+                self.state.storage().set_alarm(FLUSH_DELAY).await?;
+            }
+        }
+
+        Response::from_json(&CounterResponse {
+            pending_views: count.views,
+        })
+    }
+
+    async fn alarm(&mut self) -> Result<Response> {
+        let Ok(count) = self
+            .state
+            .storage()
+            .get::<PendingCount>(COUNT_STORAGE_KEY)
+            .await
+        else {
+            return Response::ok("");
+        };
+        if count.views == 0 {
+            return Response::ok("");
+        }
+
+        let storage = get_tenant_storage(&self.env, count.tenant.as_deref())?;
+        let mut flushed = false;
+        with_link_lock(&self.env, count.tenant.as_deref(), &count.id, async {
+            // Views of a link that has since been removed (or removed and created again) are dropped.
+            let Some(mut link) = storage
+                .get_deserialized_json::<LinkModel>(&count.id)
+                .await
+                .filter(|link| link.created_at_timestamp <= count.first_viewed_timestamp)
+            else {
+                flushed = true;
+                return Response::empty();
+            };
+            link.add_visits(count.views, count.last_viewed_timestamp);
+            if !PrivacyConfig::from_env(&self.env).last_viewed {
+                link.last_viewed_timestamp = None;
+            }
+            flushed = storage
+                .set_serialized_json(
+                    &count.id,
+                    &link,
+                    link.storage_expiration(get_tombstone_retention(&self.env).is_some()),
+                )
+                .await;
+            Response::empty()
+        })
+        .await?;
+
+        // Views counted while flushing are kept for the next flush.
+        let mut remaining = self
+            .state
+            .storage()
+            .get::<PendingCount>(COUNT_STORAGE_KEY)
+            .await
+            .unwrap_or_default();
+        if flushed {
+            remaining.views = remaining.views.saturating_sub(count.views);
+            remaining.first_viewed_timestamp = remaining.last_viewed_timestamp;
+        }
+        if remaining.views == 0 {
+            self.state.storage().delete(COUNT_STORAGE_KEY).await?;
+        } else {
+            self.state
+                .storage()
+                .put(COUNT_STORAGE_KEY, &remaining)
+                .await?;
+            self.state.storage().set_alarm(FLUSH_DELAY).await?;
+        }
+        Response::ok("")
+    }
+}
+
+/// Send a request to the view counter of the given link, returning its unflushed views.
+///
+/// Returns [`None`] if the view counter isn't bound or couldn't be reached.
+async fn send_counter_request(
+    env: &Env,
+    tenant: Option<&str>,
+    id: &str,
+    counter_request: &CounterRequest,
+) -> Option<u64> {
+    let stub = env
+        .durable_object(VIEW_COUNTER_BINDING)
+        .ok()?
+        .id_from_name(&format!("{}:{id}", tenant.unwrap_or_default()))
+        .ok()?
+        .get_stub()
+        .ok()?;
+
+    let body = serde_json::to_string(counter_request).ok()?;
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(JsValue::from_str(&body)));
+    let request = Request::new_with_init("https://view-counter/", &init).ok()?;
+
+    let response = stub
+        .fetch_with_request(request)
+        .await
+        .ok()?
+        .json::<CounterResponse>()
+        .await
+        .ok()?;
+    Some(response.pending_views)
+}
+
+/// Count a view of a link with its view counter instead of rewriting the link, returning its unflushed views.
+///
+/// Returns [`None`] if the view counter isn't bound or couldn't be reached, in which case callers should
+/// update the link themselves.
+pub async fn count_view(env: &Env, tenant: Option<&str>, id: &str) -> Option<u64> {
+    let increment = CounterRequest::Increment {
+        tenant: tenant.map(str::to_string),
+        id: id.to_string(),
+        timestamp: Date::now().as_millis(),
+    };
+    send_counter_request(env, tenant, id, &increment).await
+}
+
+/// Get the views of a link counted by its view counter that haven't been written to the link yet.
+///
+/// This is always 0 if the view counter isn't bound.
+pub async fn pending_counted_views(env: &Env, tenant: Option<&str>, id: &str) -> u64 {
+    if env.durable_object(VIEW_COUNTER_BINDING).is_err() {
+        return 0;
+    }
+    send_counter_request(env, tenant, id, &CounterRequest::Peek)
+        .await
+        .unwrap_or_default()
+}
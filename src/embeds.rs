@@ -0,0 +1,86 @@
+use crate::{api::responses::OEmbedResponse, models::link::LinkModel, templates::render_template};
+use url::Url;
+
+/// The default width of embedded link cards, in pixels.
+pub const EMBED_WIDTH: u32 = 400;
+
+/// The default height of embedded link cards, in pixels.
+pub const EMBED_HEIGHT: u32 = 120;
+
+/// Get the short URL of a link on the host the worker is being accessed from.
+pub fn short_url(host_url: &Url, id: &str) -> Url {
+    let mut url = host_url.clone();
+    url.set_path(id);
+    url.set_query(None);
+    url.set_fragment(None);
+    url
+}
+
+/// Get the URL of a link's embeddable card.
+pub fn embed_url(host_url: &Url, id: &str) -> Url {
+    let mut url = short_url(host_url, id);
+    url.set_path(&format!("{id}/embed"));
+    url
+}
+
+/// Build a `Link` header value pointing oEmbed consumers at the description of the given short URL.
+pub fn oembed_discovery_link(short_url: &Url) -> String {
+    let mut oembed_url = short_url.clone();
+    oembed_url.set_path("/api/oembed");
+    oembed_url
+        .query_pairs_mut()
+        .clear()
+        .append_pair("url", short_url.as_str())
+        .append_pair("format", "json");
+    format!(r#"<{oembed_url}>; rel="alternate"; type="application/json+oembed""#)
+}
+
+/// Render the embeddable card for a link as an HTML page.
+pub fn render_embed(host_url: &Url, id: &str, link: &LinkModel) -> String {
+    let short_url = short_url(host_url, id);
+    render_template(
+        include_str!("../static/embed.html"),
+        &[
+            ("title", short_url.as_str()),
+            ("short_url", short_url.as_str()),
+            ("domain", link.url.host_str().unwrap_or_default()),
+            ("views", &link.views.to_string()),
+        ],
+    )
+}
+
+/// Build the oEmbed description of a link, embedding its card with an iframe.
+///
+/// The card is shrunk to fit within the given maximum dimensions, if any.
+pub fn link_oembed(
+    host_url: &Url,
+    id: &str,
+    link: &LinkModel,
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+) -> OEmbedResponse {
+    let width = max_width.map_or(EMBED_WIDTH, |max| max.min(EMBED_WIDTH));
+    let height = max_height.map_or(EMBED_HEIGHT, |max| max.min(EMBED_HEIGHT));
+
+    OEmbedResponse {
+        version: "1.0",
+        kind: "rich",
+        title: format!(
+            "{} → {}",
+            short_url(host_url, id),
+            link.url.host_str().unwrap_or_default()
+        ),
+        provider_name: "workerlink",
+        provider_url: short_url(host_url, "/").to_string(),
+        html: render_template(
+            r#"<iframe src="{{ src }}" width="{{ width }}" height="{{ height }}" style="border: 0;" loading="lazy"></iframe>"#,
+            &[
+                ("src", embed_url(host_url, id).as_str()),
+                ("width", &width.to_string()),
+                ("height", &height.to_string()),
+            ],
+        ),
+        width,
+        height,
+    }
+}
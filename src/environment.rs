@@ -0,0 +1,36 @@
+use worker::Env;
+
+/// Get the value of an environment variable, or [`None`] if it is unset or empty.
+pub fn get_var(env: &Env, binding: &str) -> Option<String> {
+    let value = env.var(binding).ok()?.to_string();
+    if value.is_empty() {
+        return None;
+    }
+    Some(value)
+}
+
+/// Get the value of an environment variable as a boolean, or [`None`] if it is unset or not `true`/`false`.
+pub fn get_bool_var(env: &Env, binding: &str) -> Option<bool> {
+    get_var(env, binding)?.parse().ok()
+}
+
+/// The binding name for the variable naming the environment this deployment belongs to, e.g. `staging`.
+///
+/// Every storage key is scoped under the environment name when this is set, so that a preview deployment
+/// can share storage with production without overwriting its links.
+pub const ENVIRONMENT_BINDING: &str = "ENVIRONMENT";
+
+/// The header every response is marked with when an environment is set.
+pub const ENVIRONMENT_HEADER: &str = "X-Workerlink-Env";
+
+/// Get the environment this deployment belongs to, or [`None`] for production.
+pub fn get_environment(env: &Env) -> Option<String> {
+    get_var(env, ENVIRONMENT_BINDING)
+}
+
+/// Get the prefix storage keys are scoped under for the environment, which is empty for production.
+pub fn environment_key_prefix(env: &Env) -> String {
+    get_environment(env)
+        .map(|environment| format!("{environment}:"))
+        .unwrap_or_default()
+}
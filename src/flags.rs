@@ -0,0 +1,61 @@
+use crate::{
+    models::flags::FeatureFlagsModel,
+    storage::{get_storage, StorageDriver},
+};
+use serde::{Deserialize, Serialize};
+use worker::{js_sys::Math, Env};
+
+/// A behavior of the redirect path that can be rolled out to a percentage of requests,
+/// so risky changes can be tried on a small slice of traffic first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureFlag {
+    /// Buffering visits in memory instead of writing them on every redirect, when visit buffering is configured.
+    VisitBuffering,
+}
+
+impl FeatureFlag {
+    /// Every feature flag.
+    pub const ALL: &'static [FeatureFlag] = &[FeatureFlag::VisitBuffering];
+
+    /// Get the name of this flag as used in the API.
+    pub fn name(self) -> &'static str {
+        match self {
+            FeatureFlag::VisitBuffering => "visit_buffering",
+        }
+    }
+
+    /// Get the flag with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|flag| flag.name() == name)
+    }
+
+    /// The percentage of requests this flag is enabled for when its rollout hasn't been changed.
+    pub fn default_percentage(self) -> u8 {
+        match self {
+            FeatureFlag::VisitBuffering => 100,
+        }
+    }
+}
+
+/// Get the stored feature flag rollouts, falling back to the defaults if none could be read.
+pub async fn get_feature_flags(env: &Env) -> FeatureFlagsModel {
+    let Ok(storage) = get_storage(env) else {
+        return FeatureFlagsModel::default();
+    };
+    storage
+        .get_deserialized_json::<FeatureFlagsModel>(FeatureFlagsModel::key())
+        .await
+        .unwrap_or_default()
+}
+
+/// Whether or not a feature flag is enabled for the current request.
+///
+/// Each request is independently included in a flag's rollout with a chance equal to its percentage.
+pub async fn is_flag_enabled(env: &Env, flag: FeatureFlag) -> bool {
+    match get_feature_flags(env).await.percentage(flag) {
+        0 => false,
+        100.. => true,
+        percentage => Math::random() * 100.0 < f64::from(percentage),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    environment::{get_bool_var, get_var},
+    get_tombstone_retention,
+    jobs::{new_job_id, start_job, store_job_step},
+    models::{
+        job::{JobKind, JobModel, JobStepResult},
+        link::LinkModel,
+    },
+    notifications::{notify, Notification},
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::list_tenants,
+    urls::check_destination,
+};
+use std::collections::BTreeMap;
+use worker::{Date, Env};
+
+/// The binding name for the variable controlling whether link destinations are periodically health checked.
+pub const HEALTH_CHECKS_BINDING: &str = "HEALTH_CHECKS";
+
+/// The binding name for the variable controlling how many failed checks in a row make a link unhealthy.
+pub const HEALTH_CHECK_FAILURE_THRESHOLD_BINDING: &str = "HEALTH_CHECK_FAILURE_THRESHOLD";
+
+/// The default amount of failed checks in a row that make a link unhealthy.
+const DEFAULT_HEALTH_CHECK_FAILURE_THRESHOLD: u32 = 3;
+
+/// The binding name for the variable controlling whether unhealthy links are automatically paused until they recover.
+pub const AUTO_PAUSE_UNHEALTHY_LINKS_BINDING: &str = "AUTO_PAUSE_UNHEALTHY_LINKS";
+
+/// The amount of links checked by each step of a health check job.
+///
+/// Every redirect a destination goes through is another subrequest, so this is kept well within the subrequest limit
+/// of a single worker invocation.
+pub const HEALTH_CHECK_CHUNK_SIZE: usize = 10;
+
+/// Configuration for health checking link destinations.
+pub struct HealthCheckConfig {
+    /// The amount of failed checks in a row that make a link unhealthy.
+    failure_threshold: u32,
+    /// Whether or not unhealthy links are paused until they recover.
+    auto_pause: bool,
+}
+
+impl HealthCheckConfig {
+    /// Get the health check configuration from the environment, or [`None`] if health checks are disabled.
+    pub fn from_env(env: &Env) -> Option<Self> {
+        if !get_bool_var(env, HEALTH_CHECKS_BINDING).unwrap_or(false) {
+            return None;
+        }
+        Some(Self {
+            failure_threshold: get_var(env, HEALTH_CHECK_FAILURE_THRESHOLD_BINDING)
+                .and_then(|threshold| threshold.parse().ok())
+                .filter(|threshold| *threshold > 0)
+                .unwrap_or(DEFAULT_HEALTH_CHECK_FAILURE_THRESHOLD),
+            auto_pause: get_bool_var(env, AUTO_PAUSE_UNHEALTHY_LINKS_BINDING).unwrap_or(false),
+        })
+    }
+}
+
+/// Start a health check job for the links of every tenant, returning the amount of jobs started.
+pub async fn schedule_health_checks(storage: &impl StorageDriver, env: &Env) -> usize {
+    if HealthCheckConfig::from_env(env).is_none() {
+        return 0;
+    }
+
+    let tenants = std::iter::once(None).chain(list_tenants(storage).await.into_iter().map(Some));
+    let mut started = 0;
+    for tenant in tenants {
+        let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+            continue;
+        };
+        let ids = link_storage
+            .list("")
+            .await
+            .into_iter()
+            .filter(|key| !key.contains(':'))
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            continue;
+        }
+
+        let job_id = new_job_id(env);
+        let mut total_steps = 0;
+        for chunk in ids.chunks(HEALTH_CHECK_CHUNK_SIZE) {
+            let Ok(data) = serde_json::to_string(chunk) else {
+                continue;
+            };
+            if store_job_step(storage, &job_id, total_steps, &data).await {
+                total_steps += 1;
+            }
+        }
+
+        let job = JobModel::new(JobKind::HealthCheck { tenant }, total_steps);
+        if start_job(storage, env, &job_id, &job).await {
+            started += 1;
+        }
+    }
+    started
+}
+
+/// Check the destinations of a chunk of links stored by a health check job.
+///
+/// Links that fail [`HealthCheckConfig::failure_threshold`] checks in a row are reported as unhealthy and,
+/// if enabled, paused until their destination recovers.
+pub async fn run_health_check_step(
+    link_storage: &impl StorageDriver,
+    env: &Env,
+    tenant: Option<&str>,
+    data: &str,
+) -> JobStepResult {
+    let Some(config) = HealthCheckConfig::from_env(env) else {
+        return JobStepResult::default();
+    };
+    let Ok(ids) = serde_json::from_str::<Vec<String>>(data) else {
+        return JobStepResult::default();
+    };
+
+    let mut counts = BTreeMap::<String, usize>::new();
+    for id in ids {
+        let Some(mut link) = link_storage.get_deserialized_json::<LinkModel>(&id).await else {
+            continue;
+        };
+        // Links that were disabled or paused by hand aren't being served, so there's no need to check them.
+        let auto_paused = link
+            .health
+            .as_ref()
+            .is_some_and(|health| health.auto_paused);
+        if link.disabled || (link.paused && !auto_paused) {
+            continue;
+        }
+
+        let status = check_destination(&link.url).await;
+        let healthy = status.is_some_and(|status| status < 400);
+        let health = link.health.get_or_insert_with(Default::default);
+        let was_unhealthy = health.consecutive_failures >= config.failure_threshold;
+        health.last_status = status;
+        health.last_checked_timestamp = Date::now().as_millis();
+        *counts.entry("checked".to_string()).or_default() += 1;
+
+        let notification = if healthy {
+            health.consecutive_failures = 0;
+            *counts.entry("healthy".to_string()).or_default() += 1;
+            if auto_paused {
+                link.resume();
+                *counts.entry("resumed".to_string()).or_default() += 1;
+            }
+            was_unhealthy.then(|| Notification::LinkRecovered {
+                id: id.clone(),
+                tenant: tenant.map(str::to_string),
+                url: link.url.clone(),
+                resumed: auto_paused,
+            })
+        } else {
+            health.consecutive_failures += 1;
+            let consecutive_failures = health.consecutive_failures;
+            *counts.entry("unhealthy".to_string()).or_default() += 1;
+            if consecutive_failures == config.failure_threshold {
+                let paused = config.auto_pause && !link.paused;
+                if paused {
+                    link.pause(None);
+                    if let Some(health) = &mut link.health {
+                        health.auto_paused = true;
+                    }
+                    *counts.entry("paused".to_string()).or_default() += 1;
+                }
+                Some(Notification::LinkUnhealthy {
+                    id: id.clone(),
+                    tenant: tenant.map(str::to_string),
+                    url: link.url.clone(),
+                    consecutive_failures,
+                    last_status: status,
+                    paused,
+                })
+            } else {
+                None
+            }
+        };
+
+        link_storage
+            .set_serialized_json(
+                &id,
+                &link,
+                link.storage_expiration(get_tombstone_retention(env).is_some()),
+            )
+            .await;
+        if let Some(notification) = notification {
+            notify(env, &notification).await;
+        }
+    }
+
+    JobStepResult {
+        counts,
+        failures: Vec::new(),
+    }
+}
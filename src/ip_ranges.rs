@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// Represents a range of IP addresses in CIDR notation, e.g. `192.168.0.0/16` or `2001:db8::/32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_length: u8,
+}
+
+impl IpRange {
+    /// Whether or not the given address falls within this range.
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (self.network, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_length))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_length))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(address: IpAddr) -> Self {
+        Self {
+            network: address,
+            prefix_length: if address.is_ipv4() { 32 } else { 128 },
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some((network, prefix_length)) = value.trim().split_once('/') else {
+            return Err("IP ranges must be in CIDR notation, e.g. 192.168.0.0/16");
+        };
+        let network = network
+            .parse::<IpAddr>()
+            .map_err(|_| "IP range has an invalid network address")?;
+        let prefix_length = prefix_length
+            .parse::<u8>()
+            .map_err(|_| "IP range has an invalid prefix length")?;
+        let max_prefix_length = if network.is_ipv4() { 32 } else { 128 };
+        if prefix_length > max_prefix_length {
+            return Err("IP range has a prefix length that is too long for its address");
+        }
+        Ok(Self {
+            network,
+            prefix_length,
+        })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IpRange> for String {
+    fn from(range: IpRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_length)
+    }
+}
+
+/// Parse a comma-separated list of IP addresses and CIDR ranges, skipping any invalid entries.
+pub fn parse_ip_range_list(list: &str) -> Vec<IpRange> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.parse::<IpAddr>() {
+            Ok(address) => Some(IpRange::from(address)),
+            Err(_) => entry.parse().ok(),
+        })
+        .collect()
+}
@@ -0,0 +1,209 @@
+use crate::{
+    analytics::{
+        run_orphaned_stats_sweep_step, run_stats_rollup_step, STATS_ROLLUP_CHUNK_SIZE,
+        STATS_SWEEP_CHUNK_SIZE,
+    },
+    api::{
+        import::{run_import_step, IMPORT_CHUNK_SIZE},
+        migrate::{run_migration_step, MIGRATION_CHUNK_SIZE},
+    },
+    health::{run_health_check_step, HEALTH_CHECK_CHUNK_SIZE},
+    models::job::{JobKind, JobModel},
+    notifications::NotificationDelivery,
+    slugs::{get_random_id_alphabet, random_id},
+    storage::{get_tenant_storage, StorageDriver},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use worker::Env;
+
+/// The binding name for the queue job steps are processed from.
+pub const JOBS_QUEUE_BINDING: &str = "JOBS_QUEUE";
+
+/// The length of generated job IDs.
+const JOB_ID_LENGTH: usize = 16;
+
+/// The maximum amount of records processed by a single scheduled event when no jobs queue is bound.
+///
+/// Steps are sized to fit within the subrequest limit of a single worker invocation on their own, so a scheduled event
+/// only processes more than one step when they're small enough for all of their records to fit within this total.
+const MAX_SCHEDULED_JOB_RECORDS: usize = 20;
+
+/// Represents a queue message asking for a single step of a job to be processed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobStepMessage {
+    pub job_id: String,
+    pub step: usize,
+}
+
+/// Represents any message sent to the jobs queue, which also carries notifications being retried.
+///
+/// Messages are untagged so job steps are sent exactly as they were before notifications used the queue.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum QueueMessage {
+    JobStep(JobStepMessage),
+    NotificationDelivery(NotificationDelivery),
+}
+
+/// Whether or not a queue is bound to process job steps.
+///
+/// Job steps are processed by the scheduled event instead when no queue is bound.
+pub fn jobs_queue_bound(env: &Env) -> bool {
+    env.queue(JOBS_QUEUE_BINDING).is_ok()
+}
+
+/// Generate a new random job ID.
+pub fn new_job_id(env: &Env) -> String {
+    random_id(&get_random_id_alphabet(env), JOB_ID_LENGTH)
+}
+
+/// Store the data for a step of a job that hasn't been started yet.
+pub async fn store_job_step(
+    storage: &impl StorageDriver,
+    job_id: &str,
+    step: usize,
+    data: &str,
+) -> bool {
+    storage
+        .set(&JobModel::step_key(job_id, step), data, None)
+        .await
+}
+
+/// Start a job whose steps have already been stored.
+///
+/// Steps are sent to the jobs queue if one is bound, otherwise they're picked up by scheduled events.
+pub async fn start_job(
+    storage: &impl StorageDriver,
+    env: &Env,
+    job_id: &str,
+    job: &JobModel,
+) -> bool {
+    if !storage
+        .set_serialized_json(&JobModel::key(job_id), job, None)
+        .await
+    {
+        return false;
+    }
+
+    let Ok(queue) = env.queue(JOBS_QUEUE_BINDING) else {
+        return true;
+    };
+    for step in 0..job.total_steps {
+        let message = JobStepMessage {
+            job_id: job_id.to_string(),
+            step,
+        };
+        if queue.send(message).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Get the maximum amount of records held by each step of a job of the given kind.
+fn records_per_step(kind: &JobKind) -> usize {
+    match kind {
+        JobKind::Import { .. } => IMPORT_CHUNK_SIZE,
+        JobKind::HealthCheck { .. } => HEALTH_CHECK_CHUNK_SIZE,
+        JobKind::Migrate => MIGRATION_CHUNK_SIZE,
+        JobKind::StatsRollup { .. } => STATS_ROLLUP_CHUNK_SIZE,
+        JobKind::StatsSweep { .. } => STATS_SWEEP_CHUNK_SIZE,
+    }
+}
+
+/// Process a single step of a job, updating the job with the results.
+///
+/// Returns whether or not the step was handled and doesn't need to be retried.
+pub async fn run_job_step(
+    storage: &impl StorageDriver,
+    env: &Env,
+    job_id: &str,
+    step: usize,
+) -> bool {
+    let job_key = JobModel::key(job_id);
+    let step_key = JobModel::step_key(job_id, step);
+    let Some(mut job) = storage.get_deserialized_json::<JobModel>(&job_key).await else {
+        storage.delete(&step_key).await;
+        return true;
+    };
+    // Steps are removed once processed, so a missing step means it was already handled.
+    let Some(data) = storage.get(&step_key).await else {
+        return true;
+    };
+
+    let result = match &job.kind {
+        JobKind::Import { host_url, tenant } => {
+            let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+                return false;
+            };
+            run_import_step(&link_storage, env, host_url, &data).await
+        }
+        JobKind::HealthCheck { tenant } => {
+            let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+                return false;
+            };
+            run_health_check_step(&link_storage, env, tenant.as_deref(), &data).await
+        }
+        JobKind::Migrate => run_migration_step(env, &data).await,
+        JobKind::StatsRollup { tenant } => {
+            let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+                return false;
+            };
+            run_stats_rollup_step(&link_storage, &data).await
+        }
+        JobKind::StatsSweep { tenant } => {
+            let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+                return false;
+            };
+            run_orphaned_stats_sweep_step(&link_storage, &data).await
+        }
+    };
+
+    job.complete_step(result);
+    if !storage.set_serialized_json(&job_key, &job, None).await {
+        return false;
+    }
+    storage.delete(&step_key).await;
+    true
+}
+
+/// Process outstanding job steps from storage when no jobs queue is bound to do so.
+///
+/// Steps are processed until the next one could take the total past [`MAX_SCHEDULED_JOB_RECORDS`],
+/// although the first step is always processed so that jobs keep making progress.
+///
+/// Returns the amount of steps that were processed.
+pub async fn run_pending_job_steps(storage: &impl StorageDriver, env: &Env) -> usize {
+    if jobs_queue_bound(env) {
+        return 0;
+    }
+
+    let mut processed = 0;
+    let mut records = 0;
+    let mut job_records_per_step = HashMap::new();
+    for key in storage.list(JobModel::key_prefix()).await {
+        let Some((job_id, step)) = JobModel::parse_step_key(&key) else {
+            continue;
+        };
+        let step_records = match job_records_per_step.get(job_id) {
+            Some(step_records) => *step_records,
+            None => {
+                let step_records = storage
+                    .get_deserialized_json::<JobModel>(&JobModel::key(job_id))
+                    .await
+                    .map_or(0, |job| records_per_step(&job.kind));
+                job_records_per_step.insert(job_id.to_string(), step_records);
+                step_records
+            }
+        };
+        if processed > 0 && records + step_records > MAX_SCHEDULED_JOB_RECORDS {
+            break;
+        }
+        if run_job_step(storage, env, job_id, step).await {
+            processed += 1;
+            records += step_records;
+        }
+    }
+    processed
+}
@@ -1,42 +1,349 @@
+mod admin;
+mod analytics;
+mod anomalies;
 mod api;
+mod archives;
+mod attestations;
 mod authentication;
+mod branding;
+mod conversions;
+mod durable_objects;
+mod embeds;
+mod environment;
+mod flags;
+mod health;
+mod ip_ranges;
+mod jobs;
+mod link_cache;
+mod links;
 mod messages;
+mod metrics;
 mod models;
+mod networks;
+mod notifications;
+mod pdf;
+mod posters;
+mod qr;
+mod reports;
+mod responses;
+mod routing;
+mod schedule;
+mod screenshots;
+mod self_test;
+mod slugs;
 mod storage;
+mod templates;
+mod tenants;
+mod urls;
+mod usage;
+mod user_agents;
+mod visit_buffer;
+mod warnings;
 
-use api::{requests::CreateLinkRequestBody, responses::CreateLinkResponse};
-use authentication::authorized_guard;
+use admin::admin_handler;
+use analytics::{
+    delete_stats, estimate_views, get_sample_rate, get_stats, is_excluded_visit, record_visit,
+    schedule_orphaned_stats_sweeps, schedule_stats_rollups, PrivacyConfig, StatsRange, Visit,
+};
+use anomalies::detect_anomalies;
+use api::{
+    anomalies::get_anomalies_handler,
+    attestations::{
+        attestation_key_handler, verify_attestation_handler, verify_attestation_page_handler,
+    },
+    batch::{batch_create_links_handler, batch_delete_links_handler},
+    branding::{delete_branding_handler, get_branding_handler, set_branding_handler},
+    bulk_update::bulk_update_links_handler,
+    capabilities::capabilities_handler,
+    compare::compare_links_handler,
+    config::config_check_handler,
+    conversions::record_conversion_handler,
+    flags::{list_feature_flags_handler, reset_feature_flag_handler, set_feature_flag_handler},
+    fsck::fsck_handler,
+    grafana::{grafana_query_handler, grafana_search_handler, grafana_test_handler},
+    hooks::inbound_create_link_handler,
+    hosts::{delete_host_mapping_handler, list_host_mappings_handler, set_host_mapping_handler},
+    import::import_links_handler,
+    jobs::job_status_handler,
+    links::list_links_handler,
+    merge::merge_links_handler,
+    migrate::migrate_handler,
+    oembed::oembed_handler,
+    qr::batch_qr_codes_handler,
+    requests::{CreateLinkRequestBody, PatchLinkRequestBody, PauseLinkRequestBody},
+    responses::{AttestationResponse, CreateLinkResponse, ExplainLinkResponse, LinkStatsResponse},
+    simulate::simulate_visit_handler,
+    stats::storage_stats_handler,
+    suggest::suggest_slugs_handler,
+    sync::sync_links_handler,
+    warm::warm_links_handler,
+    warnlist::{delete_warned_domain_handler, get_warnlist_handler, set_warned_domain_handler},
+    webhooks::{
+        create_webhook_handler, delete_webhook_failure_handler, delete_webhook_handler,
+        list_webhook_failures_handler, list_webhooks_handler, replay_webhook_failure_handler,
+    },
+};
+use attestations::{
+    attestations_enabled, sign_payload, AttestationStatement, ATTESTATION_ALGORITHM,
+};
+use authentication::{authorized_guard, authorized_key_guard};
+use branding::{
+    get_branding, link_countdown_response, link_gone_response, link_not_found_response,
+    link_paused_response, link_warning_response,
+};
+use conversions::{append_visit_id, VisitId};
+use durable_objects::{
+    link_lock::with_link_lock,
+    view_counter::{count_view, pending_counted_views},
+};
+use embeds::{link_oembed, oembed_discovery_link, render_embed, short_url};
+use environment::{get_environment, get_var, ENVIRONMENT_HEADER};
+use flags::{is_flag_enabled, FeatureFlag};
+use health::schedule_health_checks;
+use jobs::{run_job_step, run_pending_job_steps, JobStepMessage, QueueMessage};
+use link_cache::{get_cached_link_following_alias, invalidate_cached_link};
+use links::{
+    find_deduplicated_id, get_link_following_alias, get_public_link, is_dependency_met,
+    patch_link_request, prepare_link_request, save_link, SaveLinkError,
+};
 use messages::*;
-use models::link::{LinkBuilderArgs, LinkModel};
-use storage::{
-    cloudflare_kv_driver::{CloudflareKVDriver, CLOUDFLARE_KV_BINDING},
-    StorageDriver,
+use metrics::push_link_metrics;
+use models::{
+    branding::BrandingAsset,
+    deletion::PendingDeletionModel,
+    link::{ExpiryBehavior, LinkModel, LinkScreenshot, RedirectMechanism},
+    stats::StatsModel,
+    tombstone::{TombstoneModel, TombstoneStatus},
+};
+use notifications::{notify_in_background, retry_delivery, Notification};
+use posters::{render_poster, PosterPaper};
+use qr::{QrCode, QrImageFormat};
+use reports::render_report;
+use responses::{apply_preconnect_hint, apply_response_headers, text_response, ResponseFormat};
+use routing::{pick_status_code, resolve_destination, Visitor};
+use screenshots::{
+    capture_screenshot, delete_screenshot, get_screenshot, screenshot_key, screenshots_enabled,
+    store_screenshot, SCREENSHOT_CONTENT_TYPE,
+};
+use self_test::run_self_test;
+use slugs::{canonicalize_id, generate_unique_id, get_random_id_alphabet, get_random_id_length};
+use std::time::Duration;
+use storage::{get_storage, get_tenant_storage, Storage, StorageDriver};
+use templates::render_template;
+use tenants::{get_request_storage, list_tenants, resolve_tenant};
+use url::Url;
+use urls::{append_path, is_web_url, merge_query_pairs, resolve_redirect_chain};
+use usage::measure_storage_usage;
+use visit_buffer::{buffer_visit, flush_visits, pending_views, VisitBufferConfig};
+use warnings::{find_domain_warning, is_warning_confirmed, CONFIRM_QUERY_PARAM};
+use worker::{
+    event, Context, Date, Env, MessageBatch, MessageExt, Request, Response, RouteContext, Router,
+    ScheduleContext, ScheduledEvent,
 };
-use validator::Validate;
-use worker::{event, Context, Date, Env, Request, Response, RouteContext, Router};
 
 #[event(fetch)]
-async fn fetch(req: Request, env: Env, _ctx: Context) -> worker::Result<Response> {
-    Router::new()
-        .get("/", index_handler)
-        .get("/favicon.ico", favicon_handler)
+async fn fetch(req: Request, env: Env, ctx: Context) -> worker::Result<Response> {
+    // The config check is still served while the self-test is failing so the problem can be investigated.
+    if req.path() != "/api/config/check" {
+        if let Err(message) = run_self_test(&env).await {
+            return Response::error(message, 503);
+        }
+    }
+
+    let environment = get_environment(&env);
+    let mut response = Router::with_data(ctx)
+        .get_async("/", index_handler)
+        .get_async("/favicon.ico", favicon_handler)
         .get("/robots.txt", robots_handler)
+        .get("/admin", admin_handler)
+        .get("/admin/", admin_handler)
+        .get("/admin/*path", admin_handler)
+        .post_async("/", create_or_update_link_handler)
+        .get_async("/api/links", list_links_handler)
+        .get_async("/api/links/compare", compare_links_handler)
+        .post_async("/api/links/batch", batch_create_links_handler)
+        .post_async("/api/links/batch-delete", batch_delete_links_handler)
+        .post_async("/api/qr/batch", batch_qr_codes_handler)
+        .post_async("/api/links/bulk-update", bulk_update_links_handler)
+        .put_async("/api/links/sync", sync_links_handler)
+        .post_async("/api/links/merge", merge_links_handler)
+        .post_async("/api/links/:id/simulate", simulate_visit_handler)
+        .post_async("/api/links/:id/:name/simulate", simulate_visit_handler)
+        .post_async("/api/conversions", record_conversion_handler)
+        .post_async("/api/hooks/create", inbound_create_link_handler)
+        .post_async("/api/suggest", suggest_slugs_handler)
+        .post_async("/api/warm", warm_links_handler)
+        .get_async("/api/oembed", oembed_handler)
+        .get_async("/.well-known/oembed", oembed_handler)
+        .get_async("/.well-known/workerlink", capabilities_handler)
+        .get_async("/.well-known/workerlink-key", attestation_key_handler)
+        .get_async("/api/attestations/verify", verify_attestation_page_handler)
+        .post_async("/api/attestations/verify", verify_attestation_handler)
+        .post_async("/api/import", import_links_handler)
+        .post_async("/api/migrate", migrate_handler)
+        .post_async("/api/fsck", fsck_handler)
+        .get_async("/api/stats", storage_stats_handler)
+        .get_async("/api/grafana", grafana_test_handler)
+        .get_async("/api/grafana/", grafana_test_handler)
+        .post_async("/api/grafana/search", grafana_search_handler)
+        .post_async("/api/grafana/query", grafana_query_handler)
+        .get_async("/api/anomalies", get_anomalies_handler)
+        .get_async("/api/config/check", config_check_handler)
+        .get_async("/api/flags", list_feature_flags_handler)
+        .post_async("/api/flags/:flag", set_feature_flag_handler)
+        .delete_async("/api/flags/:flag", reset_feature_flag_handler)
+        .get_async("/api/jobs/:job_id", job_status_handler)
+        .get_async("/api/hosts", list_host_mappings_handler)
+        .post_async("/api/hosts/:host", set_host_mapping_handler)
+        .delete_async("/api/hosts/:host", delete_host_mapping_handler)
+        .get_async("/api/branding/:host/:asset", get_branding_handler)
+        .post_async("/api/branding/:host/:asset", set_branding_handler)
+        .delete_async("/api/branding/:host/:asset", delete_branding_handler)
+        .get_async("/api/warnlist", get_warnlist_handler)
+        .post_async("/api/warnlist/:domain", set_warned_domain_handler)
+        .delete_async("/api/warnlist/:domain", delete_warned_domain_handler)
+        .get_async("/api/webhooks", list_webhooks_handler)
+        .post_async("/api/webhooks", create_webhook_handler)
+        .delete_async("/api/webhooks/:webhook_id", delete_webhook_handler)
+        .get_async("/api/webhooks/failures", list_webhook_failures_handler)
+        .post_async(
+            "/api/webhooks/failures/:delivery_id/replay",
+            replay_webhook_failure_handler,
+        )
+        .delete_async(
+            "/api/webhooks/failures/:delivery_id",
+            delete_webhook_failure_handler,
+        )
+        // Link routes are registered for both flat (/:id) and namespaced (/:id/:name) link IDs.
         .get_async("/:id", link_redirect_handler)
+        .get_async("/:id/:name", link_redirect_handler)
+        .get_async("/:id/:name/*rest", link_redirect_handler)
         .post_async("/:id", create_or_update_link_handler)
+        .post_async("/:id/:name", create_or_update_link_handler)
+        .patch_async("/:id", patch_link_handler)
+        .patch_async("/:id/:name", patch_link_handler)
         .delete_async("/:id", delete_link_handler)
+        .delete_async("/:id/:name", delete_link_handler)
         .get_async("/:id/where", link_where_handler)
+        .get_async("/:id/:name/where", link_where_handler)
+        .get_async("/:id/exists", link_exists_handler)
+        .get_async("/:id/:name/exists", link_exists_handler)
+        .head_async("/:id/exists", link_exists_handler)
+        .head_async("/:id/:name/exists", link_exists_handler)
+        .get_async("/:id/explain", link_explain_handler)
+        .get_async("/:id/:name/explain", link_explain_handler)
         .get_async("/:id/details", link_details_handler)
+        .get_async("/:id/:name/details", link_details_handler)
+        .get_async("/:id/stats", link_stats_handler)
+        .get_async("/:id/:name/stats", link_stats_handler)
+        .get_async("/:id/report.pdf", link_report_handler)
+        .get_async("/:id/:name/report.pdf", link_report_handler)
+        .post_async("/:id/resolve", resolve_link_handler)
+        .post_async("/:id/:name/resolve", resolve_link_handler)
+        .post_async("/:id/pause", pause_link_handler)
+        .post_async("/:id/:name/pause", pause_link_handler)
+        .post_async("/:id/resume", resume_link_handler)
+        .post_async("/:id/:name/resume", resume_link_handler)
+        .get_async("/:id/embed", link_embed_handler)
+        .get_async("/:id/:name/embed", link_embed_handler)
+        .get_async("/:id/oembed", link_oembed_handler)
+        .get_async("/:id/:name/oembed", link_oembed_handler)
+        .get_async("/:id/poster", link_poster_handler)
+        .get_async("/:id/:name/poster", link_poster_handler)
+        .get_async("/:id/qr", link_qr_code_handler)
+        .get_async("/:id/:name/qr", link_qr_code_handler)
+        .get_async("/:id/screenshot", link_screenshot_handler)
+        .get_async("/:id/:name/screenshot", link_screenshot_handler)
+        .post_async("/:id/screenshot", capture_screenshot_handler)
+        .post_async("/:id/:name/screenshot", capture_screenshot_handler)
+        .get_async("/:id/attestation", link_attestation_handler)
+        .get_async("/:id/:name/attestation", link_attestation_handler)
         .run(req, env)
-        .await
+        .await?;
+
+    if let Some(environment) = environment {
+        response
+            .headers_mut()
+            .set(ENVIRONMENT_HEADER, &environment)?;
+    }
+    Ok(response)
 }
 
-/// Handler to serve the index HTML.
-fn index_handler(_req: Request, _ctx: RouteContext<()>) -> worker::Result<Response> {
-    Response::from_html(include_str!("../static/index.html"))
+/// The binding name for the variable controlling how many days daily stats are kept before being compacted into monthly stats.
+const STATS_ROLLUP_AFTER_DAYS_BINDING: &str = "STATS_ROLLUP_AFTER_DAYS";
+
+/// The default amount of days daily stats are kept before being compacted into monthly stats.
+const DEFAULT_STATS_ROLLUP_AFTER_DAYS: u64 = 90;
+
+#[event(scheduled)]
+async fn scheduled(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+    let Ok(storage) = get_storage(&env) else {
+        return;
+    };
+
+    let rollup_after_days = get_var(&env, STATS_ROLLUP_AFTER_DAYS_BINDING)
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(DEFAULT_STATS_ROLLUP_AFTER_DAYS);
+    schedule_stats_rollups(&storage, &env, rollup_after_days).await;
+    schedule_orphaned_stats_sweeps(&storage, &env).await;
+    detect_anomalies(&storage, &env, None).await;
+    for tenant in list_tenants(&storage).await {
+        if let Ok(tenant_storage) = get_tenant_storage(&env, Some(&tenant)) {
+            detect_anomalies(&tenant_storage, &env, Some(&tenant)).await;
+        }
+    }
+    measure_storage_usage(&storage, &env).await;
+    push_link_metrics(&storage, &env).await;
+    schedule_health_checks(&storage, &env).await;
+    run_pending_job_steps(&storage, &env).await;
+}
+
+#[event(queue)]
+async fn queue(batch: MessageBatch<QueueMessage>, env: Env, _ctx: Context) -> worker::Result<()> {
+    let storage = get_storage(&env)?;
+    for message in batch.messages()? {
+        match message.body() {
+            QueueMessage::JobStep(JobStepMessage { job_id, step }) => {
+                if run_job_step(&storage, &env, job_id, *step).await {
+                    message.ack();
+                } else {
+                    message.retry();
+                }
+            }
+            // Failed deliveries schedule their own retry with a backoff, so they're always acknowledged.
+            QueueMessage::NotificationDelivery(delivery) => {
+                retry_delivery(&env, delivery.clone()).await;
+                message.ack();
+            }
+        }
+    }
+    Ok(())
 }
 
-/// Handler to serve the site favicon.
-fn favicon_handler(_req: Request, _ctx: RouteContext<()>) -> worker::Result<Response> {
+/// Handler to serve the index HTML, using the hostname's index template if it has one.
+async fn index_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    match get_branding(&req, &ctx.env, BrandingAsset::Index).await {
+        Some(template) => {
+            let url = req.url()?;
+            Response::from_html(render_template(
+                &template,
+                &[("host", url.host_str().unwrap_or_default())],
+            ))
+        }
+        None => Response::from_html(include_str!("../static/index.html")),
+    }
+}
+
+/// Handler to serve the site favicon, using the hostname's SVG favicon if it has one.
+async fn favicon_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    if let Some(svg) = get_branding(&req, &ctx.env, BrandingAsset::Favicon).await {
+        let mut response = Response::ok(svg)?;
+        response
+            .headers_mut()
+            .set("Content-Type", "image/svg+xml")?;
+        return Ok(response);
+    }
+
     let mut response =
         Response::from_bytes(include_bytes!("../static/favicon.ico").to_vec()).unwrap();
     response
@@ -48,7 +355,7 @@ fn favicon_handler(_req: Request, _ctx: RouteContext<()>) -> worker::Result<Resp
 }
 
 /// Handler to serve the robots.txt.
-fn robots_handler(_req: Request, _ctx: RouteContext<()>) -> worker::Result<Response> {
+fn robots_handler(_req: Request, _ctx: RouteContext<Context>) -> worker::Result<Response> {
     let mut response =
         Response::from_bytes(include_bytes!("../static/robots.txt").to_vec()).unwrap();
     response
@@ -58,166 +365,1229 @@ fn robots_handler(_req: Request, _ctx: RouteContext<()>) -> worker::Result<Respo
     Ok(response)
 }
 
-/// Get the link ID from a request.
-fn get_link_id_from_req(req: &Request) -> worker::Result<String> {
-    let path = req.path();
-    let Some(id) = path.split('/').nth(1) else {
+/// Get the canonical link ID from the route parameters of a request.
+///
+/// Namespaced IDs (`/:id/:name`) are joined into a single ID, e.g. `/team/docs` becomes `team/docs`.
+fn get_link_id_from_route(ctx: &RouteContext<Context>) -> worker::Result<String> {
+    let Some(id) = ctx.param("id") else {
         Err("Unable to find link ID from request URL.")?
     };
-    Ok(id.to_string())
+    let id = match ctx.param("name") {
+        Some(name) => format!("{id}/{name}"),
+        None => id.to_string(),
+    };
+    let Some(id) = canonicalize_id(&id) else {
+        Err("Link ID from request URL is not valid UTF-8.")?
+    };
+    Ok(id)
+}
+
+/// The binding name for the variable controlling how long removed links are kept as tombstones.
+const TOMBSTONE_RETENTION_BINDING: &str = "TOMBSTONE_RETENTION";
+
+/// Get how long removed links should be kept as tombstones, or [`None`] if tombstones are disabled.
+fn get_tombstone_retention(env: &Env) -> Option<Duration> {
+    humantime::parse_duration(&get_var(env, TOMBSTONE_RETENTION_BINDING)?).ok()
+}
+
+/// The binding name for the variable controlling how long a staged deletion of a protected link can be confirmed for.
+const DELETION_CONFIRMATION_WINDOW_BINDING: &str = "DELETION_CONFIRMATION_WINDOW";
+
+/// How long a staged deletion of a protected link can be confirmed for when the window isn't configured.
+const DEFAULT_DELETION_CONFIRMATION_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Get how long a staged deletion of a protected link can be confirmed for by another API key.
+fn get_deletion_confirmation_window(ctx: &RouteContext<Context>) -> Duration {
+    get_var(&ctx.env, DELETION_CONFIRMATION_WINDOW_BINDING)
+        .and_then(|window| humantime::parse_duration(&window).ok())
+        .unwrap_or(DEFAULT_DELETION_CONFIRMATION_WINDOW)
+}
+
+/// Archive a removed link as a tombstone if tombstone retention is enabled, otherwise deleting its stats
+/// as there's nothing left to show them alongside.
+///
+/// Returns the stored tombstone, or [`None`] if tombstones are disabled or it could not be stored.
+async fn archive_link(
+    storage: &impl StorageDriver,
+    ctx: &RouteContext<Context>,
+    id: &str,
+    link: LinkModel,
+    status: TombstoneStatus,
+) -> Option<TombstoneModel> {
+    let Some(retention) = get_tombstone_retention(&ctx.env) else {
+        delete_stats(storage, id).await;
+        return None;
+    };
+    let tombstone = TombstoneModel::new(link, status, retention);
+    if !storage
+        .set_serialized_json(&TombstoneModel::key(id), &tombstone, None)
+        .await
+    {
+        return None;
+    }
+    Some(tombstone)
+}
+
+/// Remove a link that is no longer valid according to its expiry behavior, returning its tombstone if one was stored.
+///
+/// Links whose expiry behavior keeps them around are left in storage.
+async fn remove_expired_link(
+    storage: &impl StorageDriver,
+    ctx: &RouteContext<Context>,
+    tenant: Option<&str>,
+    id: &str,
+    link: LinkModel,
+) -> Option<TombstoneModel> {
+    if !link.on_expire.keeps_link() {
+        notify_in_background(
+            &ctx.data,
+            &ctx.env,
+            Notification::LinkExpired {
+                id: id.to_string(),
+                tenant: tenant.map(str::to_string),
+                views: link.views,
+            },
+        );
+    }
+    match link.on_expire {
+        ExpiryBehavior::Delete => {
+            storage.delete(id).await;
+            delete_stats(storage, id).await;
+            None
+        }
+        ExpiryBehavior::Archive => {
+            storage.delete(id).await;
+            archive_link(storage, ctx, id, link, TombstoneStatus::Expired).await
+        }
+        ExpiryBehavior::RedirectFallback | ExpiryBehavior::Gone => None,
+    }
+}
+
+/// Get the link a visit to the route is for, following it to the link it's an alias of.
+///
+/// Paths longer than a link's ID are only served by links that forward paths, in which case the rest of the path
+/// is returned alongside it. Longer IDs are tried first, so `/docs/intro` is served by a `docs/intro` link if there
+/// is one and otherwise by a `docs` link that forwards paths.
+async fn get_route_link(
+    ctx: &RouteContext<Context>,
+    storage: &impl StorageDriver,
+    tenant: Option<&str>,
+) -> worker::Result<(String, Option<LinkModel>, Option<String>)> {
+    let id = get_link_id_from_route(ctx)?;
+    let mut candidates = match ctx.param("rest") {
+        Some(rest) => vec![(id.clone(), Some(rest.clone()))],
+        None => vec![(id.clone(), None)],
+    };
+    if let (Some(namespace), Some(name)) = (ctx.param("id"), ctx.param("name")) {
+        if let Some(namespace) = canonicalize_id(namespace) {
+            let path = match ctx.param("rest") {
+                Some(rest) => format!("{name}/{rest}"),
+                None => name.clone(),
+            };
+            candidates.push((namespace, Some(path)));
+        }
+    }
+
+    for (candidate, path) in candidates {
+        let (followed_id, link) =
+            get_cached_link_following_alias(&ctx.env, storage, tenant, candidate).await;
+        match link {
+            Some(link) if path.is_none() || link.forward_path => {
+                return Ok((followed_id, Some(link), path));
+            }
+            _ => {}
+        }
+    }
+    Ok((id, None, None))
+}
+
+/// Add the link's UTM parameters to the given destination, and forward the rest of the path and the query
+/// parameters a link was visited with to it if the link forwards them.
+///
+/// UTM parameters replace the destination's own parameters of the same name, and parameters the visit was made
+/// with replace both.
+fn forward_visit(
+    req: &Request,
+    link: &LinkModel,
+    path: Option<&str>,
+    destination: &mut Url,
+) -> worker::Result<()> {
+    if let Some(utm) = &link.utm {
+        merge_query_pairs(destination, &utm.query_pairs());
+    }
+    if let Some(path) = path.filter(|_| link.forward_path) {
+        append_path(destination, path);
+    }
+    if !link.forward_query {
+        return Ok(());
+    }
+
+    // Confirming a warning is only meant for this worker, not the destination.
+    let pairs = req
+        .url()?
+        .query_pairs()
+        .filter(|(key, _)| key != CONFIRM_QUERY_PARAM)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    merge_query_pairs(destination, &pairs);
+    Ok(())
+}
+
+/// Build the redirect response for a visit to a link using its redirect mechanism, sending the visitor to the given
+/// destination with the given status code if redirecting over HTTP.
+///
+/// The response also advertises the link's oEmbed endpoint and carries its custom headers.
+fn link_redirect_response(
+    req: &Request,
+    id: &str,
+    link: &LinkModel,
+    destination: Url,
+    status_code: u16,
+) -> worker::Result<Response> {
+    // Links saved before destinations were restricted to web URLs could still have another scheme, which the page
+    // would run on the worker's own origin, so they're only ever redirected to over HTTP.
+    let mut response = match link.mechanism {
+        RedirectMechanism::Html if is_web_url(&destination) => {
+            Response::from_html(render_template(
+                include_str!("../static/redirect.html"),
+                &[("url", destination.as_str())],
+            ))?
+        }
+        _ => Response::redirect_with_status(destination, status_code)?,
+    };
+    let headers = response.headers_mut();
+    headers.set("Link", &oembed_discovery_link(&short_url(&req.url()?, id)))?;
+    // Caches need to know the destination depends on the visitor's languages.
+    if !link.language_rules.is_empty() {
+        headers.set("Vary", "Accept-Language")?;
+    }
+    apply_response_headers(&mut response, &link.response_headers)?;
+    Ok(response)
 }
 
 /// Handle a visit to /:id by attempting to find the key in storage and redirecting to the assigned url.
 ///
 /// This handler will also deal with the following:
+///     - Serving links that were merged into another link as the link they were merged into
 ///     - Incrementing the visits count and storing the updated value
-///     - Deleting the key from storage if it is no longer valid (exceeds max views, timed expiry, etc.)
-async fn link_redirect_handler(req: Request, ctx: RouteContext<()>) -> worker::Result<Response> {
-    let storage = CloudflareKVDriver::new(ctx.kv(CLOUDFLARE_KV_BINDING)?);
-    let id = get_link_id_from_req(&req)?;
+///     - Recording the visit in the link's daily stats
+///     - Buffering the above in memory instead if visit buffering is enabled and rolled out to this request
+///     - Handling links that are no longer valid (exceeds max views, timed expiry, etc.) according to their
+///       expiry behavior, such as deleting them or redirecting to their fallback URL
+///     - Not serving links until their activation time, optionally showing a countdown to it
+///     - Not serving links outside their active hours or on days their recurrence doesn't include
+///     - Not serving links until the link they depend on has expired or reached the required views
+///     - Not counting visits from excluded IP addresses or ranges
+///     - Sending visitors to the destination matching their preferred language, if the link has one
+///     - Forwarding the rest of the path and the query parameters of the visit to the destination, if the link
+///       forwards them
+///     - Asking visitors to confirm before redirecting to flagged destination domains
+async fn link_redirect_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let (id, link, forwarded_path) = get_route_link(&ctx, &storage, tenant.as_deref()).await?;
 
-    match storage.get_deserialized_json::<LinkModel>(&id).await {
+    match link {
         Some(mut link) => {
             if link.disabled {
-                return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+                return link_not_found_response(&req, &ctx.env, &id).await;
+            }
+
+            if link.paused {
+                return link_paused_response(&req, &ctx.env, &id, link.pause_retry_after_seconds)
+                    .await;
+            }
+
+            // Account for visits buffered by this isolate that haven't been written to storage yet.
+            link.views += pending_views(tenant.as_deref(), &id);
+
+            // Views counted by the link's view counter only matter here if they can make it invalid.
+            if link.max_views.is_some() {
+                link.views += pending_counted_views(&ctx.env, tenant.as_deref(), &id).await;
             }
 
+            // Visits to links that are no longer valid are never counted.
             if !link.is_valid() {
-                storage.delete(&id).await;
-                return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+                let response = match (link.on_expire, &link.fallback_url) {
+                    (ExpiryBehavior::RedirectFallback, Some(fallback_url)) => {
+                        let status_code = pick_status_code(&link, &Visitor::from_request(&req));
+                        let mut fallback_url = fallback_url.clone();
+                        forward_visit(&req, &link, forwarded_path.as_deref(), &mut fallback_url)?;
+                        link_redirect_response(&req, &id, &link, fallback_url, status_code)
+                    }
+                    (ExpiryBehavior::Gone, _) => link_gone_response(&req, &ctx.env, &id).await,
+                    _ => link_not_found_response(&req, &ctx.env, &id).await,
+                };
+                remove_expired_link(&storage, &ctx, tenant.as_deref(), &id, link).await;
+                invalidate_cached_link(&ctx.env, tenant.as_deref(), &id).await;
+                return response;
             }
 
-            link.increment_visits();
-            storage.set_serialized_json(&id, &link).await;
-            Response::redirect(link.url)
+            // Links that aren't active yet are kept until they are, optionally counting down to it.
+            let now = Date::now().as_millis();
+            if let Some(activate_at_timestamp) = link
+                .activate_at_timestamp
+                .filter(|_| !link.is_activated_at(now))
+            {
+                if link.countdown {
+                    return link_countdown_response(
+                        &req,
+                        &ctx.env,
+                        &id,
+                        &link.url,
+                        activate_at_timestamp,
+                    )
+                    .await;
+                }
+                return link_not_found_response(&req, &ctx.env, &id).await;
+            }
+
+            // Links outside their active hours or recurrence are served again once they're back within them.
+            if !link.is_within_active_hours_at(now) || !link.is_recurring_at(now) {
+                return link_not_found_response(&req, &ctx.env, &id).await;
+            }
+
+            // Links waiting on the link they depend on aren't served yet, but are kept until they are.
+            if !is_dependency_met(&storage, &link).await {
+                return link_not_found_response(&req, &ctx.env, &id).await;
+            }
+
+            let visitor = Visitor::from_request(&req);
+            let status_code = pick_status_code(&link, &visitor);
+            let destination = resolve_destination(&link, &visitor);
+            let variant = destination.variant();
+            let mut destination = destination.url;
+            forward_visit(&req, &link, forwarded_path.as_deref(), &mut destination)?;
+
+            // Visitors have to confirm they want to continue to flagged destinations, which isn't counted as a visit.
+            if !is_warning_confirmed(&req) {
+                if let Some((domain, reason)) = find_domain_warning(&ctx.env, &destination).await {
+                    return link_warning_response(
+                        &req,
+                        &ctx.env,
+                        &id,
+                        &destination,
+                        &domain,
+                        reason.as_deref(),
+                    )
+                    .await;
+                }
+            }
+
+            // Internal traffic still gets redirected, it just isn't counted.
+            if is_excluded_visit(&req, &ctx.env, &link) {
+                return link_redirect_response(&req, &id, &link, destination, status_code);
+            }
+
+            let privacy = PrivacyConfig::from_env(&ctx.env);
+            let visit = Visit {
+                variant,
+                // Only HTTP redirects are served with the picked status code.
+                status_code: (link.mechanism == RedirectMechanism::Http).then_some(status_code),
+                ..Visit::from_request(&req, &ctx.env, &privacy, get_sample_rate(&ctx.env))
+            };
+            // Counted visits carry an ID the destination can report a conversion with.
+            if let Some(param) = &link.correlation_param {
+                append_visit_id(&mut destination, param, &VisitId::new(&id, variant));
+            }
+            let response = link_redirect_response(&req, &id, &link, destination, status_code);
+            notify_in_background(
+                &ctx.data,
+                &ctx.env,
+                Notification::LinkVisited {
+                    id: id.clone(),
+                    tenant: tenant.clone(),
+                    variant,
+                },
+            );
+
+            let buffer_config = match VisitBufferConfig::from_env(&ctx.env) {
+                Some(config) if is_flag_enabled(&ctx.env, FeatureFlag::VisitBuffering).await => {
+                    Some(config)
+                }
+                _ => None,
+            };
+            match buffer_config {
+                Some(config) => {
+                    if buffer_visit(&config, tenant.as_deref(), &id, visit) {
+                        ctx.data.wait_until(flush_visits(ctx.env.clone()));
+                    }
+                }
+                None => {
+                    // The visit is counted once the redirect has been sent so visitors never wait on storage.
+                    ctx.data
+                        .wait_until(count_visit(ctx.env.clone(), storage, tenant, id, visit));
+                }
+            }
+
+            response
         }
-        None => Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+        None => link_not_found_response(&req, &ctx.env, &id).await,
     }
 }
 
-/// Get the underlying redirect from a link key.
-async fn link_where_handler(req: Request, ctx: RouteContext<()>) -> worker::Result<Response> {
-    let storage = CloudflareKVDriver::new(ctx.kv(CLOUDFLARE_KV_BINDING)?);
-    let id = get_link_id_from_req(&req)?;
+/// Count a visit to a link towards its views and record it in its stats, which is done in the background
+/// after the visitor has been redirected.
+///
+/// Without a view counter the link is rewritten with the visit counted, which is always done on the link as it
+/// currently is in storage while holding its lock, so changes made since the visitor was redirected (or while the
+/// visit is being counted) aren't overwritten and a link removed in the meantime isn't recreated. If the lock can't be
+/// acquired in time the visit is counted without it instead, as losing the visit would be worse than the small chance
+/// of overwriting a change made at the same time.
+async fn count_visit(env: Env, storage: Storage, tenant: Option<String>, id: String, visit: Visit) {
+    // The view counter counts concurrent visits atomically without rewriting the link.
+    if count_view(&env, tenant.as_deref(), &id).await.is_none() {
+        let locked = with_link_lock(&env, tenant.as_deref(), &id, async {
+            increment_link_visits(&env, &storage, &id).await;
+            Response::empty()
+        })
+        .await
+        .is_ok_and(|response| response.status_code() == 409);
+        if locked {
+            increment_link_visits(&env, &storage, &id).await;
+        }
+    }
+    if visit.sampled {
+        record_visit(&storage, &id, visit).await;
+    }
+}
 
-    match storage.get_deserialized_json::<LinkModel>(&id).await {
+/// Rewrite a link as it currently is in storage with a visit counted towards its views.
+async fn increment_link_visits(env: &Env, storage: &Storage, id: &str) {
+    if let Some(mut counted) = storage.get_deserialized_json::<LinkModel>(id).await {
+        counted.increment_visits();
+        if !PrivacyConfig::from_env(env).last_viewed {
+            counted.last_viewed_timestamp = None;
+        }
+        storage
+            .set_serialized_json(
+                id,
+                &counted,
+                counted.storage_expiration(get_tombstone_retention(env).is_some()),
+            )
+            .await;
+    }
+}
+
+/// Get the underlying redirect from a link key, as it would be picked for the visitor making the request.
+///
+/// Browsers are shown a page linking to the destination, while other clients get it as plain text.
+async fn link_where_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let (id, link) = get_link_following_alias(&storage, get_link_id_from_route(&ctx)?).await;
+
+    match link {
         Some(link) => {
             if link.disabled {
-                return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+                return link_not_found_response(&req, &ctx.env, &id).await;
             }
 
-            if !link.is_valid() {
-                storage.delete(&id).await;
-                return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+            if link.paused {
+                return link_paused_response(&req, &ctx.env, &id, link.pause_retry_after_seconds)
+                    .await;
             }
 
-            Response::ok(link.url.to_string())
+            let mut destination = if link.is_valid() {
+                if !link.is_active() || !is_dependency_met(&storage, &link).await {
+                    return link_not_found_response(&req, &ctx.env, &id).await;
+                }
+                resolve_destination(&link, &Visitor::from_request(&req)).url
+            } else {
+                match (link.on_expire, link.fallback_url.clone()) {
+                    (ExpiryBehavior::RedirectFallback, Some(fallback_url)) => fallback_url,
+                    (ExpiryBehavior::Gone, _) => {
+                        return link_gone_response(&req, &ctx.env, &id).await;
+                    }
+                    _ => {
+                        remove_expired_link(&storage, &ctx, tenant.as_deref(), &id, link).await;
+                        return link_not_found_response(&req, &ctx.env, &id).await;
+                    }
+                }
+            };
+            if let Some(utm) = &link.utm {
+                merge_query_pairs(&mut destination, &utm.query_pairs());
+            }
+            match ResponseFormat::negotiate(&req) {
+                ResponseFormat::Text => text_response(destination.as_str(), 200),
+                ResponseFormat::Html => {
+                    let mut response = Response::from_html(render_template(
+                        include_str!("../static/where.html"),
+                        &[("id", &id), ("url", destination.as_str())],
+                    ))?;
+                    apply_preconnect_hint(&ctx.env, &mut response, &destination)?;
+                    Ok(response)
+                }
+            }
         }
-        None => Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+        None => link_not_found_response(&req, &ctx.env, &id).await,
+    }
+}
+
+/// Check whether a link ID is taken without returning its details or counting a visit.
+///
+/// Responds with an empty 200 if the ID is in use, or an empty 404 if it is not.
+async fn link_exists_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    match storage.get(&id).await {
+        Some(_) => Response::empty(),
+        None => Ok(Response::empty()?.with_status(404)),
     }
 }
 
+/// Explain whether a link is served to visitors, returning the result of every check as JSON.
+///
+/// Nothing is written, so expired links aren't removed.
+async fn link_explain_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let requested_id = get_link_id_from_route(&ctx)?;
+    let (id, link) = get_link_following_alias(&storage, requested_id.clone()).await;
+    let Some(link) = link else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    let views = link.views
+        + pending_views(tenant.as_deref(), &id)
+        + pending_counted_views(&ctx.env, tenant.as_deref(), &id).await;
+    let dependency = match &link.requires {
+        Some(requires) => storage.get_deserialized_json::<LinkModel>(requires).await,
+        None => None,
+    };
+    Response::from_json(&ExplainLinkResponse::from_link(
+        &requested_id,
+        id,
+        &link,
+        dependency.as_ref(),
+        views,
+        Date::now().as_millis(),
+    ))
+}
+
 /// Get a link and return its details as JSON.
-async fn link_details_handler(req: Request, ctx: RouteContext<()>) -> worker::Result<Response> {
-    let auth_guard = authorized_guard(&req, &ctx);
+///
+/// If the link has been removed but is still retained as a tombstone, the archived
+/// record will be returned instead alongside a status marker explaining why it was removed.
+async fn link_details_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
     if let Err(err) = auth_guard {
         return err;
     }
 
-    let storage = CloudflareKVDriver::new(ctx.kv(CLOUDFLARE_KV_BINDING)?);
-    let id = get_link_id_from_req(&req)?;
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
 
     match storage.get_deserialized_json::<LinkModel>(&id).await {
         Some(link) => {
-            if !link.is_valid() {
-                storage.delete(&id).await;
-                return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+            if !link.is_valid() && !link.on_expire.keeps_link() {
+                return match remove_expired_link(&storage, &ctx, tenant.as_deref(), &id, link).await
+                {
+                    Some(tombstone) => Response::from_json(&tombstone),
+                    None => Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+                };
             }
 
             Response::from_json(&link)
         }
-        None => Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+        None => {
+            let tombstone_key = TombstoneModel::key(&id);
+            match storage
+                .get_deserialized_json::<TombstoneModel>(&tombstone_key)
+                .await
+            {
+                Some(tombstone) if tombstone.is_retained() => Response::from_json(&tombstone),
+                Some(_) => {
+                    storage.delete(&tombstone_key).await;
+                    Response::error(LINK_DOESNT_EXIST_RESPONSE, 404)
+                }
+                None => Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+            }
+        }
+    }
+}
+
+/// Get a link's lifetime view count and visit histograms alongside its daily stats and older monthly stats as JSON.
+async fn link_stats_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let Some(range) = StatsRange::from_url(&req.url()?) else {
+        return Response::error(INVALID_STATS_RANGE_RESPONSE, 400);
+    };
+    let Some(link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    // Only the view count is available when visit stats aren't stored, so don't bother looking for any.
+    let (days, months) = if PrivacyConfig::from_env(&ctx.env).visit_stats {
+        (
+            range.filter(get_stats(&storage, &StatsModel::day_key_prefix(&id)).await),
+            range.filter(get_stats(&storage, &StatsModel::month_key_prefix(&id)).await),
+        )
+    } else {
+        Default::default()
+    };
+
+    // The link's own view count can't be split up by time, so the views within a range are estimated
+    // from its stats instead.
+    let sample_rate = get_sample_rate(&ctx.env);
+    let views = if range.is_bounded() {
+        estimate_views(days.values().chain(months.values()), sample_rate)
+    } else {
+        link.views + pending_counted_views(&ctx.env, tenant.as_deref(), &id).await
+    };
+
+    Response::from_json(&LinkStatsResponse::from_stats(
+        views,
+        range,
+        VisitBufferConfig::from_env(&ctx.env).is_some(),
+        sample_rate,
+        days,
+        months,
+    ))
+}
+
+/// Get a one-page PDF report of a link's views, daily views and top countries that can be shared with
+/// people who won't use the stats API.
+async fn link_report_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
     }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let Some(range) = StatsRange::from_url(&req.url()?) else {
+        return Response::error(INVALID_STATS_RANGE_RESPONSE, 400);
+    };
+    let Some(link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+    let views = link.views + pending_counted_views(&ctx.env, tenant.as_deref(), &id).await;
+
+    let stats = if PrivacyConfig::from_env(&ctx.env).visit_stats {
+        Some((
+            range.filter(get_stats(&storage, &StatsModel::day_key_prefix(&id)).await),
+            range.filter(get_stats(&storage, &StatsModel::month_key_prefix(&id)).await),
+        ))
+    } else {
+        None
+    };
+
+    let report = render_report(
+        &short_url(&req.url()?, &id),
+        &link,
+        views,
+        stats.as_ref().map(|(days, months)| (days, months)),
+        range,
+        get_sample_rate(&ctx.env),
+        Date::now().as_millis(),
+    );
+    let mut response = Response::from_bytes(report)?;
+    let headers = response.headers_mut();
+    headers.set("Content-Type", "application/pdf")?;
+    headers.set(
+        "Content-Disposition",
+        r#"inline; filename="link-report.pdf""#,
+    )?;
+    Ok(response)
+}
+
+/// Re-resolve where a link's destination ultimately lands and return the updated redirect chain.
+async fn resolve_link_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+        let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+            return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+        };
+
+        link.resolved_chain = resolve_redirect_chain(&link.url).await;
+        if !storage
+            .set_serialized_json(
+                &id,
+                &link,
+                link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+            )
+            .await
+        {
+            return Response::error(GENERIC_LINK_UPDATE_ERROR_RESPONSE, 500);
+        }
+
+        Response::from_json(&link.resolved_chain)
+    })
+    .await
 }
 
 /// Create a new link or update an existing one.
+///
+/// When called without an ID and with `dedupe` enabled, the ID is derived from the destination URL
+/// so that repeatedly shortening the same URL returns the same link. Otherwise links created without
+/// an ID are given a random one.
 async fn create_or_update_link_handler(
     mut req: Request,
-    ctx: RouteContext<()>,
+    ctx: RouteContext<Context>,
 ) -> worker::Result<Response> {
-    let auth_guard = authorized_guard(&req, &ctx);
+    let auth_guard = authorized_guard(&req, &ctx).await;
     if let Err(err) = auth_guard {
         return err;
     }
 
-    let storage = CloudflareKVDriver::new(ctx.kv(CLOUDFLARE_KV_BINDING)?);
-    let id: String = get_link_id_from_req(&req)?;
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let route_id = match ctx.param("id") {
+        Some(_) => Some(get_link_id_from_route(&ctx)?),
+        None => None,
+    };
 
     // Validate the JSON from the request can be deserialized.
-    let Ok(body) = req.json::<CreateLinkRequestBody>().await else {
+    let Ok(mut body) = req.json::<CreateLinkRequestBody>().await else {
         return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
     };
 
-    // Validate that the struct is valid using the custom struct validator.
-    if body.validate().is_err() {
+    if let Err(err) = prepare_link_request(&ctx.env, &req.url()?, &mut body) {
+        return err.to_response();
+    }
+
+    let id = match route_id {
+        Some(_) if body.dedupe => return SaveLinkError::DedupeWithId.to_response(),
+        Some(id) => id,
+        None if body.dedupe => {
+            // Identical destinations share a single link, so hand back the existing one if there is one.
+            match find_deduplicated_id(&storage, &body.url).await {
+                Some((id, Some(existing_model))) => {
+                    return Response::from_json(&CreateLinkResponse::from_model(
+                        &existing_model,
+                        &id,
+                        req.url()?,
+                        false,
+                    ));
+                }
+                Some((id, None)) => id,
+                None => return Response::error(GENERIC_LINK_CREATE_ERROR_RESPONSE, 500),
+            }
+        }
+        // Links created without an ID are given a random one.
+        None => match generate_unique_id(
+            &storage,
+            &get_random_id_alphabet(&ctx.env),
+            get_random_id_length(&ctx.env),
+        )
+        .await
+        {
+            Some(id) => id,
+            None => return Response::error(GENERIC_LINK_CREATE_ERROR_RESPONSE, 500),
+        },
+    };
+
+    let host_url = req.url()?;
+    with_link_lock(&ctx.env, tenant.as_deref(), &id.clone(), async {
+        match save_link(&storage, &ctx.env, id, body, true).await {
+            Ok(saved) => {
+                invalidate_cached_link(&ctx.env, tenant.as_deref(), &saved.id).await;
+                if saved.created {
+                    notify_in_background(
+                        &ctx.data,
+                        &ctx.env,
+                        Notification::LinkCreated {
+                            id: saved.id.clone(),
+                            tenant: tenant.clone(),
+                        },
+                    );
+                }
+                Response::from_json(&CreateLinkResponse::from_model(
+                    &saved.model,
+                    &saved.id,
+                    host_url,
+                    saved.created,
+                ))
+            }
+            Err(err) => err.to_response(),
+        }
+    })
+    .await
+}
+
+/// Partially update a link, only changing the fields given in the request body.
+///
+/// The updated link is validated as a whole, exactly as if every field had been sent to [`create_or_update_link_handler`].
+async fn patch_link_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
+    }
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let Ok(patch) = req.json::<PatchLinkRequestBody>().await else {
         return Response::error(INVALID_PAYLOAD_RESPONSE, 400);
+    };
+
+    let host_url = req.url()?;
+    with_link_lock(&ctx.env, tenant.as_deref(), &id.clone(), async {
+        let Some(link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+            return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+        };
+
+        let mut body = patch_link_request(&link, patch);
+        if let Err(err) = prepare_link_request(&ctx.env, &host_url, &mut body) {
+            return err.to_response();
+        }
+        match save_link(&storage, &ctx.env, id, body, true).await {
+            Ok(saved) => {
+                invalidate_cached_link(&ctx.env, tenant.as_deref(), &saved.id).await;
+                Response::from_json(&CreateLinkResponse::from_model(
+                    &saved.model,
+                    &saved.id,
+                    host_url,
+                    saved.created,
+                ))
+            }
+            Err(err) => err.to_response(),
+        }
+    })
+    .await
+}
+
+/// Pause a link so that it serves a temporarily unavailable page instead of redirecting.
+///
+/// The request body is optional and can set how long clients are asked to wait before retrying.
+async fn pause_link_handler(
+    mut req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
     }
 
-    // Prevent making a link that recurses forever on the same domain.
-    if req.url()?.domain() == body.url.domain() {
-        return Response::error(NO_LINK_OWN_DOMAIN_RESPONSE, 400);
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let body = match req.text().await {
+        Ok(text) if text.trim().is_empty() => PauseLinkRequestBody::default(),
+        Ok(text) => match serde_json::from_str::<PauseLinkRequestBody>(&text) {
+            Ok(body) => body,
+            Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+        },
+        Err(_) => return Response::error(INVALID_PAYLOAD_RESPONSE, 400),
+    };
+
+    with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+        let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+            return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+        };
+
+        link.pause(body.retry_after);
+        if !storage
+            .set_serialized_json(
+                &id,
+                &link,
+                link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+            )
+            .await
+        {
+            return Response::error(GENERIC_LINK_UPDATE_ERROR_RESPONSE, 500);
+        }
+        invalidate_cached_link(&ctx.env, tenant.as_deref(), &id).await;
+
+        Response::ok(LINK_PAUSE_SUCCESS_RESPONSE)
+    })
+    .await
+}
+
+/// Resume a paused link so that it redirects again.
+async fn resume_link_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
+    if let Err(err) = auth_guard {
+        return err;
     }
 
-    // Grab the existing model and check if we can overwrite it (if it exists).
-    let existing_model = storage.get_deserialized_json::<LinkModel>(&id).await;
-    if !body.overwrite && existing_model.is_some() {
-        return Response::error(LINK_ALREADY_EXISTS_NO_OVERWRITE, 409);
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+        let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+            return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+        };
+
+        link.resume();
+        if !storage
+            .set_serialized_json(
+                &id,
+                &link,
+                link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+            )
+            .await
+        {
+            return Response::error(GENERIC_LINK_UPDATE_ERROR_RESPONSE, 500);
+        }
+        invalidate_cached_link(&ctx.env, tenant.as_deref(), &id).await;
+
+        Response::ok(LINK_RESUME_SUCCESS_RESPONSE)
+    })
+    .await
+}
+
+/// Serve a card for a link as an HTML page that can be embedded in other sites with an iframe.
+async fn link_embed_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let Some(link) = get_public_link(&storage, &id).await else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    Response::from_html(render_embed(&req.url()?, &id, &link))
+}
+
+/// Get the oEmbed description of a link's embeddable card as JSON.
+async fn link_oembed_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let Some(link) = get_public_link(&storage, &id).await else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    Response::from_json(&link_oembed(&req.url()?, &id, &link, None, None))
+}
+
+/// Serve a print-friendly poster for a link with a QR code of its short URL and the hostname's logo, if it has one.
+///
+/// The poster is sized for A4 paper unless `?paper=letter` is given.
+async fn link_poster_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let url = req.url()?;
+    let paper = match url.query_pairs().find(|(key, _)| key == "paper") {
+        Some((_, name)) => match PosterPaper::from_name(&name) {
+            Some(paper) => paper,
+            None => return Response::error(INVALID_POSTER_PAPER_RESPONSE, 400),
+        },
+        None => PosterPaper::default(),
+    };
+
+    if get_public_link(&storage, &id).await.is_none() {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
     }
 
-    let model = match existing_model {
-        Some(model) => model.modify(LinkBuilderArgs {
-            url: body.url,
-            max_views: body.max_views,
-            disabled: body.disabled,
-            expiry_timestamp: body
-                .expire_in
-                .map(|time| Date::now().as_millis() + time.as_millis() as u64),
-        }),
-        None => LinkModel::new(LinkBuilderArgs {
-            url: body.url,
-            max_views: body.max_views,
-            disabled: body.disabled,
-            expiry_timestamp: body
-                .expire_in
-                .map(|time| Date::now().as_millis() + time.as_millis() as u64),
-        }),
+    let logo = get_branding(&req, &ctx.env, BrandingAsset::Logo).await;
+    match render_poster(&url, &id, paper, logo.as_deref()) {
+        Some(html) => Response::from_html(html),
+        None => Response::error(QR_CODE_TOO_LONG_RESPONSE, 500),
+    }
+}
+
+/// Serve a QR code of a link's short URL as an SVG image, or a PNG image with `?format=png`.
+async fn link_qr_code_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    let url = req.url()?;
+    let format = match url.query_pairs().find(|(key, _)| key == "format") {
+        Some((_, name)) => match QrImageFormat::from_name(&name) {
+            Some(format) => format,
+            None => return Response::error(INVALID_QR_FORMAT_RESPONSE, 400),
+        },
+        None => QrImageFormat::default(),
     };
 
-    if !storage.set_serialized_json::<&LinkModel>(&id, &model).await {
-        return Response::error(GENERIC_LINK_CREATE_ERROR_RESPONSE, 500);
+    if get_public_link(&storage, &id).await.is_none() {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
     }
 
-    Response::from_json(&CreateLinkResponse::from_model(&model, req.url()?))
+    let Some(code) = QrCode::encode(short_url(&url, &id).as_str().as_bytes()) else {
+        return Response::error(QR_CODE_TOO_LONG_RESPONSE, 500);
+    };
+    let mut response = Response::from_bytes(code.render(format))?;
+    response
+        .headers_mut()
+        .set("Content-Type", format.content_type())?;
+    Ok(response)
 }
 
-/// Delete a link.
-async fn delete_link_handler(req: Request, ctx: RouteContext<()>) -> worker::Result<Response> {
-    let auth_guard = authorized_guard(&req, &ctx);
+/// Serve the latest screenshot captured of a link's destination.
+async fn link_screenshot_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    match storage.get_deserialized_json::<LinkModel>(&id).await {
+        Some(link) if !link.disabled && link.is_valid() => {
+            if link.screenshot.is_none() {
+                return Response::error(SCREENSHOT_DOESNT_EXIST_RESPONSE, 404);
+            }
+            let key = screenshot_key(tenant.as_deref(), &id);
+            let Some(image) = get_screenshot(&ctx.env, &key).await else {
+                return Response::error(SCREENSHOT_DOESNT_EXIST_RESPONSE, 404);
+            };
+
+            let mut response = Response::from_bytes(image)?;
+            response
+                .headers_mut()
+                .set("Content-Type", SCREENSHOT_CONTENT_TYPE)?;
+            Ok(response)
+        }
+        _ => Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+    }
+}
+
+/// Sign a statement of where a link currently sends visitors, so downstream systems can later verify
+/// that its destination hasn't changed since they vetted it.
+///
+/// This is public as it only reveals the destinations the link already redirects to.
+async fn link_attestation_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    if !attestations_enabled(&ctx.env) {
+        return Response::error(ATTESTATIONS_DISABLED_RESPONSE, 501);
+    }
+
+    let storage = get_request_storage(&req, &ctx.env).await?;
+    let id = get_link_id_from_route(&ctx)?;
+    let (_, link) = get_link_following_alias(&storage, id.clone()).await;
+    let Some(link) = link.filter(|link| !link.disabled && link.is_valid()) else {
+        return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+    };
+
+    let host = req.url()?.host_str().unwrap_or_default().to_string();
+    let statement = AttestationStatement::new(host, id, &link, Date::now().as_millis());
+    let payload = serde_json::to_string(&statement)?;
+    let Some(signature) = sign_payload(&ctx.env, &payload).await else {
+        return Response::error(GENERIC_ATTESTATION_SIGN_ERROR_RESPONSE, 500);
+    };
+
+    Response::from_json(&AttestationResponse {
+        algorithm: ATTESTATION_ALGORITHM,
+        statement,
+        payload,
+        signature,
+    })
+}
+
+/// Capture a new screenshot of a link's destination, replacing the previous one.
+async fn capture_screenshot_handler(
+    req: Request,
+    ctx: RouteContext<Context>,
+) -> worker::Result<Response> {
+    let auth_guard = authorized_guard(&req, &ctx).await;
     if let Err(err) = auth_guard {
         return err;
     }
 
-    let storage = CloudflareKVDriver::new(ctx.kv(CLOUDFLARE_KV_BINDING)?);
+    if !screenshots_enabled(&ctx.env) {
+        return Response::error(SCREENSHOTS_DISABLED_RESPONSE, 501);
+    }
 
-    let id = get_link_id_from_req(&req)?;
-    match storage.get(&id).await {
-        Some(_) => (),
-        None => return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404),
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+    let id = get_link_id_from_route(&ctx)?;
+
+    with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+        let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await else {
+            return Response::error(LINK_DOESNT_EXIST_RESPONSE, 404);
+        };
+
+        let Some(image) = capture_screenshot(&ctx.env, &link.url).await else {
+            return Response::error(GENERIC_SCREENSHOT_CAPTURE_ERROR_RESPONSE, 502);
+        };
+        if !store_screenshot(&ctx.env, &screenshot_key(tenant.as_deref(), &id), image).await {
+            return Response::error(GENERIC_SCREENSHOT_CAPTURE_ERROR_RESPONSE, 500);
+        }
+
+        link.screenshot = Some(LinkScreenshot {
+            url: link.url.clone(),
+            captured_at_timestamp: Date::now().as_millis(),
+        });
+        if !storage
+            .set_serialized_json(
+                &id,
+                &link,
+                link.storage_expiration(get_tombstone_retention(&ctx.env).is_some()),
+            )
+            .await
+        {
+            return Response::error(GENERIC_LINK_UPDATE_ERROR_RESPONSE, 500);
+        }
+
+        Response::from_json(&link.screenshot)
+    })
+    .await
+}
+
+/// Delete a link.
+async fn delete_link_handler(req: Request, ctx: RouteContext<Context>) -> worker::Result<Response> {
+    let key = match authorized_key_guard(&req, &ctx).await {
+        Ok(key) => key,
+        Err(err) => return err,
+    };
+
+    let tenant = resolve_tenant(&req, &ctx.env).await;
+    let storage = get_tenant_storage(&ctx.env, tenant.as_deref())?;
+
+    let id = get_link_id_from_route(&ctx)?;
+    with_link_lock(&ctx.env, tenant.as_deref(), &id, async {
+        match delete_link(&storage, &ctx, tenant.as_deref(), &id, &key).await {
+            Ok(()) => Response::ok(LINK_DELETE_SUCCESS_RESPONSE),
+            // Staging isn't a failure, the link just isn't gone until another key confirms it.
+            Err(DeleteLinkError::ConfirmationRequired) => {
+                Ok(Response::ok(LINK_DELETE_STAGED_RESPONSE)?.with_status(202))
+            }
+            Err(err) => Response::error(err.message(), err.status()),
+        }
+    })
+    .await
+}
+
+/// The reasons a link could not be deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeleteLinkError {
+    NotFound,
+    /// The link is protected and its deletion was staged, waiting for another API key to confirm it.
+    ConfirmationRequired,
+    /// The link is protected and its staged deletion was confirmed with the key that staged it.
+    SameKey,
+    StorageFailure,
+}
+
+impl DeleteLinkError {
+    /// The message describing this error to the client.
+    fn message(&self) -> &'static str {
+        match self {
+            Self::NotFound => LINK_DOESNT_EXIST_RESPONSE,
+            Self::ConfirmationRequired => LINK_DELETE_STAGED_RESPONSE,
+            Self::SameKey => LINK_DELETE_SAME_KEY_RESPONSE,
+            Self::StorageFailure => GENERIC_LINK_DELETE_ERROR_RESPONSE,
+        }
+    }
+
+    /// The HTTP status code for this error.
+    fn status(&self) -> u16 {
+        match self {
+            Self::NotFound => 404,
+            Self::ConfirmationRequired => 202,
+            Self::SameKey => 409,
+            Self::StorageFailure => 500,
+        }
+    }
+}
+
+/// Delete a link along with its screenshot, archiving it as a tombstone if tombstone retention is enabled.
+///
+/// Protected links are only deleted once two different API keys have requested it within the
+/// confirmation window, the first request staging the deletion and the second confirming it.
+///
+/// Callers should hold the link's lock so a concurrent update can't recreate it halfway through.
+async fn delete_link(
+    storage: &impl StorageDriver,
+    ctx: &RouteContext<Context>,
+    tenant: Option<&str>,
+    id: &str,
+    requested_by: &str,
+) -> Result<(), DeleteLinkError> {
+    let Some(raw_link) = storage.get(id).await else {
+        return Err(DeleteLinkError::NotFound);
     };
 
-    if !storage.delete(&id).await {
-        return Response::error(GENERIC_LINK_DELETE_ERROR_RESPONSE, 500);
+    let protected = serde_json::from_str::<LinkModel>(&raw_link).is_ok_and(|link| link.protected);
+    if protected {
+        let pending_key = PendingDeletionModel::key(id);
+        let pending = storage
+            .get_deserialized_json::<PendingDeletionModel>(&pending_key)
+            .await
+            .filter(PendingDeletionModel::is_pending);
+        match pending {
+            Some(pending) if pending.requested_by == requested_by => {
+                return Err(DeleteLinkError::SameKey);
+            }
+            Some(_) => {
+                storage.delete(&pending_key).await;
+            }
+            None => {
+                let pending = PendingDeletionModel::new(
+                    requested_by.to_string(),
+                    get_deletion_confirmation_window(ctx),
+                );
+                if !storage
+                    .set_serialized_json(&pending_key, &pending, None)
+                    .await
+                {
+                    return Err(DeleteLinkError::StorageFailure);
+                }
+                return Err(DeleteLinkError::ConfirmationRequired);
+            }
+        }
     }
 
-    Response::ok(LINK_DELETE_SUCCESS_RESPONSE)
+    if !storage.delete(id).await {
+        return Err(DeleteLinkError::StorageFailure);
+    }
+    invalidate_cached_link(&ctx.env, tenant, id).await;
+    notify_in_background(
+        &ctx.data,
+        &ctx.env,
+        Notification::LinkDeleted {
+            id: id.to_string(),
+            tenant: tenant.map(str::to_string),
+        },
+    );
+
+    if let Ok(link) = serde_json::from_str::<LinkModel>(&raw_link) {
+        if link.screenshot.is_some() {
+            delete_screenshot(&ctx.env, &screenshot_key(tenant, id)).await;
+        }
+        archive_link(storage, ctx, id, link, TombstoneStatus::Deleted).await;
+    }
+    Ok(())
 }
@@ -0,0 +1,105 @@
+use crate::{
+    environment::{environment_key_prefix, get_var},
+    models::link::LinkModel,
+    storage::StorageDriver,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::time::Duration;
+use worker::{Cache, Env, Response};
+
+/// The binding name for the variable controlling how long links are kept in the edge cache for redirects.
+///
+/// Links are always read from storage unless this is set.
+pub const LINK_CACHE_TTL_BINDING: &str = "LINK_CACHE_TTL";
+
+/// The origin of the URLs links are cached under, which is never requested and only namespaces the cache keys.
+const LINK_CACHE_ORIGIN: &str = "https://link-cache.workerlink.internal";
+
+/// Get how long links are kept in the edge cache for, or [`None`] if the cache is disabled.
+pub fn get_link_cache_ttl(env: &Env) -> Option<Duration> {
+    humantime::parse_duration(&get_var(env, LINK_CACHE_TTL_BINDING)?)
+        .ok()
+        .filter(|ttl| !ttl.is_zero())
+}
+
+/// Get the URL a link of the given tenant is cached under.
+fn cache_key(env: &Env, tenant: Option<&str>, id: &str) -> String {
+    let key = format!(
+        "{}{}{id}",
+        environment_key_prefix(env),
+        tenant
+            .map(|tenant| format!("tenant:{tenant}:"))
+            .unwrap_or_default()
+    );
+    format!(
+        "{LINK_CACHE_ORIGIN}/{}",
+        utf8_percent_encode(&key, NON_ALPHANUMERIC)
+    )
+}
+
+/// Get a link for a redirect, serving it from the edge cache of the current location when possible and
+/// otherwise reading it from storage and caching it for next time.
+///
+/// Cached links can be out of date by up to [`LINK_CACHE_TTL_BINDING`], such as having fewer views than
+/// the link in storage, so they must never be written back to storage. Links that don't exist aren't cached
+/// so newly created links are served immediately.
+pub async fn get_cached_link(
+    env: &Env,
+    storage: &impl StorageDriver,
+    tenant: Option<&str>,
+    id: &str,
+) -> Option<LinkModel> {
+    let Some(ttl) = get_link_cache_ttl(env) else {
+        return storage.get_deserialized_json::<LinkModel>(id).await;
+    };
+
+    let key = cache_key(env, tenant, id);
+    let cache = Cache::default();
+    if let Ok(Some(mut cached)) = cache.get(&key, false).await {
+        if let Ok(link) = cached.json::<LinkModel>().await {
+            return Some(link);
+        }
+    }
+
+    let link = storage.get_deserialized_json::<LinkModel>(id).await?;
+    if let Ok(mut response) = Response::from_json(&link) {
+        let _ = response.headers_mut().set(
+            "Cache-Control",
+            &format!("max-age={}", ttl.as_secs().max(1)),
+        );
+        let _ = cache.put(&key, response).await;
+    }
+    Some(link)
+}
+
+/// Get a link for a redirect from the edge cache like [`get_cached_link`], following it to the link it's an alias of.
+///
+/// Returns the ID of the link that was followed to alongside it.
+pub async fn get_cached_link_following_alias(
+    env: &Env,
+    storage: &impl StorageDriver,
+    tenant: Option<&str>,
+    id: String,
+) -> (String, Option<LinkModel>) {
+    let link = get_cached_link(env, storage, tenant, &id).await;
+    match link.as_ref().and_then(|link| link.alias_of.clone()) {
+        Some(target) => {
+            let link = get_cached_link(env, storage, tenant, &target).await;
+            (target, link)
+        }
+        None => (id, link),
+    }
+}
+
+/// Remove a link from the edge cache after it's been changed or deleted, so the change is served immediately.
+///
+/// The Cache API only removes it from the cache of the current location, so other locations can keep serving
+/// the previous version of the link until it expires from their cache.
+pub async fn invalidate_cached_link(env: &Env, tenant: Option<&str>, id: &str) {
+    if get_link_cache_ttl(env).is_none() {
+        return;
+    }
+    let _ = Cache::default()
+        .delete(cache_key(env, tenant, id), false)
+        .await;
+}
@@ -0,0 +1,569 @@
+use crate::{
+    api::requests::{CreateLinkRequestBody, PatchLinkRequestBody},
+    conversions::is_valid_correlation_param,
+    environment::get_bool_var,
+    get_tombstone_retention,
+    messages::*,
+    models::link::{ExpiryBehavior, LinkBuilderArgs, LinkModel, GEO_TARGET_DEFAULT},
+    responses::is_allowed_response_header,
+    routing::{is_valid_country_code, is_valid_language_tag, REDIRECT_STATUS_CODES},
+    schedule::{is_valid_timezone, local_date_time_to_timestamp, parse_local_date_time},
+    slugs::{is_valid_id, normalize_id, short_hash},
+    storage::StorageDriver,
+    urls::{is_web_url, normalize_url, resolve_redirect_chain},
+};
+use url::Url;
+use validator::Validate;
+use worker::{Date, Env, Response};
+
+/// The binding name for the variable controlling whether link IDs are normalized by default.
+pub const NORMALIZE_IDS_BINDING: &str = "NORMALIZE_IDS";
+
+/// The binding name for the variable controlling whether destination URLs are normalized by default.
+pub const NORMALIZE_URLS_BINDING: &str = "NORMALIZE_URLS";
+
+/// The binding name for the variable controlling whether tracking parameters are stripped when normalizing URLs.
+pub const STRIP_TRACKING_PARAMS_BINDING: &str = "STRIP_TRACKING_PARAMS";
+
+/// The reasons a link could not be saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveLinkError {
+    InvalidPayload,
+    OwnDomain,
+    UnsupportedScheme,
+    InvalidId,
+    DedupeWithId,
+    AlreadyExists,
+    Frozen,
+    Protected,
+    StorageFailure,
+}
+
+impl SaveLinkError {
+    /// The message describing this error to the client.
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::InvalidPayload => INVALID_PAYLOAD_RESPONSE,
+            Self::OwnDomain => NO_LINK_OWN_DOMAIN_RESPONSE,
+            Self::UnsupportedScheme => UNSUPPORTED_URL_SCHEME_RESPONSE,
+            Self::InvalidId => INVALID_LINK_ID_RESPONSE,
+            Self::DedupeWithId => DEDUPE_WITH_ID_RESPONSE,
+            Self::AlreadyExists => LINK_ALREADY_EXISTS_NO_OVERWRITE,
+            Self::Frozen => LINK_FROZEN_RESPONSE,
+            Self::Protected => LINK_PROTECTED_RESPONSE,
+            Self::StorageFailure => GENERIC_LINK_CREATE_ERROR_RESPONSE,
+        }
+    }
+
+    /// The HTTP status code for this error.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::InvalidPayload
+            | Self::OwnDomain
+            | Self::UnsupportedScheme
+            | Self::InvalidId
+            | Self::DedupeWithId => 400,
+            Self::AlreadyExists => 409,
+            Self::Frozen => 423,
+            Self::Protected => 403,
+            Self::StorageFailure => 500,
+        }
+    }
+
+    /// Build an error response for this error.
+    pub fn to_response(self) -> worker::Result<Response> {
+        Response::error(self.message(), self.status())
+    }
+}
+
+/// Get a link to serve visits for, following it to the link it was merged into if it's an alias.
+///
+/// Returns the ID of the link that was actually found alongside it, which is the one visits should be counted for.
+pub async fn get_link_following_alias(
+    storage: &impl StorageDriver,
+    id: String,
+) -> (String, Option<LinkModel>) {
+    let link = storage.get_deserialized_json::<LinkModel>(&id).await;
+    match link.as_ref().and_then(|link| link.alias_of.clone()) {
+        Some(target) => {
+            let link = storage.get_deserialized_json::<LinkModel>(&target).await;
+            (target, link)
+        }
+        None => (id, link),
+    }
+}
+
+/// Get a link that can be publicly shown, such as in an embed, or [`None`] if it's missing, disabled, paused, expired,
+/// not active yet or waiting on the link it depends on.
+pub async fn get_public_link(storage: &impl StorageDriver, id: &str) -> Option<LinkModel> {
+    let link = get_link_following_alias(storage, id.to_string())
+        .await
+        .1
+        .filter(|link| !link.disabled && !link.paused && link.is_valid() && link.is_active())?;
+    is_dependency_met(storage, &link).await.then_some(link)
+}
+
+/// Whether or not the link a link depends on allows it to be served, which is always the case for links
+/// without a dependency.
+pub async fn is_dependency_met(storage: &impl StorageDriver, link: &LinkModel) -> bool {
+    let Some(requires) = &link.requires else {
+        return true;
+    };
+    let dependency = storage.get_deserialized_json::<LinkModel>(requires).await;
+    link.is_dependency_met_by(dependency.as_ref())
+}
+
+/// The longest value a single UTM parameter of a link can have.
+const MAX_UTM_VALUE_LENGTH: usize = 256;
+
+/// The length of IDs derived from a hash of a link's destination URL.
+const DEDUPLICATED_ID_LENGTH: usize = 7;
+
+/// The maximum length a deduplicated ID can grow to when resolving hash collisions.
+const MAX_DEDUPLICATED_ID_LENGTH: usize = 13;
+
+/// Find the ID for a deduplicated link to the given URL.
+///
+/// The ID is derived from a truncated hash of the URL and grows longer on hash collisions with links
+/// to other destinations. Returns the ID alongside the existing link if one already redirects to the same
+/// URL, or [`None`] if no usable ID could be found.
+pub async fn find_deduplicated_id(
+    storage: &impl StorageDriver,
+    url: &Url,
+) -> Option<(String, Option<LinkModel>)> {
+    for length in DEDUPLICATED_ID_LENGTH..=MAX_DEDUPLICATED_ID_LENGTH {
+        let id = short_hash(url.as_str(), length);
+        match storage.get_deserialized_json::<LinkModel>(&id).await {
+            Some(link) if link.url == *url && link.is_valid() => return Some((id, Some(link))),
+            Some(link) if link.url == *url => return Some((id, None)),
+            Some(_) => continue,
+            None if storage.get(&id).await.is_some() => continue,
+            None => return Some((id, None)),
+        }
+    }
+    None
+}
+
+/// Whether or not a request leaves every destination of a frozen link as it is without shortening the freeze.
+///
+/// Everything else, such as the expiry or maximum views, can still be changed while a link is frozen.
+fn keeps_frozen_destinations(link: &LinkModel, body: &CreateLinkRequestBody) -> bool {
+    body.url == link.url
+        && body.fallback_url == link.fallback_url
+        && body.language_rules == link.language_rules
+        && body.geo_targets == link.geo_targets
+        && body.variants == link.variants
+        && body.freeze_until_timestamp >= link.freeze_until_timestamp
+}
+
+/// A link that was successfully saved to storage.
+pub struct SavedLink {
+    pub id: String,
+    pub model: LinkModel,
+    pub created: bool,
+}
+
+/// Build the request for updating a link from a partial update, taking every field the update doesn't set
+/// from the link as it currently is.
+///
+/// The result still needs to be prepared with [`prepare_link_request`] so the updated link is validated as a whole.
+pub fn patch_link_request(link: &LinkModel, patch: PatchLinkRequestBody) -> CreateLinkRequestBody {
+    // Relative and local times replace the stored timestamps they're converted into.
+    let expiry_timestamp = match patch.expire_in {
+        Some(_) => patch.expiry_timestamp.flatten(),
+        None => patch.expiry_timestamp.unwrap_or(link.expiry_timestamp),
+    };
+    let activate_at_timestamp = match (&patch.activate_at, patch.start_in) {
+        (Some(_), _) | (_, Some(_)) => patch.activate_at_timestamp.flatten(),
+        (None, None) => patch
+            .activate_at_timestamp
+            .unwrap_or(link.activate_at_timestamp),
+    };
+
+    CreateLinkRequestBody {
+        url: patch.url.unwrap_or_else(|| link.url.clone()),
+        overwrite: true,
+        expire_in: patch.expire_in.flatten(),
+        expiry_timestamp,
+        max_views: patch.max_views.unwrap_or(link.max_views),
+        on_expire: patch.on_expire.unwrap_or(link.on_expire),
+        fallback_url: patch
+            .fallback_url
+            .unwrap_or_else(|| link.fallback_url.clone()),
+        rollout_percent: patch.rollout_percent.unwrap_or(link.rollout_percent),
+        disabled: patch.disabled.unwrap_or(link.disabled),
+        activate_at_timestamp,
+        activate_at: patch.activate_at,
+        start_in: patch.start_in.flatten(),
+        countdown: patch.countdown.unwrap_or(link.countdown),
+        active_hours: patch
+            .active_hours
+            .unwrap_or_else(|| link.active_hours.clone()),
+        recurrence: patch.recurrence.unwrap_or_else(|| link.recurrence.clone()),
+        timezone: patch.timezone.unwrap_or_else(|| link.timezone.clone()),
+        // The link is already stored under its ID, so it's never normalized again.
+        normalize_id: Some(false),
+        normalize_url: patch.normalize_url,
+        dedupe: false,
+        exclude_ips: patch
+            .exclude_ips
+            .unwrap_or_else(|| link.exclude_ips.clone()),
+        exclude_cidrs: patch
+            .exclude_cidrs
+            .unwrap_or_else(|| link.exclude_cidrs.clone()),
+        exclude_datacenter: patch.exclude_datacenter.unwrap_or(link.exclude_datacenter),
+        response_headers: patch
+            .response_headers
+            .unwrap_or_else(|| link.response_headers.clone()),
+        mechanism: patch.mechanism.unwrap_or(link.mechanism),
+        status_codes: patch
+            .status_codes
+            .unwrap_or_else(|| link.status_codes.clone()),
+        language_rules: patch
+            .language_rules
+            .unwrap_or_else(|| link.language_rules.clone()),
+        geo_targets: patch
+            .geo_targets
+            .unwrap_or_else(|| link.geo_targets.clone()),
+        variants: patch.variants.unwrap_or_else(|| link.variants.clone()),
+        sticky_variants: patch.sticky_variants.unwrap_or(link.sticky_variants),
+        correlation_param: patch
+            .correlation_param
+            .unwrap_or_else(|| link.correlation_param.clone()),
+        forward_query: patch.forward_query.unwrap_or(link.forward_query),
+        forward_path: patch.forward_path.unwrap_or(link.forward_path),
+        utm: patch.utm.unwrap_or_else(|| link.utm.clone()),
+        requires: patch.requires.unwrap_or_else(|| link.requires.clone()),
+        requires_views: patch.requires_views.unwrap_or(link.requires_views),
+        freeze_until_timestamp: patch
+            .freeze_until_timestamp
+            .unwrap_or(link.freeze_until_timestamp),
+        protected: patch.protected.unwrap_or(link.protected),
+    }
+}
+
+/// Get every URL a link request can send visitors to.
+fn destination_urls(body: &CreateLinkRequestBody) -> impl Iterator<Item = &Url> {
+    std::iter::once(&body.url)
+        .chain(body.language_rules.values())
+        .chain(body.geo_targets.values())
+        .chain(&body.fallback_url)
+        .chain(body.variants.iter().map(|variant| &variant.url))
+}
+
+/// Validate a link request and normalize its destination URL according to the request and environment defaults.
+///
+/// `host_url` is the URL the worker is being accessed from and is used to prevent links that redirect to themselves.
+pub fn prepare_link_request(
+    env: &Env,
+    host_url: &Url,
+    body: &mut CreateLinkRequestBody,
+) -> Result<(), SaveLinkError> {
+    // Validate that the struct is valid using the custom struct validator.
+    if body.validate().is_err() {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+
+    // Every destination ends up in a redirect or a link on one of the worker's pages, where other schemes could run
+    // script on the worker's own origin.
+    if !destination_urls(body).all(is_web_url) {
+        return Err(SaveLinkError::UnsupportedScheme);
+    }
+
+    // Normalize the destination URL if requested, falling back to the environment default.
+    // Deduplicated links are always normalized so equivalent URLs share the same link.
+    if body.dedupe
+        || body
+            .normalize_url
+            .or_else(|| get_bool_var(env, NORMALIZE_URLS_BINDING))
+            .unwrap_or(false)
+    {
+        normalize_url(
+            &mut body.url,
+            get_bool_var(env, STRIP_TRACKING_PARAMS_BINDING).unwrap_or(false),
+        );
+    }
+
+    // Only allow headers that can't change how the redirect itself behaves.
+    if !body
+        .response_headers
+        .iter()
+        .all(|(name, value)| is_allowed_response_header(name, value))
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+
+    // Language tags are matched case-insensitively, so store them in the same form they're compared in.
+    if !body
+        .language_rules
+        .keys()
+        .all(|tag| is_valid_language_tag(tag))
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+    body.language_rules = std::mem::take(&mut body.language_rules)
+        .into_iter()
+        .map(|(tag, url)| (tag.to_ascii_lowercase(), url))
+        .collect();
+
+    // Country codes are matched in the uppercase form Cloudflare gives them in, so store them that way.
+    if !body
+        .geo_targets
+        .keys()
+        .all(|key| key.eq_ignore_ascii_case(GEO_TARGET_DEFAULT) || is_valid_country_code(key))
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+    body.geo_targets = std::mem::take(&mut body.geo_targets)
+        .into_iter()
+        .map(|(key, url)| {
+            if key.eq_ignore_ascii_case(GEO_TARGET_DEFAULT) {
+                (GEO_TARGET_DEFAULT.to_string(), url)
+            } else {
+                (key.to_ascii_uppercase(), url)
+            }
+        })
+        .collect();
+
+    if body
+        .correlation_param
+        .as_deref()
+        .is_some_and(|param| !is_valid_correlation_param(param))
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+
+    // UTM parameters left blank are dropped, and a link without any is stored without them.
+    if let Some(utm) = &mut body.utm {
+        for value in [
+            &mut utm.source,
+            &mut utm.medium,
+            &mut utm.campaign,
+            &mut utm.term,
+            &mut utm.content,
+        ] {
+            *value = value.take().filter(|value| !value.trim().is_empty());
+            if value
+                .as_ref()
+                .is_some_and(|value| value.len() > MAX_UTM_VALUE_LENGTH)
+            {
+                return Err(SaveLinkError::InvalidPayload);
+            }
+        }
+    }
+    body.utm = body.utm.take().filter(|utm| !utm.query_pairs().is_empty());
+
+    // Only redirect status codes that keep the destination in the Location header can be used.
+    if !body
+        .status_codes
+        .iter()
+        .all(|status_code| REDIRECT_STATUS_CODES.contains(status_code))
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+    body.status_codes.sort_unstable();
+    body.status_codes.dedup();
+
+    // Every variant needs a share of visitors, otherwise it would never be picked.
+    if body.variants.iter().any(|variant| variant.weight == 0) {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+
+    // Expiry can be given relative to now or as a timestamp, but not both.
+    if let Some(expire_in) = body.expire_in {
+        if body.expiry_timestamp.is_some() {
+            return Err(SaveLinkError::InvalidPayload);
+        }
+        body.expiry_timestamp = Some(Date::now().as_millis() + expire_in.as_millis() as u64);
+    }
+
+    // Local activation times are converted using the link's time zone, so it has to exist.
+    if body
+        .timezone
+        .as_deref()
+        .is_some_and(|timezone| !is_valid_timezone(timezone))
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+    if let Some(activate_at) = &body.activate_at {
+        if body.activate_at_timestamp.is_some() || body.start_in.is_some() {
+            return Err(SaveLinkError::InvalidPayload);
+        }
+        let Some(timestamp) = parse_local_date_time(activate_at)
+            .and_then(|local| local_date_time_to_timestamp(local, body.timezone.as_deref()))
+        else {
+            return Err(SaveLinkError::InvalidPayload);
+        };
+        body.activate_at_timestamp = Some(timestamp);
+    }
+
+    // Activation can also be given relative to now, the same way as expiry.
+    if let Some(start_in) = body.start_in {
+        if body.activate_at_timestamp.is_some() {
+            return Err(SaveLinkError::InvalidPayload);
+        }
+        body.activate_at_timestamp = Some(Date::now().as_millis() + start_in.as_millis() as u64);
+    }
+
+    // Links can only send visitors to their fallback URL if they have one.
+    if (body.on_expire == ExpiryBehavior::RedirectFallback || body.rollout_percent.is_some())
+        && body.fallback_url.is_none()
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+
+    // Prevent making a link that recurses forever on the same domain.
+    if destination_urls(body).any(|url| host_url.domain() == url.domain()) {
+        return Err(SaveLinkError::OwnDomain);
+    }
+
+    Ok(())
+}
+
+/// Save a prepared link request under the given ID, creating a new link or updating the existing one.
+///
+/// The ID is normalized first if requested, so the returned [`SavedLink`] holds the ID the link was actually stored under.
+/// Resolving the destination's redirect chain makes several subrequests, so it should only be requested when saving a
+/// single link. Otherwise an updated link keeps its chain if its destination didn't change, and is left without one
+/// until it's resolved with `POST /<ID>/resolve` if it did.
+pub async fn save_link(
+    storage: &impl StorageDriver,
+    env: &Env,
+    mut id: String,
+    body: CreateLinkRequestBody,
+    resolve_chain: bool,
+) -> Result<SavedLink, SaveLinkError> {
+    // Normalize the ID into its canonical form if requested, falling back to the environment default.
+    if body
+        .normalize_id
+        .or_else(|| get_bool_var(env, NORMALIZE_IDS_BINDING))
+        .unwrap_or(false)
+    {
+        id = normalize_id(&id);
+    }
+
+    // Prevent making a link with an ID that could collide with internal storage keys or routes.
+    if !is_valid_id(&id) {
+        return Err(SaveLinkError::InvalidId);
+    }
+
+    // A link can only depend on another valid link ID, and never on itself.
+    if body
+        .requires
+        .as_deref()
+        .is_some_and(|requires| requires == id || !is_valid_id(requires))
+        || (body.requires_views.is_some() && body.requires.is_none())
+    {
+        return Err(SaveLinkError::InvalidPayload);
+    }
+
+    // Grab the existing model and check if we can overwrite it (if it exists).
+    // Deduplicated IDs are only ever reused for the same destination, so any existing link there has expired.
+    let existing_model = if body.dedupe {
+        None
+    } else {
+        storage.get_deserialized_json::<LinkModel>(&id).await
+    };
+    let created = existing_model.is_none();
+    if !body.overwrite && !created {
+        return Err(SaveLinkError::AlreadyExists);
+    }
+    if let Some(link) = &existing_model {
+        check_link_update(link, &body)?;
+    }
+
+    let resolved_chain = match &existing_model {
+        _ if resolve_chain => resolve_redirect_chain(&body.url).await,
+        Some(link) if link.url == body.url => link.resolved_chain.clone(),
+        _ => Vec::new(),
+    };
+    let args = link_builder_args(body, resolved_chain);
+    let model = match existing_model {
+        Some(model) => model.modify(args),
+        None => LinkModel::new(args),
+    };
+
+    if !storage
+        .set_serialized_json::<&LinkModel>(
+            &id,
+            &model,
+            model.storage_expiration(get_tombstone_retention(env).is_some()),
+        )
+        .await
+    {
+        return Err(SaveLinkError::StorageFailure);
+    }
+
+    Ok(SavedLink { id, model, created })
+}
+
+/// Check whether a link can be updated with the given request, which isn't the case for frozen destinations
+/// or removing a link's protection.
+pub fn check_link_update(
+    link: &LinkModel,
+    body: &CreateLinkRequestBody,
+) -> Result<(), SaveLinkError> {
+    if link.is_frozen_at(Date::now().as_millis()) && !keeps_frozen_destinations(link, body) {
+        return Err(SaveLinkError::Frozen);
+    }
+    // Otherwise a single API key could remove the protection and then delete the link on its own.
+    if link.protected && !body.protected {
+        return Err(SaveLinkError::Protected);
+    }
+    Ok(())
+}
+
+/// Whether or not updating a link with the given request would leave it exactly as it is.
+///
+/// The link's resolved redirect chain is kept as it is, as resolving it again needs requests to the destination.
+pub fn is_link_unchanged(link: &LinkModel, body: CreateLinkRequestBody) -> bool {
+    let Ok(current) = serde_json::to_value(link) else {
+        return false;
+    };
+    let Ok(copy) = serde_json::from_value::<LinkModel>(current.clone()) else {
+        return false;
+    };
+    let updated = copy.modify(link_builder_args(body, link.resolved_chain.clone()));
+    let Ok(mut updated) = serde_json::to_value(updated) else {
+        return false;
+    };
+    updated["modified_at_timestamp"] = current["modified_at_timestamp"].clone();
+    updated == current
+}
+
+/// Build the arguments for creating or modifying a link model from a prepared link request.
+fn link_builder_args(body: CreateLinkRequestBody, resolved_chain: Vec<Url>) -> LinkBuilderArgs {
+    LinkBuilderArgs {
+        url: body.url,
+        max_views: body.max_views,
+        disabled: body.disabled,
+        expiry_timestamp: body.expiry_timestamp,
+        on_expire: body.on_expire,
+        fallback_url: body.fallback_url,
+        rollout_percent: body.rollout_percent,
+        activate_at_timestamp: body.activate_at_timestamp,
+        countdown: body.countdown,
+        active_hours: body.active_hours,
+        recurrence: body.recurrence,
+        timezone: body.timezone,
+        exclude_ips: body.exclude_ips,
+        exclude_cidrs: body.exclude_cidrs,
+        exclude_datacenter: body.exclude_datacenter,
+        resolved_chain,
+        response_headers: body.response_headers,
+        mechanism: body.mechanism,
+        status_codes: body.status_codes,
+        language_rules: body.language_rules,
+        geo_targets: body.geo_targets,
+        variants: body.variants,
+        sticky_variants: body.sticky_variants,
+        correlation_param: body.correlation_param,
+        forward_query: body.forward_query,
+        forward_path: body.forward_path,
+        utm: body.utm,
+        requires: body.requires,
+        requires_views: body.requires_views,
+        freeze_until_timestamp: body.freeze_until_timestamp,
+        protected: body.protected,
+    }
+}
@@ -1,15 +1,138 @@
 pub const UNAUTHORIZED_REQUEST_RESPONSE: &str = "Unauthorized";
 pub const FORBIDDEN_REQUEST_RESPONSE: &str = "Forbidden";
+pub const TOO_MANY_AUTH_FAILURES_RESPONSE: &str =
+    "Too many failed authentication attempts, try again later.";
 pub const INVALID_PAYLOAD_RESPONSE: &str = "Invalid Payload";
+pub const INVALID_LINK_ID_RESPONSE: &str =
+    "Link IDs cannot be empty, contain the ':' character, contain malformed emoji, have more than one namespace or use a reserved name.";
+pub const DEDUPE_WITH_ID_RESPONSE: &str =
+    "Deduplicated links have their ID derived from their URL, so an ID cannot be provided.";
 pub const LINK_DOESNT_EXIST_RESPONSE: &str =
     "A link with that ID was not found, it may have been removed by its owner or expired.";
 pub const NO_LINK_OWN_DOMAIN_RESPONSE: &str =
     "Cannot make a link redirect to the same domain as where link is hosted as this could cause an infinite redirect.";
+pub const UNSUPPORTED_URL_SCHEME_RESPONSE: &str = "Links can only redirect to http and https URLs.";
 pub const NOT_INITIALISED_WITH_AUTHTOKEN_RESPONSE: &str = "The link worker was initialised with no AUTH_TOKEN, all authenticated requests will be rejected until it has been set.";
 pub const LINK_ALREADY_EXISTS_NO_OVERWRITE: &str =
     "A link with the given ID already exists and overwriting was not enabled.";
 pub const GENERIC_LINK_CREATE_ERROR_RESPONSE: &str =
     "Something went wrong while trying to create a link.";
+pub const GENERIC_LINK_UPDATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to update a link.";
 pub const GENERIC_LINK_DELETE_ERROR_RESPONSE: &str =
     "Something went wrong while trying to delete a link.";
 pub const LINK_DELETE_SUCCESS_RESPONSE: &str = "link successfully deleted.";
+pub const UNSUPPORTED_IMPORT_CONTENT_TYPE_RESPONSE: &str =
+    "Imports must be sent as newline-delimited JSON with the application/x-ndjson content type.";
+pub const JOB_DOESNT_EXIST_RESPONSE: &str = "A job with that ID was not found.";
+pub const GENERIC_JOB_CREATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to create a job.";
+pub const INVALID_TENANT_NAME_RESPONSE: &str =
+    "Tenant names must be between 1 and 64 characters and only contain ASCII letters, numbers, '-' or '_'.";
+pub const HOST_MAPPING_DOESNT_EXIST_RESPONSE: &str = "No tenant is mapped to that hostname.";
+pub const GENERIC_HOST_MAPPING_UPDATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to update a hostname mapping.";
+pub const HOST_MAPPING_DELETE_SUCCESS_RESPONSE: &str = "hostname mapping successfully deleted.";
+pub const WEBHOOK_DOESNT_EXIST_RESPONSE: &str = "A webhook with that ID was not found.";
+pub const INVALID_WEBHOOK_URL_RESPONSE: &str = "Webhook URLs must use http or https.";
+pub const GENERIC_WEBHOOK_UPDATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to update the webhooks.";
+pub const WEBHOOK_DELETE_SUCCESS_RESPONSE: &str = "webhook successfully deleted.";
+pub const WEBHOOK_FAILURE_DOESNT_EXIST_RESPONSE: &str =
+    "A failed webhook delivery with that ID was not found.";
+pub const WEBHOOK_REPLAY_SUCCESS_RESPONSE: &str = "notification successfully delivered.";
+pub const WEBHOOK_REPLAY_FAILED_RESPONSE: &str =
+    "The notification still couldn't be delivered to the webhook, its last_error has been updated.";
+pub const GENERIC_WEBHOOK_FAILURE_DELETE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to delete a failed webhook delivery.";
+pub const WEBHOOK_FAILURE_DELETE_SUCCESS_RESPONSE: &str =
+    "failed webhook delivery successfully deleted.";
+pub const ADMIN_ASSET_DOESNT_EXIST_RESPONSE: &str =
+    "An admin interface file with that path was not found.";
+pub const UNKNOWN_BRANDING_ASSET_RESPONSE: &str =
+    "Unknown branding asset, expected one of 'index', 'not-found', 'paused', 'warning' or 'favicon'.";
+pub const BRANDING_DOESNT_EXIST_RESPONSE: &str =
+    "That branding asset has not been overridden for this hostname.";
+pub const GENERIC_BRANDING_UPDATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to update a branding asset.";
+pub const BRANDING_UPDATE_SUCCESS_RESPONSE: &str = "branding asset successfully updated.";
+pub const BRANDING_DELETE_SUCCESS_RESPONSE: &str = "branding asset successfully deleted.";
+pub const LINK_PAUSED_RESPONSE: &str = "This link is temporarily unavailable, check back soon.";
+pub const LINK_GONE_RESPONSE: &str = "This link has expired and is no longer available.";
+pub const LINK_NOT_ACTIVE_RESPONSE: &str = "This link isn't active yet, check back soon.";
+pub const LINK_PAUSE_SUCCESS_RESPONSE: &str = "link successfully paused.";
+pub const LINK_RESUME_SUCCESS_RESPONSE: &str = "link successfully resumed.";
+pub const LINK_WARNING_RESPONSE: &str =
+    "This link leads to an external site that has been flagged, make sure you trust it before continuing.";
+pub const WARNED_DOMAIN_DOESNT_EXIST_RESPONSE: &str = "That domain is not on the warn list.";
+pub const GENERIC_WARNLIST_UPDATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to update the warn list.";
+pub const WARNED_DOMAIN_DELETE_SUCCESS_RESPONSE: &str =
+    "domain successfully removed from the warn list.";
+pub const FEATURE_FLAG_DOESNT_EXIST_RESPONSE: &str = "That feature flag does not exist.";
+pub const GENERIC_FEATURE_FLAG_UPDATE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to update a feature flag.";
+pub const FEATURE_FLAG_RESET_SUCCESS_RESPONSE: &str = "feature flag successfully reset.";
+pub const UNSUPPORTED_OEMBED_FORMAT_RESPONSE: &str = "Only the 'json' oEmbed format is supported.";
+pub const INVALID_OEMBED_URL_RESPONSE: &str =
+    "A valid short URL must be provided with the 'url' query parameter.";
+pub const LINK_LOCKED_RESPONSE: &str =
+    "This link is being updated by another request, try again shortly.";
+pub const SCREENSHOTS_DISABLED_RESPONSE: &str =
+    "Screenshots are not enabled, bind a SCREENSHOTS bucket and set the Browser Rendering credentials to enable them.";
+pub const SCREENSHOT_DOESNT_EXIST_RESPONSE: &str = "No screenshot has been captured for this link.";
+pub const GENERIC_SCREENSHOT_CAPTURE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to capture a screenshot of the destination.";
+pub const CANNOT_MERGE_LINK_INTO_ITSELF_RESPONSE: &str = "A link cannot be merged into itself.";
+pub const LINK_ALREADY_MERGED_RESPONSE: &str =
+    "Links that have already been merged into another link cannot be merged.";
+pub const GENERIC_LINK_MERGE_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to merge the links.";
+pub const CONVERSIONS_DISABLED_RESPONSE: &str =
+    "Conversions are recorded in visit stats, enable RECORD_VISIT_STATS to record them.";
+pub const INVALID_VISIT_ID_RESPONSE: &str =
+    "The visit ID is malformed, it must be given exactly as it was appended to the destination.";
+pub const CONVERSION_ALREADY_RECORDED_RESPONSE: &str =
+    "A conversion has already been recorded for this visit.";
+pub const GENERIC_CONVERSION_RECORD_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to record a conversion.";
+pub const ATTESTATIONS_DISABLED_RESPONSE: &str =
+    "Attestations are not enabled, set an ATTESTATION_SIGNING_KEY to enable them.";
+pub const GENERIC_ATTESTATION_SIGN_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to sign the attestation, check that ATTESTATION_SIGNING_KEY is a valid Ed25519 private key.";
+pub const GENERIC_ATTESTATION_VERIFY_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to verify the attestation, check that ATTESTATION_SIGNING_KEY is a valid Ed25519 private key.";
+pub const LINK_FROZEN_RESPONSE: &str =
+    "This link's destinations are frozen and can't be changed until its freeze_until_timestamp, although other fields can.";
+pub const INVALID_POSTER_PAPER_RESPONSE: &str =
+    "The 'paper' query parameter must be either 'a4' or 'letter'.";
+pub const INVALID_QR_FORMAT_RESPONSE: &str =
+    "The 'format' query parameter must be either 'svg' or 'png'.";
+pub const QR_CODE_TOO_LONG_RESPONSE: &str =
+    "This link's short URL is too long to fit in a QR code.";
+pub const LINK_PROTECTED_RESPONSE: &str =
+    "This link is protected and can only be unprotected by deleting it with two different API keys.";
+pub const LINK_DELETE_STAGED_RESPONSE: &str =
+    "This link is protected, confirm its deletion by deleting it again with a different API key before the confirmation window ends.";
+pub const LINK_DELETE_SAME_KEY_RESPONSE: &str =
+    "This link's deletion was staged with the same API key, it must be confirmed with a different key from ADDITIONAL_AUTH_TOKENS.";
+pub const INVALID_BATCH_SIZE_RESPONSE: &str =
+    "A batch must contain between 1 and 100 links, split larger batches into several requests.";
+pub const INVALID_SYNC_LINKS_RESPONSE: &str =
+    "A sync can declare at most 250 links, each with a unique ID starting with its 'prefix'.";
+pub const INVALID_LIST_LIMIT_RESPONSE: &str =
+    "The 'limit' query parameter must be a number between 1 and 100.";
+pub const INVALID_COMPARE_IDS_RESPONSE: &str =
+    "The 'ids' query parameter must be a comma-separated list of between 1 and 20 link IDs.";
+pub const INVALID_STATS_RANGE_RESPONSE: &str =
+    "The 'from' and 'to' query parameters must be RFC 3339 timestamps, with 'from' before 'to'.";
+pub const INVALID_GRAFANA_QUERY_RESPONSE: &str =
+    "A Grafana query must have a 'range' of RFC 3339 timestamps and at most 20 'targets'.";
+pub const INVALID_LINK_RECORD_RESPONSE: &str = "This record could not be read as a link.";
+pub const GENERIC_FSCK_REPAIR_ERROR_RESPONSE: &str =
+    "Something went wrong while trying to repair this record.";
+pub const SELF_TEST_LINKS_KV_MISSING_RESPONSE: &str = "The link worker is not configured correctly: the 'links' KV namespace is not bound. Add it to kv_namespaces in wrangler.toml and redeploy.";
+pub const SELF_TEST_LINKS_KV_UNREADABLE_RESPONSE: &str = "The link worker is not configured correctly: the 'links' KV namespace could not be read. Check that its ID in wrangler.toml is correct.";
+pub const SELF_TEST_LINKS_D1_MISSING_RESPONSE: &str = "The link worker is not configured correctly: STORAGE_DRIVER is 'd1' but the 'links_db' D1 database is not bound. Add it to d1_databases in wrangler.toml and redeploy.";
+pub const SELF_TEST_LINKS_D1_UNREADABLE_RESPONSE: &str = "The link worker is not configured correctly: the 'links_db' D1 database could not be read. Check that its ID in wrangler.toml is correct and that its migrations have been applied.";
+pub const SELF_TEST_ANALYTICS_KV_UNREADABLE_RESPONSE: &str = "The link worker is not configured correctly: the 'analytics' KV namespace could not be read. Check that its ID in wrangler.toml is correct or remove the binding.";
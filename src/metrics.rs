@@ -0,0 +1,184 @@
+use crate::{
+    durable_objects::view_counter::pending_counted_views,
+    environment::get_var,
+    models::link::LinkModel,
+    storage::{get_tenant_storage, StorageDriver},
+    tenants::list_tenants,
+};
+use worker::{
+    js_sys::Uint8Array, wasm_bindgen::JsValue, Date, Env, Fetch, Headers, Method, Request,
+    RequestInit,
+};
+
+/// The binding name for the variable holding the Prometheus remote-write endpoint link counters are pushed to.
+pub const PROMETHEUS_REMOTE_WRITE_URL_BINDING: &str = "PROMETHEUS_REMOTE_WRITE_URL";
+
+/// The binding name for the secret holding the bearer token sent to the Prometheus remote-write endpoint.
+pub const PROMETHEUS_REMOTE_WRITE_TOKEN_BINDING: &str = "PROMETHEUS_REMOTE_WRITE_TOKEN";
+
+/// The name of the metric holding the total views of every link.
+const LINK_VIEWS_METRIC: &str = "workerlink_link_views_total";
+
+/// The most series sent in a single remote-write request, to keep request bodies small.
+const SERIES_PER_REQUEST: usize = 500;
+
+/// The longest literal a single Snappy element can hold with a two byte length.
+const SNAPPY_MAX_LITERAL: usize = 1 << 16;
+
+/// Whether or not link counters are pushed to a Prometheus remote-write endpoint.
+pub fn metrics_export_enabled(env: &Env) -> bool {
+    get_var(env, PROMETHEUS_REMOTE_WRITE_URL_BINDING).is_some()
+}
+
+/// The counter of a single link, labelled with its ID and the tenant it belongs to.
+struct LinkCounter {
+    id: String,
+    tenant: Option<String>,
+    views: u64,
+}
+
+/// Append a Protobuf varint to the buffer.
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Append a length-delimited Protobuf field (strings and embedded messages) to the buffer.
+fn write_bytes_field(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(buffer, field << 3 | 2);
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+/// Encode the counters as a Prometheus remote-write `WriteRequest` message, all sampled at the given timestamp.
+///
+/// Each `TimeSeries` holds its labels sorted by name, as remote-write requires, followed by a single sample.
+fn encode_write_request(counters: &[LinkCounter], timestamp: u64) -> Vec<u8> {
+    let mut request = Vec::new();
+    for counter in counters {
+        let mut labels = vec![("__name__", LINK_VIEWS_METRIC), ("id", &counter.id)];
+        if let Some(tenant) = &counter.tenant {
+            labels.push(("tenant", tenant));
+        }
+
+        let mut series = Vec::new();
+        for (name, value) in labels {
+            let mut label = Vec::new();
+            write_bytes_field(&mut label, 1, name.as_bytes());
+            write_bytes_field(&mut label, 2, value.as_bytes());
+            write_bytes_field(&mut series, 1, &label);
+        }
+        let mut sample = Vec::new();
+        // The value is a double (fixed64) and the timestamp an int64 (varint) in milliseconds.
+        write_varint(&mut sample, 1 << 3 | 1);
+        sample.extend_from_slice(&(counter.views as f64).to_le_bytes());
+        write_varint(&mut sample, 2 << 3);
+        write_varint(&mut sample, timestamp);
+        write_bytes_field(&mut series, 2, &sample);
+
+        write_bytes_field(&mut request, 1, &series);
+    }
+    request
+}
+
+/// Compress data into the Snappy block format that remote-write expects.
+///
+/// Everything is stored as literals rather than back-references, which any Snappy decoder reads but doesn't
+/// actually make the data smaller. The bodies are small enough that this doesn't matter.
+fn snappy_encode(data: &[u8]) -> Vec<u8> {
+    let mut block = Vec::with_capacity(data.len() + data.len() / SNAPPY_MAX_LITERAL * 3 + 8);
+    write_varint(&mut block, data.len() as u64);
+    for literal in data.chunks(SNAPPY_MAX_LITERAL) {
+        let length = literal.len() - 1;
+        if length < 60 {
+            block.push((length as u8) << 2);
+        } else if length < 1 << 8 {
+            block.push(60 << 2);
+            block.push(length as u8);
+        } else {
+            block.push(61 << 2);
+            block.extend_from_slice(&(length as u16).to_le_bytes());
+        }
+        block.extend_from_slice(literal);
+    }
+    block
+}
+
+/// Send a single remote-write request, returning whether the endpoint accepted it.
+async fn send_write_request(url: &str, token: Option<&str>, body: &[u8]) -> bool {
+    let mut headers = Headers::new();
+    let headers_set = headers
+        .set("Content-Type", "application/x-protobuf")
+        .and_then(|_| headers.set("Content-Encoding", "snappy"))
+        .and_then(|_| headers.set("X-Prometheus-Remote-Write-Version", "0.1.0"))
+        .and_then(|_| match token {
+            Some(token) => headers.set("Authorization", &format!("Bearer {token}")),
+            None => Ok(()),
+        });
+    if headers_set.is_err() {
+        return false;
+    }
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_headers(headers)
+        .with_body(Some(JsValue::from(Uint8Array::from(body))));
+    let Ok(request) = Request::new_with_init(url, &init) else {
+        return false;
+    };
+    match Fetch::Request(request).send().await {
+        Ok(response) => (200..=299).contains(&response.status_code()),
+        Err(_) => false,
+    }
+}
+
+/// Push the view counter of every link to the Prometheus remote-write endpoint, if one is configured, returning
+/// how many series were accepted.
+///
+/// Counters are sampled once per scheduled run, so how often they're pushed depends on the worker's cron triggers.
+pub async fn push_link_metrics(storage: &impl StorageDriver, env: &Env) -> usize {
+    let Some(url) = get_var(env, PROMETHEUS_REMOTE_WRITE_URL_BINDING) else {
+        return 0;
+    };
+    let token = get_var(env, PROMETHEUS_REMOTE_WRITE_TOKEN_BINDING);
+
+    let mut counters = Vec::new();
+    let tenants = std::iter::once(None).chain(list_tenants(storage).await.into_iter().map(Some));
+    for tenant in tenants {
+        let Ok(link_storage) = get_tenant_storage(env, tenant.as_deref()) else {
+            continue;
+        };
+        // Internal keys (tombstones, etc.) are namespaced with ':', which link IDs cannot contain.
+        for id in link_storage.list("").await {
+            if id.contains(':') {
+                continue;
+            }
+            let Some(link) = link_storage.get_deserialized_json::<LinkModel>(&id).await else {
+                continue;
+            };
+            // Merged links redirect through their target, which their views were already added to.
+            if link.alias_of.is_some() {
+                continue;
+            }
+            let views = link.views + pending_counted_views(env, tenant.as_deref(), &id).await;
+            counters.push(LinkCounter {
+                id,
+                tenant: tenant.clone(),
+                views,
+            });
+        }
+    }
+
+    let timestamp = Date::now().as_millis();
+    let mut pushed = 0;
+    for chunk in counters.chunks(SERIES_PER_REQUEST) {
+        let body = snappy_encode(&encode_write_request(chunk, timestamp));
+        if send_write_request(&url, token.as_deref(), &body).await {
+            pushed += chunk.len();
+        }
+    }
+    pushed
+}
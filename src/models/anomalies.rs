@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Represents a kind of anomalous traffic pattern that can be flagged on a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// The link was visited far more than usual.
+    ViewSpike,
+    /// Most of the link's visits came from bots.
+    BotTraffic,
+    /// Most of the link's visits came from datacenter networks.
+    DatacenterTraffic,
+}
+
+/// Represents an anomalous traffic pattern found in a link's stats for a day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkAnomaly {
+    pub kind: AnomalyKind,
+    /// The day (`YYYY-MM-DD`) the pattern was found in.
+    pub day: String,
+    /// The amount of recorded visits on that day.
+    pub views: u64,
+    /// The average amount of recorded visits per day over the days before, for view spikes.
+    #[serde(default)]
+    pub baseline_views: Option<f64>,
+    /// The amount of recorded visits on that day that came from bots, for bot traffic.
+    #[serde(default)]
+    pub bot_views: Option<u64>,
+    /// The amount of recorded visits on that day that came from datacenter networks, for datacenter traffic.
+    #[serde(default)]
+    pub datacenter_views: Option<u64>,
+}
+
+/// Represents the results of the last analysis of link stats for anomalous traffic.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnomaliesModel {
+    /// The anomalies found for each link, keyed by link ID.
+    pub links: BTreeMap<String, Vec<LinkAnomaly>>,
+    /// The UNIX timestamp of when the stats were analyzed.
+    pub analyzed_at_timestamp: u64,
+}
+
+impl AnomaliesModel {
+    /// Get the storage key the anomalies are stored under.
+    pub fn key() -> &'static str {
+        "config:anomalies"
+    }
+
+    /// Whether or not the given kind of anomaly was flagged on the given link.
+    pub fn has_anomaly(&self, id: &str, kind: AnomalyKind) -> bool {
+        self.links
+            .get(id)
+            .is_some_and(|anomalies| anomalies.iter().any(|anomaly| anomaly.kind == kind))
+    }
+}
@@ -0,0 +1,54 @@
+/// The parts of the worker's pages that can be overridden for a hostname.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrandingAsset {
+    /// The HTML template served at the root of the site.
+    Index,
+    /// The HTML template served when a link can't be found.
+    NotFound,
+    /// The HTML template served when a link is paused.
+    Paused,
+    /// The HTML template served with a countdown when a link isn't active yet.
+    Countdown,
+    /// The HTML template served before redirecting to a flagged destination domain.
+    Warning,
+    /// An SVG icon served in place of the default favicon.
+    Favicon,
+    /// An SVG logo shown on printed link posters.
+    Logo,
+}
+
+impl BrandingAsset {
+    /// All assets that can be overridden.
+    pub const ALL: [BrandingAsset; 7] = [
+        Self::Index,
+        Self::NotFound,
+        Self::Paused,
+        Self::Countdown,
+        Self::Warning,
+        Self::Favicon,
+        Self::Logo,
+    ];
+
+    /// Get the name of the asset as used in storage keys and routes.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Index => "index",
+            Self::NotFound => "not-found",
+            Self::Paused => "paused",
+            Self::Countdown => "countdown",
+            Self::Warning => "warning",
+            Self::Favicon => "favicon",
+            Self::Logo => "logo",
+        }
+    }
+
+    /// Get the asset with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|asset| asset.name() == name)
+    }
+
+    /// Get the storage key used for this asset on the given hostname.
+    pub fn key(&self, host: &str) -> String {
+        format!("branding:{}:{}", host.to_lowercase(), self.name())
+    }
+}
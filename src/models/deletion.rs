@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use worker::Date;
+
+/// Represents the staged deletion of a protected link, which another API key has to confirm before it expires.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingDeletionModel {
+    /// The name of the API key that staged the deletion, see [`crate::authentication::authorized_key_guard`].
+    pub requested_by: String,
+    /// The UNIX timestamp of when the deletion was staged.
+    pub requested_at_timestamp: u64,
+    /// The UNIX timestamp after which the deletion can no longer be confirmed.
+    pub expires_at_timestamp: u64,
+}
+
+impl PendingDeletionModel {
+    /// Stage a new deletion requested by the given API key that can be confirmed within the given window.
+    pub fn new(requested_by: String, window: Duration) -> Self {
+        let now = Date::now().as_millis();
+        Self {
+            requested_by,
+            requested_at_timestamp: now,
+            expires_at_timestamp: now + window.as_millis() as u64,
+        }
+    }
+
+    /// Whether or not this deletion can still be confirmed.
+    pub fn is_pending(&self) -> bool {
+        Date::now().as_millis() <= self.expires_at_timestamp
+    }
+
+    /// Get the storage key used for the pending deletion of the given link ID.
+    pub fn key(id: &str) -> String {
+        format!("deletion:{id}")
+    }
+}
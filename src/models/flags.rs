@@ -0,0 +1,44 @@
+use crate::flags::FeatureFlag;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use worker::Date;
+
+/// Represents the rollout of feature flags that have been changed from their defaults.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FeatureFlagsModel {
+    /// The rollout of each changed flag, keyed by flag.
+    pub flags: BTreeMap<FeatureFlag, FeatureFlagRollout>,
+}
+
+/// Represents how much traffic a single feature flag is enabled for.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FeatureFlagRollout {
+    /// The percentage of requests the flag is enabled for, from 0 to 100.
+    pub percentage: u8,
+    /// The UNIX timestamp of when this rollout was last changed.
+    pub modified_at_timestamp: u64,
+}
+
+impl FeatureFlagRollout {
+    /// Roll a flag out to the given percentage of requests.
+    pub fn new(percentage: u8) -> Self {
+        Self {
+            percentage,
+            modified_at_timestamp: Date::now().as_millis(),
+        }
+    }
+}
+
+impl FeatureFlagsModel {
+    /// Get the storage key the feature flags are stored under.
+    pub fn key() -> &'static str {
+        "config:flags"
+    }
+
+    /// Get the percentage of requests a flag is enabled for, using its default if its rollout hasn't been changed.
+    pub fn percentage(&self, flag: FeatureFlag) -> u8 {
+        self.flags
+            .get(&flag)
+            .map_or(flag.default_percentage(), |rollout| rollout.percentage)
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use worker::Date;
+
+/// Represents the mapping of an incoming hostname to the tenant whose links it serves.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostMappingModel {
+    /// The name of the tenant the hostname belongs to.
+    pub tenant: String,
+    /// The UNIX timestamp of when this mapping was created or last changed.
+    pub modified_at_timestamp: u64,
+}
+
+impl HostMappingModel {
+    /// Create a new mapping to the given tenant.
+    pub fn new(tenant: String) -> Self {
+        Self {
+            tenant,
+            modified_at_timestamp: Date::now().as_millis(),
+        }
+    }
+
+    /// Get the storage key prefix shared by all hostname mappings.
+    pub fn key_prefix() -> &'static str {
+        "host:"
+    }
+
+    /// Get the storage key used for the mapping of the given hostname.
+    pub fn key(host: &str) -> String {
+        format!("host:{}", host.to_lowercase())
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use url::Url;
+use worker::Date;
+
+/// The maximum amount of failed items kept on a job.
+const MAX_JOB_FAILURES: usize = 100;
+
+/// The state of a background job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// The job's steps are waiting to be picked up.
+    Queued,
+    /// Some, but not all, of the job's steps have been processed.
+    Running,
+    /// All of the job's steps have been processed.
+    Completed,
+}
+
+/// The kind of work a job performs, alongside any data shared by all of its steps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobKind {
+    /// Importing links from chunks of newline-delimited JSON.
+    Import {
+        /// The URL the import was submitted to, used to prevent links that redirect to the worker itself.
+        host_url: Url,
+        /// The tenant links are imported into, if any.
+        #[serde(default)]
+        tenant: Option<String>,
+    },
+    /// Checking whether the destinations of links are reachable.
+    HealthCheck {
+        /// The tenant whose links are checked, if any.
+        #[serde(default)]
+        tenant: Option<String>,
+    },
+    /// Upgrading stored links of every tenant to the current schema version.
+    Migrate,
+    /// Compacting old daily stats into monthly stats.
+    StatsRollup {
+        /// The tenant whose stats are compacted, if any.
+        #[serde(default)]
+        tenant: Option<String>,
+    },
+    /// Deleting the stats of links that no longer exist.
+    StatsSweep {
+        /// The tenant whose stats are swept, if any.
+        #[serde(default)]
+        tenant: Option<String>,
+    },
+}
+
+/// Represents an item that failed to be processed by a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobFailure {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub error: String,
+}
+
+/// Represents the outcome of processing a single step of a job.
+#[derive(Debug, Default)]
+pub struct JobStepResult {
+    pub counts: BTreeMap<String, usize>,
+    pub failures: Vec<JobFailure>,
+}
+
+/// Represents a long-running operation that is split into steps processed across many worker invocations.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobModel {
+    /// What the job does.
+    #[serde(flatten)]
+    pub kind: JobKind,
+    /// The current state of the job.
+    pub status: JobStatus,
+    /// The amount of steps the job was split into.
+    pub total_steps: usize,
+    /// The amount of steps that have been processed so far.
+    pub completed_steps: usize,
+    /// Totals reported by processed steps, e.g. the amount of links created.
+    pub counts: BTreeMap<String, usize>,
+    /// The first items that failed to be processed, up to [`MAX_JOB_FAILURES`].
+    pub failures: Vec<JobFailure>,
+    /// The UNIX timestamp of when the job was created.
+    pub created_at_timestamp: u64,
+    /// The UNIX timestamp of when the job last made progress.
+    pub modified_at_timestamp: u64,
+}
+
+impl JobModel {
+    /// Create a new queued job, which is immediately complete if it has no steps.
+    pub fn new(kind: JobKind, total_steps: usize) -> Self {
+        let now = Date::now().as_millis();
+        Self {
+            kind,
+            status: if total_steps == 0 {
+                JobStatus::Completed
+            } else {
+                JobStatus::Queued
+            },
+            total_steps,
+            completed_steps: 0,
+            counts: BTreeMap::new(),
+            failures: Vec::new(),
+            created_at_timestamp: now,
+            modified_at_timestamp: now,
+        }
+    }
+
+    /// Record a processed step and its results on the job.
+    pub fn complete_step(&mut self, result: JobStepResult) {
+        self.completed_steps += 1;
+        for (name, count) in result.counts {
+            *self.counts.entry(name).or_default() += count;
+        }
+        let remaining = MAX_JOB_FAILURES.saturating_sub(self.failures.len());
+        self.failures
+            .extend(result.failures.into_iter().take(remaining));
+        self.status = if self.completed_steps >= self.total_steps {
+            JobStatus::Completed
+        } else {
+            JobStatus::Running
+        };
+        self.modified_at_timestamp = Date::now().as_millis();
+    }
+
+    /// Get the storage key used for the job with the given ID.
+    pub fn key(job_id: &str) -> String {
+        format!("job:{job_id}")
+    }
+
+    /// Get the storage key prefix shared by all jobs and their steps.
+    pub fn key_prefix() -> &'static str {
+        "job:"
+    }
+
+    /// Get the storage key used for the data of a step of the job with the given ID.
+    pub fn step_key(job_id: &str, step: usize) -> String {
+        format!("job:{job_id}:step:{step}")
+    }
+
+    /// Parse the job ID and step from a key created by [`JobModel::step_key`].
+    pub fn parse_step_key(key: &str) -> Option<(&str, usize)> {
+        let (job_id, step) = key.strip_prefix("job:")?.split_once(":step:")?;
+        Some((job_id, step.parse().ok()?))
+    }
+}
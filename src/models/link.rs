@@ -1,29 +1,247 @@
+use crate::{
+    ip_ranges::IpRange,
+    schedule::{local_date_time, ActiveHours, Recurrence},
+};
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, net::IpAddr, time::Duration};
 use url::Url;
 use worker::Date;
 
+/// The key of a link's geo target used for visitors whose country has no geo target of its own, or is unknown.
+pub const GEO_TARGET_DEFAULT: &str = "default";
+
+/// The current version of the [`LinkModel`] schema.
+///
+/// Records stored before versioning was introduced have no version and are treated as version 0.
+pub const LINK_SCHEMA_VERSION: u32 = 1;
+
 /// Represents a link.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LinkModel {
+    /// The version of the schema this record was written with, see [`LINK_SCHEMA_VERSION`].
+    #[serde(default)]
+    pub schema_version: u32,
     /// The URL to redirect to upon visiting this link.
     pub url: Url,
     /// Whether or not this link is disabled.
     // TODO: See if there is a better term than 'disabled' to represent this value?
     pub disabled: bool,
+    /// Whether or not this link is paused, serving a temporarily unavailable page instead of redirecting.
+    #[serde(default)]
+    pub paused: bool,
+    /// How many seconds clients are asked to wait before retrying this link while it is paused.
+    #[serde(default)]
+    pub pause_retry_after_seconds: Option<u64>,
     /// The amount times this link has been viewed.
     pub views: u64,
     /// The maximum amount of times this link can be viewed before it becomes invalid.
     pub max_views: Option<u64>,
     /// The UNIX timestamp for when the link will become invalid.
     pub expiry_timestamp: Option<u64>,
+    /// What happens to the link once it becomes invalid.
+    #[serde(default)]
+    pub on_expire: ExpiryBehavior,
+    /// The URL visitors are sent to instead of the destination in some circumstances, such as once the link has expired.
+    #[serde(default)]
+    pub fallback_url: Option<Url>,
+    /// The percentage of visitors sent to the destination while it's being rolled out, with the rest sent to the fallback URL.
+    #[serde(default)]
+    pub rollout_percent: Option<u8>,
+    /// The UNIX timestamp before which the link isn't served, if it was created ahead of time.
+    #[serde(default)]
+    pub activate_at_timestamp: Option<u64>,
+    /// Whether or not visitors are shown a countdown to the activation time instead of the not found page.
+    #[serde(default)]
+    pub countdown: bool,
+    /// The hours of the day the link is served during, or [`None`] if it's served at any time.
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+    /// The days the link is served on, or [`None`] if it's served on any day.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// The IANA time zone (e.g. `Europe/Berlin`) active hours and recurrences are in, or [`None`] for UTC.
+    #[serde(default)]
+    pub timezone: Option<String>,
     /// The time this link was viewed..
     pub last_viewed_timestamp: Option<u64>,
+    /// Client IP addresses whose visits aren't counted towards views or stats.
+    #[serde(default)]
+    pub exclude_ips: Vec<IpAddr>,
+    /// Client IP ranges whose visits aren't counted towards views or stats.
+    #[serde(default)]
+    pub exclude_cidrs: Vec<IpRange>,
+    /// Whether or not visits from datacenter networks aren't counted towards views or stats.
+    #[serde(default)]
+    pub exclude_datacenter: bool,
+    /// Every URL the destination redirects through, ending where it ultimately lands.
+    #[serde(default)]
+    pub resolved_chain: Vec<Url>,
+    /// Extra headers attached to the redirect response, keyed by header name.
+    #[serde(default)]
+    pub response_headers: BTreeMap<String, String>,
+    /// How visitors are sent to the destination.
+    #[serde(default)]
+    pub mechanism: RedirectMechanism,
+    /// The HTTP status codes visitors are redirected with, split between visitors if there's more than one.
+    ///
+    /// Visitors are redirected with a `302` if empty.
+    #[serde(default)]
+    pub status_codes: Vec<u16>,
+    /// Destinations used instead of [`LinkModel::url`] for visitors preferring a language, keyed by lowercase language tag.
+    #[serde(default)]
+    pub language_rules: BTreeMap<String, Url>,
+    /// Destinations used instead of [`LinkModel::url`] for visitors from a country, keyed by uppercase ISO 3166-1
+    /// Alpha 2 country code, with the destination under [`GEO_TARGET_DEFAULT`] used for visitors from anywhere else.
+    #[serde(default)]
+    pub geo_targets: BTreeMap<String, Url>,
+    /// Destinations visitors are split between by weight instead of [`LinkModel::url`], if any.
+    #[serde(default)]
+    pub variants: Vec<LinkVariant>,
+    /// Whether or not a returning visitor is always sent to the same variant.
+    #[serde(default)]
+    pub sticky_variants: bool,
+    /// The query parameter a visit ID is appended to the destination under for counted visits, so conversions can be reported.
+    #[serde(default)]
+    pub correlation_param: Option<String>,
+    /// Whether or not the query parameters a link is visited with are forwarded to its destination.
+    #[serde(default)]
+    pub forward_query: bool,
+    /// Whether or not the rest of a path starting with the link's ID is appended to its destination.
+    #[serde(default)]
+    pub forward_path: bool,
+    /// The UTM parameters added to the destination when the link is visited, if any.
+    #[serde(default)]
+    pub utm: Option<LinkUtm>,
+    /// The results of checking whether the destination is reachable, if it has been checked.
+    #[serde(default)]
+    pub health: Option<LinkHealth>,
+    /// The latest screenshot captured of the destination, if one has been captured.
+    #[serde(default)]
+    pub screenshot: Option<LinkScreenshot>,
+    /// The ID of the link this link was merged into, which is used in place of this link when it's visited.
+    #[serde(default)]
+    pub alias_of: Option<String>,
+    /// The ID of a link this link depends on, which must have expired (or reached [`LinkModel::requires_views`])
+    /// before this link is served.
+    #[serde(default)]
+    pub requires: Option<String>,
+    /// The amount of views the link this link depends on needs before this link is served, instead of it expiring.
+    #[serde(default)]
+    pub requires_views: Option<u64>,
+    /// The UNIX timestamp until which the link's destinations can't be changed, such as while it's printed on physical materials.
+    #[serde(default)]
+    pub freeze_until_timestamp: Option<u64>,
+    /// Whether or not deleting the link needs to be confirmed by a second API key, see [`crate::models::deletion`].
+    #[serde(default)]
+    pub protected: bool,
     /// The UNIX timestamp of original creation.
     pub created_at_timestamp: u64,
     /// The UNIX timestamp of last modification.
     pub modified_at_timestamp: u64,
 }
 
+/// Represents what happens to a link once it becomes invalid, such as by expiring or reaching its maximum views.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryBehavior {
+    /// The link is removed without keeping a tombstone.
+    Delete,
+    /// The link is removed and kept as a tombstone if tombstone retention is enabled.
+    #[default]
+    Archive,
+    /// The link is kept and visitors are redirected to its fallback URL without being counted.
+    RedirectFallback,
+    /// The link is kept and visitors are told it's permanently gone.
+    Gone,
+}
+
+impl ExpiryBehavior {
+    /// Whether or not links with this behavior are kept in storage once they become invalid.
+    pub fn keeps_link(self) -> bool {
+        matches!(self, Self::RedirectFallback | Self::Gone)
+    }
+}
+
+/// Represents how visitors are sent to a link's destination.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectMechanism {
+    /// An HTTP redirect using the `Location` header.
+    #[default]
+    Http,
+    /// A small HTML page that redirects with a meta refresh and JavaScript, for destinations that
+    /// mishandle redirect referrers or networks that mangle `Location` headers.
+    Html,
+}
+
+/// Represents one of the destinations a link splits its visitors between.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkVariant {
+    /// The URL visitors assigned to this variant are sent to.
+    pub url: Url,
+    /// The share of visitors sent to this variant, relative to the weights of the link's other variants.
+    #[serde(default = "default_variant_weight")]
+    pub weight: u32,
+}
+
+fn default_variant_weight() -> u32 {
+    1
+}
+
+/// Represents the UTM parameters added to a link's destination when it's visited, so they don't have to be part of
+/// every stored URL.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkUtm {
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub medium: Option<String>,
+    #[serde(default)]
+    pub campaign: Option<String>,
+    #[serde(default)]
+    pub term: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+impl LinkUtm {
+    /// Get the parameters that are set as query pairs, such as `utm_source`.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        [
+            ("utm_source", &self.source),
+            ("utm_medium", &self.medium),
+            ("utm_campaign", &self.campaign),
+            ("utm_term", &self.term),
+            ("utm_content", &self.content),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.clone()?)))
+        .collect()
+    }
+}
+
+/// Represents the results of checking whether a link's destination is reachable.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LinkHealth {
+    /// The amount of checks in a row that have failed.
+    pub consecutive_failures: u32,
+    /// The status code returned by the destination during the last check, if it responded.
+    pub last_status: Option<u16>,
+    /// The UNIX timestamp of the last check.
+    pub last_checked_timestamp: u64,
+    /// Whether or not the link was paused automatically because its destination was failing.
+    pub auto_paused: bool,
+}
+
+/// Represents a screenshot captured of a link's destination.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkScreenshot {
+    /// The destination URL the screenshot was captured from.
+    pub url: Url,
+    /// The UNIX timestamp of when the screenshot was captured.
+    pub captured_at_timestamp: u64,
+}
+
 /// Arguments for building a link.
 pub struct LinkBuilderArgs {
     /// The URL to redirect to.
@@ -34,18 +252,105 @@ pub struct LinkBuilderArgs {
     pub max_views: Option<u64>,
     /// The UNIX timestamp for when the link will become invalid.
     pub expiry_timestamp: Option<u64>,
+    /// What happens to the link once it becomes invalid.
+    pub on_expire: ExpiryBehavior,
+    /// The URL visitors are sent to instead of the destination in some circumstances.
+    pub fallback_url: Option<Url>,
+    /// The percentage of visitors sent to the destination while it's being rolled out.
+    pub rollout_percent: Option<u8>,
+    /// The UNIX timestamp before which the link isn't served.
+    pub activate_at_timestamp: Option<u64>,
+    /// Whether or not visitors are shown a countdown to the activation time.
+    pub countdown: bool,
+    /// The hours of the day the link is served during.
+    pub active_hours: Option<ActiveHours>,
+    /// The days the link is served on.
+    pub recurrence: Option<Recurrence>,
+    /// The IANA time zone active hours and recurrences are in.
+    pub timezone: Option<String>,
+    /// Client IP addresses whose visits aren't counted towards views or stats.
+    pub exclude_ips: Vec<IpAddr>,
+    /// Client IP ranges whose visits aren't counted towards views or stats.
+    pub exclude_cidrs: Vec<IpRange>,
+    /// Whether or not visits from datacenter networks aren't counted towards views or stats.
+    pub exclude_datacenter: bool,
+    /// Every URL the destination redirects through, ending where it ultimately lands.
+    pub resolved_chain: Vec<Url>,
+    /// Extra headers attached to the redirect response.
+    pub response_headers: BTreeMap<String, String>,
+    /// How visitors are sent to the destination.
+    pub mechanism: RedirectMechanism,
+    /// The HTTP status codes visitors are redirected with.
+    pub status_codes: Vec<u16>,
+    /// Destinations used for visitors preferring a language, keyed by lowercase language tag.
+    pub language_rules: BTreeMap<String, Url>,
+    /// Destinations used for visitors from a country, keyed by uppercase country code or [`GEO_TARGET_DEFAULT`].
+    pub geo_targets: BTreeMap<String, Url>,
+    /// Destinations visitors are split between by weight.
+    pub variants: Vec<LinkVariant>,
+    /// Whether or not a returning visitor is always sent to the same variant.
+    pub sticky_variants: bool,
+    /// The query parameter a visit ID is appended to the destination under.
+    pub correlation_param: Option<String>,
+    /// Whether or not the query parameters a link is visited with are forwarded to its destination.
+    pub forward_query: bool,
+    /// Whether or not the rest of a path starting with the link's ID is appended to its destination.
+    pub forward_path: bool,
+    /// The UTM parameters added to the destination when the link is visited.
+    pub utm: Option<LinkUtm>,
+    /// The ID of a link this link depends on.
+    pub requires: Option<String>,
+    /// The amount of views the link this link depends on needs, instead of it expiring.
+    pub requires_views: Option<u64>,
+    /// The UNIX timestamp until which the link's destinations can't be changed.
+    pub freeze_until_timestamp: Option<u64>,
+    /// Whether or not deleting the link needs to be confirmed by a second API key.
+    pub protected: bool,
 }
 
 impl LinkModel {
     /// Create a new model using the given builder while setting some default values.
     pub fn new(args: LinkBuilderArgs) -> Self {
         Self {
+            schema_version: LINK_SCHEMA_VERSION,
             url: args.url,
             disabled: args.disabled,
+            paused: false,
+            pause_retry_after_seconds: None,
             views: 0,
             max_views: args.max_views,
             expiry_timestamp: args.expiry_timestamp,
+            on_expire: args.on_expire,
+            fallback_url: args.fallback_url,
+            rollout_percent: args.rollout_percent,
+            activate_at_timestamp: args.activate_at_timestamp,
+            countdown: args.countdown,
+            active_hours: args.active_hours,
+            recurrence: args.recurrence,
+            timezone: args.timezone,
             last_viewed_timestamp: None,
+            exclude_ips: args.exclude_ips,
+            exclude_cidrs: args.exclude_cidrs,
+            exclude_datacenter: args.exclude_datacenter,
+            resolved_chain: args.resolved_chain,
+            response_headers: args.response_headers,
+            mechanism: args.mechanism,
+            status_codes: args.status_codes,
+            language_rules: args.language_rules,
+            geo_targets: args.geo_targets,
+            variants: args.variants,
+            sticky_variants: args.sticky_variants,
+            correlation_param: args.correlation_param,
+            forward_query: args.forward_query,
+            forward_path: args.forward_path,
+            utm: args.utm,
+            health: None,
+            screenshot: None,
+            alias_of: None,
+            requires: args.requires,
+            requires_views: args.requires_views,
+            freeze_until_timestamp: args.freeze_until_timestamp,
+            protected: args.protected,
             created_at_timestamp: Date::now().as_millis(),
             modified_at_timestamp: Date::now().as_millis(),
         }
@@ -53,28 +358,101 @@ impl LinkModel {
 
     /// Consume the current model and creates a modified version of it with of the original data.
     pub fn modify(self, args: LinkBuilderArgs) -> Self {
+        // A new destination needs to be checked again and shouldn't stay paused because the old one was failing.
+        let destination_changed = args.url != self.url;
+        let auto_paused = self
+            .health
+            .as_ref()
+            .is_some_and(|health| health.auto_paused);
         Self {
+            schema_version: LINK_SCHEMA_VERSION,
+            paused: self.paused && !(destination_changed && auto_paused),
+            health: if destination_changed {
+                None
+            } else {
+                self.health
+            },
             url: args.url,
             disabled: args.disabled,
             max_views: args.max_views,
             expiry_timestamp: args.expiry_timestamp,
+            on_expire: args.on_expire,
+            fallback_url: args.fallback_url,
+            rollout_percent: args.rollout_percent,
+            activate_at_timestamp: args.activate_at_timestamp,
+            countdown: args.countdown,
+            active_hours: args.active_hours,
+            recurrence: args.recurrence,
+            timezone: args.timezone,
+            exclude_ips: args.exclude_ips,
+            exclude_cidrs: args.exclude_cidrs,
+            exclude_datacenter: args.exclude_datacenter,
+            resolved_chain: args.resolved_chain,
+            response_headers: args.response_headers,
+            mechanism: args.mechanism,
+            status_codes: args.status_codes,
+            language_rules: args.language_rules,
+            geo_targets: args.geo_targets,
+            variants: args.variants,
+            sticky_variants: args.sticky_variants,
+            correlation_param: args.correlation_param,
+            forward_query: args.forward_query,
+            forward_path: args.forward_path,
+            utm: args.utm,
+            requires: args.requires,
+            requires_views: args.requires_views,
+            freeze_until_timestamp: args.freeze_until_timestamp,
+            protected: args.protected,
+            // Giving a merged link its own destination again turns it back into a regular link.
+            alias_of: None,
             modified_at_timestamp: Date::now().as_millis(),
             ..self
         }
     }
 
+    /// Pause this link in-place, optionally telling clients how long to wait before retrying.
+    pub fn pause(&mut self, retry_after: Option<Duration>) {
+        if let Some(health) = &mut self.health {
+            health.auto_paused = false;
+        }
+        self.paused = true;
+        self.pause_retry_after_seconds = retry_after.map(|duration| duration.as_secs());
+        self.modified_at_timestamp = Date::now().as_millis();
+    }
+
+    /// Resume this link in-place so it redirects again.
+    pub fn resume(&mut self) {
+        if let Some(health) = &mut self.health {
+            health.auto_paused = false;
+        }
+        self.paused = false;
+        self.pause_retry_after_seconds = None;
+        self.modified_at_timestamp = Date::now().as_millis();
+    }
+
     /// Increment the number of visits for this link in-place.
     pub fn increment_visits(&mut self) {
         self.last_viewed_timestamp = Some(Date::now().as_millis());
         self.views += 1;
     }
 
+    /// Add visits that were counted elsewhere (e.g. buffered in memory) to this link in-place.
+    pub fn add_visits(&mut self, views: u64, last_viewed_timestamp: u64) {
+        self.last_viewed_timestamp = self.last_viewed_timestamp.max(Some(last_viewed_timestamp));
+        self.views += views;
+    }
+
     /// Whether or not this link is still considered valid after checking:
     ///     - It's expiry date compared to the current date.
     ///     - It's max view count compared to current view count
     pub fn is_valid(&self) -> bool {
+        self.is_valid_at(Date::now().as_millis())
+    }
+
+    /// Whether or not this link would be considered valid at the given UNIX timestamp, see [`LinkModel::is_valid`].
+    pub fn is_valid_at(&self, timestamp: u64) -> bool {
         if let Some(expires_at_ms) = self.expiry_timestamp {
-            if Date::now().as_millis() > expires_at_ms {
+            if timestamp > expires_at_ms {
                 return false;
             }
         }
@@ -87,4 +465,91 @@ impl LinkModel {
 
         true
     }
+
+    /// The UNIX timestamp in seconds after which storage can delete this link by itself, see [`StorageDriver::set`].
+    ///
+    /// Only links that are deleted outright once they expire have one, as links with other behaviors are kept after
+    /// they expire. The exception is archived links when tombstones are disabled, since archiving them then deletes them
+    /// outright too, whereas they need a tombstone written when `tombstones` is `true`.
+    ///
+    /// [`StorageDriver::set`]: crate::storage::StorageDriver::set
+    pub fn storage_expiration(&self, tombstones: bool) -> Option<u64> {
+        self.expiry_timestamp
+            .filter(|_| match self.on_expire {
+                ExpiryBehavior::Delete => true,
+                ExpiryBehavior::Archive => !tombstones,
+                ExpiryBehavior::RedirectFallback | ExpiryBehavior::Gone => false,
+            })
+            .map(|expires_at_ms| expires_at_ms.div_ceil(1000))
+    }
+
+    /// Whether or not this link has reached its activation time and is within its active hours and recurrence, if it has them.
+    ///
+    /// Unlike invalid links, links that aren't active are kept so they can be served once they are.
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(Date::now().as_millis())
+    }
+
+    /// Whether or not this link would be active at the given UNIX timestamp, see [`LinkModel::is_active`].
+    pub fn is_active_at(&self, timestamp: u64) -> bool {
+        self.is_activated_at(timestamp)
+            && self.is_within_active_hours_at(timestamp)
+            && self.is_recurring_at(timestamp)
+    }
+
+    /// Whether or not this link's destinations are frozen at the given UNIX timestamp.
+    pub fn is_frozen_at(&self, timestamp: u64) -> bool {
+        self.freeze_until_timestamp
+            .is_some_and(|freeze_until_timestamp| timestamp < freeze_until_timestamp)
+    }
+
+    /// Whether or not this link has reached its activation time at the given UNIX timestamp.
+    pub fn is_activated_at(&self, timestamp: u64) -> bool {
+        self.activate_at_timestamp
+            .map_or(true, |activate_at_timestamp| {
+                timestamp >= activate_at_timestamp
+            })
+    }
+
+    /// Whether or not the given UNIX timestamp falls within this link's active hours in its time zone.
+    ///
+    /// Links whose time zone can no longer be resolved are treated as outside their active hours.
+    pub fn is_within_active_hours_at(&self, timestamp: u64) -> bool {
+        let Some(active_hours) = &self.active_hours else {
+            return true;
+        };
+        local_date_time(timestamp, self.timezone.as_deref())
+            .is_some_and(|local| active_hours.contains(local))
+    }
+
+    /// Whether or not the given UNIX timestamp falls on a day included in this link's recurrence in its time zone.
+    ///
+    /// Repetitions are counted from the link's activation time, or when it was created if it doesn't have one.
+    pub fn is_recurring_at(&self, timestamp: u64) -> bool {
+        let Some(recurrence) = &self.recurrence else {
+            return true;
+        };
+        let timezone = self.timezone.as_deref();
+        let start = self
+            .activate_at_timestamp
+            .unwrap_or(self.created_at_timestamp);
+        match (
+            local_date_time(timestamp, timezone),
+            local_date_time(start, timezone),
+        ) {
+            (Some(local), Some(start)) => recurrence.occurs_on(local.date(), start.date()),
+            _ => false,
+        }
+    }
+
+    /// Whether or not the link this link depends on has reached the required views, or has expired if no views are required.
+    ///
+    /// Links that no longer exist are treated as expired.
+    pub fn is_dependency_met_by(&self, dependency: Option<&LinkModel>) -> bool {
+        match (dependency, self.requires_views) {
+            (Some(dependency), Some(requires_views)) => dependency.views >= requires_views,
+            (Some(dependency), None) => !dependency.is_valid(),
+            (None, _) => true,
+        }
+    }
 }
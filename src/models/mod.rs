@@ -1 +1,13 @@
+pub mod anomalies;
+pub mod branding;
+pub mod deletion;
+pub mod flags;
+pub mod host;
+pub mod job;
 pub mod link;
+pub mod stats;
+pub mod tombstone;
+pub mod usage;
+pub mod warnlist;
+pub mod webhook;
+pub mod webhook_failure;
@@ -0,0 +1,337 @@
+use crate::analytics::Visit;
+use chrono::{DateTime, Datelike, Timelike};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Represents the visit statistics of a link over a period of time (a day or a month).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatsModel {
+    /// The amount of times the link was visited during this period.
+    pub views: u64,
+    /// The amount of visits from each country, keyed by ISO 3166-1 Alpha 2 country code.
+    #[serde(default)]
+    pub countries: BTreeMap<String, u64>,
+    /// The amount of visits during each hour of the day (UTC), starting from midnight.
+    #[serde(default)]
+    pub hours: [u64; 24],
+    /// The amount of visits on each day of the week (UTC), starting from Monday.
+    #[serde(default)]
+    pub weekdays: [u64; 7],
+    /// The amount of visits from each browser family.
+    #[serde(default)]
+    pub browsers: BTreeMap<String, u64>,
+    /// The amount of visits from each operating system family.
+    #[serde(default)]
+    pub operating_systems: BTreeMap<String, u64>,
+    /// The amount of visits from each class of device (`desktop`, `mobile`, `tablet` or `bot`).
+    #[serde(default)]
+    pub devices: BTreeMap<String, u64>,
+    /// The amount of visits from each network, keyed by Autonomous System Number.
+    #[serde(default)]
+    pub asns: BTreeMap<String, u64>,
+    /// The amount of visits from each kind of network (`datacenter` or `residential`).
+    #[serde(default)]
+    pub networks: BTreeMap<String, u64>,
+    /// The visits redirected with each HTTP status code, keyed by status code.
+    #[serde(default)]
+    pub status_codes: BTreeMap<String, StatusCodeStats>,
+    /// The amount of conversions reported during this period.
+    #[serde(default)]
+    pub conversions: u64,
+    /// The total value of the conversions reported during this period.
+    #[serde(default)]
+    pub conversion_value: f64,
+    /// The visits and conversions of each variant, keyed by the variant's index.
+    #[serde(default)]
+    pub variants: BTreeMap<String, VariantStats>,
+    /// An estimate of the distinct visitors during this period.
+    #[serde(default, skip_serializing_if = "UniqueVisitors::is_empty")]
+    pub visitors: UniqueVisitors,
+}
+
+/// Represents the visits redirected with a single HTTP status code.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct StatusCodeStats {
+    /// The amount of visits redirected with the status code.
+    pub views: u64,
+    /// The amount of visits from bots, such as search engine crawlers, redirected with the status code.
+    pub bot_views: u64,
+}
+
+impl StatusCodeStats {
+    /// Add the visits of a status code from another period into these in-place.
+    pub fn merge(&mut self, other: &StatusCodeStats) {
+        self.views += other.views;
+        self.bot_views += other.bot_views;
+    }
+}
+
+/// Represents the visits and conversions of a single variant of a link.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VariantStats {
+    /// The amount of visits sent to the variant.
+    pub views: u64,
+    /// The amount of conversions reported for visits sent to the variant.
+    pub conversions: u64,
+    /// The total value of the conversions reported for visits sent to the variant.
+    pub conversion_value: f64,
+}
+
+impl VariantStats {
+    /// Add the visits and conversions of a variant from another period into these in-place.
+    pub fn merge(&mut self, other: &VariantStats) {
+        self.views += other.views;
+        self.conversions += other.conversions;
+        self.conversion_value += other.conversion_value;
+    }
+}
+
+/// The amount of bits of a visitor's hash used to pick their register in [`UniqueVisitors`].
+const UNIQUE_VISITORS_PRECISION: u32 = 8;
+
+/// The amount of registers in [`UniqueVisitors`], which gives estimates within about 6.5% of the real count.
+const UNIQUE_VISITORS_REGISTERS: usize = 1 << UNIQUE_VISITORS_PRECISION;
+
+/// A HyperLogLog sketch estimating how many distinct visitors a link had.
+///
+/// Only the longest run of zero bits seen in the hashes of visitors is kept for each register, so visitors
+/// can't be recovered from it, and sketches of different periods can be merged to count visitors across them.
+/// It's stored as a hex string of its registers, or left out entirely while nobody has been counted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UniqueVisitors {
+    registers: Vec<u8>,
+}
+
+impl UniqueVisitors {
+    /// Whether or not no visitors have been counted.
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+
+    /// Count a visitor with the given hash in-place, which has no effect if they were already counted.
+    pub fn add(&mut self, hash: u64) {
+        // The hash is mixed again (with the finalizer of MurmurHash3) as the sketch relies on every bit being random.
+        let mut hash = hash;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+        hash ^= hash >> 33;
+
+        if self.registers.is_empty() {
+            self.registers = vec![0; UNIQUE_VISITORS_REGISTERS];
+        }
+        let index = (hash >> (64 - UNIQUE_VISITORS_PRECISION)) as usize;
+        let rank = ((hash << UNIQUE_VISITORS_PRECISION).leading_zeros() + 1)
+            .min(64 - UNIQUE_VISITORS_PRECISION + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Add the visitors counted by another sketch into this one in-place, counting visitors seen by both once.
+    pub fn merge(&mut self, other: &UniqueVisitors) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            self.registers.clone_from(&other.registers);
+            return;
+        }
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// Estimate how many distinct visitors have been counted.
+    pub fn estimate(&self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        let registers = UNIQUE_VISITORS_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / registers);
+        let sum = self
+            .registers
+            .iter()
+            .map(|register| 2f64.powi(-i32::from(*register)))
+            .sum::<f64>();
+        let estimate = alpha * registers * registers / sum;
+
+        // Small counts are estimated much more accurately from how many registers are still empty.
+        let empty = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+        if estimate <= 2.5 * registers && empty > 0 {
+            return (registers * (registers / empty as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
+
+impl Serialize for UniqueVisitors {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hex = self
+            .registers
+            .iter()
+            .map(|register| format!("{register:02x}"))
+            .collect::<String>();
+        serializer.serialize_str(&hex)
+    }
+}
+
+impl<'de> Deserialize<'de> for UniqueVisitors {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if hex.is_empty() {
+            return Ok(Self::default());
+        }
+        if hex.len() != UNIQUE_VISITORS_REGISTERS * 2 {
+            return Err(D::Error::custom(
+                "unique visitors sketch has the wrong size",
+            ));
+        }
+        let registers = (0..hex.len())
+            .step_by(2)
+            .map(|index| {
+                let byte = hex
+                    .get(index..index + 2)
+                    .ok_or_else(|| D::Error::custom("unique visitors sketch isn't valid hex"))?;
+                u8::from_str_radix(byte, 16).map_err(D::Error::custom)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { registers })
+    }
+}
+
+impl StatsModel {
+    /// Get the storage key prefix shared by all daily stats of the given link ID.
+    pub fn day_key_prefix(id: &str) -> String {
+        format!("stats:{id}:day:")
+    }
+
+    /// Get the storage key for the stats of the given link ID on the given day (`YYYY-MM-DD`).
+    pub fn day_key(id: &str, day: &str) -> String {
+        format!("{}{day}", Self::day_key_prefix(id))
+    }
+
+    /// Get the storage key prefix shared by all monthly stats of the given link ID.
+    pub fn month_key_prefix(id: &str) -> String {
+        format!("stats:{id}:month:")
+    }
+
+    /// Get the storage key for the stats of the given link ID in the given month (`YYYY-MM`).
+    pub fn month_key(id: &str, month: &str) -> String {
+        format!("{}{month}", Self::month_key_prefix(id))
+    }
+
+    /// Get the storage key marking that a conversion was recorded for the visit with the given token.
+    pub fn conversion_key(id: &str, token: &str) -> String {
+        format!("stats:{id}:conversion:{token}")
+    }
+
+    /// Get the storage key prefix shared by all conversion markers of the given link ID.
+    pub fn conversion_key_prefix(id: &str) -> String {
+        format!("stats:{id}:conversion:")
+    }
+
+    /// Add a single visit to these stats in-place.
+    pub fn add_visit(&mut self, visit: Visit) {
+        self.views += 1;
+        if let Some(visitor) = visit.visitor {
+            self.visitors.add(visitor);
+        }
+        if let Some(variant) = visit.variant {
+            self.variants.entry(variant.to_string()).or_default().views += 1;
+        }
+        if let Some(country) = visit.country {
+            *self.countries.entry(country).or_default() += 1;
+        }
+        if let Some(status_code) = visit.status_code {
+            let status_code = self
+                .status_codes
+                .entry(status_code.to_string())
+                .or_default();
+            status_code.views += 1;
+            if visit
+                .user_agent
+                .is_some_and(|user_agent| user_agent.device == "bot")
+            {
+                status_code.bot_views += 1;
+            }
+        }
+        if let Some(user_agent) = visit.user_agent {
+            *self
+                .browsers
+                .entry(user_agent.browser.to_string())
+                .or_default() += 1;
+            *self
+                .operating_systems
+                .entry(user_agent.os.to_string())
+                .or_default() += 1;
+            *self
+                .devices
+                .entry(user_agent.device.to_string())
+                .or_default() += 1;
+        }
+        if let Some((asn, network)) = visit.network {
+            *self.asns.entry(asn.to_string()).or_default() += 1;
+            *self.networks.entry(network.to_string()).or_default() += 1;
+        }
+        if let Some(time) = DateTime::from_timestamp_millis(visit.timestamp as i64) {
+            self.hours[time.hour() as usize] += 1;
+            self.weekdays[time.weekday().num_days_from_monday() as usize] += 1;
+        }
+    }
+
+    /// Add a single conversion with the given value to these stats in-place.
+    pub fn add_conversion(&mut self, variant: Option<usize>, value: f64) {
+        self.conversions += 1;
+        self.conversion_value += value;
+        if let Some(variant) = variant {
+            let variant = self.variants.entry(variant.to_string()).or_default();
+            variant.conversions += 1;
+            variant.conversion_value += value;
+        }
+    }
+
+    /// Add the stats from another period into this one in-place.
+    pub fn merge(&mut self, other: StatsModel) {
+        self.views += other.views;
+        self.visitors.merge(&other.visitors);
+        self.conversions += other.conversions;
+        self.conversion_value += other.conversion_value;
+        for (status_code, stats) in other.status_codes {
+            self.status_codes
+                .entry(status_code)
+                .or_default()
+                .merge(&stats);
+        }
+        for (variant, stats) in other.variants {
+            self.variants.entry(variant).or_default().merge(&stats);
+        }
+        for (country, views) in other.countries {
+            *self.countries.entry(country).or_default() += views;
+        }
+        for (browser, views) in other.browsers {
+            *self.browsers.entry(browser).or_default() += views;
+        }
+        for (os, views) in other.operating_systems {
+            *self.operating_systems.entry(os).or_default() += views;
+        }
+        for (device, views) in other.devices {
+            *self.devices.entry(device).or_default() += views;
+        }
+        for (asn, views) in other.asns {
+            *self.asns.entry(asn).or_default() += views;
+        }
+        for (network, views) in other.networks {
+            *self.networks.entry(network).or_default() += views;
+        }
+        for (hour, views) in self.hours.iter_mut().zip(other.hours) {
+            *hour += views;
+        }
+        for (weekday, views) in self.weekdays.iter_mut().zip(other.weekdays) {
+            *weekday += views;
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::link::LinkModel;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use worker::Date;
+
+/// The reason a link was removed from storage.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TombstoneStatus {
+    /// The link was deleted by its owner.
+    Deleted,
+    /// The link became invalid (exceeded max views, timed expiry, etc.)
+    Expired,
+}
+
+/// Represents an archived copy of a link that has been removed from storage.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TombstoneModel {
+    /// Why the link was removed.
+    pub status: TombstoneStatus,
+    /// The UNIX timestamp of when the link was removed.
+    pub removed_at_timestamp: u64,
+    /// The UNIX timestamp for when this tombstone will no longer be retained.
+    pub retain_until_timestamp: u64,
+    /// The link as it was at the time of removal.
+    #[serde(flatten)]
+    pub link: LinkModel,
+}
+
+impl TombstoneModel {
+    /// Create a new tombstone for the given link that will be retained for the given duration.
+    pub fn new(link: LinkModel, status: TombstoneStatus, retention: Duration) -> Self {
+        let now = Date::now().as_millis();
+        Self {
+            status,
+            removed_at_timestamp: now,
+            retain_until_timestamp: now + retention.as_millis() as u64,
+            link,
+        }
+    }
+
+    /// Whether or not this tombstone is still within its retention period.
+    pub fn is_retained(&self) -> bool {
+        Date::now().as_millis() <= self.retain_until_timestamp
+    }
+
+    /// Get the storage key used for the tombstone of the given link ID.
+    pub fn key(id: &str) -> String {
+        format!("tombstone:{id}")
+    }
+}
@@ -0,0 +1,24 @@
+use crate::storage::StorageUsage;
+use serde::{Deserialize, Serialize};
+
+/// Represents the last measurement of how much data is held in storage.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StorageUsageModel {
+    #[serde(flatten)]
+    pub usage: StorageUsage,
+    /// Whether or not the key count was over its alert threshold when last measured.
+    #[serde(default)]
+    pub keys_alerted: bool,
+    /// Whether or not the stored bytes were over their alert threshold when last measured.
+    #[serde(default)]
+    pub bytes_alerted: bool,
+    /// The UNIX timestamp of when the usage was measured.
+    pub measured_at_timestamp: u64,
+}
+
+impl StorageUsageModel {
+    /// Get the storage key the usage is stored under.
+    pub fn key() -> &'static str {
+        "config:usage"
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use worker::Date;
+
+/// Represents the destination domains that visitors are warned about before being redirected.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WarnListModel {
+    /// The flagged domains, keyed by domain.
+    pub domains: BTreeMap<String, WarnedDomain>,
+}
+
+/// Represents a single flagged destination domain.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WarnedDomain {
+    /// The warning shown to visitors, or [`None`] to use the default warning.
+    pub reason: Option<String>,
+    /// The UNIX timestamp of when this domain was flagged or last changed.
+    pub modified_at_timestamp: u64,
+}
+
+impl WarnedDomain {
+    /// Flag a domain with the given warning.
+    pub fn new(reason: Option<String>) -> Self {
+        Self {
+            reason,
+            modified_at_timestamp: Date::now().as_millis(),
+        }
+    }
+}
+
+impl WarnListModel {
+    /// Get the storage key the warn list is stored under.
+    pub fn key() -> &'static str {
+        "config:warnlist"
+    }
+}
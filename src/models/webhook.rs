@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use url::Url;
+use worker::Date;
+
+/// The kinds of events a webhook can be subscribed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A link was created.
+    Created,
+    /// A link was deleted.
+    Deleted,
+    /// An expired link was removed.
+    Expired,
+    /// A link was visited.
+    Visited,
+    /// A conversion was reported for a visit to a link.
+    Reported,
+    /// A link's destination became unhealthy.
+    Unhealthy,
+    /// An unhealthy link's destination recovered.
+    Recovered,
+    /// A link was merged into another link.
+    Merged,
+    /// A link's stats showed anomalous traffic.
+    Anomaly,
+    /// The amount of data in storage went over a threshold.
+    Storage,
+}
+
+/// Represents the webhooks notifications are delivered to, alongside the one configured by the environment.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WebhooksModel {
+    /// The webhooks, keyed by their generated ID.
+    pub endpoints: BTreeMap<String, WebhookEndpoint>,
+}
+
+/// Represents a single webhook and the events it's sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    /// The URL notifications are POSTed to.
+    pub url: Url,
+    /// The events the webhook is sent, or every event if empty.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// The secret notifications are signed with, so the webhook can check they came from this worker.
+    pub secret: String,
+    /// The UNIX timestamp of when the webhook was added.
+    pub created_at_timestamp: u64,
+}
+
+impl WebhookEndpoint {
+    /// Create a webhook for the given events, signing its notifications with the given secret.
+    pub fn new(url: Url, events: Vec<WebhookEvent>, secret: String) -> Self {
+        Self {
+            url,
+            events,
+            secret,
+            created_at_timestamp: Date::now().as_millis(),
+        }
+    }
+
+    /// Whether or not the webhook is sent the given event.
+    pub fn receives(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+impl WebhooksModel {
+    /// Get the storage key the webhooks are stored under.
+    pub fn key() -> &'static str {
+        "config:webhooks"
+    }
+}
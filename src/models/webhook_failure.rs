@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Represents a notification that couldn't be delivered to a webhook after every attempt, kept so it can
+/// be inspected and replayed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookFailureModel {
+    /// The ID of the delivery, which is sent to the webhook with every attempt.
+    pub id: String,
+    /// The ID of the webhook the notification was being delivered to, or [`None`] for the one configured
+    /// by the environment.
+    #[serde(default)]
+    pub webhook_id: Option<String>,
+    /// The notification that was being delivered.
+    pub notification: Value,
+    /// The amount of times delivering the notification was attempted.
+    pub attempts: u32,
+    /// Why the last attempt failed.
+    pub last_error: String,
+    /// The UNIX timestamp of when the notification was first attempted.
+    pub created_at_timestamp: u64,
+    /// The UNIX timestamp of when the last attempt failed.
+    pub failed_at_timestamp: u64,
+}
+
+impl WebhookFailureModel {
+    /// Get the storage key used for the failed delivery with the given ID.
+    pub fn key(id: &str) -> String {
+        format!("webhook_failure:{id}")
+    }
+
+    /// Get the storage key prefix shared by all failed deliveries.
+    pub fn key_prefix() -> &'static str {
+        "webhook_failure:"
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    environment::{get_bool_var, get_var},
+    models::link::LinkModel,
+};
+use serde::Serialize;
+use std::fmt;
+use worker::Env;
+
+/// The binding name for the variable holding comma-separated ASNs to treat as datacenter networks,
+/// on top of the built-in list of cloud providers.
+pub const DATACENTER_ASNS_BINDING: &str = "DATACENTER_ASNS";
+
+/// The binding name for the variable controlling whether visits from datacenter networks are never counted.
+pub const EXCLUDE_DATACENTER_VISITS_BINDING: &str = "EXCLUDE_DATACENTER_VISITS";
+
+/// The ASNs of well-known cloud and hosting providers, whose traffic is almost always bots or scanners
+/// rather than people.
+const DATACENTER_ASNS: &[u32] = &[
+    7224,   // Amazon
+    8075,   // Microsoft
+    12876,  // Scaleway
+    13335,  // Cloudflare
+    14061,  // DigitalOcean
+    14618,  // Amazon
+    15169,  // Google
+    16276,  // OVH
+    16509,  // Amazon
+    20473,  // Vultr
+    24940,  // Hetzner
+    31898,  // Oracle
+    37963,  // Alibaba
+    45102,  // Alibaba
+    51167,  // Contabo
+    63949,  // Akamai (Linode)
+    132203, // Tencent
+    396982, // Google Cloud
+];
+
+/// Represents the kind of network a visit came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkType {
+    /// A cloud or hosting provider.
+    Datacenter,
+    /// Any other network, such as a home or mobile connection.
+    Residential,
+}
+
+impl fmt::Display for NetworkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Datacenter => write!(f, "datacenter"),
+            Self::Residential => write!(f, "residential"),
+        }
+    }
+}
+
+/// Classify the network with the given ASN as a datacenter or residential network.
+pub fn classify_asn(env: &Env, asn: u32) -> NetworkType {
+    let is_datacenter = DATACENTER_ASNS.contains(&asn)
+        || get_var(env, DATACENTER_ASNS_BINDING).is_some_and(|list| {
+            list.split(',')
+                .filter_map(|entry| entry.trim().trim_start_matches("AS").parse::<u32>().ok())
+                .any(|listed| listed == asn)
+        });
+    if is_datacenter {
+        NetworkType::Datacenter
+    } else {
+        NetworkType::Residential
+    }
+}
+
+/// Whether or not visits to the given link from the network with the given ASN are left out of its views and stats.
+pub fn is_excluded_network(env: &Env, link: &LinkModel, asn: u32) -> bool {
+    (link.exclude_datacenter
+        || get_bool_var(env, EXCLUDE_DATACENTER_VISITS_BINDING).unwrap_or(false))
+        && classify_asn(env, asn) == NetworkType::Datacenter
+}
@@ -0,0 +1,370 @@
+use crate::{
+    attestations::{call_subtle_crypto, to_hex},
+    environment::get_var,
+    jobs::{QueueMessage, JOBS_QUEUE_BINDING},
+    models::{
+        anomalies::AnomalyKind,
+        webhook::{WebhookEvent, WebhooksModel},
+        webhook_failure::WebhookFailureModel,
+    },
+    slugs::{get_random_id_alphabet, random_id},
+    storage::{get_storage, StorageDriver},
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use wasm_bindgen::{JsCast, JsValue};
+use worker::{
+    js_sys::{global, Array, Function, Object, Reflect, Uint8Array},
+    Context, Date, Env, Fetch, Headers, MessageBuilder, Method, Request, RequestInit,
+};
+
+/// The binding name for the variable holding a URL every notification except visits is sent to, alongside
+/// the webhooks added with the API.
+pub const NOTIFY_WEBHOOK_URL_BINDING: &str = "NOTIFY_WEBHOOK_URL";
+
+/// The binding name for the variable controlling how many times a notification is attempted before it's
+/// kept as a failed delivery.
+pub const NOTIFY_MAX_ATTEMPTS_BINDING: &str = "NOTIFY_MAX_ATTEMPTS";
+
+/// How many times a notification is attempted when the amount of attempts isn't configured.
+const DEFAULT_NOTIFY_MAX_ATTEMPTS: u32 = 5;
+
+/// How long to wait before the first retry of a notification, which doubles with every retry after it.
+const RETRY_BASE_DELAY_SECONDS: u32 = 30;
+
+/// The longest to wait between retries of a notification.
+const RETRY_MAX_DELAY_SECONDS: u32 = 60 * 60;
+
+/// The length of generated delivery IDs.
+const DELIVERY_ID_LENGTH: usize = 16;
+
+/// The header the ID of a delivery is sent in, so webhooks can ignore notifications they've already received.
+const DELIVERY_ID_HEADER: &str = "X-Workerlink-Delivery";
+
+/// The header the signature of a notification is sent in, for webhooks that have a signing secret.
+const SIGNATURE_HEADER: &str = "X-Workerlink-Signature";
+
+/// The amount of random bytes in generated signing secrets.
+const SECRET_BYTES: u32 = 32;
+
+/// Represents an event the worker can notify about.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    /// A link was created.
+    LinkCreated { id: String, tenant: Option<String> },
+    /// A link was deleted.
+    LinkDeleted { id: String, tenant: Option<String> },
+    /// An expired link was removed, which happens the next time it's requested.
+    LinkExpired {
+        id: String,
+        tenant: Option<String>,
+        views: u64,
+    },
+    /// A link was visited, which isn't sent for visits that aren't counted.
+    LinkVisited {
+        id: String,
+        tenant: Option<String>,
+        /// The index of the variant the visitor was sent to, if they were assigned one.
+        variant: Option<usize>,
+    },
+    /// A conversion was reported for a visit to a link.
+    ConversionReported {
+        id: String,
+        tenant: Option<String>,
+        /// The index of the variant the visitor was sent to, if they were assigned one.
+        variant: Option<usize>,
+        value: f64,
+    },
+    /// A link's destination failed enough health checks in a row to be considered unhealthy.
+    LinkUnhealthy {
+        id: String,
+        tenant: Option<String>,
+        url: Url,
+        consecutive_failures: u32,
+        last_status: Option<u16>,
+        /// Whether or not the link was automatically paused.
+        paused: bool,
+    },
+    /// An unhealthy link's destination is passing health checks again.
+    LinkRecovered {
+        id: String,
+        tenant: Option<String>,
+        url: Url,
+        /// Whether or not the link was automatically resumed.
+        resumed: bool,
+    },
+    /// A link was merged into another link and is now an alias of it.
+    LinksMerged {
+        source: String,
+        target: String,
+        tenant: Option<String>,
+        /// The total views of the target link after merging.
+        views: u64,
+    },
+    /// A link's stats showed anomalous traffic, such as a sudden spike in visits or mostly bot visits.
+    LinkAnomalyDetected {
+        id: String,
+        tenant: Option<String>,
+        kind: AnomalyKind,
+        /// The day (`YYYY-MM-DD`) the anomaly was found in.
+        day: String,
+        views: u64,
+    },
+    /// The amount of data in storage went over an operator-configured threshold.
+    StorageThresholdExceeded {
+        /// What went over its threshold, either `keys` or `bytes`.
+        resource: String,
+        usage: u64,
+        threshold: u64,
+    },
+}
+
+impl Notification {
+    /// Get the kind of event webhooks subscribe to for this notification.
+    pub fn event(&self) -> WebhookEvent {
+        match self {
+            Self::LinkCreated { .. } => WebhookEvent::Created,
+            Self::LinkDeleted { .. } => WebhookEvent::Deleted,
+            Self::LinkExpired { .. } => WebhookEvent::Expired,
+            Self::LinkVisited { .. } => WebhookEvent::Visited,
+            Self::ConversionReported { .. } => WebhookEvent::Reported,
+            Self::LinkUnhealthy { .. } => WebhookEvent::Unhealthy,
+            Self::LinkRecovered { .. } => WebhookEvent::Recovered,
+            Self::LinksMerged { .. } => WebhookEvent::Merged,
+            Self::LinkAnomalyDetected { .. } => WebhookEvent::Anomaly,
+            Self::StorageThresholdExceeded { .. } => WebhookEvent::Storage,
+        }
+    }
+}
+
+/// Represents a notification being delivered to a webhook, which is sent through the jobs queue to be retried.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationDelivery {
+    /// The ID of the delivery, which stays the same across retries.
+    pub delivery_id: String,
+    /// The ID of the webhook the notification is delivered to, or [`None`] for [`NOTIFY_WEBHOOK_URL_BINDING`].
+    #[serde(default)]
+    pub webhook_id: Option<String>,
+    /// The notification serialized as JSON.
+    pub body: String,
+    /// The amount of times delivering the notification has been attempted.
+    pub attempts: u32,
+    /// The UNIX timestamp of when the notification was first attempted.
+    pub created_at_timestamp: u64,
+}
+
+/// Whether or not any webhook is configured to send notifications to.
+pub async fn notifications_enabled(env: &Env) -> bool {
+    if get_var(env, NOTIFY_WEBHOOK_URL_BINDING).is_some() {
+        return true;
+    }
+    get_webhooks(env)
+        .await
+        .is_some_and(|webhooks| !webhooks.endpoints.is_empty())
+}
+
+/// Get the webhooks added with the API.
+async fn get_webhooks(env: &Env) -> Option<WebhooksModel> {
+    get_storage(env)
+        .ok()?
+        .get_deserialized_json::<WebhooksModel>(WebhooksModel::key())
+        .await
+}
+
+/// Get how many times a notification is attempted before it's kept as a failed delivery.
+fn get_max_attempts(env: &Env) -> u32 {
+    get_var(env, NOTIFY_MAX_ATTEMPTS_BINDING)
+        .and_then(|attempts| attempts.parse().ok())
+        .filter(|attempts| *attempts > 0)
+        .unwrap_or(DEFAULT_NOTIFY_MAX_ATTEMPTS)
+}
+
+/// Send a notification as JSON to every webhook subscribed to its event, each as its own delivery.
+///
+/// Notifications that can't be delivered are retried with an exponential backoff through the jobs queue,
+/// and are kept as a failed delivery that can be replayed once every attempt has failed (or immediately
+/// when no jobs queue is bound).
+///
+/// Returns whether or not the notification was delivered to every webhook on the first attempt, which is
+/// always false if no webhook is subscribed to it.
+pub async fn notify(env: &Env, notification: &Notification) -> bool {
+    let event = notification.event();
+    let mut webhook_ids = Vec::new();
+    // Visits would flood the webhook configured by the environment, so only webhooks that ask for them get them.
+    if event != WebhookEvent::Visited && get_var(env, NOTIFY_WEBHOOK_URL_BINDING).is_some() {
+        webhook_ids.push(None);
+    }
+    if let Some(webhooks) = get_webhooks(env).await {
+        webhook_ids.extend(
+            webhooks
+                .endpoints
+                .into_iter()
+                .filter(|(_, webhook)| webhook.receives(event))
+                .map(|(webhook_id, _)| Some(webhook_id)),
+        );
+    }
+    if webhook_ids.is_empty() {
+        return false;
+    }
+    let Ok(body) = serde_json::to_string(notification) else {
+        return false;
+    };
+
+    let mut delivered = true;
+    for webhook_id in webhook_ids {
+        let delivery = NotificationDelivery {
+            delivery_id: random_id(&get_random_id_alphabet(env), DELIVERY_ID_LENGTH),
+            webhook_id,
+            body: body.clone(),
+            attempts: 0,
+            created_at_timestamp: Date::now().as_millis(),
+        };
+        delivered &= attempt_delivery(env, delivery).await;
+    }
+    delivered
+}
+
+/// Send a notification in the background once the response to the current request has been sent,
+/// so requests never wait on webhooks.
+pub fn notify_in_background(ctx: &Context, env: &Env, notification: Notification) {
+    let env = env.clone();
+    ctx.wait_until(async move {
+        notify(&env, &notification).await;
+    });
+}
+
+/// Attempt to deliver a notification that's being retried from the jobs queue.
+pub async fn retry_delivery(env: &Env, delivery: NotificationDelivery) {
+    attempt_delivery(env, delivery).await;
+}
+
+/// Attempt to deliver a notification, scheduling a retry or keeping it as a failed delivery if it fails.
+///
+/// Returns whether or not the notification was delivered.
+async fn attempt_delivery(env: &Env, mut delivery: NotificationDelivery) -> bool {
+    delivery.attempts += 1;
+    let Err(error) = send_delivery(env, &delivery).await else {
+        return true;
+    };
+    // Notifications for webhooks that have since been removed are dropped.
+    let Some(error) = error else {
+        return false;
+    };
+
+    if delivery.attempts < get_max_attempts(env) {
+        if let Ok(queue) = env.queue(JOBS_QUEUE_BINDING) {
+            let delay_seconds = RETRY_BASE_DELAY_SECONDS
+                .saturating_mul(2u32.saturating_pow(delivery.attempts - 1))
+                .min(RETRY_MAX_DELAY_SECONDS);
+            let message = MessageBuilder::new(QueueMessage::NotificationDelivery(delivery.clone()))
+                .delay_seconds(delay_seconds)
+                .build();
+            if queue.send(message).await.is_ok() {
+                return false;
+            }
+        }
+    }
+
+    if let Ok(storage) = get_storage(env) {
+        let failure = WebhookFailureModel {
+            id: delivery.delivery_id.clone(),
+            webhook_id: delivery.webhook_id.clone(),
+            notification: serde_json::from_str(&delivery.body).unwrap_or_default(),
+            attempts: delivery.attempts,
+            last_error: error,
+            created_at_timestamp: delivery.created_at_timestamp,
+            failed_at_timestamp: Date::now().as_millis(),
+        };
+        storage
+            .set_serialized_json(&WebhookFailureModel::key(&failure.id), &failure, None)
+            .await;
+    }
+    false
+}
+
+/// Send a single attempt of a delivery to its webhook, signing it if the webhook has a secret.
+///
+/// Returns why the attempt failed if it did, which is [`None`] when the webhook no longer exists.
+pub async fn send_delivery(
+    env: &Env,
+    delivery: &NotificationDelivery,
+) -> Result<(), Option<String>> {
+    let (webhook_url, secret) = match &delivery.webhook_id {
+        Some(webhook_id) => {
+            let webhook = get_webhooks(env)
+                .await
+                .and_then(|mut webhooks| webhooks.endpoints.remove(webhook_id))
+                .ok_or(None)?;
+            (webhook.url.to_string(), Some(webhook.secret))
+        }
+        None => (get_var(env, NOTIFY_WEBHOOK_URL_BINDING).ok_or(None)?, None),
+    };
+
+    let mut headers = Headers::new();
+    headers
+        .set("Content-Type", "application/json")
+        .and_then(|_| headers.set(DELIVERY_ID_HEADER, &delivery.delivery_id))
+        .map_err(|err| Some(err.to_string()))?;
+    if let Some(secret) = secret {
+        let signature = sign_body(&secret, &delivery.body)
+            .await
+            .ok_or_else(|| Some("The notification couldn't be signed.".to_string()))?;
+        headers
+            .set(SIGNATURE_HEADER, &format!("sha256={signature}"))
+            .map_err(|err| Some(err.to_string()))?;
+    }
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_headers(headers)
+        .with_body(Some(JsValue::from_str(&delivery.body)));
+    let request =
+        Request::new_with_init(&webhook_url, &init).map_err(|err| Some(err.to_string()))?;
+
+    let response = Fetch::Request(request)
+        .send()
+        .await
+        .map_err(|err| Some(err.to_string()))?;
+    match response.status_code() {
+        200..=299 => Ok(()),
+        status => Err(Some(format!("The webhook responded with status {status}."))),
+    }
+}
+
+/// Sign a notification with a webhook's secret using HMAC-SHA256, returning the signature as lowercase hex.
+async fn sign_body(secret: &str, body: &str) -> Option<String> {
+    let algorithm = Object::new();
+    Reflect::set(&algorithm, &"name".into(), &"HMAC".into()).ok()?;
+    Reflect::set(&algorithm, &"hash".into(), &"SHA-256".into()).ok()?;
+    let key = call_subtle_crypto(
+        "importKey",
+        &Array::of5(
+            &"raw".into(),
+            &Uint8Array::from(secret.as_bytes()),
+            &algorithm,
+            &false.into(),
+            &Array::of1(&"sign".into()),
+        ),
+    )
+    .await?;
+    let signature = call_subtle_crypto(
+        "sign",
+        &Array::of3(&"HMAC".into(), &key, &Uint8Array::from(body.as_bytes())),
+    )
+    .await?;
+    Some(to_hex(&Uint8Array::new(&signature).to_vec()))
+}
+
+/// Generate a random secret for signing a webhook's notifications, as lowercase hex.
+///
+/// The secret comes from the runtime's cryptographically secure random number generator, so it can't be guessed.
+pub fn generate_webhook_secret() -> Option<String> {
+    let crypto = Reflect::get(&global(), &"crypto".into()).ok()?;
+    let get_random_values = Reflect::get(&crypto, &"getRandomValues".into())
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+    let bytes = Uint8Array::new_with_length(SECRET_BYTES);
+    get_random_values.call1(&crypto, &bytes).ok()?;
+    Some(to_hex(&bytes.to_vec()))
+}
@@ -0,0 +1,230 @@
+use std::fmt::Write;
+
+/// The fonts text can be drawn with, which are standard PDF fonts so nothing has to be embedded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfFont {
+    Regular,
+    Bold,
+}
+
+impl PdfFont {
+    /// Get the name the font is registered under in the page's resources.
+    fn resource_name(&self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+        }
+    }
+}
+
+/// Escape text to be drawn as a PDF string, using the Windows-1252 encoding of the standard fonts.
+///
+/// Characters the standard fonts can't draw are replaced with a question mark.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            ' '..='~' => escaped.push(character),
+            // Latin-1 characters share their code with Windows-1252, so they can be written as octal escapes.
+            '\u{A0}'..='\u{FF}' => {
+                let _ = write!(escaped, "\\{:03o}", character as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// A single PDF page drawn with a small set of text and vector operations.
+///
+/// Coordinates are in points (1/72 inch) starting from the bottom left of the page, and colors are
+/// shades of gray from `0.0` (black) to `1.0` (white).
+pub struct PdfPage {
+    width: f64,
+    height: f64,
+    /// The content stream of drawing operators.
+    content: String,
+}
+
+impl PdfPage {
+    /// Create a blank page of the given size.
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            content: String::new(),
+        }
+    }
+
+    /// Draw a line of text with its baseline starting at the given position.
+    pub fn text(&mut self, x: f64, y: f64, font: PdfFont, size: f64, gray: f64, text: &str) {
+        let _ = writeln!(
+            self.content,
+            "BT /{} {size:.2} Tf {gray:.2} g {x:.2} {y:.2} Td ({}) Tj ET",
+            font.resource_name(),
+            escape_text(text)
+        );
+    }
+
+    /// Fill a rectangle with its bottom left corner at the given position.
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, gray: f64) {
+        let _ = writeln!(
+            self.content,
+            "{gray:.2} g {x:.2} {y:.2} {width:.2} {height:.2} re f"
+        );
+    }
+
+    /// Draw a line through the given points.
+    pub fn polyline(&mut self, points: &[(f64, f64)], line_width: f64, gray: f64) {
+        let Some(((start_x, start_y), rest)) = points.split_first() else {
+            return;
+        };
+        let _ = write!(
+            self.content,
+            "{gray:.2} G {line_width:.2} w 1 j {start_x:.2} {start_y:.2} m"
+        );
+        for (x, y) in rest {
+            let _ = write!(self.content, " {x:.2} {y:.2} l");
+        }
+        self.content.push_str(" S\n");
+    }
+
+    /// Finish the page, returning it as a complete PDF document.
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>",
+                self.width, self.height
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                self.content.len(),
+                self.content
+            ),
+        ];
+
+        // The binary comment marks the file as binary for tools that would otherwise treat it as text.
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{object}\nendobj\n", index + 1).as_bytes());
+        }
+
+        // Every cross-reference entry has to be exactly 20 bytes long, including its trailing space.
+        let xref_offset = pdf.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(xref, "{offset:010} 00000 n ");
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            objects.len() + 1
+        );
+        pdf.extend(xref.as_bytes());
+        pdf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Vec<u8> {
+        let mut page = PdfPage::new(595.0, 842.0);
+        page.text(
+            40.0,
+            800.0,
+            PdfFont::Bold,
+            18.0,
+            0.0,
+            "Report for (launch) – Café 🔥",
+        );
+        page.rect(40.0, 700.0, 100.0, 20.0, 0.9);
+        page.polyline(&[(40.0, 600.0), (80.0, 650.0), (120.0, 620.0)], 1.5, 0.2);
+        page.to_pdf()
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    fn line_at(pdf: &[u8], offset: usize) -> &str {
+        let end = offset + find(&pdf[offset..], b"\n").unwrap();
+        std::str::from_utf8(&pdf[offset..end]).unwrap()
+    }
+
+    #[test]
+    fn startxref_points_at_the_cross_reference_table() {
+        let pdf = document();
+        let trailer = find(&pdf, b"startxref\n").unwrap();
+        let xref_offset = line_at(&pdf, trailer + 10).parse::<usize>().unwrap();
+        assert_eq!(line_at(&pdf, xref_offset), "xref");
+        assert_eq!(find(&pdf, b"xref\n"), Some(xref_offset));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+    }
+
+    #[test]
+    fn cross_reference_offsets_match_the_objects() {
+        let pdf = document();
+        let xref_offset = find(&pdf, b"\nxref\n").unwrap() + 1;
+        let table = std::str::from_utf8(&pdf[xref_offset..]).unwrap();
+        let mut lines = table.split_inclusive('\n').skip(1);
+        let count = lines
+            .next()
+            .unwrap()
+            .trim_end()
+            .strip_prefix("0 ")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert_eq!(count, 7);
+
+        let entries = lines.by_ref().take(count).collect::<Vec<_>>();
+        assert!(entries.iter().all(|entry| entry.len() == 20));
+        assert_eq!(entries[0], "0000000000 65535 f \n");
+        for (number, entry) in entries.iter().enumerate().skip(1) {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert_eq!(&entry[10..], " 00000 n \n");
+            assert_eq!(line_at(&pdf, offset), format!("{number} 0 obj"));
+        }
+        assert!(table.contains(&format!("/Size {count} /Root 1 0 R")));
+    }
+
+    #[test]
+    fn stream_length_matches_its_content() {
+        let pdf = document();
+        let length_start = find(&pdf, b"<< /Length ").unwrap() + 11;
+        let length = line_at(&pdf, length_start)
+            .trim_end_matches(" >>")
+            .parse::<usize>()
+            .unwrap();
+        let stream_start = find(&pdf, b"stream\n").unwrap() + 7;
+        assert_eq!(
+            find(&pdf[stream_start..], b"endstream"),
+            Some(length),
+            "the stream's length is wrong"
+        );
+    }
+
+    #[test]
+    fn text_is_escaped_for_the_standard_fonts() {
+        assert_eq!(escape_text("a (b) \\c"), "a \\(b\\) \\\\c");
+        assert_eq!(escape_text("Café"), "Caf\\351");
+        assert_eq!(escape_text("🔥 – ok"), "? ? ok");
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{embeds::short_url, qr::QrCode, templates::render_template};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+/// The characters that need encoding for an SVG to be used in a `data:` URI.
+const SVG_DATA_URI_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>');
+
+/// The paper sizes posters can be printed on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PosterPaper {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PosterPaper {
+    /// Get the paper size with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "a4" => Some(Self::A4),
+            "letter" => Some(Self::Letter),
+            _ => None,
+        }
+    }
+
+    /// Get the size of the paper as used by the CSS `@page` rule.
+    fn css_size(&self) -> &'static str {
+        match self {
+            Self::A4 => "A4",
+            Self::Letter => "letter",
+        }
+    }
+}
+
+/// Turn an SVG image into a `data:` URI so it can be shown with an `<img>`, which also stops any scripts in it from running.
+fn svg_data_uri(svg: &str) -> String {
+    format!(
+        "data:image/svg+xml,{}",
+        utf8_percent_encode(svg, SVG_DATA_URI_ENCODE_SET)
+    )
+}
+
+/// Render a print-friendly poster for a link as an HTML page, showing a large QR code of its short URL
+/// alongside the short URL itself and the given SVG logo, if any.
+///
+/// Returns [`None`] if the short URL is too long to fit in a QR code.
+pub fn render_poster(
+    host_url: &Url,
+    id: &str,
+    paper: PosterPaper,
+    logo: Option<&str>,
+) -> Option<String> {
+    let short_url = short_url(host_url, id);
+    let qr_code = QrCode::encode(short_url.as_str().as_bytes())?;
+    // The scheme is left out of the printed URL as it's noise for people typing it in.
+    let display_url = short_url
+        .as_str()
+        .trim_start_matches(short_url.scheme())
+        .trim_start_matches("://");
+    Some(render_template(
+        include_str!("../static/poster.html"),
+        &[
+            ("title", short_url.as_str()),
+            ("paper", paper.css_size()),
+            ("qr_code", &svg_data_uri(&qr_code.to_svg())),
+            ("logo", &logo.map(svg_data_uri).unwrap_or_default()),
+            ("short_url", display_url),
+        ],
+    ))
+}
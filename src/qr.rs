@@ -0,0 +1,750 @@
+use crate::archives::crc32;
+use serde::Deserialize;
+
+/// The most data a QR code can hold, as the version (`1` to `40`) which decides its size.
+const MAX_VERSION: usize = 40;
+
+/// The amount of error correction codewords in each block for every version at error correction level M,
+/// indexed by version.
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION + 1] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// The amount of error correction blocks for every version at error correction level M, indexed by version.
+const ECC_BLOCKS: [usize; MAX_VERSION + 1] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// The format bits identifying error correction level M.
+const ECC_LEVEL_M_BITS: u32 = 0;
+
+/// The amount of light modules around a QR code that scanners need to find it.
+pub const QUIET_ZONE: usize = 4;
+
+/// The width and height of each module in pixels when rendered as a PNG.
+const PNG_MODULE_PIXELS: usize = 8;
+
+/// The image formats QR codes can be rendered in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrImageFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl QrImageFormat {
+    /// Get the format with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+
+    /// The file extension of images in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png => "png",
+        }
+    }
+
+    /// The content type of images in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Svg => "image/svg+xml",
+            Self::Png => "image/png",
+        }
+    }
+}
+
+/// A QR code encoding bytes at error correction level M, which can be read with up to 15% of it damaged.
+///
+/// Only what links need is supported, so data is always encoded in byte mode and the smallest
+/// version that fits is used.
+pub struct QrCode {
+    /// The width and height of the code in modules.
+    size: usize,
+    /// Whether or not each module is dark, row by row.
+    modules: Vec<bool>,
+    /// Whether or not each module is part of a function pattern rather than data, row by row.
+    function_modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode the given data as a QR code, or [`None`] if it's too long to fit in one.
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let mut code = Self::encode_unmasked(data)?;
+
+        // Every mask is tried and the one leaving the fewest patterns that confuse scanners is kept.
+        let mask = (0..8)
+            .min_by_key(|mask| {
+                code.apply_mask(*mask);
+                code.draw_format_bits(*mask);
+                let penalty = code.penalty();
+                code.apply_mask(*mask);
+                penalty
+            })
+            .unwrap_or_default();
+        code.apply_mask(mask);
+        code.draw_format_bits(mask);
+        Some(code)
+    }
+
+    /// Lay out the function patterns and codewords of the given data, without masking them yet.
+    fn encode_unmasked(data: &[u8]) -> Option<Self> {
+        let version = (1..=MAX_VERSION).find(|version| {
+            data_bits_needed(*version, data.len()) <= data_codewords(*version) * 8
+        })?;
+
+        let mut code = Self {
+            size: version * 4 + 17,
+            modules: vec![false; (version * 4 + 17).pow(2)],
+            function_modules: vec![false; (version * 4 + 17).pow(2)],
+        };
+        code.draw_function_patterns(version);
+        code.draw_codewords(&add_error_correction(
+            version,
+            &data_codewords_for(version, data),
+        ));
+        Some(code)
+    }
+
+    /// Whether or not the module at the given coordinates is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// Render the code as an SVG image surrounded by its quiet zone, scaling to whatever size it's shown at.
+    pub fn to_svg(&self) -> String {
+        let dimension = self.size + QUIET_ZONE * 2;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in (0..self.size).filter(|x| self.is_dark(*x, y)) {
+                path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+            }
+        }
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {dimension} {dimension}" shape-rendering="crispEdges"><rect width="100%" height="100%" fill="#fff"/><path d="{path}" fill="#000"/></svg>"##
+        )
+    }
+
+    /// Render the code as a black and white PNG image surrounded by its quiet zone.
+    ///
+    /// The image data isn't compressed, which keeps this simple while still being small at this size.
+    pub fn to_png(&self) -> Vec<u8> {
+        let pixels = (self.size + QUIET_ZONE * 2) * PNG_MODULE_PIXELS;
+        let row_length = pixels.div_ceil(8);
+        let mut image = Vec::with_capacity((row_length + 1) * pixels);
+        for pixel_y in 0..pixels {
+            // Every row starts with its filter type, which is always none.
+            image.push(0);
+            let mut row = vec![0u8; row_length];
+            for pixel_x in 0..pixels {
+                let (x, y) = (pixel_x / PNG_MODULE_PIXELS, pixel_y / PNG_MODULE_PIXELS);
+                let modules = QUIET_ZONE..QUIET_ZONE + self.size;
+                let dark = modules.contains(&x)
+                    && modules.contains(&y)
+                    && self.is_dark(x - QUIET_ZONE, y - QUIET_ZONE);
+                // Pixels are one bit of grayscale, where a set bit is white.
+                if !dark {
+                    row[pixel_x / 8] |= 0x80 >> (pixel_x % 8);
+                }
+            }
+            image.extend(row);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((pixels as u32).to_be_bytes());
+        header.extend((pixels as u32).to_be_bytes());
+        header.extend([1, 0, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        png.extend(png_chunk(b"IHDR", &header));
+        png.extend(png_chunk(b"IDAT", &zlib_stored(&image)));
+        png.extend(png_chunk(b"IEND", &[]));
+        png
+    }
+
+    /// Render the code as an image in the given format.
+    pub fn render(&self, format: QrImageFormat) -> Vec<u8> {
+        match format {
+            QrImageFormat::Svg => self.to_svg().into_bytes(),
+            QrImageFormat::Png => self.to_png(),
+        }
+    }
+
+    /// Set a module that is part of a function pattern, which data and masks skip over.
+    fn set_function_module(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function_modules[y * self.size + x] = true;
+    }
+
+    /// Draw the finder, alignment and timing patterns and version information, reserving the format bits.
+    fn draw_function_patterns(&mut self, version: usize) {
+        for i in 0..self.size {
+            self.set_function_module(6, i, i % 2 == 0);
+            self.set_function_module(i, 6, i % 2 == 0);
+        }
+
+        for (x, y) in [(3, 3), (self.size - 4, 3), (3, self.size - 4)] {
+            for dy in -4..=4_isize {
+                for dx in -4..=4_isize {
+                    let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                    else {
+                        continue;
+                    };
+                    if x < self.size && y < self.size {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function_module(x, y, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+
+        let positions = alignment_pattern_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, y) in positions.iter().enumerate() {
+            for (j, x) in positions.iter().enumerate() {
+                // Alignment patterns are skipped where they would overlap a finder pattern.
+                if [(0, 0), (0, last), (last, 0)].contains(&(i, j)) {
+                    continue;
+                }
+                for dy in -2..=2_isize {
+                    for dx in -2..=2_isize {
+                        self.set_function_module(
+                            x.wrapping_add_signed(dx),
+                            y.wrapping_add_signed(dy),
+                            dx.abs().max(dy.abs()) != 1,
+                        );
+                    }
+                }
+            }
+        }
+
+        self.draw_format_bits(0);
+        if version >= 7 {
+            let mut remainder = version as u32;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let bits = (version as u32) << 12 | remainder;
+            for i in 0..18 {
+                let (a, b) = (self.size - 11 + i % 3, i / 3);
+                self.set_function_module(a, b, bit(bits, i));
+                self.set_function_module(b, a, bit(bits, i));
+            }
+        }
+    }
+
+    /// Draw both copies of the format bits describing the error correction level and mask.
+    fn draw_format_bits(&mut self, mask: usize) {
+        let data = ECC_LEVEL_M_BITS << 3 | mask as u32;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+
+        for i in 0..6 {
+            self.set_function_module(8, i, bit(bits, i));
+        }
+        self.set_function_module(8, 7, bit(bits, 6));
+        self.set_function_module(8, 8, bit(bits, 7));
+        self.set_function_module(7, 8, bit(bits, 8));
+        for i in 9..15 {
+            self.set_function_module(14 - i, 8, bit(bits, i));
+        }
+
+        for i in 0..8 {
+            self.set_function_module(self.size - 1 - i, 8, bit(bits, i));
+        }
+        for i in 8..15 {
+            self.set_function_module(8, self.size - 15 + i, bit(bits, i));
+        }
+        self.set_function_module(8, self.size - 8, true);
+    }
+
+    /// Draw the codewords into the data modules in the zigzag order scanners read them in.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let mut index = 0;
+        let mut right = self.size - 1;
+        while right >= 1 {
+            // The vertical timing pattern is skipped entirely.
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0..self.size {
+                for x in [right, right - 1] {
+                    let y = if upward {
+                        self.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function_modules[y * self.size + x] && index < codewords.len() * 8 {
+                        self.modules[y * self.size + x] =
+                            bit(codewords[index >> 3].into(), 7 - (index & 7));
+                        index += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Invert the data modules selected by the given mask, which undoes the mask when applied twice.
+    fn apply_mask(&mut self, mask: usize) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !self.function_modules[y * self.size + x] {
+                    self.modules[y * self.size + x] ^= true;
+                }
+            }
+        }
+    }
+
+    /// Score how hard the code is to scan, penalizing long runs and blocks of one color, patterns that look
+    /// like finder patterns and an imbalance between dark and light modules.
+    fn penalty(&self) -> usize {
+        const FINDER_LIKE: [bool; 11] = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+        let mut penalty = 0;
+        let lines = (0..self.size).flat_map(|i| {
+            [
+                (0..self.size)
+                    .map(|j| self.is_dark(j, i))
+                    .collect::<Vec<_>>(),
+                (0..self.size)
+                    .map(|j| self.is_dark(i, j))
+                    .collect::<Vec<_>>(),
+            ]
+        });
+        for line in lines {
+            let mut run = 1;
+            for j in 1..=line.len() {
+                if j < line.len() && line[j] == line[j - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+                run = 1;
+            }
+            for window in line.windows(FINDER_LIKE.len()) {
+                if window == FINDER_LIKE || window.iter().rev().eq(FINDER_LIKE.iter()) {
+                    penalty += 40;
+                }
+            }
+        }
+
+        for y in 0..self.size - 1 {
+            for x in 0..self.size - 1 {
+                let color = self.is_dark(x, y);
+                if color == self.is_dark(x + 1, y)
+                    && color == self.is_dark(x, y + 1)
+                    && color == self.is_dark(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().filter(|dark| **dark).count();
+        let total = self.modules.len();
+        penalty
+            + (dark * 20)
+                .abs_diff(total * 10)
+                .div_ceil(total)
+                .saturating_sub(1)
+                * 10
+    }
+}
+
+/// Build a PNG chunk of the given type, which is checksummed over its type and data.
+fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend((data.len() as u32).to_be_bytes());
+    chunk.extend(kind);
+    chunk.extend(data);
+    chunk.extend(crc32(&chunk[4..]).to_be_bytes());
+    chunk
+}
+
+/// Wrap data in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    while let Some(block) = blocks.next() {
+        stream.push(u8::from(blocks.peek().is_none()));
+        stream.extend((block.len() as u16).to_le_bytes());
+        stream.extend((!(block.len() as u16)).to_le_bytes());
+        stream.extend(block);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    stream.extend((b << 16 | a).to_be_bytes());
+    stream
+}
+
+/// Whether or not the given bit of a value is set.
+fn bit(value: u32, index: usize) -> bool {
+    (value >> index) & 1 != 0
+}
+
+/// The amount of bits needed to encode data of the given length in byte mode at the given version.
+fn data_bits_needed(version: usize, length: usize) -> usize {
+    let count_bits = if version < 10 { 8 } else { 16 };
+    4 + count_bits + length * 8
+}
+
+/// The amount of modules that hold data or error correction, rather than function patterns.
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignment_patterns = version / 7 + 2;
+        modules -= (25 * alignment_patterns - 10) * alignment_patterns - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+/// The amount of codewords that hold data, rather than error correction, at the given version.
+fn data_codewords(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * ECC_BLOCKS[version]
+}
+
+/// Encode data in byte mode as the data codewords of the given version, padded to fill all of them.
+fn data_codewords_for(version: usize, data: &[u8]) -> Vec<u8> {
+    let capacity = data_codewords(version) * 8;
+    let mut bits = Vec::with_capacity(capacity);
+    let mut push_bits = |value: u32, count: usize| {
+        bits.extend((0..count).rev().map(|index| bit(value, index)));
+    };
+    push_bits(0b0100, 4);
+    push_bits(data.len() as u32, if version < 10 { 8 } else { 16 });
+    for byte in data {
+        push_bits((*byte).into(), 8);
+    }
+    let terminator = (capacity - bits.len()).min(4);
+    bits.extend(std::iter::repeat(false).take(terminator));
+    bits.extend(std::iter::repeat(false).take((8 - bits.len() % 8) % 8));
+
+    let mut codewords = bits
+        .chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .fold(0, |value, bit| value << 1 | u8::from(*bit))
+        })
+        .collect::<Vec<_>>();
+    for padding in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= capacity / 8 {
+            break;
+        }
+        codewords.push(padding);
+    }
+    codewords
+}
+
+/// Split the data codewords into blocks, add Reed-Solomon error correction to each and interleave them.
+fn add_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks_count = ECC_BLOCKS[version];
+    let ecc_length = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks_count - raw_codewords % blocks_count;
+    let short_block_length = raw_codewords / blocks_count;
+    let divisor = reed_solomon_divisor(ecc_length);
+
+    let mut blocks = Vec::with_capacity(blocks_count);
+    let mut offset = 0;
+    for index in 0..blocks_count {
+        let length = short_block_length - ecc_length + usize::from(index >= short_blocks);
+        let mut block = data[offset..offset + length].to_vec();
+        offset += length;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        // Short blocks are padded so every block lines up, with the padding skipped when interleaving.
+        if index < short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        blocks.push(block);
+    }
+
+    let mut codewords = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            if i != short_block_length - ecc_length || j >= short_blocks {
+                codewords.push(block[i]);
+            }
+        }
+    }
+    codewords
+}
+
+/// Compute the generator polynomial for Reed-Solomon error correction of the given degree.
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0; degree];
+    divisor[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+/// Compute the Reed-Solomon error correction codewords of the given data.
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0; divisor.len()];
+    for byte in data {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (value, coefficient) in remainder.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(*coefficient, factor);
+        }
+    }
+    remainder
+}
+
+/// Multiply two elements of the Galois field used by QR codes, GF(2^8) modulo `x^8 + x^4 + x^3 + x^2 + 1`.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut product: u32 = 0;
+    for i in (0..8).rev() {
+        product = (product << 1) ^ ((product >> 7) * 0x11D);
+        product ^= ((u32::from(y) >> i) & 1) * u32::from(x);
+    }
+    product as u8
+}
+
+/// Get the positions of alignment patterns along each axis for the given version.
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let size = version * 4 + 17;
+    let mut positions = (0..count - 1)
+        .map(|i| size - 7 - i * step)
+        .collect::<Vec<_>>();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::{
+        bits::Bits,
+        canvas::{Canvas, MaskPattern},
+        ec::construct_codewords,
+        types::{Color, EcLevel, Version},
+    };
+
+    /// Payloads whose lengths need different versions, including ones with version information (7 and up) and
+    /// a 16 bit length (10 and up).
+    const PAYLOAD_LENGTHS: [usize; 7] = [5, 14, 20, 60, 120, 250, 600];
+
+    const MASKS: [MaskPattern; 8] = [
+        MaskPattern::Checkerboard,
+        MaskPattern::HorizontalLines,
+        MaskPattern::VerticalLines,
+        MaskPattern::DiagonalLines,
+        MaskPattern::LargeCheckerboard,
+        MaskPattern::Fields,
+        MaskPattern::Diamonds,
+        MaskPattern::Meadow,
+    ];
+
+    fn payload(length: usize) -> Vec<u8> {
+        format!("https://example.com/{}", "launch-day/".repeat(length))
+            .into_bytes()
+            .into_iter()
+            .take(length)
+            .collect()
+    }
+
+    fn version(code: &QrCode) -> usize {
+        (code.size - 17) / 4
+    }
+
+    /// Encode the data in byte mode at level M with the `qrcode` crate, without masking it yet.
+    fn reference(data: &[u8], version: usize) -> Canvas {
+        let version = Version::Normal(version as i16);
+        let mut bits = Bits::new(version);
+        bits.push_byte_data(data).unwrap();
+        bits.push_terminator(EcLevel::M).unwrap();
+        let (data, ec) = construct_codewords(&bits.into_bytes(), version, EcLevel::M).unwrap();
+        let mut canvas = Canvas::new(version, EcLevel::M);
+        canvas.draw_all_functional_patterns();
+        canvas.draw_data(&data, &ec);
+        canvas
+    }
+
+    fn modules(code: &QrCode) -> Vec<bool> {
+        (0..code.size)
+            .flat_map(|y| (0..code.size).map(move |x| code.is_dark(x, y)))
+            .collect()
+    }
+
+    fn reference_modules(canvas: Canvas) -> Vec<bool> {
+        canvas
+            .into_colors()
+            .into_iter()
+            .map(|color| color == Color::Dark)
+            .collect()
+    }
+
+    #[test]
+    fn payloads_cover_several_versions() {
+        let versions =
+            PAYLOAD_LENGTHS.map(|length| version(&QrCode::encode(&payload(length)).unwrap()));
+        assert_eq!(versions, [1, 1, 2, 4, 7, 11, 19]);
+    }
+
+    #[test]
+    fn every_mask_matches_the_reference_encoder() {
+        for length in PAYLOAD_LENGTHS {
+            let data = payload(length);
+            let unmasked = QrCode::encode_unmasked(&data).unwrap();
+            for (mask, pattern) in MASKS.into_iter().enumerate() {
+                let mut code = QrCode::encode_unmasked(&data).unwrap();
+                code.apply_mask(mask);
+                code.draw_format_bits(mask);
+
+                let mut canvas = reference(&data, version(&unmasked));
+                canvas.apply_mask(pattern);
+                assert!(
+                    modules(&code) == reference_modules(canvas),
+                    "{length} bytes with mask {mask} don't match"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_codes_match_the_reference_encoder() {
+        // Which mask scores best is up to the encoder, as any of them can be scanned, so the code only has to
+        // match the reference with one of them.
+        for length in PAYLOAD_LENGTHS {
+            let data = payload(length);
+            let code = QrCode::encode(&data).unwrap();
+            assert!(
+                MASKS.into_iter().any(|pattern| {
+                    let mut canvas = reference(&data, version(&code));
+                    canvas.apply_mask(pattern);
+                    modules(&code) == reference_modules(canvas)
+                }),
+                "{length} bytes don't match"
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_data_is_rejected() {
+        assert!(QrCode::encode(&[b'a'; 2331]).is_some());
+        assert!(QrCode::encode(&[b'a'; 2332]).is_none());
+    }
+
+    #[test]
+    fn png_chunks_are_well_formed() {
+        let code = QrCode::encode(b"https://example.com/launch").unwrap();
+        let png = code.to_png();
+        assert_eq!(png[..8], [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            assert_eq!(
+                crc,
+                crc32(&rest[4..8 + length]),
+                "the CRC of {kind:?} is wrong"
+            );
+            chunks.push((kind.to_vec(), data.to_vec()));
+            rest = &rest[12 + length..];
+        }
+        // Every PNG ends with the same IEND chunk, so its CRC is known.
+        assert_eq!(png[png.len() - 4..], [0xAE, 0x42, 0x60, 0x82]);
+        let kinds = chunks
+            .iter()
+            .map(|(kind, _)| kind.as_slice())
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+        let pixels = ((code.size + QUIET_ZONE * 2) * PNG_MODULE_PIXELS) as u32;
+        let header = &chunks[0].1;
+        assert_eq!(header[..4], pixels.to_be_bytes());
+        assert_eq!(header[4..8], pixels.to_be_bytes());
+        // One bit grayscale, with the default compression, filtering and no interlacing.
+        assert_eq!(header[8..], [1, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn png_image_data_is_a_valid_stored_zlib_stream() {
+        let data = vec![7u8; 70_000];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream[..2], [0x78, 0x01]);
+        assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+
+        let mut inflated = Vec::<u8>::new();
+        let mut rest = &stream[2..];
+        loop {
+            let last = rest[0] == 1;
+            let length = u16::from_le_bytes([rest[1], rest[2]]);
+            assert_eq!(u16::from_le_bytes([rest[3], rest[4]]), !length);
+            inflated.extend(&rest[5..5 + length as usize]);
+            rest = &rest[5 + length as usize..];
+            if last {
+                break;
+            }
+        }
+        assert_eq!(inflated, data);
+        // The Adler-32 of 70,000 sevens.
+        let (a, b) = (
+            1 + 7 * 70_000u64,
+            (1..=70_000u64).map(|n| 1 + 7 * n).sum::<u64>(),
+        );
+        assert_eq!(
+            rest,
+            ((((b % 65521) << 16) | (a % 65521)) as u32).to_be_bytes()
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use crate::{
+    analytics::{estimate_views, StatsRange},
+    models::{link::LinkModel, stats::StatsModel},
+    pdf::{PdfFont, PdfPage},
+};
+use chrono::{DateTime, Days};
+use std::collections::BTreeMap;
+use url::Url;
+
+/// Stats keyed by the period they cover, as returned by [`crate::analytics::get_stats`].
+type PeriodStats = BTreeMap<String, StatsModel>;
+
+/// The size of an A4 page, in points.
+const PAGE_SIZE: (f64, f64) = (595.0, 842.0);
+
+/// The space left around the contents of the page, in points.
+const MARGIN: f64 = 56.0;
+
+/// The amount of days of daily views shown in the report.
+const REPORT_DAYS: u64 = 30;
+
+/// The amount of countries listed in the report.
+const TOP_COUNTRIES: usize = 10;
+
+/// The longest a line of text in the report can be before it's cut off, in characters.
+const MAX_LINE_LENGTH: usize = 90;
+
+/// Format a UNIX timestamp in milliseconds with the given `strftime` format, in UTC.
+fn format_timestamp(timestamp: u64, format: &str) -> String {
+    DateTime::from_timestamp_millis(timestamp as i64)
+        .unwrap_or_default()
+        .format(format)
+        .to_string()
+}
+
+/// Cut a line of text off with an ellipsis if it's too long to fit on the page.
+fn fit_line(text: &str) -> String {
+    if text.chars().count() <= MAX_LINE_LENGTH {
+        return text.to_string();
+    }
+    let mut fitted = text.chars().take(MAX_LINE_LENGTH - 3).collect::<String>();
+    fitted.push_str("...");
+    fitted
+}
+
+/// Get the views of each of the last [`REPORT_DAYS`] days up to and including the day of the given
+/// timestamp, oldest first, counting days without stats as having no views.
+fn daily_views(days: &PeriodStats, now: u64) -> Vec<(String, u64)> {
+    let today = DateTime::from_timestamp_millis(now as i64)
+        .unwrap_or_default()
+        .date_naive();
+    (0..REPORT_DAYS)
+        .rev()
+        .filter_map(|days_ago| today.checked_sub_days(Days::new(days_ago)))
+        .map(|date| {
+            let day = date.format("%Y-%m-%d").to_string();
+            let views = days.get(&day).map_or(0, |stats| stats.views);
+            (day, views)
+        })
+        .collect()
+}
+
+/// Get the countries with the most visits across every period, most visited first, alongside the total
+/// visits with a known country.
+fn top_countries(days: &PeriodStats, months: &PeriodStats) -> (Vec<(String, u64)>, u64) {
+    let mut countries = BTreeMap::<&str, u64>::new();
+    for stats in days.values().chain(months.values()) {
+        for (country, views) in &stats.countries {
+            *countries.entry(country).or_default() += views;
+        }
+    }
+    let total = countries.values().sum();
+    let mut countries = countries
+        .into_iter()
+        .map(|(country, views)| (country.to_string(), views))
+        .collect::<Vec<_>>();
+    countries.sort_by(|(a_country, a_views), (b_country, b_views)| {
+        b_views.cmp(a_views).then_with(|| a_country.cmp(b_country))
+    });
+    countries.truncate(TOP_COUNTRIES);
+    (countries, total)
+}
+
+/// Render a one-page PDF report of a link, summarizing its views alongside a sparkline of its daily views
+/// over the last [`REPORT_DAYS`] days and the countries it was visited from the most.
+///
+/// The daily and monthly stats are [`None`] when visit stats aren't recorded, in which case only the summary is shown.
+/// Otherwise they must already be restricted to the given range, which the sparkline ends at when it ends before now.
+pub fn render_report(
+    short_url: &Url,
+    link: &LinkModel,
+    views: u64,
+    stats: Option<(&PeriodStats, &PeriodStats)>,
+    range: StatsRange,
+    sample_rate: f64,
+    now: u64,
+) -> Vec<u8> {
+    let last_day = range.to.map_or(now, |to| to.min(now));
+    let (page_width, page_height) = PAGE_SIZE;
+    let content_width = page_width - MARGIN * 2.0;
+    let mut page = PdfPage::new(page_width, page_height);
+    let mut y = page_height - MARGIN;
+
+    page.text(MARGIN, y, PdfFont::Bold, 22.0, 0.0, "Link report");
+    y -= 26.0;
+    page.text(
+        MARGIN,
+        y,
+        PdfFont::Regular,
+        12.0,
+        0.0,
+        &fit_line(short_url.as_str()),
+    );
+    y -= 16.0;
+    page.text(
+        MARGIN,
+        y,
+        PdfFont::Regular,
+        10.0,
+        0.35,
+        &fit_line(&format!("Redirects to {}", link.url)),
+    );
+    y -= 14.0;
+    page.text(
+        MARGIN,
+        y,
+        PdfFont::Regular,
+        9.0,
+        0.35,
+        &format!("Generated {} UTC", format_timestamp(now, "%Y-%m-%d %H:%M")),
+    );
+
+    // Summary
+    y -= 40.0;
+    page.text(MARGIN, y, PdfFont::Bold, 13.0, 0.0, "Summary");
+    let mut summary = vec![
+        ("Total views".to_string(), views.to_string()),
+        (
+            "Created".to_string(),
+            format_timestamp(link.created_at_timestamp, "%Y-%m-%d"),
+        ),
+        (
+            "Last viewed".to_string(),
+            link.last_viewed_timestamp
+                .map_or("Never".to_string(), |timestamp| {
+                    format_timestamp(timestamp, "%Y-%m-%d")
+                }),
+        ),
+    ];
+    if let Some(expiry_timestamp) = link.expiry_timestamp {
+        summary.push((
+            "Expires".to_string(),
+            format_timestamp(expiry_timestamp, "%Y-%m-%d %H:%M UTC"),
+        ));
+    }
+    let daily = stats.map(|(days, _)| daily_views(days, last_day));
+    if let Some((days, months)) = stats.filter(|_| range.is_bounded()) {
+        let from = format_timestamp(range.from.unwrap_or(link.created_at_timestamp), "%Y-%m-%d");
+        let to = format_timestamp(last_day, "%Y-%m-%d");
+        summary.insert(1, ("Period".to_string(), format!("{from} to {to}")));
+        summary.insert(
+            2,
+            (
+                "Views in period".to_string(),
+                estimate_views(days.values().chain(months.values()), sample_rate).to_string(),
+            ),
+        );
+    } else if let Some(daily) = &daily {
+        summary.insert(
+            1,
+            (
+                format!("Views in the last {REPORT_DAYS} days"),
+                daily
+                    .iter()
+                    .map(|(_, views)| views)
+                    .sum::<u64>()
+                    .to_string(),
+            ),
+        );
+    }
+    if let Some((days, months)) = stats {
+        let conversions = days
+            .values()
+            .chain(months.values())
+            .map(|stats| stats.conversions)
+            .sum::<u64>();
+        if conversions > 0 {
+            // Conversions within a range are compared against the views within it.
+            let views = if range.is_bounded() {
+                estimate_views(days.values().chain(months.values()), sample_rate)
+            } else {
+                views
+            };
+            let rate = conversions as f64 / views.max(1) as f64 * 100.0;
+            summary.push((
+                "Conversions".to_string(),
+                format!("{conversions} ({rate:.1}% of views)"),
+            ));
+        }
+    }
+    for (label, value) in summary {
+        y -= 18.0;
+        page.text(MARGIN, y, PdfFont::Regular, 11.0, 0.35, &label);
+        page.text(MARGIN + 180.0, y, PdfFont::Regular, 11.0, 0.0, &value);
+    }
+
+    let (Some((days, months)), Some(daily)) = (stats, daily) else {
+        y -= 40.0;
+        page.text(
+            MARGIN,
+            y,
+            PdfFont::Regular,
+            10.0,
+            0.35,
+            "Visit stats aren't recorded on this deployment, so only the total views are available.",
+        );
+        return page.to_pdf();
+    };
+
+    // Sparkline of daily views
+    y -= 40.0;
+    page.text(
+        MARGIN,
+        y,
+        PdfFont::Bold,
+        13.0,
+        0.0,
+        &match range.to.filter(|to| *to < now) {
+            Some(_) => format!(
+                "Daily views, {REPORT_DAYS} days to {}",
+                format_timestamp(last_day, "%Y-%m-%d")
+            ),
+            None => format!("Daily views, last {REPORT_DAYS} days"),
+        },
+    );
+    let chart_height = 90.0;
+    y -= 12.0 + chart_height;
+    page.rect(MARGIN, y, content_width, chart_height, 0.95);
+    let peak = daily.iter().map(|(_, views)| *views).max().unwrap_or(0);
+    let step = content_width / (daily.len().max(2) - 1) as f64;
+    let points = daily
+        .iter()
+        .enumerate()
+        .map(|(index, (_, views))| {
+            let height = if peak == 0 {
+                0.0
+            } else {
+                *views as f64 / peak as f64
+            };
+            (
+                MARGIN + index as f64 * step,
+                y + 6.0 + height * (chart_height - 12.0),
+            )
+        })
+        .collect::<Vec<_>>();
+    page.polyline(&points, 1.5, 0.15);
+    if let (Some((first_day, _)), Some((last_day, _))) = (daily.first(), daily.last()) {
+        page.text(MARGIN, y - 12.0, PdfFont::Regular, 8.0, 0.35, first_day);
+        page.text(
+            MARGIN + content_width - 42.0,
+            y - 12.0,
+            PdfFont::Regular,
+            8.0,
+            0.35,
+            last_day,
+        );
+    }
+    if let Some((peak_day, _)) = daily.iter().rev().max_by_key(|(_, views)| *views) {
+        let label = match peak {
+            0 => "No views".to_string(),
+            _ => format!("Peak: {peak} on {peak_day}"),
+        };
+        page.text(
+            MARGIN + content_width / 2.0 - 40.0,
+            y - 12.0,
+            PdfFont::Regular,
+            8.0,
+            0.35,
+            &label,
+        );
+    }
+
+    // Top countries
+    y -= 48.0;
+    page.text(MARGIN, y, PdfFont::Bold, 13.0, 0.0, "Top countries");
+    let (countries, total) = top_countries(days, months);
+    if countries.is_empty() {
+        y -= 18.0;
+        page.text(
+            MARGIN,
+            y,
+            PdfFont::Regular,
+            10.0,
+            0.35,
+            "No visits with a known country yet.",
+        );
+    }
+    let bar_width = content_width - 160.0;
+    let most_views = countries.first().map_or(1, |(_, views)| *views).max(1);
+    for (country, country_views) in &countries {
+        y -= 18.0;
+        page.text(MARGIN, y, PdfFont::Bold, 10.0, 0.0, country);
+        page.rect(
+            MARGIN + 40.0,
+            y - 1.0,
+            bar_width * *country_views as f64 / most_views as f64,
+            9.0,
+            0.55,
+        );
+        page.text(
+            MARGIN + 50.0 + bar_width,
+            y,
+            PdfFont::Regular,
+            10.0,
+            0.0,
+            &format!(
+                "{country_views} ({:.1}%)",
+                *country_views as f64 / total.max(1) as f64 * 100.0
+            ),
+        );
+    }
+
+    if sample_rate < 1.0 {
+        page.text(
+            MARGIN,
+            MARGIN,
+            PdfFont::Regular,
+            8.0,
+            0.35,
+            &format!(
+                "Daily views and countries are based on a {:.0}% sample of visits.",
+                sample_rate * 100.0
+            ),
+        );
+    }
+
+    page.to_pdf()
+}
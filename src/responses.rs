@@ -0,0 +1,106 @@
+use crate::environment::get_bool_var;
+use std::collections::BTreeMap;
+use url::Url;
+use worker::{Env, Request, Response};
+
+/// The binding name for the variable controlling whether pages shown before a destination hint the browser to preconnect to it.
+pub const PRECONNECT_HINTS_BINDING: &str = "PRECONNECT_HINTS";
+
+/// User-Agent prefixes of command-line HTTP clients, which are given plain text instead of HTML.
+const CLI_USER_AGENT_PREFIXES: &[&str] = &["curl/", "wget/", "httpie/", "xh/"];
+
+/// The format a human-readable response should be given in for the client that made a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    /// Concise plain text, for command-line clients and anything that doesn't ask for HTML.
+    Text,
+    /// A full HTML page, for browsers.
+    Html,
+}
+
+impl ResponseFormat {
+    /// Pick the format to respond to a request with.
+    ///
+    /// Command-line clients always get plain text as some send `Accept: */*`, otherwise HTML is
+    /// only used when the client explicitly accepts it.
+    pub fn negotiate(req: &Request) -> Self {
+        let header = |name: &str| req.headers().get(name).ok().flatten().unwrap_or_default();
+
+        let user_agent = header("User-Agent").to_ascii_lowercase();
+        if CLI_USER_AGENT_PREFIXES
+            .iter()
+            .any(|prefix| user_agent.starts_with(prefix))
+        {
+            return Self::Text;
+        }
+
+        if header("Accept").contains("text/html") {
+            Self::Html
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// Build a plain text response, ending with a newline so it displays cleanly in a terminal.
+pub fn text_response(text: &str, status: u16) -> worker::Result<Response> {
+    Ok(Response::ok(format!("{text}\n"))?.with_status(status))
+}
+
+/// Headers links can attach to their redirect responses.
+///
+/// Headers that could change how the redirect itself behaves (e.g. `Location` or `Set-Cookie`) aren't allowed.
+const ALLOWED_RESPONSE_HEADERS: &[&str] =
+    &["link", "onion-location", "referrer-policy", "x-robots-tag"];
+
+/// The prefix of custom headers links can attach to their redirect responses, e.g. for tracking.
+const CUSTOM_RESPONSE_HEADER_PREFIX: &str = "x-";
+
+/// Whether or not links can attach the given header and value to their redirect responses.
+pub fn is_allowed_response_header(name: &str, value: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let allowed_name = ALLOWED_RESPONSE_HEADERS.contains(&name.as_str())
+        || (name.len() > CUSTOM_RESPONSE_HEADER_PREFIX.len()
+            && name.starts_with(CUSTOM_RESPONSE_HEADER_PREFIX)
+            && name
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '-'));
+    allowed_name && !value.chars().any(|char| char.is_ascii_control())
+}
+
+/// Attach a link's custom headers to a response, adding to any `Link` header already set.
+pub fn apply_response_headers(
+    response: &mut Response,
+    headers: &BTreeMap<String, String>,
+) -> worker::Result<()> {
+    let response_headers = response.headers_mut();
+    for (name, value) in headers {
+        if name.eq_ignore_ascii_case("link") {
+            response_headers.append(name, value)?;
+        } else {
+            response_headers.set(name, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Hint the browser to connect to a destination's origin while it's shown a page leading to it, if enabled.
+///
+/// Cloudflare also turns the `Link` header into a `103 Early Hints` response on zones with Early Hints enabled.
+pub fn apply_preconnect_hint(
+    env: &Env,
+    response: &mut Response,
+    destination: &Url,
+) -> worker::Result<()> {
+    if !get_bool_var(env, PRECONNECT_HINTS_BINDING).unwrap_or(false) {
+        return Ok(());
+    }
+    let origin = destination.origin();
+    if !origin.is_tuple() {
+        return Ok(());
+    }
+    response.headers_mut().append(
+        "Link",
+        &format!("<{}>; rel=preconnect", origin.ascii_serialization()),
+    )
+}
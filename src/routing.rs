@@ -0,0 +1,338 @@
+use crate::{
+    models::link::{LinkModel, LinkVariant, GEO_TARGET_DEFAULT},
+    slugs::stable_hash,
+};
+use url::Url;
+use worker::{js_sys::Math, Request};
+
+/// The details of a visitor that decide which of a link's destinations they're sent to.
+#[derive(Debug, Default)]
+pub struct Visitor {
+    /// The Accept-Language header sent by the visitor.
+    pub accept_language: Option<String>,
+    /// The ISO 3166-1 Alpha 2 country code the visitor comes from, if known.
+    pub country: Option<String>,
+    /// A stable identifier for the visitor, so they're consistently placed on the same side of a rollout
+    /// and given the same sticky variant and status code.
+    pub fingerprint: String,
+}
+
+impl Visitor {
+    /// Get the details of the visitor that made a request.
+    pub fn from_request(req: &Request) -> Self {
+        let headers = req.headers();
+        Self {
+            accept_language: headers.get("Accept-Language").ok().flatten(),
+            // Cloudflare uses `XX` for visitors it couldn't place in a country.
+            country: req
+                .cf()
+                .and_then(|cf| cf.country())
+                .filter(|country| country != UNKNOWN_COUNTRY_CODE),
+            fingerprint: visitor_fingerprint(
+                headers.get("CF-Connecting-IP").ok().flatten().as_deref(),
+                headers.get("User-Agent").ok().flatten().as_deref(),
+            ),
+        }
+    }
+}
+
+/// Build a visitor's fingerprint from their IP address and user agent.
+///
+/// Nothing identifying is stored, the fingerprint is only hashed to pick which side of a rollout the visitor is on
+/// and which sticky variant they're given.
+pub fn visitor_fingerprint(address: Option<&str>, user_agent: Option<&str>) -> String {
+    format!(
+        "{}|{}",
+        address.unwrap_or_default(),
+        user_agent.unwrap_or_default()
+    )
+}
+
+/// The rule that sent a visitor somewhere other than a link's default destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestinationRule {
+    /// The geo target for the visitor's country was used, or the default geo target if their country has none.
+    Geo(String),
+    /// A language rule matched one of the visitor's preferred languages.
+    Language(String),
+    /// The visitor isn't part of the percentage of visitors the destination is being rolled out to.
+    Rollout(u8),
+    /// The visitor was assigned the variant at this index of the link's variants.
+    Variant(usize),
+}
+
+/// Where a visitor is sent when visiting a link.
+#[derive(Debug)]
+pub struct Destination {
+    pub url: Url,
+    /// The rule that picked the destination, or [`None`] if it's the link's default destination.
+    pub rule: Option<DestinationRule>,
+}
+
+impl Destination {
+    /// Get the index of the variant the visitor was assigned, if they were sent to one.
+    pub fn variant(&self) -> Option<usize> {
+        match self.rule {
+            Some(DestinationRule::Variant(index)) => Some(index),
+            _ => None,
+        }
+    }
+}
+
+/// The country code Cloudflare gives visitors it couldn't place in a country.
+const UNKNOWN_COUNTRY_CODE: &str = "XX";
+
+/// Whether or not a country code can be used in a geo target, which is two letters or digits to also allow
+/// Cloudflare's special codes such as `T1` for Tor.
+pub fn is_valid_country_code(code: &str) -> bool {
+    code.len() == 2
+        && code.chars().all(|char| char.is_ascii_alphanumeric())
+        && !code.eq_ignore_ascii_case(UNKNOWN_COUNTRY_CODE)
+}
+
+/// Find the geo target for the visitor's country, or the link's default geo target if their country has none or is
+/// unknown.
+fn find_geo_target<'a>(link: &'a LinkModel, visitor: &Visitor) -> Option<(&'a str, &'a Url)> {
+    visitor
+        .country
+        .as_deref()
+        .and_then(|country| {
+            link.geo_targets
+                .get_key_value(country.to_ascii_uppercase().as_str())
+        })
+        .or_else(|| link.geo_targets.get_key_value(GEO_TARGET_DEFAULT))
+        .map(|(key, url)| (key.as_str(), url))
+}
+
+/// The longest a language tag can be, per RFC 5646's recommended buffer size.
+const MAX_LANGUAGE_TAG_LENGTH: usize = 35;
+
+/// Whether or not a language tag is well-formed enough to be used in a language rule, e.g. `de` or `pt-BR`.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    tag.len() <= MAX_LANGUAGE_TAG_LENGTH
+        && tag.split('-').all(|subtag| {
+            !subtag.is_empty() && subtag.chars().all(|char| char.is_ascii_alphanumeric())
+        })
+}
+
+/// Parse an Accept-Language header into its lowercased language tags, most preferred first.
+///
+/// Wildcards and languages the client has explicitly refused (`q=0`) are left out.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut languages = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|parameter| parameter.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.trim().parse::<f32>().ok())?;
+            (tag != "*" && quality > 0.0 && is_valid_language_tag(&tag)).then_some((tag, quality))
+        })
+        .collect::<Vec<_>>();
+    // The sort is stable so languages with the same quality keep the order the client sent them in.
+    languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    languages.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Find the language rule matching the visitor's most preferred language that has one.
+///
+/// Each language matches a rule for itself first and then a rule for its primary language,
+/// e.g. `de-AT` matches a `de-at` rule or failing that a `de` rule.
+fn find_language_rule<'a>(link: &'a LinkModel, visitor: &Visitor) -> Option<(&'a str, &'a Url)> {
+    if link.language_rules.is_empty() {
+        return None;
+    }
+    parse_accept_language(visitor.accept_language.as_deref()?)
+        .iter()
+        .find_map(|language| {
+            let primary = language.split('-').next().unwrap_or(language);
+            [language.as_str(), primary]
+                .into_iter()
+                .find_map(|candidate| {
+                    link.language_rules
+                        .get_key_value(candidate)
+                        .map(|(tag, url)| (tag.as_str(), url))
+                })
+        })
+}
+
+/// Whether or not a visitor is part of the percentage of visitors a link's destination is being rolled out to.
+///
+/// Each visitor is placed in one of 100 buckets by hashing their fingerprint with when the link was created,
+/// so raising the percentage only ever adds visitors to the rollout and different links bucket visitors differently.
+fn is_in_rollout(link: &LinkModel, visitor: &Visitor, rollout_percent: u8) -> bool {
+    let bucket = stable_hash(&format!(
+        "{}:{}",
+        link.created_at_timestamp, visitor.fingerprint
+    )) % 100;
+    bucket < u64::from(rollout_percent)
+}
+
+/// Pick which of a link's variants to send a visitor to, weighted by each variant's share of visitors.
+///
+/// Sticky variants are picked by hashing the visitor's fingerprint with when the link was created,
+/// so a returning visitor gets the same variant as long as the variants don't change.
+fn pick_variant<'a>(link: &'a LinkModel, visitor: &Visitor) -> Option<(usize, &'a LinkVariant)> {
+    let total_weight = link
+        .variants
+        .iter()
+        .map(|variant| u64::from(variant.weight))
+        .sum::<u64>();
+    if total_weight == 0 {
+        return None;
+    }
+    let mut point = if link.sticky_variants {
+        stable_hash(&format!(
+            "variant:{}:{}",
+            link.created_at_timestamp, visitor.fingerprint
+        )) % total_weight
+    } else {
+        (Math::random() * total_weight as f64) as u64 % total_weight
+    };
+    link.variants.iter().enumerate().find(|(_, variant)| {
+        let weight = u64::from(variant.weight);
+        if point < weight {
+            return true;
+        }
+        point -= weight;
+        false
+    })
+}
+
+/// The HTTP status codes links can redirect visitors with.
+pub const REDIRECT_STATUS_CODES: [u16; 5] = [301, 302, 303, 307, 308];
+
+/// The HTTP status code visitors are redirected with if a link doesn't specify any.
+const DEFAULT_STATUS_CODE: u16 = 302;
+
+/// Pick the HTTP status code to redirect a visitor of a link with.
+///
+/// Links with several status codes split visitors between them by hashing the visitor's fingerprint with
+/// when the link was created, so crawlers revisiting a link are consistently served the same status code.
+pub fn pick_status_code(link: &LinkModel, visitor: &Visitor) -> u16 {
+    match link.status_codes.as_slice() {
+        [] => DEFAULT_STATUS_CODE,
+        [status_code] => *status_code,
+        status_codes => {
+            let index = stable_hash(&format!(
+                "status:{}:{}",
+                link.created_at_timestamp, visitor.fingerprint
+            )) % status_codes.len() as u64;
+            status_codes[index as usize]
+        }
+    }
+}
+
+/// Pick where to send a visitor of a link, falling back to its default destination if no rule matches.
+///
+/// Visitors left out of a rollout are sent to the link's fallback URL before any other rule is considered, and geo
+/// targets are considered before language rules and variants.
+pub fn resolve_destination(link: &LinkModel, visitor: &Visitor) -> Destination {
+    if let (Some(rollout_percent), Some(fallback_url)) = (link.rollout_percent, &link.fallback_url)
+    {
+        if !is_in_rollout(link, visitor, rollout_percent) {
+            return Destination {
+                url: fallback_url.clone(),
+                rule: Some(DestinationRule::Rollout(rollout_percent)),
+            };
+        }
+    }
+
+    if let Some((key, url)) = find_geo_target(link, visitor) {
+        return Destination {
+            url: url.clone(),
+            rule: Some(DestinationRule::Geo(key.to_string())),
+        };
+    }
+
+    match find_language_rule(link, visitor) {
+        Some((tag, url)) => Destination {
+            url: url.clone(),
+            rule: Some(DestinationRule::Language(tag.to_string())),
+        },
+        None => match pick_variant(link, visitor) {
+            Some((index, variant)) => Destination {
+                url: variant.url.clone(),
+                rule: Some(DestinationRule::Variant(index)),
+            },
+            None => Destination {
+                url: link.url.clone(),
+                rule: None,
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn link_with_language_rules(rules: &[&str]) -> LinkModel {
+        let language_rules = rules
+            .iter()
+            .map(|tag| (tag.to_string(), format!("https://example.com/{tag}")))
+            .collect::<BTreeMap<_, _>>();
+        serde_json::from_value(json!({
+            "url": "https://example.com",
+            "disabled": false,
+            "views": 0,
+            "max_views": null,
+            "expiry_timestamp": null,
+            "created_at_timestamp": 0,
+            "modified_at_timestamp": 0,
+            "language_rules": language_rules,
+        }))
+        .unwrap()
+    }
+
+    fn visitor(accept_language: &str) -> Visitor {
+        Visitor {
+            accept_language: Some(accept_language.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accept_language_is_ordered_by_quality() {
+        assert_eq!(
+            parse_accept_language("fr;q=0.5, de-AT, en;q=0.8"),
+            ["de-at", "en", "fr"]
+        );
+        // Languages with the same quality keep the order they were sent in.
+        assert_eq!(
+            parse_accept_language("nl, en;q=0.9, de"),
+            ["nl", "de", "en"]
+        );
+        assert_eq!(parse_accept_language("en; q=0.7 , fr"), ["fr", "en"]);
+    }
+
+    #[test]
+    fn accept_language_excludes_unwanted_languages() {
+        assert_eq!(parse_accept_language("de, en;q=0, fr;q=0.0"), ["de"]);
+        assert_eq!(parse_accept_language("*, de;q=0.5"), ["de"]);
+        assert_eq!(parse_accept_language("*;q=0.9"), Vec::<String>::new());
+        assert_eq!(parse_accept_language("de;q=high, en"), ["en"]);
+        assert_eq!(parse_accept_language(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn language_rules_fall_back_to_the_primary_language() {
+        let link = link_with_language_rules(&["de", "pt-br"]);
+        let rule = |accept_language| {
+            find_language_rule(&link, &visitor(accept_language)).map(|(tag, _)| tag)
+        };
+
+        assert_eq!(rule("de-AT"), Some("de"));
+        assert_eq!(rule("pt-BR"), Some("pt-br"));
+        assert_eq!(rule("pt-PT"), None);
+        assert_eq!(rule("fr, de-CH;q=0.5"), Some("de"));
+        assert_eq!(rule("fr, en"), None);
+        assert_eq!(
+            find_language_rule(&link, &Visitor::default()).map(|(tag, _)| tag),
+            None
+        );
+    }
+}
@@ -0,0 +1,584 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use wasm_bindgen::{JsCast, JsValue};
+use worker::js_sys::{global, Array, Date, Intl::DateTimeFormat, Object, Reflect};
+
+/// A time of day with minute precision, written as `HH:MM` in 24-hour time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    /// The amount of minutes since midnight.
+    minutes: u16,
+}
+
+impl FromStr for TimeOfDay {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some((hours, minutes)) = value.trim().split_once(':') else {
+            return Err("Times of day must be written as HH:MM, e.g. 09:30");
+        };
+        let hours = hours
+            .parse::<u16>()
+            .map_err(|_| "Time of day has an invalid hour")?;
+        let minutes = minutes
+            .parse::<u16>()
+            .map_err(|_| "Time of day has an invalid minute")?;
+        if hours > 23 || minutes > 59 {
+            return Err("Time of day is out of range");
+        }
+        Ok(Self {
+            minutes: hours * 60 + minutes,
+        })
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.minutes / 60, self.minutes % 60)
+    }
+}
+
+/// A day of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        match weekday {
+            chrono::Weekday::Mon => Self::Mon,
+            chrono::Weekday::Tue => Self::Tue,
+            chrono::Weekday::Wed => Self::Wed,
+            chrono::Weekday::Thu => Self::Thu,
+            chrono::Weekday::Fri => Self::Fri,
+            chrono::Weekday::Sat => Self::Sat,
+            chrono::Weekday::Sun => Self::Sun,
+        }
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = &'static str;
+
+    /// Parse a two-letter RRULE weekday, e.g. `SA`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "MO" => Ok(Self::Mon),
+            "TU" => Ok(Self::Tue),
+            "WE" => Ok(Self::Wed),
+            "TH" => Ok(Self::Thu),
+            "FR" => Ok(Self::Fri),
+            "SA" => Ok(Self::Sat),
+            "SU" => Ok(Self::Sun),
+            _ => Err("Recurrence rule has an invalid weekday"),
+        }
+    }
+}
+
+impl Weekday {
+    /// Get the two-letter RRULE name of this weekday.
+    fn rrule_name(self) -> &'static str {
+        match self {
+            Self::Mon => "MO",
+            Self::Tue => "TU",
+            Self::Wed => "WE",
+            Self::Thu => "TH",
+            Self::Fri => "FR",
+            Self::Sat => "SA",
+            Self::Sun => "SU",
+        }
+    }
+}
+
+/// How often a recurrence rule repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// The days a link is served on, written as a subset of an iCalendar RRULE, e.g. `FREQ=WEEKLY;BYDAY=SA`.
+///
+/// Only `FREQ` (`DAILY`, `WEEKLY` or `MONTHLY`), `INTERVAL`, `BYDAY` and `BYMONTHDAY` are supported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    frequency: Frequency,
+    /// How many periods pass between each repetition.
+    interval: u32,
+    /// The weekdays the rule matches, or any weekday if empty.
+    by_day: Vec<Weekday>,
+    /// The days of the month the rule matches, or any day if empty.
+    by_month_day: Vec<u32>,
+}
+
+impl Recurrence {
+    /// Whether or not this rule includes the given local date, counting repetitions from the date it started on.
+    ///
+    /// Weekly rules without `BYDAY` repeat on the weekday they started on, and monthly rules without
+    /// `BYMONTHDAY` or `BYDAY` repeat on the day of the month they started on.
+    pub fn occurs_on(&self, date: NaiveDate, start: NaiveDate) -> bool {
+        if date < start {
+            return false;
+        }
+        let interval = i64::from(self.interval);
+        let weekday = Weekday::from(date.weekday());
+        let periods = match self.frequency {
+            Frequency::Daily => (date - start).num_days(),
+            Frequency::Weekly => {
+                let week_start = |date: NaiveDate| {
+                    date - chrono::Duration::days(i64::from(date.weekday().num_days_from_monday()))
+                };
+                (week_start(date) - week_start(start)).num_weeks()
+            }
+            Frequency::Monthly => {
+                i64::from(date.year() - start.year()) * 12 + i64::from(date.month())
+                    - i64::from(start.month())
+            }
+        };
+        if periods % interval != 0 {
+            return false;
+        }
+
+        let matches_day = self.by_day.is_empty() || self.by_day.contains(&weekday);
+        let matches_month_day =
+            self.by_month_day.is_empty() || self.by_month_day.contains(&date.day());
+        match self.frequency {
+            Frequency::Weekly if self.by_day.is_empty() => weekday == start.weekday().into(),
+            Frequency::Monthly if self.by_day.is_empty() && self.by_month_day.is_empty() => {
+                date.day() == start.day()
+            }
+            _ => matches_day && matches_month_day,
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let value = value.strip_prefix("RRULE:").unwrap_or(value);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut by_month_day = Vec::new();
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err("Recurrence rule parts must be written as KEY=VALUE");
+            };
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err("Recurrence rule has an unsupported frequency"),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or("Recurrence rule has an invalid interval")?
+                }
+                "BYDAY" => by_day = value.split(',').map(str::parse).collect::<Result<_, _>>()?,
+                "BYMONTHDAY" => {
+                    by_month_day = value
+                        .split(',')
+                        .map(|day| {
+                            day.trim()
+                                .parse::<u32>()
+                                .ok()
+                                .filter(|day| (1..=31).contains(day))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or("Recurrence rule has an invalid day of the month")?
+                }
+                _ => return Err("Recurrence rule has an unsupported part"),
+            }
+        }
+
+        Ok(Self {
+            frequency: frequency.ok_or("Recurrence rule is missing its frequency")?,
+            interval,
+            by_day,
+            by_month_day,
+        })
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={frequency}")?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .map(|day| day.rrule_name())
+                .collect::<Vec<_>>();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days = self
+                .by_month_day
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// The hours of the day a link is served during, in the link's time zone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveHours {
+    /// The days of the week the link is served on, or every day if empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// The time of day the link starts being served.
+    pub start: TimeOfDay,
+    /// The time of day the link stops being served, which can be before the start to span midnight or the same as
+    /// the start to serve it all day.
+    pub end: TimeOfDay,
+}
+
+impl ActiveHours {
+    /// Whether or not a link with these active hours is served at the given local time.
+    ///
+    /// Windows spanning midnight are matched against the day the visit happens on, not the day the window started,
+    /// and a window that ends when it starts covers the whole day.
+    pub fn contains(&self, local: NaiveDateTime) -> bool {
+        if !self.days.is_empty() && !self.days.contains(&local.weekday().into()) {
+            return false;
+        }
+        let time = TimeOfDay {
+            minutes: (local.hour() * 60 + local.minute()) as u16,
+        };
+        if self.start == self.end {
+            true
+        } else if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// The format local date-times are written in, e.g. `2025-03-01T09:00`.
+const LOCAL_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Parse a local date-time written as `YYYY-MM-DDTHH:MM`, optionally with seconds.
+pub fn parse_local_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, LOCAL_DATE_TIME_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, &format!("{LOCAL_DATE_TIME_FORMAT}:%S")))
+        .ok()
+}
+
+/// Build a formatter that breaks UNIX timestamps down into the local time of the given IANA time zone.
+///
+/// Time zones are looked up in the runtime's own time zone database, which follows daylight saving time.
+/// Returns [`None`] if the time zone isn't known.
+fn local_time_formatter(timezone: &str) -> Option<DateTimeFormat> {
+    let options = Object::new();
+    for (key, value) in [
+        ("timeZone", timezone),
+        ("hourCycle", "h23"),
+        ("year", "numeric"),
+        ("month", "numeric"),
+        ("day", "numeric"),
+        ("hour", "numeric"),
+        ("minute", "numeric"),
+        ("second", "numeric"),
+    ] {
+        Reflect::set(&options, &key.into(), &value.into()).ok()?;
+    }
+
+    // The constructor is called through Reflect so an unknown time zone is returned as an error instead of aborting.
+    let intl = Reflect::get(&global(), &"Intl".into()).ok()?;
+    let constructor = Reflect::get(&intl, &"DateTimeFormat".into()).ok()?;
+    Reflect::construct(
+        constructor.unchecked_ref(),
+        &Array::of2(&Array::of1(&"en-US".into()), &options),
+    )
+    .ok()
+    .map(JsCast::unchecked_into)
+}
+
+/// Whether or not the given IANA time zone (e.g. `Europe/Berlin`) is known.
+pub fn is_valid_timezone(timezone: &str) -> bool {
+    local_time_formatter(timezone).is_some()
+}
+
+/// Get the local date-time of a UNIX timestamp in the given IANA time zone, or in UTC if no time zone is given.
+pub fn local_date_time(timestamp: u64, timezone: Option<&str>) -> Option<NaiveDateTime> {
+    let Some(timezone) = timezone else {
+        return DateTime::from_timestamp_millis(timestamp as i64).map(|time| time.naive_utc());
+    };
+
+    let parts = local_time_formatter(timezone)?
+        .format_to_parts(&Date::new(&JsValue::from_f64(timestamp as f64)));
+    let part = |name: &str| {
+        parts.iter().find_map(|part| {
+            let kind = Reflect::get(&part, &"type".into()).ok()?.as_string()?;
+            if kind != name {
+                return None;
+            }
+            Reflect::get(&part, &"value".into())
+                .ok()?
+                .as_string()?
+                .parse::<u32>()
+                .ok()
+        })
+    };
+    chrono::NaiveDate::from_ymd_opt(part("year")? as i32, part("month")?, part("day")?)?
+        .and_hms_opt(part("hour")?, part("minute")?, part("second")?)
+}
+
+/// Get the UNIX timestamp of a local date-time in the given IANA time zone, or in UTC if no time zone is given.
+///
+/// Local times skipped by daylight saving time resolve to the equivalent time after the clocks change.
+pub fn local_date_time_to_timestamp(local: NaiveDateTime, timezone: Option<&str>) -> Option<u64> {
+    let local_millis = local.and_utc().timestamp_millis();
+    // The offset is checked twice as the offset at the local time read as UTC can differ from the one at the result.
+    let mut timestamp = local_millis;
+    for _ in 0..2 {
+        let offset = local_date_time(u64::try_from(timestamp).ok()?, timezone)?
+            .and_utc()
+            .timestamp_millis()
+            - timestamp;
+        timestamp = local_millis - offset;
+    }
+    u64::try_from(timestamp).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn rule(value: &str) -> Recurrence {
+        value.parse().unwrap()
+    }
+
+    fn hours(start: &str, end: &str, days: &[Weekday]) -> ActiveHours {
+        ActiveHours {
+            days: days.to_vec(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn every_other_week_across_year_boundaries() {
+        let every_other_week = rule("FREQ=WEEKLY;INTERVAL=2");
+        let every_other_weekend = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=SA,SU");
+        for (start, day, weekly, weekend) in [
+            ("2024-12-28", "2024-12-28", true, true),
+            ("2024-12-28", "2024-12-29", false, true),
+            ("2024-12-28", "2025-01-04", false, false),
+            ("2024-12-28", "2025-01-05", false, false),
+            ("2024-12-28", "2025-01-11", true, true),
+            ("2024-12-28", "2025-01-12", false, true),
+            ("2024-12-28", "2025-01-13", false, false),
+            // 2020 has 53 ISO weeks, which doesn't shift which weeks are included.
+            ("2020-12-26", "2021-01-09", true, true),
+            ("2020-12-26", "2021-01-02", false, false),
+            ("2020-12-26", "2021-01-10", false, true),
+            ("2024-12-28", "2024-12-21", false, false),
+        ] {
+            assert_eq!(
+                every_other_week.occurs_on(date(day), date(start)),
+                weekly,
+                "{day} from {start} every other week"
+            );
+            assert_eq!(
+                every_other_weekend.occurs_on(date(day), date(start)),
+                weekend,
+                "{day} from {start} every other weekend"
+            );
+        }
+    }
+
+    #[test]
+    fn month_days_missing_from_short_months_are_skipped() {
+        let last_day = rule("FREQ=MONTHLY;BYMONTHDAY=31");
+        let start = date("2025-01-15");
+        for (day, expected) in [
+            ("2025-01-31", true),
+            ("2025-02-28", false),
+            ("2025-03-01", false),
+            ("2025-03-31", true),
+            ("2025-04-30", false),
+            ("2025-05-01", false),
+            ("2025-12-31", true),
+            ("2026-01-31", true),
+        ] {
+            assert_eq!(last_day.occurs_on(date(day), start), expected, "{day}");
+        }
+
+        // Without BYMONTHDAY a rule started on the 31st repeats on the 31st too.
+        let monthly = rule("FREQ=MONTHLY");
+        assert!(monthly.occurs_on(date("2025-03-31"), date("2025-01-31")));
+        assert!(!monthly.occurs_on(date("2025-02-28"), date("2025-01-31")));
+        assert!(!monthly.occurs_on(date("2025-04-30"), date("2025-01-31")));
+    }
+
+    #[test]
+    fn monthly_intervals_across_year_boundaries() {
+        let quarterly = rule("FREQ=MONTHLY;INTERVAL=3;BYMONTHDAY=1,15");
+        let start = date("2024-11-01");
+        for (day, expected) in [
+            ("2024-11-15", true),
+            ("2024-12-01", false),
+            ("2025-02-01", true),
+            ("2025-02-15", true),
+            ("2025-02-16", false),
+            ("2025-05-01", true),
+            ("2025-04-01", false),
+        ] {
+            assert_eq!(quarterly.occurs_on(date(day), start), expected, "{day}");
+        }
+    }
+
+    #[test]
+    fn daily_intervals() {
+        let every_third_day = rule("FREQ=DAILY;INTERVAL=3");
+        let start = date("2024-12-30");
+        assert!(every_third_day.occurs_on(date("2024-12-30"), start));
+        assert!(!every_third_day.occurs_on(date("2024-12-31"), start));
+        assert!(every_third_day.occurs_on(date("2025-01-02"), start));
+        assert!(every_third_day.occurs_on(date("2025-02-28"), start));
+    }
+
+    #[test]
+    fn unsupported_rules_are_rejected() {
+        for value in [
+            "FREQ=WEEKLY;COUNT=10",
+            "FREQ=DAILY;UNTIL=20250101T000000Z",
+            "FREQ=YEARLY",
+            "FREQ=WEEKLY;INTERVAL=0",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "INTERVAL=2",
+            "FREQ",
+        ] {
+            assert!(value.parse::<Recurrence>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        for value in [
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=SA,SU",
+            "FREQ=MONTHLY;BYMONTHDAY=1,31",
+        ] {
+            assert_eq!(rule(value).to_string(), value);
+        }
+        assert_eq!(
+            rule("RRULE:freq=weekly;byday=mo").to_string(),
+            "FREQ=WEEKLY;BYDAY=MO"
+        );
+    }
+
+    #[test]
+    fn active_hours_windows() {
+        let office = hours("09:00", "17:00", &[]);
+        let overnight = hours("22:00", "02:00", &[]);
+        let all_day = hours("08:00", "08:00", &[]);
+        for (time, in_office, in_overnight) in [
+            ("2025-01-06T08:59", false, false),
+            ("2025-01-06T09:00", true, false),
+            ("2025-01-06T16:59", true, false),
+            ("2025-01-06T17:00", false, false),
+            ("2025-01-06T21:59", false, false),
+            ("2025-01-06T22:00", false, true),
+            ("2025-01-06T23:59", false, true),
+            ("2025-01-07T00:00", false, true),
+            ("2025-01-07T01:59", false, true),
+            ("2025-01-07T02:00", false, false),
+        ] {
+            let local = parse_local_date_time(time).unwrap();
+            assert_eq!(
+                office.contains(local),
+                in_office,
+                "{time} during office hours"
+            );
+            assert_eq!(overnight.contains(local), in_overnight, "{time} overnight");
+            assert!(all_day.contains(local), "{time} all day");
+        }
+    }
+
+    #[test]
+    fn overnight_windows_match_the_day_of_the_visit() {
+        let friday_night = hours("22:00", "02:00", &[Weekday::Fri]);
+        // 2025-01-10 is a Friday.
+        assert!(friday_night.contains(parse_local_date_time("2025-01-10T23:00").unwrap()));
+        assert!(friday_night.contains(parse_local_date_time("2025-01-10T01:00").unwrap()));
+        assert!(!friday_night.contains(parse_local_date_time("2025-01-11T01:00").unwrap()));
+        assert!(!friday_night.contains(parse_local_date_time("2025-01-09T23:00").unwrap()));
+    }
+
+    #[test]
+    fn times_of_day_are_validated() {
+        assert_eq!("09:05".parse::<TimeOfDay>().unwrap().to_string(), "09:05");
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("12:60".parse::<TimeOfDay>().is_err());
+        assert!("noon".parse::<TimeOfDay>().is_err());
+    }
+}
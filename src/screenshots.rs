@@ -0,0 +1,111 @@
+use crate::environment::{environment_key_prefix, get_var};
+use serde_json::json;
+use url::Url;
+use wasm_bindgen::JsValue;
+use worker::{Env, Fetch, Headers, HttpMetadata, Method, Request, RequestInit};
+
+/// The binding name for the R2 bucket screenshots are stored in.
+///
+/// Screenshots are disabled unless this is bound alongside the Browser Rendering variables.
+pub const SCREENSHOTS_BUCKET_BINDING: &str = "SCREENSHOTS";
+
+/// The binding name for the variable holding the Cloudflare account ID used for Browser Rendering.
+pub const BROWSER_RENDERING_ACCOUNT_ID_BINDING: &str = "BROWSER_RENDERING_ACCOUNT_ID";
+
+/// The binding name for the secret holding the API token used for Browser Rendering.
+pub const BROWSER_RENDERING_API_TOKEN_BINDING: &str = "BROWSER_RENDERING_API_TOKEN";
+
+/// The content type screenshots are captured and served as.
+pub const SCREENSHOT_CONTENT_TYPE: &str = "image/png";
+
+/// Get the key a link's screenshot is stored under in the screenshots bucket.
+///
+/// Screenshots of tenant links are stored under the same `tenant:<name>:` prefix as the tenant's other keys.
+pub fn screenshot_key(tenant: Option<&str>, id: &str) -> String {
+    match tenant {
+        Some(tenant) => format!("tenant:{tenant}:{id}.png"),
+        None => format!("{id}.png"),
+    }
+}
+
+/// Get the key of an object in the screenshots bucket, scoped under the environment if one is set.
+fn object_key(env: &Env, key: &str) -> String {
+    format!("{}{key}", environment_key_prefix(env))
+}
+
+/// Whether or not screenshots can be captured with the current environment.
+pub fn screenshots_enabled(env: &Env) -> bool {
+    env.bucket(SCREENSHOTS_BUCKET_BINDING).is_ok()
+        && get_var(env, BROWSER_RENDERING_ACCOUNT_ID_BINDING).is_some()
+        && get_var(env, BROWSER_RENDERING_API_TOKEN_BINDING).is_some()
+}
+
+/// Capture a screenshot of the given URL using Browser Rendering, returning the PNG image.
+pub async fn capture_screenshot(env: &Env, url: &Url) -> Option<Vec<u8>> {
+    let account_id = get_var(env, BROWSER_RENDERING_ACCOUNT_ID_BINDING)?;
+    let api_token = get_var(env, BROWSER_RENDERING_API_TOKEN_BINDING)?;
+    let body = json!({ "url": url }).to_string();
+
+    let mut headers = Headers::new();
+    headers.set("Content-Type", "application/json").ok()?;
+    headers
+        .set("Authorization", &format!("Bearer {api_token}"))
+        .ok()?;
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_headers(headers)
+        .with_body(Some(JsValue::from_str(&body)));
+    let request = Request::new_with_init(
+        &format!(
+            "https://api.cloudflare.com/client/v4/accounts/{account_id}/browser-rendering/screenshot"
+        ),
+        &init,
+    )
+    .ok()?;
+
+    let mut response = Fetch::Request(request).send().await.ok()?;
+    if response.status_code() != 200 {
+        return None;
+    }
+    let is_image = response
+        .headers()
+        .get("Content-Type")
+        .ok()
+        .flatten()
+        .is_some_and(|content_type| content_type.starts_with("image/"));
+    if !is_image {
+        return None;
+    }
+    response.bytes().await.ok()
+}
+
+/// Store a screenshot in the screenshots bucket, replacing any existing screenshot under the same key.
+pub async fn store_screenshot(env: &Env, key: &str, image: Vec<u8>) -> bool {
+    let Ok(bucket) = env.bucket(SCREENSHOTS_BUCKET_BINDING) else {
+        return false;
+    };
+    bucket
+        .put(object_key(env, key), image)
+        .http_metadata(HttpMetadata {
+            content_type: Some(SCREENSHOT_CONTENT_TYPE.to_string()),
+            ..Default::default()
+        })
+        .execute()
+        .await
+        .is_ok()
+}
+
+/// Get a screenshot from the screenshots bucket.
+pub async fn get_screenshot(env: &Env, key: &str) -> Option<Vec<u8>> {
+    let bucket = env.bucket(SCREENSHOTS_BUCKET_BINDING).ok()?;
+    let object = bucket.get(object_key(env, key)).execute().await.ok()??;
+    object.body()?.bytes().await.ok()
+}
+
+/// Delete a screenshot from the screenshots bucket.
+pub async fn delete_screenshot(env: &Env, key: &str) -> bool {
+    let Ok(bucket) = env.bucket(SCREENSHOTS_BUCKET_BINDING) else {
+        return false;
+    };
+    bucket.delete(object_key(env, key)).await.is_ok()
+}
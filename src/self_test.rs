@@ -0,0 +1,75 @@
+use crate::{
+    messages::{
+        SELF_TEST_ANALYTICS_KV_UNREADABLE_RESPONSE, SELF_TEST_LINKS_D1_MISSING_RESPONSE,
+        SELF_TEST_LINKS_D1_UNREADABLE_RESPONSE, SELF_TEST_LINKS_KV_MISSING_RESPONSE,
+        SELF_TEST_LINKS_KV_UNREADABLE_RESPONSE,
+    },
+    storage::{
+        cloudflare_d1_driver::CLOUDFLARE_D1_BINDING,
+        cloudflare_kv_driver::{CLOUDFLARE_KV_ANALYTICS_BINDING, CLOUDFLARE_KV_BINDING},
+        get_storage_backend, StorageBackend,
+    },
+};
+use std::cell::Cell;
+use worker::{kv::KvStore, D1Database, Env};
+
+/// The key read to check that a KV namespace is accessible, which doesn't need to exist.
+const SELF_TEST_PROBE_KEY: &str = "config:self-test";
+
+thread_local! {
+    /// Whether or not this isolate has already passed the self-test.
+    static SELF_TEST_PASSED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether or not the given KV namespace can be read from.
+async fn is_readable(kv: &KvStore) -> bool {
+    kv.get(SELF_TEST_PROBE_KEY).text().await.is_ok()
+}
+
+/// Whether or not the storage table of the given D1 database can be read from, which also fails
+/// if its migrations haven't been applied.
+async fn is_d1_readable(database: &D1Database) -> bool {
+    database
+        .prepare("SELECT key FROM storage LIMIT 1")
+        .all()
+        .await
+        .is_ok_and(|result| result.success())
+}
+
+/// Check that the worker is able to serve requests, returning a message describing how to fix
+/// the first problem found if it isn't.
+///
+/// Passing results are cached for the lifetime of the isolate so only its first request pays for the check,
+/// while failures are checked again on the next request in case they were transient.
+pub async fn run_self_test(env: &Env) -> Result<(), &'static str> {
+    if SELF_TEST_PASSED.get() {
+        return Ok(());
+    }
+
+    match get_storage_backend(env) {
+        StorageBackend::KV => {
+            let Ok(links) = env.kv(CLOUDFLARE_KV_BINDING) else {
+                return Err(SELF_TEST_LINKS_KV_MISSING_RESPONSE);
+            };
+            if !is_readable(&links).await {
+                return Err(SELF_TEST_LINKS_KV_UNREADABLE_RESPONSE);
+            }
+        }
+        StorageBackend::D1 => {
+            let Ok(links) = env.d1(CLOUDFLARE_D1_BINDING) else {
+                return Err(SELF_TEST_LINKS_D1_MISSING_RESPONSE);
+            };
+            if !is_d1_readable(&links).await {
+                return Err(SELF_TEST_LINKS_D1_UNREADABLE_RESPONSE);
+            }
+        }
+    }
+    if let Ok(analytics) = env.kv(CLOUDFLARE_KV_ANALYTICS_BINDING) {
+        if !is_readable(&analytics).await {
+            return Err(SELF_TEST_ANALYTICS_KV_UNREADABLE_RESPONSE);
+        }
+    }
+
+    SELF_TEST_PASSED.set(true);
+    Ok(())
+}
@@ -0,0 +1,414 @@
+use crate::{environment::get_var, storage::StorageDriver};
+use percent_encoding::percent_decode_str;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use worker::{js_sys::Math, Env};
+
+/// The maximum length of a slug generated from arbitrary text.
+const MAX_SLUG_LENGTH: usize = 48;
+
+/// The binding name for the variable used to override the alphabet random IDs are generated from.
+pub const RANDOM_ID_ALPHABET_BINDING: &str = "RANDOM_ID_ALPHABET";
+
+/// The binding name for the variable used to override the length of randomly generated IDs.
+pub const RANDOM_ID_LENGTH_BINDING: &str = "RANDOM_ID_LENGTH";
+
+/// The default length of randomly generated IDs.
+const DEFAULT_RANDOM_ID_LENGTH: usize = 6;
+
+/// The shortest and longest randomly generated IDs can be configured to be.
+const RANDOM_ID_LENGTH_RANGE: std::ops::RangeInclusive<usize> = 4..=32;
+
+/// The default alphabet random IDs are generated from, excluding easily confused characters (0/O, 1/l/I).
+pub const DEFAULT_RANDOM_ID_ALPHABET: &str =
+    "23456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// The maximum amount of attempts to make at generating a random ID that isn't already in use.
+const MAX_RANDOM_ID_ATTEMPTS: u32 = 5;
+
+/// Words used when generating human-friendly random slugs.
+const WORDS: &[&str] = &[
+    "amber", "apple", "arrow", "aspen", "atlas", "autumn", "badge", "basil", "beacon", "birch",
+    "bloom", "breeze", "brook", "cedar", "cherry", "cinder", "clover", "comet", "coral", "crane",
+    "crystal", "dawn", "delta", "ember", "falcon", "fern", "field", "flame", "flint", "forest",
+    "frost", "galaxy", "garden", "glade", "harbor", "hazel", "heron", "honey", "island", "ivory",
+    "jade", "jasper", "juniper", "lagoon", "lantern", "lemon", "lilac", "lotus", "lunar", "maple",
+    "meadow", "mesa", "mint", "moss", "nectar", "nova", "oasis", "ocean", "olive", "orbit",
+    "otter", "pebble", "pepper", "pine", "planet", "plum", "prairie", "quartz", "rain", "raven",
+    "reef", "river", "robin", "saffron", "sage", "shadow", "sierra", "silver", "sky", "solar",
+    "sparrow", "spruce", "star", "stone", "storm", "summit", "sunset", "thistle", "thunder",
+    "tide", "timber", "topaz", "tulip", "valley", "velvet", "willow", "winter", "zephyr",
+];
+
+/// Convert arbitrary text into a lowercase, dash-separated slug.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for char in text.chars() {
+        if char.is_alphanumeric() {
+            slug.extend(char.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+
+        if slug.chars().count() >= MAX_SLUG_LENGTH {
+            break;
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Normalize a human-supplied link ID into its canonical form.
+///
+/// This applies NFKC normalization, transliterates accented characters into their
+/// base characters, lowercases the result and replaces whitespace with dashes,
+/// so that `Über Café` becomes `uber-cafe`.
+pub fn normalize_id(id: &str) -> String {
+    let mut normalized = String::new();
+    for char in id.nfkc().collect::<String>().nfd() {
+        if is_combining_mark(char) {
+            continue;
+        }
+
+        if char.is_whitespace() {
+            if !normalized.is_empty() && !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+            continue;
+        }
+
+        match char {
+            'ß' => normalized.push_str("ss"),
+            'æ' | 'Æ' => normalized.push_str("ae"),
+            'œ' | 'Œ' => normalized.push_str("oe"),
+            'ø' | 'Ø' => normalized.push('o'),
+            'ł' | 'Ł' => normalized.push('l'),
+            'đ' | 'Đ' => normalized.push('d'),
+            'þ' | 'Þ' => normalized.push_str("th"),
+            _ => normalized.extend(char.to_lowercase()),
+        }
+    }
+    normalized.trim_end_matches('-').nfc().collect()
+}
+
+/// Zero width joiner, used to combine multiple emoji into one.
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Emoji variation selector, used to request emoji presentation of a character.
+const EMOJI_VARIATION_SELECTOR: char = '\u{FE0F}';
+
+/// Convert a raw, percent-encoded link ID from a request path into its canonical form.
+///
+/// The canonical form is percent-decoded, NFC normalized and has emoji variation selectors
+/// removed so the same emoji always maps to the same ID regardless of how the client encoded it.
+pub fn canonicalize_id(raw_id: &str) -> Option<String> {
+    let decoded = percent_decode_str(raw_id).decode_utf8().ok()?;
+    Some(canonicalize_decoded_id(&decoded))
+}
+
+/// Convert an already decoded link ID (e.g. from a JSON body) into its canonical form.
+///
+/// See [`canonicalize_id`] for the IDs taken from request paths.
+pub fn canonicalize_decoded_id(id: &str) -> String {
+    id.nfc()
+        .filter(|char| *char != EMOJI_VARIATION_SELECTOR)
+        .collect()
+}
+
+/// Names that cannot be used as a flat link ID or namespace as they are used by other routes.
+const RESERVED_IDS: &[&str] = &["admin", "api", ".well-known", "favicon.ico", "robots.txt"];
+
+/// Names that cannot be used as the second segment of a namespaced link ID as they are used by link actions.
+const RESERVED_NAMES: &[&str] = &[
+    "where",
+    "exists",
+    "details",
+    "stats",
+    "resolve",
+    "pause",
+    "resume",
+    "screenshot",
+    "embed",
+    "oembed",
+    "explain",
+    "attestation",
+    "poster",
+    "qr",
+    "report.pdf",
+];
+
+/// Whether or not the given canonical ID can be used for a link.
+///
+/// IDs must be non-empty, may not contain `:` (used for internal storage keys), can have at most
+/// one `/` to separate a namespace from a name, may not use a reserved name and must only contain
+/// well-formed emoji.
+pub fn is_valid_id(id: &str) -> bool {
+    if id.contains(':') || !has_valid_emoji_sequences(id) {
+        return false;
+    }
+
+    let segments = id.split('/').collect::<Vec<_>>();
+    match segments.as_slice() {
+        [id] => !id.is_empty() && !RESERVED_IDS.contains(id),
+        [namespace, name] => {
+            !namespace.is_empty()
+                && !name.is_empty()
+                && !RESERVED_IDS.contains(namespace)
+                && !RESERVED_NAMES.contains(name)
+        }
+        _ => false,
+    }
+}
+
+/// Whether or not any emoji in the given ID form well-formed grapheme clusters.
+///
+/// This rejects dangling joiners, modifiers that aren't attached to an emoji and unpaired regional indicators.
+fn has_valid_emoji_sequences(id: &str) -> bool {
+    let chars = id.chars().collect::<Vec<_>>();
+    let mut regional_indicator_run = 0;
+
+    for (index, char) in chars.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+
+        if is_regional_indicator(*char) {
+            regional_indicator_run += 1;
+            continue;
+        } else if regional_indicator_run % 2 != 0 {
+            return false;
+        }
+        regional_indicator_run = 0;
+
+        if *char == ZERO_WIDTH_JOINER {
+            let joins_emoji = previous
+                .is_some_and(|char| is_emoji(char) || is_emoji_modifier(char))
+                && next.is_some_and(|char| is_emoji(*char));
+            if !joins_emoji {
+                return false;
+            }
+        } else if is_emoji_modifier(*char)
+            && !previous.is_some_and(|char| {
+                is_emoji(char) || char.is_ascii_digit() || char == '#' || char == '*'
+            })
+        {
+            return false;
+        }
+    }
+
+    regional_indicator_run % 2 == 0
+}
+
+/// Whether or not the given character is an emoji base character.
+fn is_emoji(char: char) -> bool {
+    matches!(char as u32,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x2194..=0x21AA
+        | 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF
+        | 0x3030 | 0x303D | 0x3297 | 0x3299 | 0x1F000..=0x1FAFF)
+        && !is_emoji_modifier(char)
+        && !is_regional_indicator(char)
+}
+
+/// Whether or not the given character modifies the emoji before it (skin tones, keycaps, tags).
+fn is_emoji_modifier(char: char) -> bool {
+    matches!(char as u32, 0x1F3FB..=0x1F3FF | 0x20E3 | 0xFE0E | 0xE0020..=0xE007F)
+}
+
+/// Whether or not the given character is a regional indicator, pairs of which form flags.
+fn is_regional_indicator(char: char) -> bool {
+    matches!(char as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Get the alphabet random IDs should be generated from, falling back to [`DEFAULT_RANDOM_ID_ALPHABET`]
+/// if the configured alphabet is unset or unusable.
+pub fn get_random_id_alphabet(env: &Env) -> Vec<char> {
+    let alphabet = get_var(env, RANDOM_ID_ALPHABET_BINDING)
+        .filter(|alphabet| is_valid_id(alphabet) && !alphabet.contains('/'))
+        .unwrap_or(DEFAULT_RANDOM_ID_ALPHABET.to_string());
+
+    let mut chars = alphabet.chars().collect::<Vec<_>>();
+    chars.sort_unstable();
+    chars.dedup();
+    if chars.len() < 2 {
+        return DEFAULT_RANDOM_ID_ALPHABET.chars().collect();
+    }
+    chars
+}
+
+/// Get the length random IDs should be generated with, falling back to [`DEFAULT_RANDOM_ID_LENGTH`]
+/// if the configured length is unset or out of range.
+pub fn get_random_id_length(env: &Env) -> usize {
+    get_var(env, RANDOM_ID_LENGTH_BINDING)
+        .and_then(|length| length.parse::<usize>().ok())
+        .filter(|length| RANDOM_ID_LENGTH_RANGE.contains(length))
+        .unwrap_or(DEFAULT_RANDOM_ID_LENGTH)
+}
+
+/// Generate a random ID of the given length from the given alphabet.
+pub fn random_id(alphabet: &[char], length: usize) -> String {
+    (0..length)
+        .map(|_| alphabet[(Math::random() * alphabet.len() as f64) as usize % alphabet.len()])
+        .collect()
+}
+
+/// Generate a random ID that isn't already in use in storage.
+///
+/// On every collision the length of the next attempt grows exponentially, which quickly makes
+/// further collisions unlikely even in heavily populated namespaces. Returns [`None`] if no unused ID
+/// could be found within [`MAX_RANDOM_ID_ATTEMPTS`] attempts.
+pub async fn generate_unique_id(
+    storage: &impl StorageDriver,
+    alphabet: &[char],
+    length: usize,
+) -> Option<String> {
+    for attempt in 0..MAX_RANDOM_ID_ATTEMPTS {
+        let id = random_id(alphabet, unique_id_attempt_length(length, attempt));
+        if is_valid_id(&id) && storage.get(&id).await.is_none() {
+            return Some(id);
+        }
+    }
+    None
+}
+
+/// Get the length of the random ID generated by the given attempt of [`generate_unique_id`].
+fn unique_id_attempt_length(length: usize, attempt: u32) -> usize {
+    length + 2usize.pow(attempt) - 1
+}
+
+/// Generate a slug made of the given amount of random words joined by dashes.
+pub fn random_words(count: usize) -> String {
+    (0..count)
+        .map(|_| WORDS[(Math::random() * WORDS.len() as f64) as usize % WORDS.len()])
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Generate a stable 64-bit FNV-1a hash of the given text.
+pub fn stable_hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Generate a short, stable base36 hash of the given text.
+pub fn short_hash(text: &str, length: usize) -> String {
+    let mut hash = stable_hash(text);
+
+    let mut encoded = String::new();
+    while encoded.len() < length {
+        encoded.push(char::from_digit((hash % 36) as u32, 36).unwrap());
+        hash /= 36;
+        if hash == 0 {
+            break;
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_joins_words_with_dashes() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  --Launch   Day--  "), "launch-day");
+        assert_eq!(slugify("Über Café"), "über-café");
+        assert_eq!(slugify("!!!"), "");
+        assert_eq!(slugify(&"a".repeat(100)).len(), MAX_SLUG_LENGTH);
+    }
+
+    #[test]
+    fn normalize_id_transliterates_and_dashes_whitespace() {
+        assert_eq!(normalize_id("Über Café"), "uber-cafe");
+        assert_eq!(normalize_id("Straße"), "strasse");
+        assert_eq!(normalize_id("Ærø Łódź"), "aero-lodz");
+        assert_eq!(normalize_id("ﬁle"), "file");
+        assert_eq!(normalize_id("trailing space "), "trailing-space");
+        assert_eq!(normalize_id("team/Launch Day"), "team/launch-day");
+    }
+
+    #[test]
+    fn emoji_sequences_must_be_well_formed() {
+        assert!(has_valid_emoji_sequences("launch"));
+        assert!(has_valid_emoji_sequences("🔥"));
+        assert!(has_valid_emoji_sequences("👨\u{200D}👩\u{200D}👧"));
+        assert!(has_valid_emoji_sequences("👍🏽"));
+        assert!(has_valid_emoji_sequences("1\u{20E3}"));
+        assert!(has_valid_emoji_sequences("🇩🇪"));
+        assert!(has_valid_emoji_sequences("🇩🇪🇫🇷"));
+
+        assert!(!has_valid_emoji_sequences("👨\u{200D}"));
+        assert!(!has_valid_emoji_sequences("\u{200D}👩"));
+        assert!(!has_valid_emoji_sequences("a\u{200D}b"));
+        assert!(!has_valid_emoji_sequences("🏽"));
+        assert!(!has_valid_emoji_sequences("a🏽"));
+        assert!(!has_valid_emoji_sequences("🇩"));
+        assert!(!has_valid_emoji_sequences("🇩a"));
+        assert!(!has_valid_emoji_sequences("🇩🇪🇫"));
+    }
+
+    #[test]
+    fn valid_ids() {
+        assert!(is_valid_id("launch"));
+        assert!(is_valid_id("team/launch"));
+        assert!(is_valid_id("🔥"));
+        // Reserved names only apply to the segment they'd clash with.
+        assert!(is_valid_id("stats"));
+        assert!(is_valid_id("launch/api"));
+
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("team/"));
+        assert!(!is_valid_id("/launch"));
+        assert!(!is_valid_id("team/launch/day"));
+        assert!(!is_valid_id("stats:launch"));
+        assert!(!is_valid_id("launch👨\u{200D}"));
+    }
+
+    #[test]
+    fn unique_id_length_grows_per_attempt() {
+        let lengths = (0..MAX_RANDOM_ID_ATTEMPTS)
+            .map(|attempt| unique_id_attempt_length(DEFAULT_RANDOM_ID_LENGTH, attempt))
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, [6, 7, 9, 13, 21]);
+    }
+
+    #[test]
+    fn stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn short_hash_is_stable() {
+        assert_eq!(short_hash("https://example.com", 6), "fhg95g");
+        assert_eq!(
+            short_hash("https://example.com", 6),
+            short_hash("https://example.com", 6)
+        );
+        assert_ne!(
+            short_hash("https://example.com", 6),
+            short_hash("https://example.org", 6)
+        );
+        assert_eq!(short_hash("https://example.com", 6).len(), 6);
+        assert!(short_hash("https://example.com", 10)
+            .starts_with(&short_hash("https://example.com", 6)));
+    }
+
+    #[test]
+    fn reserved_ids_are_invalid() {
+        for id in RESERVED_IDS {
+            assert!(!is_valid_id(id), "{id} should be reserved");
+            assert!(
+                !is_valid_id(&format!("{id}/launch")),
+                "{id} should be reserved"
+            );
+        }
+        for name in RESERVED_NAMES {
+            assert!(
+                !is_valid_id(&format!("team/{name}")),
+                "{name} should be reserved"
+            );
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::{
+    cloudflare_d1_driver::D1StorageDriver, cloudflare_kv_driver::CloudflareKVDriver, StorageDriver,
+    StorageListPage, StorageUsage,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// A driver for whichever backend was selected to store data in, see [`super::STORAGE_DRIVER_BINDING`].
+pub enum BackendStorageDriver {
+    KV(CloudflareKVDriver),
+    D1(D1StorageDriver),
+}
+
+impl StorageDriver for BackendStorageDriver {
+    async fn get(&self, key: &str) -> Option<String> {
+        match self {
+            Self::KV(driver) => driver.get(key).await,
+            Self::D1(driver) => driver.get(key).await,
+        }
+    }
+
+    async fn get_deserialized_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        match self {
+            Self::KV(driver) => driver.get_deserialized_json(key).await,
+            Self::D1(driver) => driver.get_deserialized_json(key).await,
+        }
+    }
+
+    async fn warm(&self, key: &str, cache_ttl: Duration) -> bool {
+        match self {
+            Self::KV(driver) => driver.warm(key, cache_ttl).await,
+            Self::D1(driver) => driver.warm(key, cache_ttl).await,
+        }
+    }
+
+    async fn set(&self, key: &str, value: &str, expiration: Option<u64>) -> bool {
+        match self {
+            Self::KV(driver) => driver.set(key, value, expiration).await,
+            Self::D1(driver) => driver.set(key, value, expiration).await,
+        }
+    }
+
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool {
+        match self {
+            Self::KV(driver) => driver.set_serialized_json(key, value, expiration).await,
+            Self::D1(driver) => driver.set_serialized_json(key, value, expiration).await,
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<String> {
+        match self {
+            Self::KV(driver) => driver.list(prefix).await,
+            Self::D1(driver) => driver.list(prefix).await,
+        }
+    }
+
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage {
+        match self {
+            Self::KV(driver) => driver.list_page(prefix, cursor, limit).await,
+            Self::D1(driver) => driver.list_page(prefix, cursor, limit).await,
+        }
+    }
+
+    async fn usage(&self, prefix: &str) -> StorageUsage {
+        match self {
+            Self::KV(driver) => driver.usage(prefix).await,
+            Self::D1(driver) => driver.usage(prefix).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> bool {
+        match self {
+            Self::KV(driver) => driver.delete(key).await,
+            Self::D1(driver) => driver.delete(key).await,
+        }
+    }
+}
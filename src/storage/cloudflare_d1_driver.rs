@@ -0,0 +1,167 @@
+use super::{StorageDriver, StorageListPage, StorageUsage};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+use wasm_bindgen::JsValue;
+use worker::D1Database;
+
+/// The binding name for the D1 database that stores Link data when [`super::STORAGE_DRIVER_BINDING`] is `d1`.
+pub const CLOUDFLARE_D1_BINDING: &str = "links_db";
+
+/// The condition matching every key that starts with the prefix bound as `?1`.
+///
+/// The range lets the primary key index be used, while `substr` is what actually checks the prefix.
+const PREFIX_CONDITION: &str = "key >= ?1 AND substr(key, 1, length(?1)) = ?1";
+
+/// A row containing only a key.
+#[derive(Debug, Deserialize)]
+struct KeyRow {
+    key: String,
+}
+
+/// A row containing the amount of keys and the total size of their values.
+#[derive(Debug, Deserialize)]
+struct UsageRow {
+    keys: u64,
+    bytes: u64,
+}
+
+/// A driver for Cloudflare D1.
+///
+/// Every key is a row of a single `storage` table created by `migrations/0001_create_storage.sql`, so links are stored as one row each with their
+/// model as JSON, which can be queried with SQLite's JSON functions. Unlike KV, writes are immediately
+/// visible everywhere and listings are consistent.
+///
+/// https://developers.cloudflare.com/d1/
+pub struct D1StorageDriver {
+    /// The underlying Cloudflare D1 database.
+    database: D1Database,
+}
+
+impl D1StorageDriver {
+    /// Create a new instance of [`D1StorageDriver`].
+    pub fn new(database: D1Database) -> D1StorageDriver {
+        D1StorageDriver { database }
+    }
+
+    /// List up to the given amount of keys that start with the given prefix and come after the given key.
+    async fn list_keys_after(&self, prefix: &str, after: &str, limit: Option<u64>) -> Vec<String> {
+        let mut query =
+            format!("SELECT key FROM storage WHERE {PREFIX_CONDITION} AND key > ?2 ORDER BY key");
+        let mut values = vec![JsValue::from(prefix), JsValue::from(after)];
+        if let Some(limit) = limit {
+            query.push_str(" LIMIT ?3");
+            values.push(JsValue::from(limit as f64));
+        }
+
+        let Ok(statement) = self.database.prepare(query).bind(&values) else {
+            return Vec::new();
+        };
+        match statement
+            .all()
+            .await
+            .and_then(|result| result.results::<KeyRow>())
+        {
+            Ok(rows) => rows.into_iter().map(|row| row.key).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl StorageDriver for D1StorageDriver {
+    async fn get(&self, key: &str) -> Option<String> {
+        self.database
+            .prepare("SELECT value FROM storage WHERE key = ?1")
+            .bind(&[key.into()])
+            .ok()?
+            .first::<String>(Some("value"))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    async fn get_deserialized_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_str::<T>(&self.get(key).await?).ok()
+    }
+
+    async fn warm(&self, key: &str, _cache_ttl: Duration) -> bool {
+        // D1 has no read cache to load keys into, so this only checks the key exists.
+        self.get(key).await.is_some()
+    }
+
+    async fn set(&self, key: &str, value: &str, _expiration: Option<u64>) -> bool {
+        // D1 has no native expiration, expired keys are left to be cleaned up when they're next read.
+        let Ok(statement) = self
+            .database
+            .prepare(
+                "INSERT INTO storage (key, value, size) VALUES (?1, ?2, ?3) \
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value, size = excluded.size",
+            )
+            .bind(&[key.into(), value.into(), (value.len() as f64).into()])
+        else {
+            return false;
+        };
+        statement.run().await.is_ok_and(|result| result.success())
+    }
+
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool {
+        let serialized = match serde_json::to_string(&value) {
+            Ok(serialized) => serialized,
+            Err(_) => return false,
+        };
+        self.set(key, &serialized, expiration).await
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<String> {
+        self.list_keys_after(prefix, "", None).await
+    }
+
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage {
+        // One extra key is fetched to find out whether there's another page without a second query.
+        let mut keys = self
+            .list_keys_after(prefix, cursor.unwrap_or_default(), Some(limit + 1))
+            .await;
+        let has_more = keys.len() as u64 > limit;
+        keys.truncate(limit as usize);
+        StorageListPage {
+            // Keys are listed in order, so the last key of a page is where the next one starts.
+            cursor: keys.last().filter(|_| has_more).cloned(),
+            keys,
+        }
+    }
+
+    async fn usage(&self, prefix: &str) -> StorageUsage {
+        let Ok(statement) = self
+            .database
+            .prepare(format!(
+                "SELECT COUNT(*) AS keys, COALESCE(SUM(size), 0) AS bytes FROM storage WHERE {PREFIX_CONDITION}"
+            ))
+            .bind(&[prefix.into()])
+        else {
+            return StorageUsage::default();
+        };
+        match statement.first::<UsageRow>(None).await {
+            Ok(Some(row)) => StorageUsage {
+                keys: row.keys,
+                bytes: row.bytes,
+                unsized_keys: 0,
+            },
+            _ => StorageUsage::default(),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> bool {
+        let Ok(statement) = self
+            .database
+            .prepare("DELETE FROM storage WHERE key = ?1")
+            .bind(&[key.into()])
+        else {
+            return false;
+        };
+        statement.run().await.is_ok_and(|result| result.success())
+    }
+}
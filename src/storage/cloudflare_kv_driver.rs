@@ -1,10 +1,31 @@
-use super::StorageDriver;
-use serde::{de::DeserializeOwned, Serialize};
-use worker::kv::KvStore;
+use super::{StorageDriver, StorageListPage, StorageUsage};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+use worker::{
+    kv::{Key, KvStore},
+    Date,
+};
 
 /// The binding name for the KV namespace that stores Link data.
 pub const CLOUDFLARE_KV_BINDING: &str = "links";
 
+/// The binding name for the optional KV namespace that stores analytics data.
+pub const CLOUDFLARE_KV_ANALYTICS_BINDING: &str = "analytics";
+
+/// The binding name for the optional KV namespace that link writes are mirrored to while migrating storage.
+pub const CLOUDFLARE_KV_SHADOW_BINDING: &str = "links_shadow";
+
+/// How far in the future expirations have to be at least, as KV rejects anything sooner.
+const MIN_EXPIRATION_SECONDS: u64 = 60;
+
+/// The metadata stored alongside every value, which lets storage usage be measured by listing keys
+/// instead of reading every value.
+#[derive(Debug, Serialize, Deserialize)]
+struct ValueMetadata {
+    /// The size of the value in bytes.
+    size: u64,
+}
+
 /// A driver for Cloudflare KV.
 ///
 /// https://developers.cloudflare.com/kv/
@@ -18,6 +39,32 @@ impl CloudflareKVDriver {
     pub fn new(store: KvStore) -> CloudflareKVDriver {
         CloudflareKVDriver { kv_store: store }
     }
+
+    /// List all keys that start with the given prefix alongside their metadata.
+    async fn list_keys(&self, prefix: &str) -> Vec<Key> {
+        let mut keys = Vec::new();
+        let mut cursor = None;
+
+        // KV only returns up to 1000 keys at a time, so keep following the cursor until the listing is complete.
+        loop {
+            let mut request = self.kv_store.list().prefix(prefix.to_string());
+            if let Some(cursor) = cursor {
+                request = request.cursor(cursor);
+            }
+
+            let Ok(response) = request.execute().await else {
+                break;
+            };
+            keys.extend(response.keys);
+
+            if response.list_complete || response.cursor.is_none() {
+                break;
+            }
+            cursor = response.cursor;
+        }
+
+        keys
+    }
 }
 
 impl StorageDriver for CloudflareKVDriver {
@@ -35,27 +82,86 @@ impl StorageDriver for CloudflareKVDriver {
             None => return None,
         };
 
-        match serde_json::from_str::<T>(&raw_json) {
-            Ok(value) => Some(value),
-            Err(_) => None,
-        }
+        serde_json::from_str::<T>(&raw_json).ok()
     }
 
-    async fn set(&self, key: &str, value: &str) -> bool {
+    async fn warm(&self, key: &str, cache_ttl: Duration) -> bool {
+        // KV doesn't accept cache TTLs below 60 seconds.
         self.kv_store
-            .put(key, value)
-            .unwrap()
-            .execute()
+            .get(key)
+            .cache_ttl(cache_ttl.as_secs().max(60))
+            .text()
             .await
-            .is_ok()
+            .is_ok_and(|value| value.is_some())
     }
 
-    async fn set_serialized_json<T: Serialize>(&self, key: &str, value: T) -> bool {
+    async fn set(&self, key: &str, value: &str, expiration: Option<u64>) -> bool {
+        let metadata = ValueMetadata {
+            size: value.len() as u64,
+        };
+        let Ok(mut request) = self
+            .kv_store
+            .put(key, value)
+            .and_then(|request| request.metadata(metadata))
+        else {
+            return false;
+        };
+        if let Some(expiration) = expiration {
+            let earliest = Date::now().as_millis() / 1000 + MIN_EXPIRATION_SECONDS;
+            request = request.expiration(expiration.max(earliest));
+        }
+        request.execute().await.is_ok()
+    }
+
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool {
         let serialized = match serde_json::to_string(&value) {
             Ok(serialized) => serialized,
             Err(_) => return false,
         };
-        self.set(key, &serialized).await
+        self.set(key, &serialized, expiration).await
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<String> {
+        self.list_keys(prefix)
+            .await
+            .into_iter()
+            .map(|key| key.name)
+            .collect()
+    }
+
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage {
+        let mut request = self.kv_store.list().prefix(prefix.to_string()).limit(limit);
+        if let Some(cursor) = cursor {
+            request = request.cursor(cursor.to_string());
+        }
+
+        match request.execute().await {
+            Ok(response) => StorageListPage {
+                keys: response.keys.into_iter().map(|key| key.name).collect(),
+                cursor: response.cursor.filter(|_| !response.list_complete),
+            },
+            Err(_) => StorageListPage::default(),
+        }
+    }
+
+    async fn usage(&self, prefix: &str) -> StorageUsage {
+        let mut usage = StorageUsage::default();
+        for key in self.list_keys(prefix).await {
+            usage.keys += 1;
+            match key
+                .metadata
+                .and_then(|metadata| serde_json::from_value::<ValueMetadata>(metadata).ok())
+            {
+                Some(metadata) => usage.bytes += metadata.size,
+                None => usage.unsized_keys += 1,
+            }
+        }
+        usage
     }
 
     async fn delete(&self, key: &str) -> bool {
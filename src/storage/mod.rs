@@ -1,6 +1,132 @@
+pub mod backend_driver;
+pub mod cloudflare_d1_driver;
 pub mod cloudflare_kv_driver;
+pub mod prefixed_driver;
+pub mod routed_driver;
+pub mod shadow_driver;
 
-use serde::{de::DeserializeOwned, Serialize};
+use crate::environment::{environment_key_prefix, get_bool_var, get_var};
+use backend_driver::BackendStorageDriver;
+use cloudflare_d1_driver::{D1StorageDriver, CLOUDFLARE_D1_BINDING};
+use cloudflare_kv_driver::{
+    CloudflareKVDriver, CLOUDFLARE_KV_ANALYTICS_BINDING, CLOUDFLARE_KV_BINDING,
+    CLOUDFLARE_KV_SHADOW_BINDING,
+};
+use prefixed_driver::PrefixedStorageDriver;
+use routed_driver::RoutedStorageDriver;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shadow_driver::{ShadowStorageDriver, SHADOW_READS_BINDING};
+use std::{ops::AddAssign, str::FromStr, time::Duration};
+use worker::Env;
+
+/// The binding name for the variable selecting which backend links are stored in.
+pub const STORAGE_DRIVER_BINDING: &str = "STORAGE_DRIVER";
+
+/// The storage driver used by the worker.
+pub type Storage =
+    RoutedStorageDriver<PrefixedStorageDriver<ShadowStorageDriver<BackendStorageDriver>>>;
+
+/// The backends links can be stored in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// Cloudflare KV, bound as [`CLOUDFLARE_KV_BINDING`].
+    #[default]
+    KV,
+    /// Cloudflare D1, bound as [`CLOUDFLARE_D1_BINDING`].
+    D1,
+}
+
+impl FromStr for StorageBackend {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "kv" => Ok(Self::KV),
+            "d1" => Ok(Self::D1),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Get the backend links should be stored in, defaulting to KV.
+pub fn get_storage_backend(env: &Env) -> StorageBackend {
+    get_var(env, STORAGE_DRIVER_BINDING)
+        .and_then(|backend| backend.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Create the unscoped storage driver for the worker from the bindings available in the environment.
+///
+/// Links are stored in the backend selected with [`STORAGE_DRIVER_BINDING`]. Analytics data is stored in its own
+/// KV namespace if one is bound, otherwise it shares the links backend. Writes to the links backend are also
+/// mirrored to a shadow KV namespace if one is bound, which can be used to keep KV up to date while trying out D1.
+pub fn get_storage(env: &Env) -> worker::Result<Storage> {
+    get_tenant_storage(env, None)
+}
+
+/// Create the storage driver for the given tenant, or the unscoped storage driver if there is no tenant.
+///
+/// Every key of a tenant is stored under `tenant:<name>:` so tenants can't see or overwrite each other's links,
+/// and everything is further scoped under `<environment>:` when an environment is set.
+pub fn get_tenant_storage(env: &Env, tenant: Option<&str>) -> worker::Result<Storage> {
+    let tenant_prefix = tenant
+        .map(|tenant| format!("tenant:{tenant}:"))
+        .unwrap_or_default();
+    let prefix = format!("{}{tenant_prefix}", environment_key_prefix(env));
+    let primary = match get_storage_backend(env) {
+        StorageBackend::KV => {
+            BackendStorageDriver::KV(CloudflareKVDriver::new(env.kv(CLOUDFLARE_KV_BINDING)?))
+        }
+        StorageBackend::D1 => {
+            BackendStorageDriver::D1(D1StorageDriver::new(env.d1(CLOUDFLARE_D1_BINDING)?))
+        }
+    };
+    let links = ShadowStorageDriver::new(
+        primary,
+        env.kv(CLOUDFLARE_KV_SHADOW_BINDING)
+            .ok()
+            .map(|kv| BackendStorageDriver::KV(CloudflareKVDriver::new(kv))),
+        get_bool_var(env, SHADOW_READS_BINDING).unwrap_or(false),
+    );
+    let analytics = env.kv(CLOUDFLARE_KV_ANALYTICS_BINDING).ok().map(|kv| {
+        ShadowStorageDriver::new(
+            BackendStorageDriver::KV(CloudflareKVDriver::new(kv)),
+            None,
+            false,
+        )
+    });
+    Ok(RoutedStorageDriver::new(
+        PrefixedStorageDriver::new(links, prefix.clone()),
+        analytics.map(|analytics| PrefixedStorageDriver::new(analytics, prefix)),
+    ))
+}
+
+/// Represents the approximate amount of data held in storage.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct StorageUsage {
+    /// The amount of keys.
+    pub keys: u64,
+    /// The total size of the values of every key with a known size, in bytes.
+    pub bytes: u64,
+    /// The amount of keys whose size isn't known, such as keys written before sizes were recorded.
+    pub unsized_keys: u64,
+}
+
+impl AddAssign for StorageUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.keys += other.keys;
+        self.bytes += other.bytes;
+        self.unsized_keys += other.unsized_keys;
+    }
+}
+
+/// Represents a single page of keys from a listing.
+#[derive(Debug, Default, Clone)]
+pub struct StorageListPage {
+    pub keys: Vec<String>,
+    /// The cursor to continue the listing from, or [`None`] if this is the last page.
+    pub cursor: Option<String>,
+}
 
 /// Represents a generic storage driver that can be used to store keys and values.
 pub trait StorageDriver {
@@ -13,11 +139,31 @@ pub trait StorageDriver {
     /// Get the value of a key with automatic deserialization into the given struct from JSON.
     async fn get_deserialized_json<T: DeserializeOwned>(&self, key: &str) -> Option<T>;
 
-    /// Set the value of a key.
-    async fn set(&self, key: &str, value: &str) -> bool;
+    /// Load a key into any read cache in front of the storage for at least the given duration,
+    /// returning whether or not the key exists.
+    async fn warm(&self, key: &str, cache_ttl: Duration) -> bool;
+
+    /// Set the value of a key, optionally letting the storage delete it by itself once the given
+    /// expiration (a UNIX timestamp in seconds) has passed.
+    async fn set(&self, key: &str, value: &str, expiration: Option<u64>) -> bool;
+
+    /// Set the value of a key with automatic serialization of the given struct into JSON, see [`StorageDriver::set`].
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool;
+
+    /// List all keys that start with the given prefix.
+    async fn list(&self, prefix: &str) -> Vec<String>;
+
+    /// List up to the given amount of keys that start with the given prefix, continuing from a cursor
+    /// returned by a previous page if one is given.
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage;
 
-    /// Set the value of a key with automatic serialization of the given struct into JSON.
-    async fn set_serialized_json<T: Serialize>(&self, key: &str, value: T) -> bool;
+    /// Get the amount of keys that start with the given prefix and the approximate size of their values.
+    async fn usage(&self, prefix: &str) -> StorageUsage;
 
     /// Delete a key.
     async fn delete(&self, key: &str) -> bool;
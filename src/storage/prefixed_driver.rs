@@ -0,0 +1,86 @@
+use super::{StorageDriver, StorageListPage, StorageUsage};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// A driver that scopes every key of an underlying driver under a fixed prefix.
+///
+/// This is used to give each tenant its own keyspace while sharing the same underlying storage.
+pub struct PrefixedStorageDriver<D: StorageDriver> {
+    /// The driver keys are stored in.
+    inner: D,
+    /// The prefix added to every key, which is empty for unscoped storage.
+    prefix: String,
+}
+
+impl<D: StorageDriver> PrefixedStorageDriver<D> {
+    /// Create a new instance of [`PrefixedStorageDriver`].
+    pub fn new(inner: D, prefix: String) -> Self {
+        Self { inner, prefix }
+    }
+
+    /// Get the key in the underlying driver for the given key.
+    fn prefixed(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+impl<D: StorageDriver> StorageDriver for PrefixedStorageDriver<D> {
+    async fn get(&self, key: &str) -> Option<String> {
+        self.inner.get(&self.prefixed(key)).await
+    }
+
+    async fn get_deserialized_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.inner.get_deserialized_json(&self.prefixed(key)).await
+    }
+
+    async fn warm(&self, key: &str, cache_ttl: Duration) -> bool {
+        self.inner.warm(&self.prefixed(key), cache_ttl).await
+    }
+
+    async fn set(&self, key: &str, value: &str, expiration: Option<u64>) -> bool {
+        self.inner.set(&self.prefixed(key), value, expiration).await
+    }
+
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool {
+        self.inner
+            .set_serialized_json(&self.prefixed(key), value, expiration)
+            .await
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<String> {
+        self.inner
+            .list(&self.prefixed(prefix))
+            .await
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect()
+    }
+
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage {
+        let page = self
+            .inner
+            .list_page(&self.prefixed(prefix), cursor, limit)
+            .await;
+        StorageListPage {
+            keys: page
+                .keys
+                .into_iter()
+                .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+                .collect(),
+            cursor: page.cursor,
+        }
+    }
+
+    async fn usage(&self, prefix: &str) -> StorageUsage {
+        self.inner.usage(&self.prefixed(prefix)).await
+    }
+
+    async fn delete(&self, key: &str) -> bool {
+        self.inner.delete(&self.prefixed(key)).await
+    }
+}
@@ -0,0 +1,90 @@
+use super::{StorageDriver, StorageListPage, StorageUsage};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// Key prefixes that belong to analytics data rather than link definitions.
+const ANALYTICS_KEY_PREFIXES: &[&str] = &["stats:"];
+
+/// A driver that routes keys to one of two underlying drivers based on their prefix.
+///
+/// Analytics keys are routed to a dedicated driver when one is configured, so that
+/// high-churn analytics writes don't share rate limits and listings with link definitions.
+pub struct RoutedStorageDriver<D: StorageDriver> {
+    /// The driver used for link definitions and any other non-analytics keys.
+    primary: D,
+    /// The driver used for analytics keys, if one has been configured.
+    analytics: Option<D>,
+}
+
+impl<D: StorageDriver> RoutedStorageDriver<D> {
+    /// Create a new instance of [`RoutedStorageDriver`].
+    pub fn new(primary: D, analytics: Option<D>) -> Self {
+        Self { primary, analytics }
+    }
+
+    /// Get the driver responsible for the given key.
+    fn driver_for(&self, key: &str) -> &D {
+        match &self.analytics {
+            Some(analytics)
+                if ANALYTICS_KEY_PREFIXES
+                    .iter()
+                    .any(|prefix| key.starts_with(prefix)) =>
+            {
+                analytics
+            }
+            _ => &self.primary,
+        }
+    }
+}
+
+impl<D: StorageDriver> StorageDriver for RoutedStorageDriver<D> {
+    async fn get(&self, key: &str) -> Option<String> {
+        self.driver_for(key).get(key).await
+    }
+
+    async fn get_deserialized_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.driver_for(key).get_deserialized_json(key).await
+    }
+
+    async fn warm(&self, key: &str, cache_ttl: Duration) -> bool {
+        self.driver_for(key).warm(key, cache_ttl).await
+    }
+
+    async fn set(&self, key: &str, value: &str, expiration: Option<u64>) -> bool {
+        self.driver_for(key).set(key, value, expiration).await
+    }
+
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool {
+        self.driver_for(key)
+            .set_serialized_json(key, value, expiration)
+            .await
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<String> {
+        self.driver_for(prefix).list(prefix).await
+    }
+
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage {
+        self.driver_for(prefix)
+            .list_page(prefix, cursor, limit)
+            .await
+    }
+
+    async fn usage(&self, prefix: &str) -> StorageUsage {
+        // Both drivers are counted as keys matching a prefix could be stored in either of them.
+        let mut usage = self.primary.usage(prefix).await;
+        if let Some(analytics) = &self.analytics {
+            usage += analytics.usage(prefix).await;
+        }
+        usage
+    }
+
+    async fn delete(&self, key: &str) -> bool {
+        self.driver_for(key).delete(key).await
+    }
+}
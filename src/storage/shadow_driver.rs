@@ -0,0 +1,125 @@
+use super::{StorageDriver, StorageListPage, StorageUsage};
+use futures_util::future::join;
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+use worker::console_warn;
+
+/// The binding name for the variable controlling whether reads are compared against the shadow driver.
+pub const SHADOW_READS_BINDING: &str = "SHADOW_READS";
+
+/// A driver that mirrors every write to a second "shadow" driver while still serving reads from the primary.
+///
+/// This is used to validate a new storage backend with production traffic before cutting over to it.
+/// When read comparison is enabled, reads are also made against the shadow and any mismatches are logged.
+pub struct ShadowStorageDriver<D: StorageDriver> {
+    /// The driver that reads are served from.
+    primary: D,
+    /// The driver writes are mirrored to, if one has been configured.
+    shadow: Option<D>,
+    /// Whether or not reads should also be made against the shadow and compared.
+    compare_reads: bool,
+}
+
+impl<D: StorageDriver> ShadowStorageDriver<D> {
+    /// Create a new instance of [`ShadowStorageDriver`].
+    pub fn new(primary: D, shadow: Option<D>, compare_reads: bool) -> Self {
+        Self {
+            primary,
+            shadow,
+            compare_reads,
+        }
+    }
+
+    /// Get the shadow driver if reads should be compared against it.
+    fn read_shadow(&self) -> Option<&D> {
+        self.shadow.as_ref().filter(|_| self.compare_reads)
+    }
+}
+
+impl<D: StorageDriver> StorageDriver for ShadowStorageDriver<D> {
+    async fn get(&self, key: &str) -> Option<String> {
+        let Some(shadow) = self.read_shadow() else {
+            return self.primary.get(key).await;
+        };
+
+        let (value, shadow_value) = join(self.primary.get(key), shadow.get(key)).await;
+        if value != shadow_value {
+            console_warn!("Shadow storage mismatch when reading key '{key}'");
+        }
+        value
+    }
+
+    async fn get_deserialized_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_str::<T>(&self.get(key).await?).ok()
+    }
+
+    async fn warm(&self, key: &str, cache_ttl: Duration) -> bool {
+        self.primary.warm(key, cache_ttl).await
+    }
+
+    async fn set(&self, key: &str, value: &str, expiration: Option<u64>) -> bool {
+        let Some(shadow) = &self.shadow else {
+            return self.primary.set(key, value, expiration).await;
+        };
+
+        let (success, shadow_success) = join(
+            self.primary.set(key, value, expiration),
+            shadow.set(key, value, expiration),
+        )
+        .await;
+        if success && !shadow_success {
+            console_warn!("Shadow storage failed to write key '{key}'");
+        }
+        success
+    }
+
+    async fn set_serialized_json<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        expiration: Option<u64>,
+    ) -> bool {
+        let serialized = match serde_json::to_string(&value) {
+            Ok(serialized) => serialized,
+            Err(_) => return false,
+        };
+        self.set(key, &serialized, expiration).await
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<String> {
+        let Some(shadow) = self.read_shadow() else {
+            return self.primary.list(prefix).await;
+        };
+
+        let (keys, shadow_keys) = join(self.primary.list(prefix), shadow.list(prefix)).await;
+        if keys != shadow_keys {
+            console_warn!(
+                "Shadow storage mismatch when listing prefix '{prefix}' ({} keys, {} in shadow)",
+                keys.len(),
+                shadow_keys.len()
+            );
+        }
+        keys
+    }
+
+    async fn list_page(&self, prefix: &str, cursor: Option<&str>, limit: u64) -> StorageListPage {
+        // Cursors are specific to the driver that returned them, so pages are only read from the primary.
+        self.primary.list_page(prefix, cursor, limit).await
+    }
+
+    async fn usage(&self, prefix: &str) -> StorageUsage {
+        self.primary.usage(prefix).await
+    }
+
+    async fn delete(&self, key: &str) -> bool {
+        let Some(shadow) = &self.shadow else {
+            return self.primary.delete(key).await;
+        };
+
+        let (success, shadow_success) = join(self.primary.delete(key), shadow.delete(key)).await;
+        if success && !shadow_success {
+            console_warn!("Shadow storage failed to delete key '{key}'");
+        }
+        success
+    }
+}
@@ -0,0 +1,78 @@
+/// Render a template by replacing `{{ name }}` placeholders with the value of the matching variable.
+///
+/// Values are HTML escaped, and placeholders without a matching variable are replaced with nothing.
+pub fn render_template(template: &str, variables: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut remaining = template;
+
+    while let Some(start) = remaining.find("{{") {
+        let Some(end) = remaining[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&remaining[..start]);
+
+        let name = remaining[start + 2..start + end].trim();
+        if let Some((_, value)) = variables.iter().find(|(variable, _)| *variable == name) {
+            rendered.push_str(&escape_html(value));
+        }
+        remaining = &remaining[start + end + 2..];
+    }
+
+    rendered.push_str(remaining);
+    rendered
+}
+
+/// Escape text so it can be safely included in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substituted_values_are_escaped() {
+        assert_eq!(
+            render_template(
+                r#"<a title="{{ title }}">{{title}}</a>"#,
+                &[("title", r#"<script>alert("a" + 'b' && 1)</script>"#)],
+            ),
+            "<a title=\"&lt;script&gt;alert(&quot;a&quot; + &#x27;b&#x27; &amp;&amp; 1)&lt;/script&gt;\">\
+             &lt;script&gt;alert(&quot;a&quot; + &#x27;b&#x27; &amp;&amp; 1)&lt;/script&gt;</a>"
+        );
+    }
+
+    #[test]
+    fn templates_are_not_escaped() {
+        assert_eq!(
+            render_template("<p>{{ host }} & co</p>", &[("host", "example.com")]),
+            "<p>example.com & co</p>"
+        );
+    }
+
+    #[test]
+    fn unknown_and_unterminated_placeholders() {
+        assert_eq!(render_template("a{{ missing }}b", &[]), "ab");
+        assert_eq!(
+            render_template("{{ host }} {{ host", &[("host", "example.com")]),
+            "example.com {{ host"
+        );
+        // Values are never rendered as templates themselves.
+        assert_eq!(
+            render_template("{{ a }}", &[("a", "{{ b }}"), ("b", "<b>")]),
+            "{{ b }}"
+        );
+    }
+}
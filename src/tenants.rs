@@ -0,0 +1,63 @@
+use crate::{
+    environment::get_bool_var,
+    models::host::HostMappingModel,
+    storage::{get_storage, get_tenant_storage, Storage, StorageDriver},
+};
+use std::collections::BTreeSet;
+use worker::{Env, Request};
+
+/// The binding name for the variable controlling whether incoming hostnames are mapped to tenants.
+pub const MULTI_TENANT_BINDING: &str = "MULTI_TENANT";
+
+/// The maximum length of a tenant name.
+const MAX_TENANT_NAME_LENGTH: usize = 64;
+
+/// Whether or not the given tenant name can be used.
+///
+/// Tenant names become part of storage keys, so are limited to ASCII alphanumerics, `-` and `_`.
+pub fn is_valid_tenant_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_TENANT_NAME_LENGTH
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+}
+
+/// Whether or not multi-tenant mode is enabled.
+pub fn multi_tenant_enabled(env: &Env) -> bool {
+    get_bool_var(env, MULTI_TENANT_BINDING).unwrap_or(false)
+}
+
+/// Get the tenant the request was made to, or [`None`] if multi-tenant mode is disabled or the hostname isn't mapped.
+pub async fn resolve_tenant(req: &Request, env: &Env) -> Option<String> {
+    if !multi_tenant_enabled(env) {
+        return None;
+    }
+
+    let url = req.url().ok()?;
+    let host = url.host_str()?;
+    let mapping = get_storage(env)
+        .ok()?
+        .get_deserialized_json::<HostMappingModel>(&HostMappingModel::key(host))
+        .await?;
+    Some(mapping.tenant)
+}
+
+/// Create the storage driver scoped to the tenant the request was made to.
+pub async fn get_request_storage(req: &Request, env: &Env) -> worker::Result<Storage> {
+    get_tenant_storage(env, resolve_tenant(req, env).await.as_deref())
+}
+
+/// Get the names of all tenants that have at least one hostname mapped to them.
+pub async fn list_tenants(storage: &impl StorageDriver) -> BTreeSet<String> {
+    let mut tenants = BTreeSet::new();
+    for key in storage.list(HostMappingModel::key_prefix()).await {
+        if let Some(mapping) = storage
+            .get_deserialized_json::<HostMappingModel>(&key)
+            .await
+        {
+            tenants.insert(mapping.tenant);
+        }
+    }
+    tenants
+}
@@ -0,0 +1,308 @@
+use url::Url;
+use worker::{Fetch, Method, Request, RequestInit, RequestRedirect};
+
+/// The maximum amount of redirects to follow when resolving a redirect chain.
+const MAX_REDIRECT_HOPS: usize = 10;
+
+/// Query parameters that are only used for tracking and can be safely stripped from destinations.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid",
+    "mc_eid", "_hsenc", "_hsmi",
+];
+
+/// The prefix shared by all UTM tracking query parameters.
+const UTM_PARAM_PREFIX: &str = "utm_";
+
+/// The URL schemes links can send visitors to.
+const WEB_SCHEMES: &[&str] = &["http", "https"];
+
+/// Whether or not a URL is a web page that's safe to send visitors to, rather than something like a `javascript:` or
+/// `data:` URL that would run on the worker's own origin when followed from one of its pages.
+pub fn is_web_url(url: &Url) -> bool {
+    WEB_SCHEMES.contains(&url.scheme())
+}
+
+/// Normalize a destination URL into a canonical form.
+///
+/// This lowercases the host, removes the fragment and, if enabled, strips known tracking
+/// query parameters. Default ports are already stripped when the URL is parsed.
+pub fn normalize_url(url: &mut Url, strip_tracking_params: bool) {
+    if let Some(host) = url.host_str().map(str::to_lowercase) {
+        // This can only fail for URLs that cannot have a host, which already don't have one.
+        let _ = url.set_host(Some(&host));
+    }
+    url.set_fragment(None);
+
+    if strip_tracking_params && url.query().is_some() {
+        let retained_pairs = url
+            .query_pairs()
+            .filter(|(key, _)| !is_tracking_param(key))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect::<Vec<_>>();
+
+        if retained_pairs.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(retained_pairs);
+        }
+    }
+}
+
+/// Merge query parameters into a URL, replacing the URL's own parameters of the same name and appending the rest.
+pub fn merge_query_pairs(url: &mut Url, pairs: &[(String, String)]) {
+    if pairs.is_empty() {
+        return;
+    }
+
+    let merged_pairs = url
+        .query_pairs()
+        .filter(|(key, _)| !pairs.iter().any(|(name, _)| name == key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .chain(pairs.iter().cloned())
+        .collect::<Vec<_>>();
+    url.query_pairs_mut().clear().extend_pairs(merged_pairs);
+}
+
+/// Append a path to the path of a URL, keeping the URL's query.
+///
+/// The path is expected to still be percent-encoded, which is kept as it is rather than being encoded again, and
+/// dot segments are dropped so the path can't climb out of the URL's own path.
+pub fn append_path(url: &mut Url, path: &str) {
+    let segments = path
+        .split('/')
+        .filter(|segment| {
+            let segment = segment.to_ascii_lowercase().replace("%2e", ".");
+            !segment.is_empty() && segment != "." && segment != ".."
+        })
+        .collect::<Vec<_>>();
+    if segments.is_empty() {
+        return;
+    }
+
+    let base = url.path().trim_end_matches('/').to_string();
+    let trailing_slash = if path.ends_with('/') { "/" } else { "" };
+    url.set_path(&format!("{base}/{}{trailing_slash}", segments.join("/")));
+}
+
+/// Whether or not the given query parameter is a known tracking parameter.
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with(UTM_PARAM_PREFIX) || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+/// Follow the redirects of a URL using HEAD requests, returning every URL that was redirected to in order.
+///
+/// The last URL in the chain is where the destination ultimately lands, while an empty chain means the URL
+/// doesn't redirect anywhere. Following stops after [`MAX_REDIRECT_HOPS`] redirects or when a request fails.
+pub async fn resolve_redirect_chain(url: &Url) -> Vec<Url> {
+    let mut chain = Vec::new();
+    let mut current = url.clone();
+
+    while chain.len() < MAX_REDIRECT_HOPS {
+        let mut init = RequestInit::new();
+        init.with_method(Method::Head)
+            .with_redirect(RequestRedirect::Manual);
+        let Ok(request) = Request::new_with_init(current.as_str(), &init) else {
+            break;
+        };
+        let Ok(response) = Fetch::Request(request).send().await else {
+            break;
+        };
+        if !(300..400).contains(&response.status_code()) {
+            break;
+        }
+
+        let Some(next) = response
+            .headers()
+            .get("Location")
+            .ok()
+            .flatten()
+            .and_then(|location| current.join(&location).ok())
+        else {
+            break;
+        };
+        chain.push(next.clone());
+        current = next;
+    }
+
+    chain
+}
+
+/// Check whether a destination URL is reachable, returning the final status code or [`None`] if the request failed.
+///
+/// A `HEAD` request is tried first, falling back to `GET` for servers that don't support `HEAD`.
+pub async fn check_destination(url: &Url) -> Option<u16> {
+    let mut status = None;
+    for method in [Method::Head, Method::Get] {
+        let mut init = RequestInit::new();
+        init.with_method(method)
+            .with_redirect(RequestRedirect::Follow);
+        let request = Request::new_with_init(url.as_str(), &init).ok()?;
+        let response = Fetch::Request(request).send().await.ok()?;
+        status = Some(response.status_code());
+        if !matches!(response.status_code(), 405 | 501) {
+            break;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn web_urls_are_http_or_https() {
+        assert!(is_web_url(&url("https://example.com/docs")));
+        assert!(is_web_url(&url("http://example.com")));
+        assert!(!is_web_url(&url("javascript:alert(document.domain)")));
+        assert!(!is_web_url(&url("JavaScript:alert(1)")));
+        assert!(!is_web_url(&url(
+            "data:text/html,<script>alert(1)</script>"
+        )));
+        assert!(!is_web_url(&url("vbscript:msgbox(1)")));
+        assert!(!is_web_url(&url("file:///etc/passwd")));
+        assert!(!is_web_url(&url("ftp://example.com/file")));
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn merged_query_pairs_replace_existing_ones() {
+        let mut destination = url("https://example.com/?ref=site&page=2&ref=other");
+        merge_query_pairs(
+            &mut destination,
+            &pairs(&[("ref", "newsletter"), ("lang", "de")]),
+        );
+        assert_eq!(
+            destination.as_str(),
+            "https://example.com/?page=2&ref=newsletter&lang=de"
+        );
+    }
+
+    #[test]
+    fn merged_query_pairs_are_encoded() {
+        let mut destination = url("https://example.com/search");
+        merge_query_pairs(&mut destination, &pairs(&[("q", "a b&c=d")]));
+        assert_eq!(
+            destination.as_str(),
+            "https://example.com/search?q=a+b%26c%3Dd"
+        );
+        assert_eq!(destination.query_pairs().next().unwrap().1, "a b&c=d");
+    }
+
+    #[test]
+    fn merging_no_query_pairs_leaves_the_url_untouched() {
+        let mut destination = url("https://example.com/?b=2&a=1");
+        merge_query_pairs(&mut destination, &[]);
+        assert_eq!(destination.as_str(), "https://example.com/?b=2&a=1");
+
+        let mut destination = url("https://example.com/");
+        merge_query_pairs(&mut destination, &[]);
+        assert_eq!(destination.query(), None);
+    }
+
+    #[test]
+    fn normalized_urls_lose_fragments_and_tracking_params() {
+        let mut destination = url("https://Example.COM:443/Docs?utm_source=x&id=1&FBCLID=y#intro");
+        normalize_url(&mut destination, true);
+        assert_eq!(destination.as_str(), "https://example.com/Docs?id=1");
+
+        let mut destination = url("https://example.com/?utm_source=x&gclid=y");
+        normalize_url(&mut destination, true);
+        assert_eq!(destination.as_str(), "https://example.com/");
+
+        let mut destination = url("https://example.com/?utm_source=x#intro");
+        normalize_url(&mut destination, false);
+        assert_eq!(destination.as_str(), "https://example.com/?utm_source=x");
+    }
+
+    #[test]
+    fn tracking_params_are_recognized() {
+        assert!(is_tracking_param("utm_campaign"));
+        assert!(is_tracking_param("UTM_Source"));
+        assert!(is_tracking_param("fbclid"));
+        assert!(is_tracking_param("MSCLKID"));
+        assert!(!is_tracking_param("id"));
+        assert!(!is_tracking_param("utm"));
+    }
+
+    fn appended(destination: &str, path: &str) -> String {
+        let mut destination = url(destination);
+        append_path(&mut destination, path);
+        destination.to_string()
+    }
+
+    #[test]
+    fn appended_paths_handle_trailing_slashes() {
+        assert_eq!(
+            appended("https://example.com/docs", "getting-started"),
+            "https://example.com/docs/getting-started"
+        );
+        assert_eq!(
+            appended("https://example.com/docs/", "getting-started"),
+            "https://example.com/docs/getting-started"
+        );
+        assert_eq!(
+            appended("https://example.com/docs", "guides/setup/"),
+            "https://example.com/docs/guides/setup/"
+        );
+        assert_eq!(
+            appended("https://example.com", "//guides//setup"),
+            "https://example.com/guides/setup"
+        );
+        assert_eq!(
+            appended("https://example.com/docs/", "/"),
+            "https://example.com/docs/"
+        );
+        assert_eq!(
+            appended("https://example.com/docs", ""),
+            "https://example.com/docs"
+        );
+    }
+
+    #[test]
+    fn appended_paths_drop_dot_segments() {
+        assert_eq!(
+            appended("https://example.com/docs", "../admin"),
+            "https://example.com/docs/admin"
+        );
+        assert_eq!(
+            appended("https://example.com/docs", "./guides/%2E%2E/%2e/setup"),
+            "https://example.com/docs/guides/setup"
+        );
+        assert_eq!(
+            appended("https://example.com/docs", "../.."),
+            "https://example.com/docs"
+        );
+        assert_eq!(
+            appended("https://example.com/docs", "v1.2/..config"),
+            "https://example.com/docs/v1.2/..config"
+        );
+    }
+
+    #[test]
+    fn appended_paths_keep_their_encoding_and_the_query() {
+        assert_eq!(
+            appended("https://example.com/docs?lang=en", "caf%C3%A9%20menu"),
+            "https://example.com/docs/caf%C3%A9%20menu?lang=en"
+        );
+
+        let mut destination = url("https://example.com/docs?lang=en&v=1");
+        append_path(&mut destination, "setup");
+        merge_query_pairs(&mut destination, &pairs(&[("v", "2")]));
+        assert_eq!(
+            destination.as_str(),
+            "https://example.com/docs/setup?lang=en&v=2"
+        );
+    }
+}
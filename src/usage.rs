@@ -0,0 +1,89 @@
+use crate::{
+    environment::get_var,
+    models::usage::StorageUsageModel,
+    notifications::{notify, Notification},
+    storage::StorageDriver,
+};
+use worker::{Date, Env};
+
+/// The binding name for the variable holding the key count that triggers a storage usage notification.
+pub const STORAGE_ALERT_KEYS_BINDING: &str = "STORAGE_ALERT_KEYS";
+
+/// The binding name for the variable holding the stored bytes that trigger a storage usage notification.
+pub const STORAGE_ALERT_BYTES_BINDING: &str = "STORAGE_ALERT_BYTES";
+
+/// The storage usage thresholds that trigger a notification once crossed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StorageAlertThresholds {
+    pub keys: Option<u64>,
+    pub bytes: Option<u64>,
+}
+
+impl StorageAlertThresholds {
+    /// Read the thresholds from the environment, leaving unset or invalid thresholds disabled.
+    pub fn from_env(env: &Env) -> Self {
+        Self {
+            keys: get_var(env, STORAGE_ALERT_KEYS_BINDING).and_then(|keys| keys.parse().ok()),
+            bytes: get_var(env, STORAGE_ALERT_BYTES_BINDING).and_then(|bytes| bytes.parse().ok()),
+        }
+    }
+}
+
+/// Measure how much data is held in storage, sending a notification for any threshold that has
+/// been crossed since the last measurement.
+///
+/// Sizes are recorded alongside every value when it's written, so this only needs to list keys
+/// rather than read every value.
+pub async fn measure_storage_usage(storage: &impl StorageDriver, env: &Env) -> StorageUsageModel {
+    let previous = storage
+        .get_deserialized_json::<StorageUsageModel>(StorageUsageModel::key())
+        .await
+        .unwrap_or_default();
+    let usage = storage.usage("").await;
+    let thresholds = StorageAlertThresholds::from_env(env);
+
+    let mut measurement = StorageUsageModel {
+        usage,
+        keys_alerted: previous.keys_alerted,
+        bytes_alerted: previous.bytes_alerted,
+        measured_at_timestamp: Date::now().as_millis(),
+    };
+    for (resource, value, threshold, alerted) in [
+        (
+            "keys",
+            usage.keys,
+            thresholds.keys,
+            &mut measurement.keys_alerted,
+        ),
+        (
+            "bytes",
+            usage.bytes,
+            thresholds.bytes,
+            &mut measurement.bytes_alerted,
+        ),
+    ] {
+        match threshold {
+            Some(threshold) if value >= threshold => {
+                // Only notify when a threshold is first crossed rather than on every measurement while it's exceeded.
+                if !*alerted {
+                    notify(
+                        env,
+                        &Notification::StorageThresholdExceeded {
+                            resource: resource.to_string(),
+                            usage: value,
+                            threshold,
+                        },
+                    )
+                    .await;
+                }
+                *alerted = true;
+            }
+            _ => *alerted = false,
+        }
+    }
+
+    storage
+        .set_serialized_json(StorageUsageModel::key(), &measurement, None)
+        .await;
+    measurement
+}
@@ -0,0 +1,88 @@
+/// The broad details of a client parsed from its User-Agent header.
+///
+/// Only these coarse categories are kept so raw User-Agent strings never need to be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserAgent {
+    /// The browser family, e.g. `Chrome` or `Firefox`.
+    pub browser: &'static str,
+    /// The operating system family, e.g. `Windows` or `iOS`.
+    pub os: &'static str,
+    /// The class of device, one of `desktop`, `mobile`, `tablet` or `bot`.
+    pub device: &'static str,
+}
+
+/// The value used when a family can't be determined.
+const OTHER: &str = "Other";
+
+/// Markers that identify automated clients such as crawlers and link previewers.
+const BOT_MARKERS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "slurp",
+    "preview",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "headless",
+];
+
+/// Browser families and the markers that identify them, in order of precedence.
+///
+/// Order matters as most browsers also claim to be the browsers they're based on (e.g. Edge claims to be Chrome).
+const BROWSERS: &[(&str, &[&str])] = &[
+    ("Edge", &["Edg/", "EdgA/", "EdgiOS/"]),
+    ("Opera", &["OPR/", "Opera"]),
+    ("Samsung Internet", &["SamsungBrowser/"]),
+    ("Firefox", &["Firefox/", "FxiOS/"]),
+    ("Chrome", &["Chrome/", "CriOS/"]),
+    ("Safari", &["Safari/"]),
+];
+
+/// Operating system families and the markers that identify them, in order of precedence.
+const OPERATING_SYSTEMS: &[(&str, &[&str])] = &[
+    ("iOS", &["iPhone", "iPad", "iPod"]),
+    ("Android", &["Android"]),
+    ("ChromeOS", &["CrOS"]),
+    ("Windows", &["Windows"]),
+    ("macOS", &["Macintosh", "Mac OS X"]),
+    ("Linux", &["Linux"]),
+];
+
+/// Find the first family whose markers appear in the User-Agent.
+fn find_family(user_agent: &str, families: &[(&'static str, &[&str])]) -> &'static str {
+    families
+        .iter()
+        .find(|(_, markers)| markers.iter().any(|marker| user_agent.contains(marker)))
+        .map_or(OTHER, |(family, _)| family)
+}
+
+/// Parse a User-Agent header into its browser family, operating system and device class.
+pub fn parse_user_agent(user_agent: &str) -> UserAgent {
+    let lowercase = user_agent.to_ascii_lowercase();
+    if BOT_MARKERS.iter().any(|marker| lowercase.contains(marker)) {
+        return UserAgent {
+            browser: OTHER,
+            os: OTHER,
+            device: "bot",
+        };
+    }
+
+    let os = find_family(user_agent, OPERATING_SYSTEMS);
+    let device = if user_agent.contains("iPad")
+        || user_agent.contains("Tablet")
+        || (os == "Android" && !user_agent.contains("Mobile"))
+    {
+        "tablet"
+    } else if user_agent.contains("Mobi") || user_agent.contains("iPhone") {
+        "mobile"
+    } else {
+        "desktop"
+    };
+
+    UserAgent {
+        browser: find_family(user_agent, BROWSERS),
+        os,
+        device,
+    }
+}
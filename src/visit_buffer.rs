@@ -0,0 +1,183 @@
+use crate::{
+    analytics::{day_from_timestamp, PrivacyConfig, Visit},
+    durable_objects::link_lock::with_link_lock,
+    environment::get_var,
+    get_tombstone_retention,
+    models::{link::LinkModel, stats::StatsModel},
+    storage::{get_tenant_storage, StorageDriver},
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    time::Duration,
+};
+use worker::{Date, Env, Response};
+
+/// The binding name for the variable controlling how often buffered visits are written to storage.
+///
+/// Visits are written to storage immediately unless this is set.
+pub const VISIT_FLUSH_INTERVAL_BINDING: &str = "VISIT_FLUSH_INTERVAL";
+
+/// The binding name for the variable controlling how many buffered visits cause an early write to storage.
+pub const VISIT_FLUSH_THRESHOLD_BINDING: &str = "VISIT_FLUSH_THRESHOLD";
+
+/// The default amount of buffered visits that cause an early write to storage.
+const DEFAULT_VISIT_FLUSH_THRESHOLD: u64 = 100;
+
+/// Configuration for buffering visits in memory before writing them to storage.
+pub struct VisitBufferConfig {
+    /// The maximum amount of time visits are buffered for.
+    interval: Duration,
+    /// The amount of buffered visits that cause a write regardless of the interval.
+    threshold: u64,
+}
+
+impl VisitBufferConfig {
+    /// Get the visit buffering configuration from the environment, or [`None`] if buffering is disabled.
+    pub fn from_env(env: &Env) -> Option<Self> {
+        let interval =
+            humantime::parse_duration(&get_var(env, VISIT_FLUSH_INTERVAL_BINDING)?).ok()?;
+        let threshold = get_var(env, VISIT_FLUSH_THRESHOLD_BINDING)
+            .and_then(|threshold| threshold.parse().ok())
+            .unwrap_or(DEFAULT_VISIT_FLUSH_THRESHOLD);
+        Some(Self {
+            interval,
+            threshold,
+        })
+    }
+}
+
+/// Visits to a link on a single day that have been counted but not yet written to storage.
+#[derive(Default)]
+struct PendingVisits {
+    views: u64,
+    /// The stats of the visits that were sampled.
+    stats: StatsModel,
+    last_viewed_timestamp: u64,
+}
+
+/// Identifies buffered visits by the tenant and ID of the visited link and the day of the visit.
+type PendingVisitsKey = (Option<String>, String, String);
+
+thread_local! {
+    /// Visits buffered in this isolate, keyed by tenant, link ID and day.
+    static PENDING_VISITS: RefCell<HashMap<PendingVisitsKey, PendingVisits>> = RefCell::default();
+
+    /// The UNIX timestamp of when buffered visits were last written to storage by this isolate.
+    static LAST_FLUSH_TIMESTAMP: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Buffer a visit to a link in memory, returning whether the buffer is now due to be flushed.
+pub fn buffer_visit(
+    config: &VisitBufferConfig,
+    tenant: Option<&str>,
+    id: &str,
+    visit: Visit,
+) -> bool {
+    let now = visit.timestamp;
+    let total_pending = PENDING_VISITS.with_borrow_mut(|pending| {
+        let visits = pending
+            .entry((
+                tenant.map(str::to_string),
+                id.to_string(),
+                day_from_timestamp(now),
+            ))
+            .or_default();
+        visits.views += 1;
+        visits.last_viewed_timestamp = now;
+        if visit.sampled {
+            visits.stats.add_visit(visit);
+        }
+        pending.values().map(|visits| visits.views).sum::<u64>()
+    });
+
+    let last_flush = LAST_FLUSH_TIMESTAMP.get();
+    if last_flush == 0 {
+        LAST_FLUSH_TIMESTAMP.set(now);
+    }
+
+    total_pending >= config.threshold
+        || now.saturating_sub(last_flush) >= config.interval.as_millis() as u64
+}
+
+/// Get the amount of buffered visits to a link that haven't been written to storage yet.
+pub fn pending_views(tenant: Option<&str>, id: &str) -> u64 {
+    PENDING_VISITS.with_borrow(|pending| {
+        pending
+            .iter()
+            .filter(|((pending_tenant, pending_id, _), _)| {
+                pending_tenant.as_deref() == tenant && pending_id == id
+            })
+            .map(|(_, visits)| visits.views)
+            .sum()
+    })
+}
+
+/// Write all buffered visits to storage, adding them to each link's view count and daily stats (if stored).
+pub async fn flush_visits(env: Env) {
+    LAST_FLUSH_TIMESTAMP.set(Date::now().as_millis());
+    let pending = PENDING_VISITS.with_borrow_mut(std::mem::take);
+    let privacy = PrivacyConfig::from_env(&env);
+
+    let mut link_visits = HashMap::<(Option<String>, String), (u64, u64)>::new();
+    for ((tenant, id, day), visits) in pending {
+        if privacy.visit_stats && visits.stats.views > 0 {
+            let Ok(storage) = get_tenant_storage(&env, tenant.as_deref()) else {
+                continue;
+            };
+            let key = StatsModel::day_key(&id, &day);
+            let mut stats = storage
+                .get_deserialized_json::<StatsModel>(&key)
+                .await
+                .unwrap_or_default();
+            stats.merge(visits.stats);
+            storage.set_serialized_json(&key, &stats, None).await;
+        }
+
+        let (views, last_viewed_timestamp) = link_visits.entry((tenant, id)).or_default();
+        *views += visits.views;
+        *last_viewed_timestamp = (*last_viewed_timestamp).max(visits.last_viewed_timestamp);
+    }
+
+    for ((tenant, id), (views, last_viewed_timestamp)) in link_visits {
+        let Ok(storage) = get_tenant_storage(&env, tenant.as_deref()) else {
+            continue;
+        };
+        // Links are rewritten whole, so they're read and written while holding their lock to avoid overwriting
+        // changes made to them at the same time.
+        let locked = with_link_lock(&env, tenant.as_deref(), &id, async {
+            // The link may have been removed since it was visited, in which case there's nothing to update.
+            if let Some(mut link) = storage.get_deserialized_json::<LinkModel>(&id).await {
+                link.add_visits(views, last_viewed_timestamp);
+                if !privacy.last_viewed {
+                    link.last_viewed_timestamp = None;
+                }
+                storage
+                    .set_serialized_json(
+                        &id,
+                        &link,
+                        link.storage_expiration(get_tombstone_retention(&env).is_some()),
+                    )
+                    .await;
+            }
+            Response::empty()
+        })
+        .await
+        .is_ok_and(|response| response.status_code() == 409);
+        // Views that couldn't be written because the link stayed locked are kept for the next flush.
+        if locked {
+            rebuffer_views(tenant, id, views, last_viewed_timestamp);
+        }
+    }
+}
+
+/// Put views that couldn't be written back into the buffer, without their stats as those were already written.
+fn rebuffer_views(tenant: Option<String>, id: String, views: u64, last_viewed_timestamp: u64) {
+    PENDING_VISITS.with_borrow_mut(|pending| {
+        let visits = pending
+            .entry((tenant, id, day_from_timestamp(last_viewed_timestamp)))
+            .or_default();
+        visits.views += views;
+        visits.last_viewed_timestamp = visits.last_viewed_timestamp.max(last_viewed_timestamp);
+    });
+}
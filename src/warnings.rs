@@ -0,0 +1,84 @@
+use crate::{
+    environment::get_var,
+    models::warnlist::WarnListModel,
+    storage::{get_storage, StorageDriver},
+};
+use url::Url;
+use worker::{Env, Request};
+
+/// The binding name for the variable holding comma-separated destination domains visitors are warned about.
+pub const WARN_DOMAINS_BINDING: &str = "WARN_DOMAINS";
+
+/// The query parameter visitors use to confirm they want to continue to a flagged destination.
+pub const CONFIRM_QUERY_PARAM: &str = "confirm";
+
+/// Normalize a domain for comparison, e.g. `*.Example.com.` becomes `example.com`.
+pub fn normalize_domain(domain: &str) -> String {
+    domain
+        .trim()
+        .trim_start_matches("*.")
+        .trim_matches('.')
+        .to_lowercase()
+}
+
+/// Get the flagged domain a URL falls under and its warning, if any.
+///
+/// Subdomains of a flagged domain are also flagged. Domains from the environment use the default warning,
+/// so their warning is [`None`].
+pub async fn find_domain_warning(env: &Env, url: &Url) -> Option<(String, Option<String>)> {
+    let host = normalize_domain(url.host_str()?);
+    let env_domains = get_var(env, WARN_DOMAINS_BINDING)
+        .map(|domains| {
+            domains
+                .split(',')
+                .map(normalize_domain)
+                .filter(|domain| !domain.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let warn_list = get_storage(env)
+        .ok()?
+        .get_deserialized_json::<WarnListModel>(WarnListModel::key())
+        .await
+        .unwrap_or_default();
+    if env_domains.is_empty() && warn_list.domains.is_empty() {
+        return None;
+    }
+
+    // Check the host itself and then each of its parent domains.
+    let mut candidate = host.as_str();
+    loop {
+        if let Some(warned) = warn_list.domains.get(candidate) {
+            return Some((candidate.to_string(), warned.reason.clone()));
+        }
+        if env_domains.iter().any(|domain| domain == candidate) {
+            return Some((candidate.to_string(), None));
+        }
+        candidate = candidate.split_once('.')?.1;
+    }
+}
+
+/// Whether or not the visitor has confirmed they want to continue to a flagged destination.
+pub fn is_warning_confirmed(req: &Request) -> bool {
+    req.url().is_ok_and(|url| {
+        url.query_pairs()
+            .any(|(key, value)| key == CONFIRM_QUERY_PARAM && value == "true")
+    })
+}
+
+/// Get the URL visitors follow to confirm they want to continue to a flagged destination.
+///
+/// The rest of the query is kept, as links can forward it to their destination.
+pub fn confirm_url(req: &Request) -> worker::Result<Url> {
+    let mut url = req.url()?;
+    let retained_pairs = url
+        .query_pairs()
+        .filter(|(key, _)| key != CONFIRM_QUERY_PARAM)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(retained_pairs)
+        .append_pair(CONFIRM_QUERY_PARAM, "true");
+    Ok(url)
+}
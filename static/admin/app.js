@@ -0,0 +1,365 @@
+// The admin interface is a small single-page app that only talks to the worker's own API, authenticating with an
+// API key that's kept in session storage for as long as the tab is open.
+
+const TOKEN_STORAGE_KEY = "workerlink:token";
+const BASE_PATH = "/admin";
+const app = document.getElementById("app");
+const signOutButton = document.getElementById("sign-out");
+
+/** Create an element with the given attributes and children. */
+function h(tag, attributes = {}, ...children) {
+    const element = document.createElement(tag);
+    for (const [name, value] of Object.entries(attributes)) {
+        if (name.startsWith("on")) {
+            element.addEventListener(name.slice(2), value);
+        } else if (value !== undefined && value !== null && value !== false) {
+            element.setAttribute(name, value === true ? "" : value);
+        }
+    }
+    element.append(...children.flat().filter((child) => child !== null && child !== undefined));
+    return element;
+}
+
+/** Get the path of a link's ID, keeping the `/` between a namespace and a name. */
+function linkPath(id) {
+    return id.split("/").map(encodeURIComponent).join("/");
+}
+
+/** An error response from the API, carrying the message the worker responded with. */
+class ApiError extends Error {
+    constructor(status, message) {
+        super(message || `The request failed with status ${status}.`);
+        this.status = status;
+    }
+}
+
+/** Make an authenticated request to the worker, returning the parsed JSON or text body. */
+async function api(path, { method = "GET", body } = {}) {
+    const headers = { Authorization: sessionStorage.getItem(TOKEN_STORAGE_KEY) ?? "" };
+    if (body !== undefined) {
+        headers["Content-Type"] = "application/json";
+    }
+    const response = await fetch(path, {
+        method,
+        headers,
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    if (response.status === 401) {
+        signOut();
+        throw new ApiError(401, "The API key was rejected.");
+    }
+    const text = await response.text();
+    if (!response.ok) {
+        throw new ApiError(response.status, text.trim());
+    }
+    try {
+        return JSON.parse(text);
+    } catch {
+        return text;
+    }
+}
+
+/** Navigate to a path within the admin interface without reloading the page. */
+function navigate(path) {
+    history.pushState(null, "", path);
+    render();
+}
+
+function signOut() {
+    sessionStorage.removeItem(TOKEN_STORAGE_KEY);
+    render();
+}
+
+function formatTimestamp(timestamp) {
+    return timestamp ? new Date(timestamp).toLocaleString() : "Never";
+}
+
+function errorMessage(error) {
+    return h("p", { class: "error" }, error.message);
+}
+
+function signInView() {
+    const input = h("input", { type: "password", required: true, autocomplete: "current-password" });
+    const status = h("p", { class: "error" });
+    return h(
+        "section",
+        {},
+        h("h1", {}, "Sign in"),
+        h(
+            "form",
+            {
+                onsubmit: async (event) => {
+                    event.preventDefault();
+                    // The key is checked before it's stored, so a rejected key doesn't sign out and re-render the form.
+                    const token = input.value.trim();
+                    try {
+                        const response = await fetch("/api/links?limit=1", { headers: { Authorization: token } });
+                        if (!response.ok) {
+                            throw new ApiError(response.status, response.status === 401 ? "The API key was rejected." : "");
+                        }
+                        sessionStorage.setItem(TOKEN_STORAGE_KEY, token);
+                        render();
+                    } catch (error) {
+                        status.textContent = error.message;
+                    }
+                },
+            },
+            h("label", {}, "API key", input, h("small", {}, "The worker's AUTH_TOKEN or one of its ADDITIONAL_AUTH_TOKENS.")),
+            h("button", { type: "submit", class: "primary" }, "Sign in"),
+            status,
+        ),
+    );
+}
+
+function linksView() {
+    const prefix = h("input", { type: "search", placeholder: "Filter by prefix, e.g. team/" });
+    const rows = h("tbody");
+    const more = h("button", { type: "button", hidden: true, onclick: () => load(false) }, "Load more");
+    const status = h("p", { class: "muted" }, "Loading…");
+    let cursor = null;
+
+    async function load(reset) {
+        if (reset) {
+            rows.replaceChildren();
+            cursor = null;
+        }
+        status.textContent = "Loading…";
+        const query = new URLSearchParams({ prefix: prefix.value, limit: "100" });
+        if (cursor) {
+            query.set("cursor", cursor);
+        }
+        try {
+            const page = await api(`/api/links?${query}`);
+            for (const id of page.ids) {
+                const link = page.links[id];
+                if (!link) {
+                    continue;
+                }
+                rows.append(
+                    h(
+                        "tr",
+                        {},
+                        h("td", {}, h("a", { href: `${BASE_PATH}/links/${linkPath(id)}`, "data-route": true }, id)),
+                        h("td", {}, link.alias_of ? `Alias of ${link.alias_of}` : link.url),
+                        h("td", {}, String(link.views)),
+                        h("td", {}, link.disabled ? "Disabled" : link.paused ? "Paused" : "Active"),
+                    ),
+                );
+            }
+            cursor = page.cursor;
+            more.hidden = !cursor;
+            status.textContent = rows.childElementCount === 0 && !cursor ? "No links found." : "";
+        } catch (error) {
+            status.replaceChildren(errorMessage(error));
+        }
+    }
+
+    load(true);
+    return h(
+        "section",
+        {},
+        h("h1", {}, "Links"),
+        h(
+            "form",
+            {
+                class: "toolbar",
+                onsubmit: (event) => {
+                    event.preventDefault();
+                    load(true);
+                },
+            },
+            prefix,
+            h("button", { type: "submit" }, "Filter"),
+        ),
+        h(
+            "table",
+            {},
+            h("thead", {}, h("tr", {}, h("th", {}, "ID"), h("th", {}, "Destination"), h("th", {}, "Views"), h("th", {}, "Status"))),
+            rows,
+        ),
+        status,
+        more,
+    );
+}
+
+function newLinkView() {
+    const id = h("input", { placeholder: "Leave empty for a random ID" });
+    const url = h("input", { type: "url", required: true, placeholder: "https://example.com" });
+    const expireIn = h("input", { placeholder: "e.g. 30d" });
+    const maxViews = h("input", { type: "number", min: "1" });
+    const status = h("p", { class: "error" });
+    return h(
+        "section",
+        {},
+        h("h1", {}, "New link"),
+        h(
+            "form",
+            {
+                onsubmit: async (event) => {
+                    event.preventDefault();
+                    const body = { url: url.value.trim() };
+                    if (expireIn.value.trim()) {
+                        body.expire_in = expireIn.value.trim();
+                    }
+                    if (maxViews.value) {
+                        body.max_views = Number(maxViews.value);
+                    }
+                    const path = id.value.trim() ? `/${linkPath(id.value.trim())}` : "/";
+                    try {
+                        const created = await api(path, { method: "POST", body });
+                        navigate(`${BASE_PATH}/links/${linkPath(created.id)}`);
+                    } catch (error) {
+                        status.textContent = error.message;
+                    }
+                },
+            },
+            h("label", {}, "Destination", url),
+            h("label", {}, "ID", id),
+            h("label", {}, "Expire in", expireIn, h("small", {}, "A duration such as 1h, 7d or 2w.")),
+            h("label", {}, "Maximum views", maxViews),
+            h("button", { type: "submit", class: "primary" }, "Create link"),
+            status,
+        ),
+    );
+}
+
+/** Render a small bar chart of the given values. */
+function bars(values, labels) {
+    const max = Math.max(1, ...values);
+    return h(
+        "div",
+        { class: "bars" },
+        values.map((value, index) => {
+            // Heights are set through the style property as the Content-Security-Policy blocks inline style attributes.
+            const bar = h("span", { title: `${labels[index]}: ${value}` });
+            bar.style.height = `${(value / max) * 100}%`;
+            return bar;
+        }),
+    );
+}
+
+function linkView(id) {
+    const content = h("div", {}, h("p", { class: "muted" }, "Loading…"));
+    const path = `/${linkPath(id)}`;
+
+    async function load() {
+        try {
+            const [link, stats] = await Promise.all([api(`${path}/details`), api(`${path}/stats`)]);
+            if (!link.url) {
+                content.replaceChildren(h("p", { class: "muted" }, "This link has been removed."));
+                return;
+            }
+            const action = async (request) => {
+                try {
+                    await request();
+                    load();
+                } catch (error) {
+                    content.prepend(errorMessage(error));
+                }
+            };
+            content.replaceChildren(
+                h("p", {}, h("a", { href: link.url, rel: "noreferrer", target: "_blank" }, link.url)),
+                h(
+                    "div",
+                    { class: "toolbar" },
+                    link.paused
+                        ? h("button", { type: "button", onclick: () => action(() => api(`${path}/resume`, { method: "POST" })) }, "Resume")
+                        : h("button", { type: "button", onclick: () => action(() => api(`${path}/pause`, { method: "POST" })) }, "Pause"),
+                    h("a", { href: `${path}/qr?format=svg`, target: "_blank" }, h("button", { type: "button" }, "QR code")),
+                    h(
+                        "button",
+                        {
+                            type: "button",
+                            class: "danger",
+                            onclick: () => {
+                                if (confirm(`Delete ${id}?`)) {
+                                    action(async () => {
+                                        await api(path, { method: "DELETE" });
+                                        navigate(`${BASE_PATH}/`);
+                                    });
+                                }
+                            },
+                        },
+                        "Delete",
+                    ),
+                ),
+                h(
+                    "div",
+                    { class: "cards" },
+                    h("div", { class: "card" }, h("strong", {}, String(stats.views)), "Views"),
+                    h("div", { class: "card" }, h("strong", {}, String(stats.unique_visitors)), "Unique visitors"),
+                    h("div", { class: "card" }, h("strong", {}, String(stats.conversions)), "Conversions"),
+                    h("div", { class: "card" }, h("strong", {}, link.max_views ?? "∞"), "Maximum views"),
+                ),
+                h("h2", {}, "Visits by hour (UTC)"),
+                bars(stats.hours, stats.hours.map((_, hour) => `${hour}:00`)),
+                h("h2", {}, "Visits by weekday (UTC)"),
+                bars(stats.weekdays, ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]),
+                h("h2", {}, "Details"),
+                h(
+                    "table",
+                    {},
+                    h(
+                        "tbody",
+                        {},
+                        [
+                            ["Created", formatTimestamp(link.created_at_timestamp)],
+                            ["Modified", formatTimestamp(link.modified_at_timestamp)],
+                            ["Expires", formatTimestamp(link.expiry_timestamp)],
+                            ["Status", link.disabled ? "Disabled" : link.paused ? "Paused" : "Active"],
+                        ].map(([name, value]) => h("tr", {}, h("th", {}, name), h("td", {}, value))),
+                    ),
+                ),
+            );
+        } catch (error) {
+            content.replaceChildren(errorMessage(error));
+        }
+    }
+
+    load();
+    return h("section", {}, h("h1", {}, id), content);
+}
+
+function notFoundView() {
+    return h("section", {}, h("h1", {}, "Not found"), h("p", {}, h("a", { href: `${BASE_PATH}/`, "data-route": true }, "Back to links")));
+}
+
+/** Render the view for the current path. */
+function render() {
+    const signedIn = Boolean(sessionStorage.getItem(TOKEN_STORAGE_KEY));
+    signOutButton.hidden = !signedIn;
+    if (!signedIn) {
+        app.replaceChildren(signInView());
+        return;
+    }
+
+    const path = location.pathname.replace(/^\/admin\/?/, "");
+    if (path === "") {
+        app.replaceChildren(linksView());
+    } else if (path === "new") {
+        app.replaceChildren(newLinkView());
+    } else if (path.startsWith("links/")) {
+        app.replaceChildren(linkView(decodeURIComponent(path.slice("links/".length))));
+    } else {
+        app.replaceChildren(notFoundView());
+    }
+}
+
+document.addEventListener("click", (event) => {
+    const link = event.target.closest("a[data-route]");
+    if (link && !event.metaKey && !event.ctrlKey && !event.shiftKey) {
+        event.preventDefault();
+        navigate(link.getAttribute("href"));
+    }
+});
+window.addEventListener("popstate", render);
+signOutButton.addEventListener("click", signOut);
+
+fetch("/.well-known/workerlink")
+    .then((response) => response.json())
+    .then((capabilities) => {
+        document.getElementById("footer").textContent = `${capabilities.name} ${capabilities.version}`;
+    })
+    .catch(() => {});
+
+render();